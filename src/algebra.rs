@@ -0,0 +1,223 @@
+//! Combining two schemas into their intersection or union.
+//!
+//! Naively wrapping the schemas in an `allOf` or `anyOf` works, but quickly
+//! leads to deeply nested schemas that are hard to read and slow to validate
+//! against. These helpers simplify the result where the meaning of the
+//! schemas allows it, and only fall back to wrapping otherwise.
+
+use serde_json::{json, Map, Value};
+
+use crate::merge::{merge_schemas, MergeError};
+use crate::schemas;
+use crate::util;
+
+/// Build a schema that is valid exactly when both `a` and `b` are valid.
+///
+/// Compatible keywords are merged (e.g. the strictest bounds win and
+/// `required` is unioned), using the same rules as
+/// [`merge_allOf`](fn.merge_allOf.html). If the two schemas contradict each
+/// other, a schema that is never valid is returned.
+///
+/// ## Example:
+///
+/// ```rust
+/// # use serde_json::json;
+/// # use jsonschema_valid::schemas;
+/// let base = json!({"type": "object", "required": ["id"]});
+/// let tenant = json!({"required": ["tenant"], "maxProperties": 10});
+/// assert_eq!(
+///     jsonschema_valid::intersection(&base, &tenant, &schemas::Draft7),
+///     json!({"type": "object", "required": ["id", "tenant"], "maxProperties": 10})
+/// );
+/// ```
+pub fn intersection(a: &Value, b: &Value, draft: &dyn schemas::Draft) -> Value {
+    if a == b {
+        return a.clone();
+    }
+    match merge_schemas(a, b, "") {
+        Ok(Some(merged)) => normalize(merged, draft),
+        Err(MergeError::Unsatisfiable { .. }) => never_valid(draft),
+        _ => {
+            let mut branches = Vec::new();
+            for schema in &[a, b] {
+                match only_keyword(schema, "allOf") {
+                    Some(Value::Array(inner)) => branches.extend(inner.iter().cloned()),
+                    _ => branches.push((*schema).clone()),
+                }
+            }
+            json!({ "allOf": branches })
+        }
+    }
+}
+
+/// Build a schema that is valid when either `a` or `b` is valid.
+///
+/// Schemas that only restrict `type`, or only list allowed values with
+/// `enum` or `const`, are combined directly; trivially valid or invalid
+/// schemas are simplified away. Otherwise, the schemas are combined in an
+/// `anyOf`.
+///
+/// ## Example:
+///
+/// ```rust
+/// # use serde_json::json;
+/// # use jsonschema_valid::schemas;
+/// assert_eq!(
+///     jsonschema_valid::union(&json!({"enum": [1, 2]}), &json!({"const": 3}), &schemas::Draft7),
+///     json!({"enum": [1, 2, 3]})
+/// );
+/// assert_eq!(
+///     jsonschema_valid::union(&json!({"type": "string"}), &json!({"minimum": 0}), &schemas::Draft7),
+///     json!({"anyOf": [{"type": "string"}, {"minimum": 0}]})
+/// );
+/// ```
+pub fn union(a: &Value, b: &Value, draft: &dyn schemas::Draft) -> Value {
+    if a == b {
+        return a.clone();
+    }
+    if is_always_valid(a) || is_always_valid(b) {
+        return always_valid(draft);
+    }
+    if is_never_valid(a) {
+        return b.clone();
+    }
+    if is_never_valid(b) {
+        return a.clone();
+    }
+
+    if let (Some(a_type), Some(b_type)) = (only_keyword(a, "type"), only_keyword(b, "type")) {
+        let mut types: Vec<&str> = Vec::new();
+        for name in util::iter_or_once(a_type)
+            .chain(util::iter_or_once(b_type))
+            .filter_map(Value::as_str)
+        {
+            if !types.contains(&name) {
+                types.push(name);
+            }
+        }
+        if types.contains(&"number") {
+            types.retain(|x| *x != "integer");
+        }
+        return match types.as_slice() {
+            [single] => json!({ "type": single }),
+            _ => json!({ "type": types }),
+        };
+    }
+
+    if let (Some(mut values), Some(b_values)) = (allowed_values(a), allowed_values(b)) {
+        for value in b_values {
+            if !values.contains(&value) {
+                values.push(value);
+            }
+        }
+        return json!({ "enum": values });
+    }
+
+    let mut branches = Vec::new();
+    for schema in &[a, b] {
+        match only_keyword(schema, "anyOf") {
+            Some(Value::Array(inner)) => branches.extend(inner.iter().cloned()),
+            _ => branches.push((*schema).clone()),
+        }
+    }
+    json!({ "anyOf": branches })
+}
+
+/// If the schema is an object with a single keyword `keyword`, return its
+/// value.
+fn only_keyword<'a>(schema: &'a Value, keyword: &str) -> Option<&'a Value> {
+    match schema {
+        Value::Object(object) if object.len() == 1 => object.get(keyword),
+        _ => None,
+    }
+}
+
+/// The complete list of allowed values, if the schema only consists of an
+/// `enum` or a `const`.
+fn allowed_values(schema: &Value) -> Option<Vec<Value>> {
+    if let Some(Value::Array(values)) = only_keyword(schema, "enum") {
+        Some(values.clone())
+    } else {
+        only_keyword(schema, "const").map(|value| vec![value.clone()])
+    }
+}
+
+fn is_always_valid(schema: &Value) -> bool {
+    match schema {
+        Value::Bool(b) => *b,
+        Value::Object(object) => object.is_empty(),
+        _ => false,
+    }
+}
+
+fn is_never_valid(schema: &Value) -> bool {
+    match schema {
+        Value::Bool(b) => !*b,
+        _ => match only_keyword(schema, "not") {
+            Some(not) => is_always_valid(not),
+            None => false,
+        },
+    }
+}
+
+fn always_valid(draft: &dyn schemas::Draft) -> Value {
+    if draft.get_draft_number() >= 6 {
+        Value::Bool(true)
+    } else {
+        Value::Object(Map::new())
+    }
+}
+
+fn never_valid(draft: &dyn schemas::Draft) -> Value {
+    if draft.get_draft_number() >= 6 {
+        Value::Bool(false)
+    } else {
+        json!({"not": {}})
+    }
+}
+
+/// Boolean schemas are only allowed from draft 6 on.
+fn normalize(schema: Value, draft: &dyn schemas::Draft) -> Value {
+    match schema {
+        Value::Bool(true) => always_valid(draft),
+        Value::Bool(false) => never_valid(draft),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contradiction_is_never_valid() {
+        let a = json!({"type": "string"});
+        let b = json!({"type": "integer"});
+        assert_eq!(intersection(&a, &b, &schemas::Draft7), json!(false));
+        assert_eq!(intersection(&a, &b, &schemas::Draft4), json!({"not": {}}));
+        assert_eq!(union(&json!({"not": {}}), &a, &schemas::Draft4), a);
+    }
+
+    #[test]
+    fn test_nested_applicators_are_flattened() {
+        let a = json!({"anyOf": [{"type": "string"}, {"minimum": 0}]});
+        let b = json!({"multipleOf": 2, "maximum": 4});
+        assert_eq!(
+            union(&a, &b, &schemas::Draft7),
+            json!({"anyOf": [{"type": "string"}, {"minimum": 0}, b]})
+        );
+        assert_eq!(
+            union(
+                &json!({"type": "integer"}),
+                &json!({"type": ["number", "null"]}),
+                &schemas::Draft7
+            ),
+            json!({"type": ["number", "null"]})
+        );
+        let c = json!({"$ref": "#/definitions/x"});
+        assert_eq!(
+            intersection(&json!({"allOf": [b]}), &c, &schemas::Draft7),
+            json!({"allOf": [b, c]})
+        );
+    }
+}
@@ -0,0 +1,234 @@
+//! Collecting vendor extension keywords as annotations.
+//!
+//! Schemas often carry keywords starting with `x-`, such as `x-sensitive` or
+//! `x-unit`, that validation ignores but downstream tooling needs, e.g. to
+//! mask sensitive values or to show units next to numbers. `extensions`
+//! evaluates a schema against an instance and reports every `x-` keyword of
+//! the subschemas that apply to each value in the instance, along with where
+//! it was found in both.
+//!
+//! As with the annotations of JSON Schema, those of an `anyOf` or `oneOf`
+//! branch the value is invalid against, of an `if` the value is invalid
+//! against, and of a `not` are dropped. Keywords next to a `$ref` are
+//! ignored, as they are by validation.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::{schemas, Config};
+//! use jsonschema_valid::annotations::extensions;
+//!
+//! let schema = json!({
+//!     "properties": {
+//!         "password": {"type": "string", "x-sensitive": true},
+//!         "timeout": {"$ref": "#/definitions/seconds"}
+//!     },
+//!     "definitions": {"seconds": {"type": "number", "x-unit": "s"}}
+//! });
+//! let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+//!
+//! let instance = json!({"password": "hunter2", "timeout": 30});
+//! let annotations = extensions(&cfg, &instance);
+//! assert_eq!(annotations.len(), 2);
+//! assert_eq!(annotations[0].instance_path, "/password");
+//! assert_eq!(annotations[0].keyword, "x-sensitive");
+//! assert_eq!(annotations[1].schema_path, "/properties/timeout/$ref/x-unit");
+//! assert_eq!(annotations[1].value, &json!("s"));
+//! ```
+
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::context::Context;
+use crate::explain::{children, is_valid, MAX_REF_DEPTH};
+use crate::util;
+
+/// An `x-` keyword of a subschema that applies to a value in the instance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation<'a> {
+    /// The location of the value in the instance, as a JSON pointer.
+    pub instance_path: String,
+    /// The path of keywords that leads from the root schema to the keyword,
+    /// as a JSON pointer. References are followed, and show up as a `$ref`
+    /// token.
+    pub schema_path: String,
+    /// The keyword, e.g. `x-unit`.
+    pub keyword: &'a str,
+    /// The value of the keyword.
+    pub value: &'a Value,
+}
+
+/// Collect the `x-` keywords of the subschemas of `cfg` that apply to each
+/// value in `instance`, in document order of the instance.
+pub fn extensions<'a>(cfg: &'a Config<'a>, instance: &'a Value) -> Vec<Annotation<'a>> {
+    let mut out = Vec::new();
+    collect(
+        cfg,
+        instance,
+        "",
+        String::new(),
+        cfg.get_schema(),
+        0,
+        &mut out,
+    );
+    out
+}
+
+fn collect<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a Value,
+    instance_path: &str,
+    schema_path: String,
+    schema: &'a Value,
+    ref_depth: usize,
+    out: &mut Vec<Annotation<'a>>,
+) {
+    let object = match schema {
+        Value::Object(object) => object,
+        _ => return,
+    };
+
+    if let Some(Value::String(ref_)) = object.get("$ref") {
+        if ref_depth < MAX_REF_DEPTH {
+            let root = cfg.get_schema();
+            if let Ok((_, resolved)) =
+                cfg.get_resolver()
+                    .resolve_fragment(ref_, &Context::new_from(root), root)
+            {
+                let path = format!("{}/$ref", schema_path);
+                collect(
+                    cfg,
+                    instance,
+                    instance_path,
+                    path,
+                    resolved,
+                    ref_depth + 1,
+                    out,
+                );
+            }
+        }
+        return;
+    }
+
+    for (keyword, value) in object {
+        if keyword.starts_with("x-") {
+            out.push(Annotation {
+                instance_path: instance_path.to_string(),
+                schema_path: format!("{}/{}", schema_path, util::escape_pointer_token(keyword)),
+                keyword,
+                value,
+            });
+        }
+    }
+
+    let mut in_place: Vec<(String, &'a Value)> = Vec::new();
+    if let Some(Value::Array(branches)) = object.get("allOf") {
+        for (index, branch) in branches.iter().enumerate() {
+            in_place.push((format!("allOf/{}", index), branch));
+        }
+    }
+    for keyword in &["anyOf", "oneOf"] {
+        if let Some(Value::Array(branches)) = object.get(*keyword) {
+            for (index, branch) in branches.iter().enumerate() {
+                if is_valid(cfg, instance, branch) {
+                    in_place.push((format!("{}/{}", keyword, index), branch));
+                }
+            }
+        }
+    }
+    if cfg.get_draft_number() >= 7 {
+        if let Some(if_) = object.get("if") {
+            let branch = if is_valid(cfg, instance, if_) {
+                in_place.push(("if".to_string(), if_));
+                "then"
+            } else {
+                "else"
+            };
+            if let Some(subschema) = object.get(branch) {
+                in_place.push((branch.to_string(), subschema));
+            }
+        }
+    }
+    if let (Some(Value::Object(dependencies)), Value::Object(instance_object)) =
+        (object.get("dependencies"), instance)
+    {
+        for (property, dependency) in dependencies {
+            if instance_object.contains_key(property) && !dependency.is_array() {
+                let escaped = util::escape_pointer_token(property);
+                in_place.push((format!("dependencies/{}", escaped), dependency));
+            }
+        }
+    }
+    for (path, subschema) in in_place {
+        let path = format!("{}/{}", schema_path, path);
+        collect(
+            cfg,
+            instance,
+            instance_path,
+            path,
+            subschema,
+            ref_depth,
+            out,
+        );
+    }
+
+    let members: Vec<(String, &'a Value)> = match instance {
+        Value::Object(object) => object.iter().map(|(k, v)| (k.clone(), v)).collect(),
+        Value::Array(array) => array
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i.to_string(), v))
+            .collect(),
+        _ => Vec::new(),
+    };
+    for (token, child) in members {
+        let child_path = format!("{}/{}", instance_path, util::escape_pointer_token(&token));
+        for (path, subschema) in children(instance, &token, schema) {
+            let path = format!("{}/{}", schema_path, path);
+            collect(cfg, child, &child_path, path, subschema, ref_depth, out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas;
+    use serde_json::json;
+
+    #[test]
+    fn test_failing_branches_are_dropped() {
+        let schema = json!({
+            "items": {
+                "anyOf": [
+                    {"type": "string", "x-kind": "name"},
+                    {"type": "integer", "x-kind": "id"}
+                ],
+                "not": {"x-kind": "never"}
+            },
+            "x-list": true
+        });
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let annotations: Vec<_> = extensions(&cfg, &json!(["a", 1]))
+            .into_iter()
+            .map(|x| (x.instance_path, x.schema_path, x.value.clone()))
+            .collect();
+        assert_eq!(
+            annotations,
+            vec![
+                ("".to_string(), "/x-list".to_string(), json!(true)),
+                (
+                    "/0".to_string(),
+                    "/items/anyOf/0/x-kind".to_string(),
+                    json!("name")
+                ),
+                (
+                    "/1".to_string(),
+                    "/items/anyOf/1/x-kind".to_string(),
+                    json!("id")
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,162 @@
+//! Reporting on the validation of many instances at once.
+//!
+//! Bulk-import endpoints validate a list of records and need to tell the
+//! client exactly which records failed and why. `Config::validate_batch`
+//! validates each instance of a slice and returns a `BatchReport` with the
+//! errors by index, counts of valid and invalid instances, and a JSON form
+//! of all of it to send back.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::{schemas, Config};
+//! let schema = json!({"required": ["id"]});
+//! let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+//!
+//! let report = cfg.validate_batch(&[json!({"id": 1}), json!({}), json!({"id": 3})]);
+//! assert_eq!(report.invalid_count(), 1);
+//! assert_eq!(report.is_valid(1), Some(false));
+//! assert_eq!(report.failures().map(|(index, _)| index).collect::<Vec<_>>(), [1]);
+//!
+//! let json = report.to_json();
+//! assert_eq!(json["valid"], 2);
+//! assert_eq!(json["results"][1]["errors"][0]["keyword"], "required");
+//! ```
+
+use serde_json::{json, Value};
+
+use crate::error::ValidationError;
+use crate::util;
+
+/// The result of validating each instance of a batch; see
+/// `Config::validate_batch`.
+#[derive(Debug, Clone)]
+pub struct BatchReport {
+    results: Vec<Vec<ValidationError>>,
+}
+
+fn pointer(path: &[&str]) -> String {
+    path.iter()
+        .map(|token| format!("/{}", util::escape_pointer_token(token)))
+        .collect()
+}
+
+impl BatchReport {
+    pub(crate) fn new(results: Vec<Vec<ValidationError>>) -> BatchReport {
+        BatchReport { results }
+    }
+
+    /// The number of instances in the batch.
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Returns `true` if the batch had no instances.
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// The number of valid instances.
+    pub fn valid_count(&self) -> usize {
+        self.results.iter().filter(|x| x.is_empty()).count()
+    }
+
+    /// The number of invalid instances.
+    pub fn invalid_count(&self) -> usize {
+        self.len() - self.valid_count()
+    }
+
+    /// Whether the instance at `index` is valid. Returns `None` if the batch
+    /// has no instance at that index.
+    pub fn is_valid(&self, index: usize) -> Option<bool> {
+        self.errors(index).map(|errors| errors.is_empty())
+    }
+
+    /// The validation errors of the instance at `index`. Returns `None` if
+    /// the batch has no instance at that index.
+    pub fn errors(&self, index: usize) -> Option<&[ValidationError]> {
+        self.results.get(index).map(Vec::as_slice)
+    }
+
+    /// Iterate over the index and errors of each invalid instance, in order.
+    pub fn failures(&self) -> impl Iterator<Item = (usize, &[ValidationError])> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter(|(_, errors)| !errors.is_empty())
+            .map(|(index, errors)| (index, errors.as_slice()))
+    }
+
+    /// Represent the report as JSON: the `total`, `valid` and `invalid`
+    /// counts, and in `results` the `index`, `valid` flag and `errors` of
+    /// each instance.
+    ///
+    /// Each error has the JSON pointer to the offending value in the
+    /// instance as `pointer`, the one to the failing keyword in the schema as
+    /// `schemaPointer`, the `keyword` if any, and the `message`.
+    pub fn to_json(&self) -> Value {
+        let results: Vec<Value> = self
+            .results
+            .iter()
+            .enumerate()
+            .map(|(index, errors)| {
+                let errors: Vec<Value> = errors
+                    .iter()
+                    .map(|err| {
+                        let mut error = json!({
+                            "pointer": pointer(&err.instance_path()),
+                            "schemaPointer": pointer(&err.schema_path()),
+                            "message": err.message(),
+                        });
+                        if let Some(keyword) = err.keyword() {
+                            error["keyword"] = json!(keyword);
+                        }
+                        error
+                    })
+                    .collect();
+                json!({"index": index, "valid": errors.is_empty(), "errors": errors})
+            })
+            .collect();
+        json!({
+            "total": self.len(),
+            "valid": self.valid_count(),
+            "invalid": self.invalid_count(),
+            "results": results,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::schemas;
+    use serde_json::json;
+
+    #[test]
+    fn test_batch_report() {
+        let schema = json!({"items": {"type": "integer"}});
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let report = cfg.validate_batch(&[json!([1, "a/b"]), json!([2])]);
+        assert_eq!(report.len(), 2);
+        assert_eq!(report.valid_count(), 1);
+        assert_eq!(report.errors(0).unwrap().len(), 1);
+        assert_eq!(report.is_valid(2), None);
+        assert_eq!(
+            report.to_json()["results"],
+            json!([
+                {
+                    "index": 0,
+                    "valid": false,
+                    "errors": [{
+                        "pointer": "/1",
+                        "schemaPointer": "/items/type",
+                        "keyword": "type",
+                        "message": report.errors(0).unwrap()[0].message(),
+                    }]
+                },
+                {"index": 1, "valid": true, "errors": []}
+            ])
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! Minimal command line parsing.
+
+/// Parsed command line arguments for a subcommand: positional arguments,
+/// options taking a value (`--name value` or `--name=value`) and boolean flags.
+pub struct Args {
+    positional: Vec<String>,
+    values: Vec<(String, String)>,
+    flags: Vec<String>,
+}
+
+impl Args {
+    /// Parse `args`, where `with_value` lists the options that take a value and
+    /// `flags` lists the boolean flags. Anything else starting with `-` is an
+    /// error; everything after `--` is positional.
+    pub fn parse(args: &[String], with_value: &[&str], flags: &[&str]) -> Result<Args, String> {
+        let mut result = Args {
+            positional: Vec::new(),
+            values: Vec::new(),
+            flags: Vec::new(),
+        };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if arg == "--" {
+                result.positional.extend(iter.cloned());
+                break;
+            } else if arg.starts_with('-') && arg.len() > 1 {
+                let (name, inline_value) = match arg.find('=') {
+                    Some(index) => (&arg[..index], Some(arg[index + 1..].to_string())),
+                    None => (arg.as_str(), None),
+                };
+                if with_value.contains(&name) {
+                    let value = match inline_value {
+                        Some(value) => value,
+                        None => iter
+                            .next()
+                            .cloned()
+                            .ok_or_else(|| format!("Option {} requires a value", name))?,
+                    };
+                    result.values.push((name.to_string(), value));
+                } else if flags.contains(&name) && inline_value.is_none() {
+                    result.flags.push(name.to_string());
+                } else {
+                    return Err(format!("Unknown option {}", arg));
+                }
+            } else {
+                result.positional.push(arg.clone());
+            }
+        }
+        Ok(result)
+    }
+
+    /// The value of the last occurrence of any of the given option names.
+    pub fn value(&self, names: &[&str]) -> Option<&str> {
+        self.values
+            .iter()
+            .rev()
+            .find(|(name, _)| names.contains(&name.as_str()))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Whether any of the given flags was given.
+    pub fn flag(&self, names: &[&str]) -> bool {
+        self.flags.iter().any(|name| names.contains(&name.as_str()))
+    }
+
+    /// The positional arguments.
+    pub fn positional(&self) -> &[String] {
+        &self.positional
+    }
+}
@@ -0,0 +1,62 @@
+//! The `bundle` subcommand.
+
+use std::fs;
+
+use serde_json::{json, Value};
+use url::Url;
+
+use jsonschema_valid::bundle::bundle;
+
+use crate::args::Args;
+use crate::log;
+use crate::{load_json, CommandResult};
+
+pub fn run(args: &[String]) -> CommandResult {
+    let args = Args::parse(args, &["-o", "--output"], &[])?;
+    let schema_path = match args.positional() {
+        [path] => path,
+        _ => return Err("bundle requires exactly one schema".to_string()),
+    };
+    let schema = load_json(schema_path)?;
+    let base = file_url(schema_path)?;
+    let retrieve = retriever();
+    let bundled = bundle(&schema, &base, &retrieve).map_err(|err| err.to_string())?;
+    let output = serde_json::to_string_pretty(&bundled).map_err(|err| err.to_string())?;
+    match args.value(&["-o", "--output"]) {
+        Some(path) => fs::write(path, output + "\n").map_err(|err| format!("{}: {}", path, err))?,
+        None => println!("{}", output),
+    }
+    Ok(0)
+}
+
+/// The URL of the file at `path`, for resolving the references in it.
+pub fn file_url(path: &str) -> Result<Url, String> {
+    fs::canonicalize(path)
+        .ok()
+        .and_then(|path| Url::from_file_path(path).ok())
+        .ok_or_else(|| format!("{}: can't determine the file URL", path))
+}
+
+/// A function that retrieves referenced documents: from files, or over http
+/// and https with the remote feature.
+pub fn retriever() -> impl Fn(&Url) -> Result<Value, String> {
+    #[cfg(feature = "remote")]
+    let http = jsonschema_valid::remote::HttpResolver::new();
+    move |url: &Url| -> Result<Value, String> {
+        log::debug(
+            "Retrieving referenced document",
+            &[("url", json!(url.as_str()))],
+        );
+        match url.scheme() {
+            "file" => {
+                let path = url
+                    .to_file_path()
+                    .map_err(|_| "not a local file".to_string())?;
+                load_json(&path.to_string_lossy())
+            }
+            #[cfg(feature = "remote")]
+            "http" | "https" => http.fetch(url).map_err(|err| err.message().to_string()),
+            other => Err(format!("{} references are not supported", other)),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! The `compile` subcommand.
+
+use std::fs;
+
+use jsonschema_valid::{cache, Config};
+
+use crate::args::Args;
+use crate::bundle::{file_url, retriever};
+use crate::{load_json, parse_draft, CommandResult};
+
+pub fn run(args: &[String]) -> CommandResult {
+    let args = Args::parse(args, &["--draft", "-o", "--output"], &[])?;
+    let schema_path = match args.positional() {
+        [path] => path,
+        _ => return Err("compile requires exactly one schema".to_string()),
+    };
+    let output = args
+        .value(&["-o", "--output"])
+        .ok_or("compile requires --output")?;
+    let schema = load_json(schema_path)?;
+    let draft = parse_draft(args.value(&["--draft"]))?;
+    let base = file_url(schema_path)?;
+    let mut cfg = Config::from_schema(&schema, draft)
+        .and_then(|cfg| cfg.with_base_url(&base))
+        .map_err(|err| err.to_string())?;
+
+    // Referenced documents are embedded in the compiled schema.
+    let retrieve = retriever();
+    loop {
+        let missing = cfg.get_resolver().missing_documents();
+        if missing.is_empty() {
+            break;
+        }
+        for url in missing {
+            let document = retrieve(&url).map_err(|err| format!("{}: {}", url, err))?;
+            cfg = cfg
+                .with_document(&url, document)
+                .map_err(|err| err.to_string())?;
+        }
+    }
+
+    let compiled = match cache::compile(&cfg) {
+        Ok(compiled) => compiled,
+        Err(errors) => {
+            for error in errors {
+                println!("{}: {}", schema_path, error);
+            }
+            return Ok(1);
+        }
+    };
+    fs::write(output, compiled.to_string()).map_err(|err| format!("{}: {}", output, err))?;
+    Ok(0)
+}
@@ -0,0 +1,28 @@
+//! The `conformance` subcommand.
+
+use std::fs;
+use std::path::Path;
+
+use jsonschema_valid::{conformance, schemas};
+
+use crate::args::Args;
+use crate::{parse_draft, CommandResult};
+
+pub fn run(args: &[String]) -> CommandResult {
+    let args = Args::parse(args, &["--draft", "-o", "--output"], &[])?;
+    let dir = match args.positional() {
+        [dir] => dir,
+        _ => return Err("conformance requires exactly one test suite directory".to_string()),
+    };
+    let draft = parse_draft(args.value(&["--draft"]))?.unwrap_or(&schemas::Draft7);
+    let report = conformance::run_test_suite(Path::new(dir), draft)
+        .map_err(|err| format!("{}: {}", dir, err))?;
+
+    let output = serde_json::to_string_pretty(&report.to_json()).map_err(|err| err.to_string())?;
+    match args.value(&["-o", "--output"]) {
+        Some(path) => fs::write(path, output + "\n").map_err(|err| format!("{}: {}", path, err))?,
+        None => println!("{}", output),
+    }
+    eprintln!("{} passed, {} failed", report.passed(), report.failed());
+    Ok(0)
+}
@@ -0,0 +1,52 @@
+//! The `convert` subcommand.
+
+use std::fs;
+
+use jsonschema_valid::convert::{convert, Target};
+use jsonschema_valid::schemas;
+
+use crate::args::Args;
+use crate::{load_json, parse_draft, CommandResult};
+
+pub fn run(args: &[String]) -> CommandResult {
+    let args = Args::parse(args, &["--to", "--from", "-o", "--output"], &[])?;
+    let schema_path = match args.positional() {
+        [path] => path,
+        _ => return Err("convert requires exactly one schema".to_string()),
+    };
+    let to: Target = args
+        .value(&["--to"])
+        .ok_or_else(|| "convert requires --to".to_string())?
+        .parse()?;
+    let schema = load_json(schema_path)?;
+    let from = parse_draft(args.value(&["--from"]))?
+        .or_else(|| schemas::draft_from_schema(&schema))
+        .unwrap_or(&schemas::Draft7);
+
+    let conversion = convert(&schema, from, to).ok_or_else(|| {
+        format!(
+            "Can't convert from draft {} to the older draft {}",
+            from.get_draft_number(),
+            to
+        )
+    })?;
+    let output = serde_json::to_string_pretty(&conversion.schema).map_err(|err| err.to_string())?;
+    match args.value(&["-o", "--output"]) {
+        Some(path) => fs::write(path, output + "\n").map_err(|err| format!("{}: {}", path, err))?,
+        None => println!("{}", output),
+    }
+
+    for item in &conversion.unconverted {
+        let pointer = if item.pointer.is_empty() {
+            "/"
+        } else {
+            &item.pointer
+        };
+        eprintln!("{}: {}: {}", schema_path, pointer, item.message);
+    }
+    Ok(if conversion.unconverted.is_empty() {
+        0
+    } else {
+        1
+    })
+}
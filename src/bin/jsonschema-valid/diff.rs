@@ -0,0 +1,49 @@
+//! The `diff` subcommand.
+
+use serde_json::{json, Value};
+
+use jsonschema_valid::diff::diff;
+
+use crate::args::Args;
+use crate::{load_json, CommandResult};
+
+pub fn run(args: &[String]) -> CommandResult {
+    let args = Args::parse(args, &["--format"], &[])?;
+    let (old_path, new_path) = match args.positional() {
+        [old, new] => (old, new),
+        _ => return Err("diff requires an old and a new schema".to_string()),
+    };
+    let changes = diff(&load_json(old_path)?, &load_json(new_path)?);
+    let breaking = changes.iter().filter(|x| x.breaking).count();
+
+    match args.value(&["--format"]).unwrap_or("human") {
+        "human" => {
+            for change in &changes {
+                let pointer = if change.pointer.is_empty() {
+                    "/"
+                } else {
+                    &change.pointer
+                };
+                let label = if change.breaking {
+                    "breaking"
+                } else {
+                    "compatible"
+                };
+                println!("{}: {}: {}", label, pointer, change.message);
+            }
+            println!("{} change(s), {} breaking", changes.len(), breaking);
+        }
+        "json" => {
+            let report = json!({
+                "breaking": breaking > 0,
+                "changes": changes.iter().map(|x| x.to_json()).collect::<Vec<Value>>(),
+            });
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).map_err(|err| err.to_string())?
+            );
+        }
+        other => return Err(format!("Unknown format {}", other)),
+    }
+    Ok(if breaking > 0 { 1 } else { 0 })
+}
@@ -0,0 +1,50 @@
+//! The `explain` subcommand.
+
+use jsonschema_valid::explain::explain;
+use jsonschema_valid::Config;
+
+use crate::args::Args;
+use crate::{load_json, parse_draft, CommandResult};
+
+pub fn run(args: &[String]) -> CommandResult {
+    let args = Args::parse(args, &["--schema", "-s", "--draft", "--pointer", "-p"], &[])?;
+    let schema_path = args
+        .value(&["--schema", "-s"])
+        .ok_or_else(|| "explain requires --schema".to_string())?;
+    let path = match args.positional() {
+        [path] => path,
+        _ => return Err("explain requires a single instance".to_string()),
+    };
+    let pointer = args.value(&["--pointer", "-p"]).unwrap_or("");
+
+    let schema = load_json(schema_path)?;
+    let draft = parse_draft(args.value(&["--draft"]))?;
+    let cfg = Config::from_schema(&schema, draft).map_err(|err| err.to_string())?;
+    let instance = load_json(path)?;
+    let explanation = explain(&cfg, &instance, pointer)
+        .ok_or_else(|| format!("{}: no value at {}", path, pointer))?;
+
+    println!(
+        "Value at {}: {}",
+        if pointer.is_empty() { "/" } else { pointer },
+        explanation.instance
+    );
+    println!("Applicable subschemas:");
+    for subschema in &explanation.subschemas {
+        println!(
+            "  {:<8} #{}: {}",
+            if subschema.valid { "valid" } else { "invalid" },
+            subschema.schema_path,
+            subschema.schema
+        );
+    }
+    if explanation.errors.is_empty() {
+        println!("No errors.");
+        return Ok(0);
+    }
+    println!("Errors:");
+    for error in &explanation.errors {
+        println!("  {}", error);
+    }
+    Ok(1)
+}
@@ -0,0 +1,44 @@
+//! The `fmt` subcommand.
+
+use std::fs;
+
+use jsonschema_valid::{normalize, schemas};
+
+use crate::args::Args;
+use crate::{load_json, parse_draft, CommandResult};
+
+pub fn run(args: &[String]) -> CommandResult {
+    let args = Args::parse(args, &["--draft"], &["--check", "--write", "-w"])?;
+    if args.positional().is_empty() {
+        return Err("fmt requires at least one schema".to_string());
+    }
+    let check = args.flag(&["--check"]);
+    let write = args.flag(&["--write", "-w"]);
+    if check && write {
+        return Err("--check and --write can't be combined".to_string());
+    }
+    let draft = parse_draft(args.value(&["--draft"]))?;
+
+    let mut code = 0;
+    for path in args.positional() {
+        let schema = load_json(path)?;
+        let draft = draft
+            .or_else(|| schemas::draft_from_schema(&schema))
+            .unwrap_or(&schemas::Draft7);
+        let output = serde_json::to_string_pretty(&normalize(&schema, draft))
+            .map_err(|err| err.to_string())?
+            + "\n";
+        if check {
+            let contents = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+            if contents != output {
+                println!("{}: not normalized", path);
+                code = 1;
+            }
+        } else if write {
+            fs::write(path, output).map_err(|err| format!("{}: {}", path, err))?;
+        } else {
+            print!("{}", output);
+        }
+    }
+    Ok(code)
+}
@@ -0,0 +1,51 @@
+//! The `generate` subcommand.
+
+use jsonschema_valid::generate::Generator;
+use jsonschema_valid::Config;
+
+use crate::args::Args;
+use crate::{load_json, parse_draft, CommandResult};
+
+pub fn run(args: &[String]) -> CommandResult {
+    let args = Args::parse(
+        args,
+        &["--schema", "-s", "--draft", "-n", "--seed"],
+        &["--invalid"],
+    )?;
+    let schema_path = args
+        .value(&["--schema", "-s"])
+        .ok_or("generate requires --schema")?;
+    let count: usize = match args.value(&["-n"]) {
+        Some(n) => n.parse().map_err(|_| format!("Invalid count {}", n))?,
+        None => 1,
+    };
+    let seed: u64 = match args.value(&["--seed"]) {
+        Some(seed) => seed.parse().map_err(|_| format!("Invalid seed {}", seed))?,
+        None => 0,
+    };
+    let schema = load_json(schema_path)?;
+    let draft = parse_draft(args.value(&["--draft"]))?;
+    let cfg = Config::from_schema(&schema, draft).map_err(|err| err.to_string())?;
+
+    let invalid = args.flag(&["--invalid"]);
+    let mut generator = Generator::new(&cfg).with_seed(seed);
+    for _ in 0..count {
+        let instance = if invalid {
+            generator.invalid()
+        } else {
+            generator.valid()
+        };
+        match instance {
+            Some(instance) => println!("{}", instance),
+            None => {
+                eprintln!(
+                    "{}: could not generate {} instance",
+                    schema_path,
+                    if invalid { "an invalid" } else { "a valid" }
+                );
+                return Ok(1);
+            }
+        }
+    }
+    Ok(0)
+}
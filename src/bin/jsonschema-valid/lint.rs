@@ -0,0 +1,71 @@
+//! The `lint` subcommand.
+
+use jsonschema_valid::lint::{Linter, Severity, RULES};
+use jsonschema_valid::schemas;
+
+use crate::args::Args;
+use crate::{load_json, parse_draft, CommandResult};
+
+pub fn run(args: &[String]) -> CommandResult {
+    let args = Args::parse(
+        args,
+        &["--draft", "--rules", "--disable", "--min-severity"],
+        &["--list-rules"],
+    )?;
+    if args.flag(&["--list-rules"]) {
+        for (rule, severity, description) in RULES {
+            println!("{} ({}): {}", rule, severity, description);
+        }
+        return Ok(0);
+    }
+    if args.positional().is_empty() {
+        return Err("lint requires at least one schema".to_string());
+    }
+
+    let mut linter = Linter::new();
+    if let Some(rules) = args.value(&["--rules"]) {
+        linter = linter.with_rules(&split(rules))?;
+    }
+    if let Some(rules) = args.value(&["--disable"]) {
+        for rule in split(rules) {
+            linter = linter.without_rule(rule)?;
+        }
+    }
+    let min_severity: Severity = args.value(&["--min-severity"]).unwrap_or("info").parse()?;
+    let draft = parse_draft(args.value(&["--draft"]))?;
+
+    let mut code = 0;
+    for path in args.positional() {
+        let schema = load_json(path)?;
+        let draft = draft
+            .or_else(|| schemas::draft_from_schema(&schema))
+            .unwrap_or(&schemas::Draft7);
+        for lint in linter.lint(&schema, draft) {
+            if lint.severity < min_severity {
+                continue;
+            }
+            if lint.severity == Severity::Error {
+                code = 1;
+            }
+            let pointer = if lint.pointer.is_empty() {
+                "/"
+            } else {
+                &lint.pointer
+            };
+            println!(
+                "{}: {}: {} [{}]: {}",
+                path, pointer, lint.severity, lint.rule, lint.message
+            );
+        }
+    }
+    Ok(code)
+}
+
+/// Split a comma-separated list of rule names.
+fn split(rules: &str) -> Vec<&str> {
+    rules
+        .split(',')
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .collect()
+}
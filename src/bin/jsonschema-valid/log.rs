@@ -0,0 +1,110 @@
+//! Logging of progress, resolver activity and summaries to stderr, separate
+//! from the reports printed to stdout.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use serde_json::{Map, Value};
+
+/// The severity of a log message; only messages at or above the level set
+/// with the global options are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error = 0,
+    Warn = 1,
+    Info = 2,
+    Debug = 3,
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+        }
+    }
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Warn as u8);
+static JSON: AtomicU8 = AtomicU8::new(0);
+
+/// Parse the global logging options before the command:
+/// `--log-format text|json`, `-v`/`--verbose` (repeatable, for info and then
+/// debug messages) and `-q`/`--quiet` (errors only). Returns the number of
+/// arguments consumed.
+pub fn parse_options(args: &[String]) -> Result<usize, String> {
+    let mut level = Level::Warn as u8;
+    let mut index = 0;
+    while let Some(arg) = args.get(index) {
+        let format = match arg.as_str() {
+            "-v" | "--verbose" => {
+                level = (level + 1).min(Level::Debug as u8);
+                None
+            }
+            "-vv" => {
+                level = Level::Debug as u8;
+                None
+            }
+            "-q" | "--quiet" => {
+                level = Level::Error as u8;
+                None
+            }
+            "--log-format" => {
+                index += 1;
+                Some(args.get(index).map(String::as_str).unwrap_or_default())
+            }
+            other => match other.strip_prefix("--log-format=") {
+                Some(format) => Some(format),
+                None => break,
+            },
+        };
+        match format {
+            Some("text") => JSON.store(0, Ordering::Relaxed),
+            Some("json") => JSON.store(1, Ordering::Relaxed),
+            Some(other) => return Err(format!("Unknown log format {}", other)),
+            None => {}
+        }
+        index += 1;
+    }
+    LEVEL.store(level, Ordering::Relaxed);
+    Ok(index)
+}
+
+/// Write `message` with the given fields at `level`: as a line of text, or
+/// as a JSON object with a `timestamp`, `level` and `message` besides the
+/// fields.
+pub fn log(level: Level, message: &str, fields: &[(&str, Value)]) {
+    if level as u8 > LEVEL.load(Ordering::Relaxed) {
+        return;
+    }
+    if JSON.load(Ordering::Relaxed) == 1 {
+        let mut object = Map::new();
+        object.insert(
+            "timestamp".to_string(),
+            Value::String(chrono::Utc::now().to_rfc3339()),
+        );
+        object.insert("level".to_string(), Value::String(level.name().to_string()));
+        object.insert("message".to_string(), Value::String(message.to_string()));
+        for (name, value) in fields {
+            object.insert(name.to_string(), value.clone());
+        }
+        eprintln!("{}", Value::Object(object));
+    } else {
+        let fields: String = fields
+            .iter()
+            .map(|(name, value)| format!(" {}={}", name, value))
+            .collect();
+        eprintln!("{}: {}{}", level.name(), message, fields);
+    }
+}
+
+/// Write `message` at the info level.
+pub fn info(message: &str, fields: &[(&str, Value)]) {
+    log(Level::Info, message, fields)
+}
+
+/// Write `message` at the debug level.
+pub fn debug(message: &str, fields: &[(&str, Value)]) {
+    log(Level::Debug, message, fields)
+}
@@ -0,0 +1,160 @@
+//! Command line interface to jsonschema-valid.
+
+use std::env;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::process;
+
+use serde_json::Value;
+
+use jsonschema_valid::{schemas, stream};
+
+mod args;
+mod bundle;
+mod compile;
+mod conformance;
+mod convert;
+mod diff;
+mod explain;
+mod fmt;
+mod generate;
+mod lint;
+mod log;
+mod repl;
+mod validate;
+
+const USAGE: &str = "\
+Usage: jsonschema-valid [--log-format text|json] [-v | -vv | -q] <command> [options]
+
+Progress, resolver activity and summaries are logged to stderr, as text or
+as one JSON object per line with --log-format json. Only warnings and errors
+are logged by default; -v adds summaries, -vv details about each file, and
+-q leaves only errors.
+
+Drafts are given to --draft and --from as 3, 4, 6, 7, 2019-09 or 2020-12,
+or as openapi-3.0 for the schema dialect of OpenAPI 3.0.
+
+Commands:
+  validate (--schema <schema> [--draft <n>] | --cache <compiled>)
+           [--format text|lsp|snippet] [--stream] [--seq] [--jobs <n>] <instance>...
+      Validate JSON instances against a schema. With --format lsp, print
+      a JSON object mapping each file to Language Server Protocol
+      diagnostics; with --format snippet, print each error under the
+      offending line of the file. With --stream, instances are
+      memory-mapped and validated without loading them, for files larger
+      than memory; enum, const and uniqueItems can then only be checked
+      on scalars.
+      With --seq, instances are JSON text sequences (RFC 7464), streamed
+      the same way, and each document is reported as <file>[<index>].
+      Instances compressed with gzip or zstd are decompressed, if
+      support for the format was compiled in. With --jobs, instances are
+      validated on <n> threads, and reported in the same order.
+  compile <schema> [--draft <n>] -o <output>
+      Check a schema and its references, and write it in compiled form
+      for use with `validate --cache`. Referenced documents are read the
+      same way as by bundle, and embedded in the compiled schema.
+  generate --schema <schema> [--draft <n>] [-n <count>] [--seed <n>] [--invalid]
+      Print sample instances that are valid against a schema, one per
+      line, or invalid counterexamples with --invalid.
+  bundle <schema> [-o <output>]
+      Embed all documents referenced by a schema under $defs, writing a
+      single self-contained schema. Referenced documents are read from
+      files, or fetched over http and https if support for it was
+      compiled in (the remote feature).
+  convert <schema> --to 6|7|2019-09|2020-12 [--from <n>] [-o <output>]
+      Convert a schema to a newer draft. Constructs that need to be
+      reviewed by hand are listed on stderr, and make it exit with 1.
+  diff <old-schema> <new-schema> [--format human|json]
+      List the changes between two versions of a schema. Exits with 1 if
+      any change is breaking, i.e. may reject previously valid instances.
+  explain --schema <schema> [--draft <n>] <instance> [--pointer <pointer>]
+      List the subschemas that apply to the value at a JSON pointer in an
+      instance, whether the value is valid against each, and the errors
+      reported at or below that location.
+  fmt <schema>... [--draft <n>] [--check | --write]
+      Print schemas in normalized form, with sorted keys and unified
+      boolean schemas. --write rewrites the files in place; --check lists
+      the files that aren't normalized and exits with 1 if there are any.
+  lint <schema>... [--draft <n>] [--rules <a,b>] [--disable <a,b>]
+       [--min-severity info|warning|error] [--list-rules]
+      Check schemas for likely mistakes. Exits with 1 if any error-level
+      problem is found.
+  repl --schema <schema> [--draft <n>]
+      Interactively validate JSON values against a schema, showing which
+      anyOf/oneOf branches they match.
+  conformance <suite-dir> [--draft <n>] [-o <output>]
+      Run a directory of the JSON Schema Test Suite and write a
+      machine-readable conformance report.
+";
+
+/// The result of running a subcommand: an exit code, or an error message
+/// (which exits with code 2).
+pub type CommandResult = Result<i32, String>;
+
+/// Read a text file, decompressing it if it's compressed with gzip or zstd.
+pub fn read_text(path: &str) -> Result<String, String> {
+    let file = File::open(path).map_err(|err| format!("{}: {}", path, err))?;
+    let mut contents = String::new();
+    stream::Decompress::new(BufReader::new(file))
+        .read_to_string(&mut contents)
+        .map_err(|err| format!("{}: {}", path, err))?;
+    Ok(contents)
+}
+
+/// Read and parse a JSON document from a file, which may be compressed.
+pub fn load_json(path: &str) -> Result<Value, String> {
+    let contents = read_text(path)?;
+    serde_json::from_str(&contents).map_err(|err| format!("{}: {}", path, err))
+}
+
+/// Look up a draft from its number as given on the command line.
+pub fn parse_draft(draft: Option<&str>) -> Result<Option<&'static dyn schemas::Draft>, String> {
+    match draft {
+        None => Ok(None),
+        Some("3") => Ok(Some(&schemas::Draft3)),
+        Some("4") => Ok(Some(&schemas::Draft4)),
+        Some("6") => Ok(Some(&schemas::Draft6)),
+        Some("7") => Ok(Some(&schemas::Draft7)),
+        Some("2019-09") => Ok(Some(&schemas::Draft201909)),
+        Some("2020-12") => Ok(Some(&schemas::Draft202012)),
+        Some("openapi-3.0") => Ok(Some(&schemas::OpenApi30)),
+        Some(other) => Err(format!("Unknown draft {}", other)),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args = match log::parse_options(&args) {
+        Ok(consumed) => &args[consumed..],
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(2);
+        }
+    };
+    let result = match args.first().map(String::as_str) {
+        Some("validate") => validate::run(&args[1..]),
+        Some("compile") => compile::run(&args[1..]),
+        Some("generate") => generate::run(&args[1..]),
+        Some("bundle") => bundle::run(&args[1..]),
+        Some("convert") => convert::run(&args[1..]),
+        Some("diff") => diff::run(&args[1..]),
+        Some("explain") => explain::run(&args[1..]),
+        Some("fmt") => fmt::run(&args[1..]),
+        Some("lint") => lint::run(&args[1..]),
+        Some("repl") => repl::run(&args[1..]),
+        Some("conformance") => conformance::run(&args[1..]),
+        Some("-h") | Some("--help") | Some("help") => {
+            print!("{}", USAGE);
+            Ok(0)
+        }
+        Some(other) => Err(format!("Unknown command {}\n\n{}", other, USAGE)),
+        None => Err(USAGE.to_string()),
+    };
+    match result {
+        Ok(code) => process::exit(code),
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(2);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! The `repl` subcommand.
+
+use std::io::{self, BufRead, Write};
+
+use serde_json::Value;
+
+use jsonschema_valid::explain::explain;
+use jsonschema_valid::Config;
+
+use crate::args::Args;
+use crate::{load_json, parse_draft, CommandResult};
+
+const HELP: &str = "\
+Enter a JSON value to validate it; it may span several lines.
+  :at <pointer>   Show the branches taken at a JSON pointer in the value
+                  (default: the root)
+  :help           Show this help
+  :quit           Exit
+";
+
+pub fn run(args: &[String]) -> CommandResult {
+    let args = Args::parse(args, &["--schema", "-s", "--draft"], &[])?;
+    let schema_path = args
+        .value(&["--schema", "-s"])
+        .ok_or_else(|| "repl requires --schema".to_string())?;
+    let schema = load_json(schema_path)?;
+    let draft = parse_draft(args.value(&["--draft"]))?;
+    let cfg = Config::from_schema(&schema, draft).map_err(|err| err.to_string())?;
+    if let Err(errors) = cfg.validate_schema() {
+        for error in errors {
+            println!("{}: {}", schema_path, error);
+        }
+        return Ok(1);
+    }
+
+    print!("{}", HELP);
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut pointer = String::new();
+    let mut buffer = String::new();
+    loop {
+        prompt(if buffer.is_empty() { "> " } else { ". " })?;
+        let line = match lines.next() {
+            Some(line) => line.map_err(|err| err.to_string())?,
+            None => return Ok(0),
+        };
+
+        if buffer.is_empty() {
+            let command = line.trim();
+            if command == ":quit" || command == ":q" {
+                return Ok(0);
+            } else if command == ":help" {
+                print!("{}", HELP);
+                continue;
+            } else if command == ":at" || command.starts_with(":at ") {
+                pointer = command[3..].trim().to_string();
+                continue;
+            } else if command.starts_with(':') {
+                println!("Unknown command {}", command);
+                continue;
+            } else if command.is_empty() {
+                continue;
+            }
+        }
+
+        buffer.push_str(&line);
+        buffer.push('\n');
+        let instance: Value = match serde_json::from_str(&buffer) {
+            Ok(instance) => instance,
+            // Wait for the rest of the value.
+            Err(err) if err.is_eof() => continue,
+            Err(err) => {
+                println!("Invalid JSON: {}", err);
+                buffer.clear();
+                continue;
+            }
+        };
+        buffer.clear();
+        report(&cfg, &instance, &pointer);
+    }
+}
+
+fn prompt(text: &str) -> Result<(), String> {
+    let mut stdout = io::stdout();
+    write!(stdout, "{}", text)
+        .and_then(|_| stdout.flush())
+        .map_err(|err| err.to_string())
+}
+
+/// Print whether the instance is valid, and which `anyOf`/`oneOf` branches
+/// match at `pointer`.
+fn report(cfg: &Config, instance: &Value, pointer: &str) {
+    match cfg.validate(instance) {
+        Ok(()) => println!("valid"),
+        Err(errors) => {
+            println!("invalid");
+            for error in errors {
+                println!("  {}", error);
+            }
+        }
+    }
+
+    let explanation = match explain(cfg, instance, pointer) {
+        Some(explanation) => explanation,
+        None => {
+            println!("No value at {}", pointer);
+            return;
+        }
+    };
+    for subschema in &explanation.subschemas {
+        let mut tokens = subschema.schema_path.rsplit('/');
+        let is_branch = match (tokens.next(), tokens.next()) {
+            (Some(index), Some("anyOf")) | (Some(index), Some("oneOf")) => {
+                index.parse::<usize>().is_ok()
+            }
+            _ => false,
+        };
+        if is_branch {
+            println!(
+                "  {} #{}",
+                if subschema.valid {
+                    "matched"
+                } else {
+                    "not matched"
+                },
+                subschema.schema_path
+            );
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! The `validate` subcommand.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Instant;
+
+use serde_json::{json, Map, Value};
+
+use jsonschema_valid::{cache, lsp, snippet, stream, Config, ValidationError};
+
+use crate::args::Args;
+use crate::log;
+use crate::{load_json, parse_draft, read_text, CommandResult};
+
+/// How validation results are printed.
+enum Format {
+    /// One line per error.
+    Text,
+    /// A JSON object mapping each file to its LSP diagnostics.
+    Lsp(Map<String, Value>),
+    /// Each error as an annotated snippet of the file.
+    Snippet,
+}
+
+impl Format {
+    fn report(&mut self, path: &str, errors: Vec<ValidationError>) -> Result<(), String> {
+        match self {
+            Format::Text if errors.is_empty() => println!("{}: ok", path),
+            Format::Text => {
+                for error in errors {
+                    println!("{}: {}", path, error);
+                }
+            }
+            Format::Snippet if errors.is_empty() => println!("{}: ok", path),
+            Format::Snippet => {
+                println!("{}", snippet::render_all(path, &read_text(path)?, &errors))
+            }
+            Format::Lsp(files) => {
+                let text = read_text(path)?;
+                let diagnostics = errors
+                    .iter()
+                    .map(|err| lsp::to_diagnostic(&text, err).to_json())
+                    .collect();
+                files.insert(path.to_string(), Value::Array(diagnostics));
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), String> {
+        if let Format::Lsp(files) = self {
+            let output = serde_json::to_string_pretty(&Value::Object(files))
+                .map_err(|err| err.to_string())?;
+            println!("{}", output);
+        }
+        Ok(())
+    }
+}
+
+pub fn run(args: &[String]) -> CommandResult {
+    let args = Args::parse(
+        args,
+        &[
+            "--schema", "-s", "--cache", "--draft", "--format", "--jobs", "-j",
+        ],
+        &["--stream", "--seq"],
+    )?;
+    let mut format = match args.value(&["--format"]).unwrap_or("text") {
+        "text" => Format::Text,
+        "lsp" => Format::Lsp(Map::new()),
+        "snippet" => Format::Snippet,
+        other => return Err(format!("Unknown format {}", other)),
+    };
+    let schema;
+    let cfg = match (args.value(&["--schema", "-s"]), args.value(&["--cache"])) {
+        (Some(schema_path), None) => {
+            schema = load_json(schema_path)?;
+            let draft = parse_draft(args.value(&["--draft"]))?;
+            let cfg = Config::from_schema(&schema, draft).map_err(|err| err.to_string())?;
+            if let Err(errors) = cfg.validate_schema() {
+                format.report(schema_path, errors.collect())?;
+                format.finish()?;
+                return Ok(1);
+            }
+            cfg
+        }
+        // A compiled schema has already been checked.
+        (None, Some(cache_path)) => cache::load_compiled(load_json(cache_path)?)
+            .map_err(|err| format!("{}: {}", cache_path, err.message()))?,
+        _ => return Err("validate requires either --schema or --cache".to_string()),
+    };
+    // Snippets show the failing constraint.
+    let cfg = match format {
+        Format::Snippet => cfg.with_error_values(),
+        _ => cfg,
+    };
+    log::debug(
+        "Loaded schema",
+        &[
+            ("schema", json!(args.value(&["--schema", "-s", "--cache"]))),
+            ("draft", json!(cfg.get_draft_number())),
+        ],
+    );
+
+    let seq = args.flag(&["--seq"]);
+    if seq {
+        match format {
+            Format::Lsp(_) => return Err("--seq can't be used with --format lsp".to_string()),
+            Format::Snippet => return Err("--seq can't be used with --format snippet".to_string()),
+            Format::Text => {}
+        }
+    }
+
+    let jobs = match args.value(&["--jobs", "-j"]) {
+        Some(jobs) => jobs
+            .parse::<usize>()
+            .ok()
+            .filter(|x| *x > 0)
+            .ok_or_else(|| format!("Invalid number of jobs {}", jobs))?,
+        None => 1,
+    };
+    let streamed = args.flag(&["--stream"]);
+    let paths = args.positional();
+    log::info(
+        "Validating instances",
+        &[("files", json!(paths.len())), ("jobs", json!(jobs))],
+    );
+    let start = Instant::now();
+    // With several jobs, all files are validated before reporting on them in
+    // order; otherwise each is reported as soon as it's validated.
+    let mut results = if jobs > 1 {
+        check_all(&cfg, paths, jobs, streamed, seq)
+    } else {
+        Vec::new()
+    }
+    .into_iter();
+
+    let (mut documents_count, mut invalid) = (0, 0);
+    let mut code = 0;
+    for path in paths {
+        let documents = match results.next() {
+            Some(documents) => documents,
+            None => check(&cfg, path, streamed, seq),
+        }?;
+        for (label, outcome) in documents {
+            documents_count += 1;
+            match outcome {
+                Ok(errors) => {
+                    if !errors.is_empty() {
+                        code = 1;
+                        invalid += 1;
+                    }
+                    format.report(&label, errors)?;
+                }
+                Err(err) => {
+                    code = 1;
+                    invalid += 1;
+                    println!("{}: {}", label, err);
+                }
+            }
+        }
+    }
+    format.finish()?;
+    log::info(
+        "Validated instances",
+        &[
+            ("files", json!(paths.len())),
+            ("documents", json!(documents_count)),
+            ("invalid", json!(invalid)),
+            ("elapsed_ms", json!(start.elapsed().as_millis() as u64)),
+        ],
+    );
+    Ok(code)
+}
+
+/// The errors of a document, or why it couldn't be validated.
+type Outcome = Result<Vec<ValidationError>, String>;
+
+/// Validate the instance file at `path`, returning the label and outcome of
+/// each document in it.
+fn check(
+    cfg: &Config,
+    path: &str,
+    streamed: bool,
+    seq: bool,
+) -> Result<Vec<(String, Outcome)>, String> {
+    if seq {
+        let documents =
+            stream::validate_seq_file(cfg, path).map_err(|err| format!("{}: {}", path, err))?;
+        let documents: Vec<_> = documents
+            .map(|(index, result)| {
+                let label = format!("{}[{}]", path, index);
+                (label, result.map_err(|err| err.to_string()))
+            })
+            .collect();
+        log::debug(
+            "Validated file",
+            &[("file", json!(path)), ("documents", json!(documents.len()))],
+        );
+        return Ok(documents);
+    }
+    let errors = if streamed {
+        stream::validate_file(cfg, path).map_err(|err| format!("{}: {}", path, err))?
+    } else {
+        let instance = load_json(path)?;
+        let errors = match cfg.validate(&instance) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors.collect(),
+        };
+        errors
+    };
+    log::debug(
+        "Validated file",
+        &[("file", json!(path)), ("errors", json!(errors.len()))],
+    );
+    Ok(vec![(path.to_string(), Ok(errors))])
+}
+
+/// Run `check` on each of `paths` on `jobs` threads, returning the results
+/// in the order of `paths`.
+fn check_all(
+    cfg: &Config,
+    paths: &[String],
+    jobs: usize,
+    streamed: bool,
+    seq: bool,
+) -> Vec<Result<Vec<(String, Outcome)>, String>> {
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<_> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs.min(paths.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        match paths.get(index) {
+                            Some(path) => done.push((index, check(cfg, path, streamed, seq))),
+                            None => return done,
+                        }
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect()
+    });
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
@@ -0,0 +1,331 @@
+//! Building schemas in code.
+//!
+//! A `SchemaBuilder` assembles a schema from typed method calls rather than
+//! from JSON text, e.g. when the rules are assembled at runtime from
+//! settings held in a database. It produces a schema `Value`, or compiles it
+//! directly to a `Config`. The functions `object`, `string`, etc. start a
+//! builder for a schema of that type.
+//!
+//! The keywords are those of Draft 7, which `compile` uses unless told
+//! otherwise. Keywords without a method can be set with `keyword`.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! use jsonschema_valid::builder::{integer, string, SchemaBuilder};
+//!
+//! let schema = SchemaBuilder::object()
+//!     .property("name", string().min_length(1))
+//!     .property("age", integer().minimum(0))
+//!     .required(["name"]);
+//! assert_eq!(
+//!     schema.clone().build(),
+//!     json!({
+//!         "type": "object",
+//!         "properties": {
+//!             "name": {"type": "string", "minLength": 1},
+//!             "age": {"type": "integer", "minimum": 0}
+//!         },
+//!         "required": ["name"]
+//!     })
+//! );
+//!
+//! let cfg = schema.compile(None).unwrap();
+//! assert!(cfg.validate(&json!({"name": "x", "age": 3})).is_ok());
+//! assert!(cfg.validate(&json!({"name": ""})).is_err());
+//! ```
+
+use serde_json::{json, Map, Value};
+
+use crate::config::Config;
+use crate::error::ValidationError;
+use crate::schemas;
+
+/// A schema under construction.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SchemaBuilder {
+    schema: Map<String, Value>,
+}
+
+/// Start a schema for objects; see `SchemaBuilder::object`.
+pub fn object() -> SchemaBuilder {
+    SchemaBuilder::object()
+}
+
+/// Start a schema for arrays; see `SchemaBuilder::array`.
+pub fn array() -> SchemaBuilder {
+    SchemaBuilder::array()
+}
+
+/// Start a schema for strings; see `SchemaBuilder::string`.
+pub fn string() -> SchemaBuilder {
+    SchemaBuilder::string()
+}
+
+/// Start a schema for integers; see `SchemaBuilder::integer`.
+pub fn integer() -> SchemaBuilder {
+    SchemaBuilder::integer()
+}
+
+/// Start a schema for numbers; see `SchemaBuilder::number`.
+pub fn number() -> SchemaBuilder {
+    SchemaBuilder::number()
+}
+
+/// Start a schema for booleans; see `SchemaBuilder::boolean`.
+pub fn boolean() -> SchemaBuilder {
+    SchemaBuilder::boolean()
+}
+
+impl SchemaBuilder {
+    /// Start a schema without any constraints, which any value is valid
+    /// against.
+    pub fn new() -> SchemaBuilder {
+        SchemaBuilder::default()
+    }
+
+    fn of_type(typename: &str) -> SchemaBuilder {
+        SchemaBuilder::new().keyword("type", json!(typename))
+    }
+
+    /// Start a schema for objects.
+    pub fn object() -> SchemaBuilder {
+        SchemaBuilder::of_type("object")
+    }
+
+    /// Start a schema for arrays.
+    pub fn array() -> SchemaBuilder {
+        SchemaBuilder::of_type("array")
+    }
+
+    /// Start a schema for strings.
+    pub fn string() -> SchemaBuilder {
+        SchemaBuilder::of_type("string")
+    }
+
+    /// Start a schema for integers.
+    pub fn integer() -> SchemaBuilder {
+        SchemaBuilder::of_type("integer")
+    }
+
+    /// Start a schema for numbers.
+    pub fn number() -> SchemaBuilder {
+        SchemaBuilder::of_type("number")
+    }
+
+    /// Start a schema for booleans.
+    pub fn boolean() -> SchemaBuilder {
+        SchemaBuilder::of_type("boolean")
+    }
+
+    /// Set the keyword `name` to `value`, replacing any previous value.
+    pub fn keyword(mut self, name: &str, value: Value) -> Self {
+        self.schema.insert(name.to_string(), value);
+        self
+    }
+
+    /// Set the schema of the property `name` of objects.
+    pub fn property(mut self, name: &str, schema: SchemaBuilder) -> Self {
+        if let Value::Object(properties) =
+            self.schema.entry("properties").or_insert_with(|| json!({}))
+        {
+            properties.insert(name.to_string(), schema.build());
+        }
+        self
+    }
+
+    /// Require objects to have the properties `names`, in addition to any
+    /// already required.
+    pub fn required<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        if let Value::Array(required) = self.schema.entry("required").or_insert_with(|| json!([])) {
+            for name in names {
+                let name = Value::String(name.into());
+                if !required.contains(&name) {
+                    required.push(name);
+                }
+            }
+        }
+        self
+    }
+
+    /// Set the schema of the properties of objects not set with `property`.
+    pub fn additional_properties(self, schema: SchemaBuilder) -> Self {
+        self.keyword("additionalProperties", schema.build())
+    }
+
+    /// Reject objects with properties not set with `property`.
+    pub fn deny_additional_properties(self) -> Self {
+        self.keyword("additionalProperties", json!(false))
+    }
+
+    /// Set the schema of the elements of arrays.
+    pub fn items(self, schema: SchemaBuilder) -> Self {
+        self.keyword("items", schema.build())
+    }
+
+    /// Set the minimum number of elements of arrays.
+    pub fn min_items(self, min: u64) -> Self {
+        self.keyword("minItems", json!(min))
+    }
+
+    /// Set the maximum number of elements of arrays.
+    pub fn max_items(self, max: u64) -> Self {
+        self.keyword("maxItems", json!(max))
+    }
+
+    /// Require the elements of arrays to be unique.
+    pub fn unique_items(self) -> Self {
+        self.keyword("uniqueItems", json!(true))
+    }
+
+    /// Set the minimum length of strings, in characters.
+    pub fn min_length(self, min: u64) -> Self {
+        self.keyword("minLength", json!(min))
+    }
+
+    /// Set the maximum length of strings, in characters.
+    pub fn max_length(self, max: u64) -> Self {
+        self.keyword("maxLength", json!(max))
+    }
+
+    /// Require strings to match the regular expression `pattern`.
+    pub fn pattern(self, pattern: &str) -> Self {
+        self.keyword("pattern", json!(pattern))
+    }
+
+    /// Require strings to be in the format `format`, e.g. `"date-time"`.
+    pub fn format(self, format: &str) -> Self {
+        self.keyword("format", json!(format))
+    }
+
+    /// Set the inclusive minimum of numbers.
+    pub fn minimum<N: Into<Value>>(self, min: N) -> Self {
+        self.keyword("minimum", min.into())
+    }
+
+    /// Set the inclusive maximum of numbers.
+    pub fn maximum<N: Into<Value>>(self, max: N) -> Self {
+        self.keyword("maximum", max.into())
+    }
+
+    /// Set the exclusive minimum of numbers.
+    pub fn exclusive_minimum<N: Into<Value>>(self, min: N) -> Self {
+        self.keyword("exclusiveMinimum", min.into())
+    }
+
+    /// Set the exclusive maximum of numbers.
+    pub fn exclusive_maximum<N: Into<Value>>(self, max: N) -> Self {
+        self.keyword("exclusiveMaximum", max.into())
+    }
+
+    /// Require numbers to be a multiple of `factor`.
+    pub fn multiple_of<N: Into<Value>>(self, factor: N) -> Self {
+        self.keyword("multipleOf", factor.into())
+    }
+
+    /// Allow only the given values.
+    pub fn enum_values<I, V>(self, values: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<Value>,
+    {
+        self.keyword("enum", values.into_iter().map(Into::into).collect())
+    }
+
+    /// Allow only the value `value`.
+    pub fn const_value<V: Into<Value>>(self, value: V) -> Self {
+        self.keyword("const", value.into())
+    }
+
+    /// Require values to be valid against all of `schemas`.
+    pub fn all_of<I: IntoIterator<Item = SchemaBuilder>>(self, schemas: I) -> Self {
+        self.keyword("allOf", schemas.into_iter().map(|x| x.build()).collect())
+    }
+
+    /// Require values to be valid against at least one of `schemas`.
+    pub fn any_of<I: IntoIterator<Item = SchemaBuilder>>(self, schemas: I) -> Self {
+        self.keyword("anyOf", schemas.into_iter().map(|x| x.build()).collect())
+    }
+
+    /// Require values to be valid against exactly one of `schemas`.
+    pub fn one_of<I: IntoIterator<Item = SchemaBuilder>>(self, schemas: I) -> Self {
+        self.keyword("oneOf", schemas.into_iter().map(|x| x.build()).collect())
+    }
+
+    /// Require values to be invalid against `schema`.
+    pub fn not(self, schema: SchemaBuilder) -> Self {
+        self.keyword("not", schema.build())
+    }
+
+    /// Set the title of the schema.
+    pub fn title(self, title: &str) -> Self {
+        self.keyword("title", json!(title))
+    }
+
+    /// Set the description of the schema.
+    pub fn description(self, description: &str) -> Self {
+        self.keyword("description", json!(description))
+    }
+
+    /// Set the default value.
+    pub fn default_value<V: Into<Value>>(self, value: V) -> Self {
+        self.keyword("default", value.into())
+    }
+
+    /// The schema that was built.
+    pub fn build(self) -> Value {
+        Value::Object(self.schema)
+    }
+
+    /// Compile the schema that was built for validation; see
+    /// `Config::from_schema`.
+    pub fn compile<'a>(
+        self,
+        draft: Option<&'a dyn schemas::Draft>,
+    ) -> Result<Config<'a>, ValidationError> {
+        Config::from_owned_schema(self.build(), draft)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let schema = array()
+            .items(
+                object()
+                    .property("kind", SchemaBuilder::new().enum_values(vec!["a", "b"]))
+                    .property("size", number().exclusive_minimum(0.5).multiple_of(0.5))
+                    .required(vec!["kind".to_string()])
+                    .required(["kind", "size"])
+                    .deny_additional_properties(),
+            )
+            .max_items(2);
+        assert_eq!(
+            schema.clone().build(),
+            json!({
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "kind": {"enum": ["a", "b"]},
+                        "size": {"type": "number", "exclusiveMinimum": 0.5, "multipleOf": 0.5}
+                    },
+                    "required": ["kind", "size"],
+                    "additionalProperties": false
+                },
+                "maxItems": 2
+            })
+        );
+        let cfg = schema.compile(Some(&schemas::Draft7)).unwrap();
+        assert!(cfg.validate(&json!([{"kind": "a", "size": 1.5}])).is_ok());
+        assert!(cfg.validate(&json!([{"kind": "c", "size": 0.5}])).is_err());
+    }
+}
@@ -0,0 +1,425 @@
+//! Bundling a schema and the documents it references into a single document.
+//!
+//! Schemas are often split over several files that refer to each other with
+//! `$ref`. Bundling retrieves every referenced document, embeds it under
+//! `$defs` in the root schema and rewrites all references to point to the
+//! embedded copies, so the result can be used without access to the other
+//! documents.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::{json, Value};
+//! use jsonschema_valid::bundle::bundle;
+//!
+//! let root = json!({"properties": {"address": {"$ref": "address.json#/definitions/street"}}});
+//! let base = url::Url::parse("file:///schemas/root.json").unwrap();
+//! let retrieve = |url: &url::Url| -> Result<Value, String> {
+//!     match url.as_str() {
+//!         "file:///schemas/address.json" => {
+//!             Ok(json!({"definitions": {"street": {"type": "string"}}}))
+//!         }
+//!         other => Err(format!("{} not found", other)),
+//!     }
+//! };
+//!
+//! assert_eq!(
+//!     bundle(&root, &base, &retrieve).unwrap(),
+//!     json!({
+//!         "properties": {"address": {"$ref": "#/$defs/address/definitions/street"}},
+//!         "$defs": {"address": {"definitions": {"street": {"type": "string"}}}}
+//!     })
+//! );
+//! ```
+
+use std::error::Error;
+use std::fmt;
+
+use serde_json::{Map, Value};
+use url::Url;
+
+use crate::schemas;
+use crate::util;
+
+/// An error that can occur while bundling a schema.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BundleError {
+    /// A referenced document could not be retrieved.
+    Retrieve {
+        /// The URL of the document.
+        url: String,
+        /// Why the document could not be retrieved.
+        message: String,
+    },
+    /// A reference is not a valid URL.
+    InvalidRef {
+        /// The value of the `$ref`.
+        reference: String,
+    },
+    /// A reference has a fragment that is not a JSON pointer, such as a
+    /// plain-name anchor, which can't be rewritten to point into the bundle.
+    UnsupportedFragment {
+        /// The value of the `$ref`.
+        reference: String,
+    },
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BundleError::Retrieve { url, message } => {
+                write!(f, "Can't retrieve {}: {}", url, message)
+            }
+            BundleError::InvalidRef { reference } => write!(f, "Invalid $ref {}", reference),
+            BundleError::UnsupportedFragment { reference } => write!(
+                f,
+                "Can't bundle $ref {}: only JSON pointer fragments are supported",
+                reference
+            ),
+        }
+    }
+}
+
+impl Error for BundleError {}
+
+/// A document taking part in the bundle.
+struct Document {
+    /// Where the document was retrieved from.
+    url: Url,
+    /// The URLs that identify the document or a schema within it: where it
+    /// was retrieved from, and the `$id`s in it, with the JSON pointer to
+    /// the schema they identify.
+    ids: Vec<(Url, String)>,
+    /// The name of the document under `$defs`, or `None` for the root.
+    name: Option<String>,
+    value: Value,
+}
+
+impl Document {
+    fn new(value: Value, url: Url, name: Option<String>) -> Document {
+        let mut ids = vec![(url.clone(), String::new())];
+        collect_ids(&value, &url, &mut String::new(), &mut ids);
+        Document {
+            url,
+            ids,
+            name,
+            value,
+        }
+    }
+}
+
+/// The base URL of `schema`, if its `$id` changes it from `base`.
+fn schema_base(schema: &Value, base: &Url) -> Option<Url> {
+    let id = schema.get("$id").and_then(Value::as_str)?;
+    if id.starts_with('#') {
+        return None;
+    }
+    let mut url = base.join(id).ok()?;
+    url.set_fragment(None);
+    Some(url)
+}
+
+/// Collect the URLs given by the `$id`s in `schema`, with the JSON pointers
+/// of the schemas they identify.
+fn collect_ids(schema: &Value, base: &Url, pointer: &mut String, ids: &mut Vec<(Url, String)>) {
+    let base = match schema_base(schema, base) {
+        Some(url) => {
+            ids.push((url.clone(), pointer.clone()));
+            url
+        }
+        None => base.clone(),
+    };
+    util::for_each_subschema(schema, &mut |path, subschema| {
+        let len = pointer.len();
+        pointer.push('/');
+        pointer.push_str(&path);
+        collect_ids(subschema, &base, pointer, ids);
+        pointer.truncate(len);
+    });
+}
+
+/// Bundle `root` and all documents it references (directly or indirectly)
+/// into a single schema.
+///
+/// `base` is the URL `root` was loaded from, against which relative
+/// references are resolved. `retrieve` is called once for every other
+/// document that is referenced, with the URL of the document (without
+/// fragment). References to the JSON Schema metaschemas are left as they are.
+///
+/// Every retrieved document is embedded under `$defs` in the root schema,
+/// named after the last segment of its URL. References are resolved against
+/// the `$id`s that apply to them, including those of nested schemas, and
+/// rewritten to be relative to the root. The `$id`s are then removed, except
+/// for the one of the root schema.
+pub fn bundle(
+    root: &Value,
+    base: &Url,
+    retrieve: &dyn Fn(&Url) -> Result<Value, String>,
+) -> Result<Value, BundleError> {
+    let mut base = base.clone();
+    base.set_fragment(None);
+    let mut documents = vec![Document::new(root.clone(), base, None)];
+    let mut reserved: Vec<String> = root
+        .get("$defs")
+        .and_then(Value::as_object)
+        .map(|x| x.keys().cloned().collect())
+        .unwrap_or_default();
+
+    let mut index = 0;
+    while index < documents.len() {
+        let mut value = documents[index].value.take();
+        let url = documents[index].url.clone();
+        let mut result = Ok(());
+        rewrite_refs(&mut value, &url, &mut |base, reference| {
+            if result.is_ok() {
+                result = rewrite(reference, base, &mut documents, &mut reserved, retrieve);
+            }
+        });
+        result?;
+        remove_ids(&mut value, index > 0);
+        documents[index].value = value;
+        index += 1;
+    }
+
+    let mut documents = documents.into_iter();
+    let mut result = match documents.next() {
+        Some(root) => root.value,
+        None => return Ok(root.clone()),
+    };
+    if let Value::Object(object) = &mut result {
+        for document in documents {
+            if let Value::Object(defs) = object
+                .entry("$defs")
+                .or_insert_with(|| Value::Object(Map::new()))
+            {
+                defs.insert(document.name.unwrap_or_default(), document.value);
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Rewrite a single `$ref`, with the given base URL, to point into the
+/// bundle, retrieving the document it refers to if necessary.
+fn rewrite(
+    reference: &mut String,
+    base: &Url,
+    documents: &mut Vec<Document>,
+    reserved: &mut Vec<String>,
+    retrieve: &dyn Fn(&Url) -> Result<Value, String>,
+) -> Result<(), BundleError> {
+    let target = base.join(reference).map_err(|_| BundleError::InvalidRef {
+        reference: reference.clone(),
+    })?;
+    let mut resource = target.clone();
+    resource.set_fragment(None);
+    if schemas::draft_from_url(resource.as_str()).is_some() {
+        return Ok(());
+    }
+
+    let fragment = percent_encoding::percent_decode(target.fragment().unwrap_or("").as_bytes())
+        .decode_utf8()
+        .map_err(|_| BundleError::InvalidRef {
+            reference: reference.clone(),
+        })?
+        .to_string();
+    if !fragment.is_empty() && !fragment.starts_with('/') {
+        return Err(BundleError::UnsupportedFragment {
+            reference: reference.clone(),
+        });
+    }
+
+    let found = documents.iter().enumerate().find_map(|(index, document)| {
+        document
+            .ids
+            .iter()
+            .find(|(url, _)| *url == resource)
+            .map(|(_, pointer)| (index, pointer.clone()))
+    });
+    let (target_index, pointer) = match found {
+        Some(found) => found,
+        None => {
+            let value = retrieve(&resource).map_err(|message| BundleError::Retrieve {
+                url: resource.to_string(),
+                message,
+            })?;
+            let name = unique_name(&resource, reserved);
+            documents.push(Document::new(value, resource, Some(name)));
+            (documents.len() - 1, String::new())
+        }
+    };
+    *reference = match &documents[target_index].name {
+        Some(name) => format!(
+            "#/$defs/{}{}{}",
+            util::escape_pointer_token(name),
+            pointer,
+            fragment
+        ),
+        None => format!("#{}{}", pointer, fragment),
+    };
+    Ok(())
+}
+
+/// Pick a name under `$defs` for a document, based on the last segment of
+/// its URL.
+fn unique_name(url: &Url, reserved: &mut Vec<String>) -> String {
+    let segment = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .unwrap_or("");
+    let stem = match segment.find('.') {
+        Some(dot) if dot > 0 => &segment[..dot],
+        _ => segment,
+    };
+    let stem = if stem.is_empty() { "schema" } else { stem };
+    let mut name = stem.to_string();
+    let mut counter = 1;
+    while reserved.contains(&name) {
+        counter += 1;
+        name = format!("{}{}", stem, counter);
+    }
+    reserved.push(name.clone());
+    name
+}
+
+/// Call `f` with the value of every `$ref` in the schema, and the base URL
+/// to resolve it against.
+fn rewrite_refs(schema: &mut Value, base: &Url, f: &mut dyn FnMut(&Url, &mut String)) {
+    let base = schema_base(schema, base).unwrap_or_else(|| base.clone());
+    if let Some(Value::String(reference)) = schema.get_mut("$ref") {
+        f(&base, reference);
+    }
+    util::for_each_subschema_mut(schema, &mut |_, subschema| {
+        rewrite_refs(subschema, &base, f)
+    });
+}
+
+/// Remove the `$id`s that change the base URL from the schema, and from the
+/// schema itself only if `top` is set.
+fn remove_ids(schema: &mut Value, top: bool) {
+    if let Value::Object(object) = schema {
+        let changes_base = match object.get("$id") {
+            Some(Value::String(id)) => !id.starts_with('#'),
+            _ => false,
+        };
+        if top && changes_base {
+            object.remove("$id");
+        }
+    }
+    util::for_each_subschema_mut(schema, &mut |_, subschema| remove_ids(subschema, true));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_nested_and_cyclic_references() {
+        let root = json!({
+            "$defs": {"a": {"type": "integer"}},
+            "items": [
+                {"$ref": "a.json"},
+                {"$ref": "dir/b.json#/definitions/x"},
+                {"$ref": "http://json-schema.org/draft-07/schema#"}
+            ]
+        });
+        let retrieve = |url: &Url| -> Result<Value, String> {
+            match url.as_str() {
+                "file:///s/a.json" => Ok(json!({"not": {"$ref": "dir/b.json"}})),
+                "file:///s/dir/b.json" => Ok(json!({
+                    "$id": "b.json",
+                    "definitions": {"x": {"$ref": "#/definitions/y"}, "y": {"$ref": "../a.json"}}
+                })),
+                other => Err(format!("{} not found", other)),
+            }
+        };
+        let base = Url::parse("file:///s/root.json").unwrap();
+        assert_eq!(
+            bundle(&root, &base, &retrieve).unwrap(),
+            json!({
+                "$defs": {
+                    "a": {"type": "integer"},
+                    "a2": {"not": {"$ref": "#/$defs/b"}},
+                    "b": {
+                        "definitions": {
+                            "x": {"$ref": "#/$defs/b/definitions/y"},
+                            "y": {"$ref": "#/$defs/a2"}
+                        }
+                    }
+                },
+                "items": [
+                    {"$ref": "#/$defs/a2"},
+                    {"$ref": "#/$defs/b/definitions/x"},
+                    {"$ref": "http://json-schema.org/draft-07/schema#"}
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_nested_ids() {
+        let root = json!({
+            "$id": "http://example.com/root.json",
+            "properties": {
+                "item": {"$ref": "item.json"},
+                "tags": {"$ref": "types/tags.json#/definitions/tag"}
+            },
+            "$defs": {
+                "item": {
+                    "$id": "item.json",
+                    "properties": {"price": {"$ref": "types/money.json"}}
+                }
+            }
+        });
+        let retrieve = |url: &Url| -> Result<Value, String> {
+            match url.as_str() {
+                "http://example.com/types/money.json" => Ok(json!({"type": "number"})),
+                "http://example.com/types/tags.json" => Ok(json!({
+                    "definitions": {
+                        "tag": {"$ref": "nested/name.json"},
+                        "names": {
+                            "$id": "nested/",
+                            "definitions": {"name": {"$id": "name.json", "type": "string"}}
+                        }
+                    }
+                })),
+                other => Err(format!("{} not found", other)),
+            }
+        };
+        let base = Url::parse("file:///s/root.json").unwrap();
+        assert_eq!(
+            bundle(&root, &base, &retrieve).unwrap(),
+            json!({
+                "$id": "http://example.com/root.json",
+                "properties": {
+                    "item": {"$ref": "#/$defs/item"},
+                    "tags": {"$ref": "#/$defs/tags/definitions/tag"}
+                },
+                "$defs": {
+                    "item": {"properties": {"price": {"$ref": "#/$defs/money"}}},
+                    "money": {"type": "number"},
+                    "tags": {
+                        "definitions": {
+                            "tag": {"$ref": "#/$defs/tags/definitions/names/definitions/name"},
+                            "names": {"definitions": {"name": {"type": "string"}}}
+                        }
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_missing_document() {
+        let root = json!({"$ref": "missing.json"});
+        let base = Url::parse("file:///s/root.json").unwrap();
+        assert_eq!(
+            bundle(&root, &base, &|_| Err("not found".to_string())),
+            Err(BundleError::Retrieve {
+                url: "file:///s/missing.json".to_string(),
+                message: "not found".to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,440 @@
+//! A cache of compiled schemas, keyed by the content of the schema document.
+//!
+//! Services that receive schemas dynamically (e.g. with each webhook call, or
+//! per tenant) often see the same schema over and over again. A
+//! [`SchemaCache`](struct.SchemaCache.html) builds the `Config` for a given
+//! schema only once, and hands out shared references to it afterward.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! use jsonschema_valid::cache::SchemaCache;
+//!
+//! let cache = SchemaCache::new();
+//! let cfg = cache.get_or_compile(&json!({"type": "integer"}), None).unwrap();
+//! assert!(cfg.validate(&json!(42)).is_ok());
+//!
+//! // The same schema, received again, reuses the compiled configuration.
+//! let again = cache.get_or_compile(&json!({"type": "integer"}), None).unwrap();
+//! assert!(std::sync::Arc::ptr_eq(&cfg, &again));
+//! assert_eq!(cache.len(), 1);
+//! ```
+//!
+//! The [`global`](struct.SchemaCache.html#method.global) cache keeps the
+//! `GLOBAL_CAPACITY` most recently used schemas; caches made with `new` are
+//! unbounded, and those made with `with_capacity` keep as many as given.
+//!
+//! To avoid the cost of checking a schema at startup, e.g. in CI jobs, a
+//! checked schema can also be persisted with [`compile`](fn.compile.html) and
+//! loaded again with [`load_compiled`](fn.load_compiled.html).
+
+use std::borrow::Cow;
+use std::fmt::Write;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use lazy_static::lazy_static;
+use lru::LruCache;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::bundle::{self, BundleError};
+use crate::config::Config;
+use crate::error::{make_error, ErrorIterator, ValidationError};
+use crate::schemas;
+
+/// The number of schemas kept by the global cache, unless changed with
+/// `resize`.
+pub const GLOBAL_CAPACITY: usize = 1024;
+
+lazy_static! {
+    static ref GLOBAL: SchemaCache = SchemaCache::with_capacity(GLOBAL_CAPACITY);
+}
+
+type Entries = LruCache<(String, &'static str), Arc<Config<'static>>>;
+
+/// A thread-safe cache of compiled schemas.
+///
+/// Entries are keyed by the [`content_hash`](fn.content_hash.html) of the
+/// schema and the requested draft, so two schemas that only differ in the
+/// order of their keys or in whitespace share an entry.
+pub struct SchemaCache {
+    entries: Mutex<Entries>,
+}
+
+impl Default for SchemaCache {
+    fn default() -> SchemaCache {
+        SchemaCache::new()
+    }
+}
+
+impl SchemaCache {
+    /// Create a new, empty cache without a limit on its size.
+    pub fn new() -> SchemaCache {
+        SchemaCache {
+            entries: Mutex::new(LruCache::unbounded()),
+        }
+    }
+
+    /// Create a new, empty cache that keeps the `capacity` most recently
+    /// used schemas (at least one).
+    pub fn with_capacity(capacity: usize) -> SchemaCache {
+        SchemaCache {
+            entries: Mutex::new(LruCache::new(capacity_of(capacity))),
+        }
+    }
+
+    /// The process-wide cache, which keeps the `GLOBAL_CAPACITY` most
+    /// recently used schemas.
+    pub fn global() -> &'static SchemaCache {
+        &GLOBAL
+    }
+
+    /// Get the compiled configuration for the given schema, building it if
+    /// an identical schema hasn't been seen before.
+    ///
+    /// `draft` works as in `Config::from_schema`: if not provided, the draft
+    /// is determined from the schema itself.
+    pub fn get_or_compile(
+        &self,
+        schema: &Value,
+        draft: Option<&'static dyn schemas::Draft>,
+    ) -> Result<Arc<Config<'static>>, ValidationError> {
+        let key = (content_hash(schema), draft.map_or("", |x| x.get_dialect()));
+        if let Some(cfg) = self.lock().get(&key) {
+            return Ok(cfg.clone());
+        }
+
+        // Build outside of the lock, so other schemas can be looked up in the
+        // meantime. If another thread won the race, its entry is kept.
+        let cfg = Arc::new(Config::from_owned_schema(schema.clone(), draft)?);
+        let mut entries = self.lock();
+        if let Some(existing) = entries.get(&key) {
+            return Ok(existing.clone());
+        }
+        entries.put(key, cfg.clone());
+        Ok(cfg)
+    }
+
+    /// Remove the entry for the given schema and draft, if there is one.
+    /// Returns `true` if it was removed.
+    pub fn remove(&self, schema: &Value, draft: Option<&'static dyn schemas::Draft>) -> bool {
+        let key = (content_hash(schema), draft.map_or("", |x| x.get_dialect()));
+        self.lock().pop(&key).is_some()
+    }
+
+    /// Change the number of schemas the cache keeps (at least one), dropping
+    /// the least recently used ones if there are more.
+    pub fn resize(&self, capacity: usize) {
+        self.lock().resize(capacity_of(capacity))
+    }
+
+    /// The number of compiled schemas in the cache.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns `true` if the cache has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Remove all entries from the cache.
+    pub fn clear(&self) {
+        self.lock().clear()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Entries> {
+        // The map is never left in an inconsistent state, so a poisoned lock
+        // can safely be reused.
+        self.entries.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+fn capacity_of(capacity: usize) -> NonZeroUsize {
+    NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN)
+}
+
+/// Compute a SHA-256 hash (as lowercase hex) of the canonical form of a JSON
+/// document: object keys are sorted and all insignificant whitespace is
+/// removed.
+pub fn content_hash(schema: &Value) -> String {
+    let mut hasher = Sha256::new();
+    write_canonical(schema, &mut hasher);
+    let mut result = String::with_capacity(64);
+    for byte in hasher.finalize() {
+        let _ = write!(result, "{:02x}", byte);
+    }
+    result
+}
+
+fn write_canonical(value: &Value, hasher: &mut Sha256) {
+    match value {
+        Value::Object(object) => {
+            let mut keys: Vec<&String> = object.keys().collect();
+            keys.sort();
+            hasher.update(b"{");
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    hasher.update(b",");
+                }
+                hasher.update(Value::String(key.clone()).to_string());
+                hasher.update(b":");
+                write_canonical(&object[key], hasher);
+            }
+            hasher.update(b"}");
+        }
+        Value::Array(array) => {
+            hasher.update(b"[");
+            for (i, item) in array.iter().enumerate() {
+                if i > 0 {
+                    hasher.update(b",");
+                }
+                write_canonical(item, hasher);
+            }
+            hasher.update(b"]");
+        }
+        other => hasher.update(other.to_string()),
+    }
+}
+
+/// Identifies documents written by `compile`.
+const COMPILED_FORMAT: &str = "jsonschema-valid/compiled";
+
+/// The version of the documents written by `compile`. Version 1 recorded
+/// the draft number instead of the dialect.
+const COMPILED_VERSION: u64 = 2;
+
+/// Check the schema of `cfg` against its metaschema and make sure all of its
+/// references can be resolved, then return a document that can be persisted
+/// and loaded later with `load_compiled`.
+///
+/// The documents added to `cfg` with `with_document` are embedded in the
+/// stored schema with [`bundle`](../bundle/fn.bundle.html), so the compiled
+/// schema doesn't need them anymore. Otherwise the schema is stored as it
+/// is. To store an optimized schema, compile a `Config` for the result of
+/// [`optimize`](../fn.optimize.html).
+///
+/// ## Example:
+///
+/// ```rust
+/// # use serde_json::json;
+/// # use jsonschema_valid::{cache, schemas, Config};
+/// let schema = json!({"properties": {"id": {"$ref": "common.json#/definitions/id"}}});
+/// let common = url::Url::parse("document:///common.json").unwrap();
+/// let cfg = Config::from_schema(&schema, Some(&schemas::Draft7))
+///     .unwrap()
+///     .with_document(&common, json!({"definitions": {"id": {"type": "integer"}}}))
+///     .unwrap();
+/// let compiled = cache::compile(&cfg).ok().unwrap();
+///
+/// let loaded = cache::load_compiled(compiled).unwrap();
+/// assert!(loaded.validate(&json!({"id": "1"})).is_err());
+/// ```
+pub fn compile<'a>(cfg: &'a Config<'a>) -> Result<Value, ErrorIterator<'a>> {
+    cfg.validate_schema()?;
+    cfg.validate_refs()?;
+    let schema = bundled(cfg).map_err(|err| make_error(err.to_string()))?;
+    Ok(json!({
+        "format": COMPILED_FORMAT,
+        "version": COMPILED_VERSION,
+        "dialect": cfg.get_draft().get_dialect(),
+        "hash": content_hash(&schema),
+        "schema": schema,
+    }))
+}
+
+/// The schema of `cfg`, with the documents added to it embedded.
+fn bundled<'a>(cfg: &'a Config<'a>) -> Result<Cow<'a, Value>, BundleError> {
+    let resolver = cfg.get_resolver();
+    if resolver.documents().next().is_none() {
+        return Ok(Cow::Borrowed(cfg.get_schema()));
+    }
+    let base = Url::parse(resolver.base_url()).map_err(|err| BundleError::Retrieve {
+        url: resolver.base_url().to_string(),
+        message: err.to_string(),
+    })?;
+    let retrieve = |url: &Url| -> Result<Value, String> {
+        resolver
+            .documents()
+            .find(|(document_url, _)| *document_url == url.as_str())
+            .map(|(_, document)| document.clone())
+            .ok_or_else(|| "not loaded".to_string())
+    };
+    bundle::bundle(cfg.get_schema(), &base, &retrieve).map(Cow::Owned)
+}
+
+/// Load a document written by `compile`.
+///
+/// The schema is not checked again, but the document is rejected if the
+/// schema doesn't match the hash recorded when it was compiled.
+pub fn load_compiled(mut document: Value) -> Result<Config<'static>, ValidationError> {
+    if document["format"] != COMPILED_FORMAT {
+        return Err(ValidationError::new("Not a compiled schema"));
+    }
+    if document["version"] != COMPILED_VERSION {
+        return Err(ValidationError::new(&format!(
+            "Unsupported compiled schema version {}",
+            document["version"]
+        )));
+    }
+    let draft = document["dialect"]
+        .as_str()
+        .and_then(schemas::draft_from_url)
+        .ok_or_else(|| ValidationError::new(&format!("Unknown dialect {}", document["dialect"])))?;
+    let schema = document["schema"].take();
+    if document["hash"] != content_hash(&schema) {
+        return Err(ValidationError::new(
+            "Compiled schema doesn't match its hash",
+        ));
+    }
+    Config::from_owned_schema(schema, Some(draft))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_content_hash_is_canonical() {
+        let a: Value = serde_json::from_str(r#"{"b": [1, 2], "a": {"y": 1, "x": 2}}"#).unwrap();
+        let b: Value = serde_json::from_str(r#"{"a":{"x":2,"y":1},"b":[1,2]}"#).unwrap();
+        assert_eq!(content_hash(&a), content_hash(&b));
+        assert_ne!(content_hash(&a), content_hash(&json!({"b": [2, 1]})));
+    }
+
+    #[test]
+    fn test_draft_is_part_of_key() {
+        let cache = SchemaCache::new();
+        let schema = json!({"exclusiveMinimum": 0});
+        let draft4 = cache
+            .get_or_compile(&schema, Some(&schemas::Draft4))
+            .unwrap();
+        let draft7 = cache
+            .get_or_compile(&schema, Some(&schemas::Draft7))
+            .unwrap();
+        let openapi = cache
+            .get_or_compile(&schema, Some(&schemas::OpenApi30))
+            .unwrap();
+        assert!(!Arc::ptr_eq(&draft4, &draft7));
+        assert!(!Arc::ptr_eq(&draft4, &openapi));
+        assert_eq!(cache.len(), 3);
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_capacity() {
+        let cache = SchemaCache::with_capacity(2);
+        for maximum in 0..3 {
+            cache
+                .get_or_compile(&json!({ "maximum": maximum }), None)
+                .unwrap();
+        }
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.remove(&json!({"maximum": 0}), None));
+        assert!(cache.remove(&json!({"maximum": 2}), None));
+        cache.resize(0);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_compiled_round_trip() {
+        let schema = json!({"definitions": {"a": {"type": "string"}}, "$ref": "#/definitions/a"});
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft6)).unwrap();
+        let compiled = compile(&cfg).ok().unwrap();
+        let loaded = load_compiled(compiled.clone()).unwrap();
+        assert_eq!(loaded.get_draft_number(), 6);
+        assert!(loaded.validate(&json!(1)).is_err());
+
+        let mut tampered = compiled;
+        tampered["schema"]["$ref"] = json!("#/definitions/b");
+        assert!(load_compiled(tampered).is_err());
+
+        let schema = json!({"items": {"$ref": "other.json"}});
+        let url = url::Url::parse("document:///other.json").unwrap();
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7))
+            .unwrap()
+            .with_document(
+                &url,
+                json!({"$ref": "#/definitions/a", "definitions": {"a": {"type": "string"}}}),
+            )
+            .unwrap();
+        let compiled = compile(&cfg).ok().unwrap();
+        assert_eq!(
+            compiled["schema"],
+            json!({
+                "items": {"$ref": "#/$defs/other"},
+                "$defs": {
+                    "other": {
+                        "$ref": "#/$defs/other/definitions/a",
+                        "definitions": {"a": {"type": "string"}}
+                    }
+                }
+            })
+        );
+        let loaded = load_compiled(compiled).unwrap();
+        assert!(loaded.validate(&json!(["a"])).is_ok());
+        assert!(loaded.validate(&json!([1])).is_err());
+
+        let broken = json!({"$ref": "#/definitions/b"});
+        let cfg = Config::from_schema(&broken, None).unwrap();
+        let errors: Vec<ValidationError> = compile(&cfg).err().unwrap().collect();
+        assert_eq!(errors[0].instance_path(), vec!["$ref"]);
+    }
+
+    #[test]
+    fn test_result_cache() {
+        use crate::keywords::Keyword;
+        use crate::node::JsonNode;
+        use crate::{Context, Errors};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct Counted(Arc<AtomicUsize>);
+
+        impl Keyword for Counted {
+            fn validate<'a>(
+                &'a self,
+                _cfg: &'a Config<'a>,
+                instance: &'a dyn JsonNode,
+                _schema: &'a Value,
+                _parent_schema: Option<&'a Value>,
+                _ref_context: Context<'a>,
+            ) -> ErrorIterator<'a> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                match instance.as_str() {
+                    Some(_) => crate::error::no_error(),
+                    None => crate::error::make_error("not a string"),
+                }
+            }
+        }
+
+        let schema = json!({"counted": true});
+        let count = Arc::new(AtomicUsize::new(0));
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7))
+            .unwrap()
+            .with_keyword("counted", Counted(count.clone()))
+            .with_result_cache(2);
+        let invalid = |result| match result {
+            Err(Errors::Invalid(errors)) => errors.len(),
+            _ => 0,
+        };
+        assert!(cfg.validate_payload(b"\"a\"").is_ok());
+        assert!(cfg.validate_payload(b"\"a\"").is_ok());
+        assert_eq!(invalid(cfg.validate_payload(b"1")), 1);
+        assert_eq!(invalid(cfg.validate_payload(b"1")), 1);
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+        // Payloads that are the same JSON but not the same bytes are
+        // validated again, as are those evicted from the cache.
+        assert!(cfg.validate_payload(b" \"a\"").is_ok());
+        assert!(cfg.validate_payload(b"\"a\"").is_ok());
+        assert_eq!(count.load(Ordering::SeqCst), 4);
+        assert!(matches!(
+            cfg.validate_payload(b"["),
+            Err(Errors::InstanceSyntax(_))
+        ));
+    }
+}
@@ -0,0 +1,252 @@
+//! Representing errors in the shapes other JSON Schema validators use.
+//!
+//! Code that handles the errors of another validator can be moved to this
+//! crate by converting its errors to the same JSON shape:
+//!
+//! * `jsonschema_output` gives the "basic" output format of the `jsonschema`
+//!   crate, as returned by its `Output::basic`.
+//! * `valico_errors` gives the serialized errors of the `valico` crate, with
+//!   valico's error codes and titles.
+//! * `ajv_errors` gives ajv's `validate.errors` array, with the `params` ajv
+//!   attaches for each keyword.
+//!
+//! Messages are this crate's own in all of them. ajv's `params` are taken
+//! from the values recorded with `Config::with_error_values`.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::{schemas, Config, ValidationError};
+//! use jsonschema_valid::compat;
+//!
+//! let schema = json!({"properties": {"a": {"type": "integer"}}, "required": ["a", "b"]});
+//! let cfg = Config::from_schema(&schema, Some(&schemas::Draft7))
+//!     .unwrap()
+//!     .with_error_values();
+//! let errors: Vec<ValidationError> = cfg.validate(&json!({"a": "x"})).unwrap_err().collect();
+//!
+//! let ajv = compat::ajv_errors(&errors);
+//! assert_eq!(ajv[0]["instancePath"], "/a");
+//! assert_eq!(ajv[0]["schemaPath"], "#/properties/a/type");
+//! assert_eq!(ajv[0]["params"], json!({"type": "integer"}));
+//! assert_eq!(ajv[1]["params"], json!({"missingProperty": "b"}));
+//!
+//! assert_eq!(compat::valico_errors(&errors)[0]["code"], "wrong_type");
+//! assert_eq!(compat::jsonschema_output(&errors)["valid"], false);
+//! ```
+
+use serde_json::{json, Map, Value};
+
+use crate::error::ValidationError;
+use crate::util;
+
+fn pointer(path: &[&str]) -> String {
+    path.iter()
+        .map(|token| format!("/{}", util::escape_pointer_token(token)))
+        .collect()
+}
+
+/// Represent `errors` in the "basic" output format of the `jsonschema`
+/// crate: an object with `valid` and an `errors` array, where each error has
+/// the `keywordLocation`, `instanceLocation` and `error` message.
+pub fn jsonschema_output<'a, I>(errors: I) -> Value
+where
+    I: IntoIterator<Item = &'a ValidationError>,
+{
+    let errors: Vec<Value> = errors
+        .into_iter()
+        .map(|err| {
+            json!({
+                "keywordLocation": pointer(&err.schema_path()),
+                "instanceLocation": pointer(&err.instance_path()),
+                "error": err.message(),
+            })
+        })
+        .collect();
+    json!({"valid": errors.is_empty(), "errors": errors})
+}
+
+/// The code and title `valico` uses for errors from `keyword`.
+fn valico_code(keyword: &str) -> (String, String) {
+    match keyword {
+        "type" => ("wrong_type".into(), "Type of the value is wrong".into()),
+        "multipleOf" => ("multiple_of".into(), "Wrong number of the value".into()),
+        "required" => ("required".into(), "This property is required".into()),
+        "format" => ("format".into(), "Format is wrong".into()),
+        "additionalProperties" | "patternProperties" | "propertyNames" => (
+            "properties".into(),
+            "Property conditions are not met".into(),
+        ),
+        _ => {
+            let mut code = String::new();
+            for c in keyword.chars() {
+                if c.is_ascii_uppercase() {
+                    code.push('_');
+                }
+                code.push(c.to_ascii_lowercase());
+            }
+            let mut title = keyword.to_string();
+            if let Some(first) = title.get_mut(0..1) {
+                first.make_ascii_uppercase();
+            }
+            (code, format!("{} condition is not met", title))
+        }
+    }
+}
+
+/// Represent `errors` as the `valico` crate serializes its errors: an array
+/// of objects with a `code`, a `title`, the `path` to the failing value and
+/// a `detail` message.
+pub fn valico_errors<'a, I>(errors: I) -> Value
+where
+    I: IntoIterator<Item = &'a ValidationError>,
+{
+    errors
+        .into_iter()
+        .map(|err| {
+            let (code, title) = match err.keyword() {
+                Some(keyword) => valico_code(keyword),
+                None => ("not".into(), "Not condition is not met".into()),
+            };
+            json!({
+                "code": code,
+                "title": title,
+                "path": pointer(&err.instance_path()),
+                "detail": err.message(),
+            })
+        })
+        .collect()
+}
+
+/// The `params` ajv attaches to an error from `keyword` with the value
+/// `value` in the schema.
+fn ajv_params(keyword: &str, value: &Value) -> Value {
+    match keyword {
+        "type" => json!({ "type": value }),
+        "minimum" => json!({"comparison": ">=", "limit": value}),
+        "maximum" => json!({"comparison": "<=", "limit": value}),
+        "exclusiveMinimum" => json!({"comparison": ">", "limit": value}),
+        "exclusiveMaximum" => json!({"comparison": "<", "limit": value}),
+        "minLength" | "maxLength" | "minItems" | "maxItems" | "minProperties" | "maxProperties" => {
+            json!({ "limit": value })
+        }
+        "multipleOf" => json!({ "multipleOf": value }),
+        "pattern" => json!({ "pattern": value }),
+        "format" => json!({ "format": value }),
+        "enum" => json!({ "allowedValues": value }),
+        "const" => json!({ "allowedValue": value }),
+        _ => Value::Object(Map::new()),
+    }
+}
+
+/// Represent `errors` as ajv's `validate.errors` array, where each error has
+/// the `instancePath`, the `schemaPath` as a URI fragment, the `keyword`, the
+/// `params` ajv gives for the keyword, and a `message`.
+///
+/// As with ajv's `allErrors` option, a failing `required` gives an error
+/// for each missing property, and a failing `additionalProperties` one for
+/// each additional property.
+pub fn ajv_errors<'a, I>(errors: I) -> Value
+where
+    I: IntoIterator<Item = &'a ValidationError>,
+{
+    let mut result = Vec::new();
+    for err in errors {
+        let keyword = err.keyword().unwrap_or("false schema");
+        let value = err.keyword_value().unwrap_or(&Value::Null);
+        let error = |params: Value| {
+            json!({
+                "instancePath": pointer(&err.instance_path()),
+                "schemaPath": format!("#{}", pointer(&err.schema_path())),
+                "keyword": keyword,
+                "params": params,
+                "message": err.message(),
+            })
+        };
+        let instance = err.instance().and_then(Value::as_object);
+        match (keyword, instance) {
+            ("required", Some(instance)) => result.extend(
+                value
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .filter(|property| !instance.contains_key(*property))
+                    .map(|property| error(json!({ "missingProperty": property }))),
+            ),
+            ("additionalProperties", Some(instance)) => result.extend(
+                err.message()
+                    .rsplit("Found ")
+                    .next()
+                    .into_iter()
+                    .flat_map(|found| found.split(", "))
+                    .filter(|property| instance.contains_key(*property))
+                    .map(|property| error(json!({ "additionalProperty": property }))),
+            ),
+            _ => result.push(error(ajv_params(keyword, value))),
+        }
+    }
+    Value::Array(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::schemas;
+
+    #[test]
+    fn test_compat() {
+        let schema = json!({
+            "properties": {"a": {"maxLength": 1}},
+            "additionalProperties": false,
+        });
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7))
+            .unwrap()
+            .with_error_values();
+        let errors: Vec<ValidationError> = cfg
+            .validate(&json!({"a": "xy", "b": 1, "c": 2}))
+            .unwrap_err()
+            .collect();
+
+        assert_eq!(
+            ajv_errors(&errors),
+            json!([
+                {
+                    "instancePath": "",
+                    "schemaPath": "#/additionalProperties",
+                    "keyword": "additionalProperties",
+                    "params": {"additionalProperty": "b"},
+                    "message": errors[0].message(),
+                },
+                {
+                    "instancePath": "",
+                    "schemaPath": "#/additionalProperties",
+                    "keyword": "additionalProperties",
+                    "params": {"additionalProperty": "c"},
+                    "message": errors[0].message(),
+                },
+                {
+                    "instancePath": "/a",
+                    "schemaPath": "#/properties/a/maxLength",
+                    "keyword": "maxLength",
+                    "params": {"limit": 1},
+                    "message": errors[1].message(),
+                },
+            ])
+        );
+        let valico = valico_errors(&errors);
+        assert_eq!(valico[0]["code"], "properties");
+        assert_eq!(valico[1]["code"], "max_length");
+        assert_eq!(valico[1]["title"], "MaxLength condition is not met");
+        assert_eq!(
+            jsonschema_output(&errors)["errors"][1],
+            json!({
+                "keywordLocation": "/properties/a/maxLength",
+                "instanceLocation": "/a",
+                "error": errors[1].message(),
+            })
+        );
+    }
+}
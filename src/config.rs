@@ -1,29 +1,99 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::{Mutex, PoisonError, RwLock};
+
+use lru::LruCache;
+use regex::Regex;
 use serde_json::Value;
+use sha2::{Digest, Sha256};
 
+use crate::batch::BatchReport;
+use crate::cache;
 use crate::context::Context;
-use crate::error::{ErrorIterator, ValidationError};
-use crate::format::FormatChecker;
-use crate::resolver::Resolver;
+use crate::error::{ErrorIterator, Errors, ValidationError};
+use crate::formats::{DateTimeOptions, EmailOptions, FormatChecker, FormatMode, HostnameOptions};
+use crate::fragment::Fragment;
+use crate::keywords;
+use crate::keywords::Keyword;
+use crate::node::JsonNode;
+use crate::options::{self, ValidationOptions};
+use crate::output::{ContextMode, MessageFormatter, Redaction};
+use crate::profile::Profile;
+#[cfg(feature = "remote")]
+use crate::remote::HttpResolver;
+use crate::resolver::{self, Resolver, SchemeResolver};
 use crate::schemas;
-use crate::validators;
-use crate::validators::Validator;
+use crate::util;
+use crate::vocabulary::Vocabulary;
+
+/// The schema types of `schemars` that `Config::from_schemars` accepts.
+#[cfg(feature = "schemars")]
+pub trait SchemarsSchema: serde::Serialize {}
+
+#[cfg(feature = "schemars")]
+impl SchemarsSchema for schemars::schema::RootSchema {}
+
+#[cfg(feature = "schemars")]
+impl SchemarsSchema for schemars::schema::Schema {}
+
+#[cfg(feature = "schemars")]
+impl SchemarsSchema for schemars::schema::SchemaObject {}
 
 /// A structure to hold configuration for a validation run.
 pub struct Config<'a> {
-    schema: &'a Value,
-    resolver: Resolver<'a>,
+    schema: Cow<'a, Value>,
+    resolver: Resolver,
     draft: &'a dyn schemas::Draft,
+    keywords: HashMap<String, Box<dyn Keyword + 'a>>,
+    formats: HashMap<String, Option<FormatChecker>>,
+    scheme_resolvers: HashMap<String, Box<dyn SchemeResolver + 'a>>,
+    pinned_hashes: HashMap<String, String>,
+    docs_url: Option<String>,
+    keyword_docs_urls: HashMap<String, String>,
+    formatter: Option<Box<dyn MessageFormatter + 'a>>,
+    error_context: Option<ContextMode>,
+    redaction: Option<Redaction>,
+    error_values: bool,
+    epsilon: f64,
+    format_mode: FormatMode,
+    date_time_options: DateTimeOptions,
+    email_options: EmailOptions,
+    hostname_options: HostnameOptions,
+    check_examples: bool,
+    data_references: bool,
+    profile: Option<Profile>,
+    result_cache: Option<Mutex<ResultCache>>,
+    regexes: RwLock<HashMap<String, Result<Regex, regex::Error>>>,
+    vocabularies: HashMap<String, Vocabulary<'a>>,
+    vocabulary_warnings: Vec<ValidationError>,
 }
 
+/// The results of validating payloads, by the SHA-256 digest of the payload.
+type ResultCache = LruCache<[u8; 32], Result<(), Vec<ValidationError>>>;
+
 impl<'a> Config<'a> {
-    /// Get the validator object for the draft in use.
-    pub fn get_validator(&self, key: &str) -> Option<Validator> {
-        self.draft.get_validator(key)
+    /// Get the implementation of the given keyword, either from the draft
+    /// in use or added with `with_keyword`.
+    pub fn get_keyword(&self, key: &str) -> Option<&(dyn Keyword + 'a)> {
+        self.keywords.get(key).map(|keyword| keyword.as_ref())
+    }
+
+    /// Check whether the given keyword takes part in validation, either
+    /// because the draft in use supports it or because it was added with
+    /// `with_keyword`.
+    pub fn supports(&self, key: &str) -> bool {
+        self.keywords.contains_key(key) || schemas::supports(key, self.draft)
     }
 
-    /// Get the string format checker for the draft in use.
+    /// Get the string format checker for the given format, either set with
+    /// `with_format` or from the draft in use, unless it was disabled with
+    /// `without_format`.
     pub fn get_format_checker(&self, key: &str) -> Option<FormatChecker> {
-        self.draft.get_format_checker(key)
+        match self.formats.get(key) {
+            Some(checker) => *checker,
+            None => self.draft.get_format_checker(key),
+        }
     }
 
     /// Get the draft number in use.
@@ -31,16 +101,62 @@ impl<'a> Config<'a> {
         self.draft.get_draft_number()
     }
 
+    /// Get the draft in use.
+    pub fn get_draft(&self) -> &'a dyn schemas::Draft {
+        self.draft
+    }
+
     /// Get the metaschema associated with the draft in use.
     pub fn get_metaschema(&self) -> &Value {
         self.draft.get_schema()
     }
 
+    /// Get how much of the enclosing object to attach to errors inside
+    /// objects, if any.
+    pub fn get_error_context(&self) -> Option<ContextMode> {
+        self.error_context
+    }
+
     /// Get the resolver for the parsing context.
-    pub fn get_resolver(&self) -> &Resolver<'a> {
+    pub fn get_resolver(&self) -> &Resolver {
         &self.resolver
     }
 
+    /// Get the profile selecting the keywords to validate, if set with
+    /// `with_profile`.
+    pub fn get_profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    /// Get the tolerance for comparing numbers, set with `with_epsilon`.
+    pub fn get_epsilon(&self) -> f64 {
+        self.epsilon
+    }
+
+    /// Get whether `format` takes part in validation, set with
+    /// `with_format_mode`.
+    pub fn get_format_mode(&self) -> FormatMode {
+        self.format_mode
+    }
+
+    /// Get how strictly `date-time` and `time` values are checked, set with
+    /// `with_date_time_options`.
+    pub fn get_date_time_options(&self) -> &DateTimeOptions {
+        &self.date_time_options
+    }
+
+    /// Get how strictly `email` values are checked, set with
+    /// `with_email_options`.
+    pub fn get_email_options(&self) -> &EmailOptions {
+        &self.email_options
+    }
+
+    /// Get how strictly `hostname` values are checked, set with
+    /// `with_hostname_options`.
+    pub fn get_hostname_options(&self) -> &HostnameOptions {
+        &self.hostname_options
+    }
+
     /// Get the schema currently being checked against.
     pub fn get_schema(&self) -> &Value {
         &self.schema
@@ -50,36 +166,872 @@ impl<'a> Config<'a> {
     ///
     /// Will use the Draft of JSON schema specified by `draft`. If `draft` is
     /// `None`, it will be automatically determined from the `$schema` entry in
-    /// the given `shema`. If no `$schema` entry is present Draft 7 will be used
-    /// by default.
+    /// the given `shema`, which selects the keywords and their semantics. If
+    /// no `$schema` entry is present Draft 7 will be used by default; see
+    /// `from_schema_with_fallback` to choose another one.
     pub fn from_schema(
         schema: &'a Value,
         draft: Option<&'a dyn schemas::Draft>,
     ) -> Result<Config<'a>, ValidationError> {
+        Config::from_cow(Cow::Borrowed(schema), draft)
+    }
+
+    /// Create a new Config object from a given schema, using the draft of
+    /// JSON schema named by its `$schema` entry, or `fallback` if it has no
+    /// `$schema` entry or names an unknown draft.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// # use serde_json::json;
+    /// # use jsonschema_valid::{schemas, Config};
+    /// let schema = json!({"minimum": 0, "exclusiveMinimum": true});
+    /// let cfg = Config::from_schema_with_fallback(&schema, &schemas::Draft4).unwrap();
+    /// assert_eq!(cfg.get_draft_number(), 4);
+    /// assert!(cfg.validate(&json!(0)).is_err());
+    ///
+    /// let schema = json!({"$schema": "http://json-schema.org/draft-06/schema#"});
+    /// let cfg = Config::from_schema_with_fallback(&schema, &schemas::Draft4).unwrap();
+    /// assert_eq!(cfg.get_draft_number(), 6);
+    /// ```
+    pub fn from_schema_with_fallback(
+        schema: &'a Value,
+        fallback: &'a dyn schemas::Draft,
+    ) -> Result<Config<'a>, ValidationError> {
+        let draft = schemas::draft_from_schema(schema).unwrap_or(fallback);
+        Config::from_schema(schema, Some(draft))
+    }
+
+    /// Create a new Config object that takes ownership of the given schema.
+    ///
+    /// This is otherwise the same as `Config::from_schema`, but the resulting
+    /// `Config` doesn't borrow the schema, so it can be stored and shared
+    /// (e.g. in a `SchemaCache`) independently of where the schema came from.
+    pub fn from_owned_schema(
+        schema: Value,
+        draft: Option<&'a dyn schemas::Draft>,
+    ) -> Result<Config<'a>, ValidationError> {
+        Config::from_cow(Cow::Owned(schema), draft)
+    }
+
+    /// Create a new Config object from a schema generated by `schemars`: a
+    /// `RootSchema`, a `Schema` or a `SchemaObject`.
+    ///
+    /// The schema is converted to a `serde_json::Value` directly, without
+    /// going through its text. The draft is determined as in
+    /// `Config::from_schema`; schemas generated by `schemars` name Draft 7
+    /// by default.
+    ///
+    /// This is only available with the `schemars` feature.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// # use serde_json::json;
+    /// use jsonschema_valid::Config;
+    /// use schemars::JsonSchema;
+    ///
+    /// #[derive(JsonSchema)]
+    /// struct Point {
+    ///     x: i32,
+    ///     y: i32,
+    /// }
+    ///
+    /// let schema = schemars::schema_for!(Point);
+    /// let cfg = Config::from_schemars(&schema, None).unwrap();
+    /// assert!(cfg.validate(&json!({"x": 1, "y": 2})).is_ok());
+    /// assert!(cfg.validate(&json!({"x": 1, "y": "2"})).is_err());
+    /// ```
+    #[cfg(feature = "schemars")]
+    pub fn from_schemars<S: SchemarsSchema>(
+        schema: &S,
+        draft: Option<&'a dyn schemas::Draft>,
+    ) -> Result<Config<'a>, ValidationError> {
+        let schema = serde_json::to_value(schema)
+            .map_err(|err| ValidationError::new(&format!("Invalid schema: {}", err)))?;
+        Config::from_owned_schema(schema, draft)
+    }
+
+    fn from_cow(
+        schema: Cow<'a, Value>,
+        draft: Option<&'a dyn schemas::Draft>,
+    ) -> Result<Config<'a>, ValidationError> {
+        let draft = draft
+            .unwrap_or_else(|| schemas::draft_from_schema(&schema).unwrap_or(&schemas::Draft7));
+        let keywords = draft
+            .get_keywords()
+            .iter()
+            .chain(schemas::NEXT_KEYWORDS)
+            .filter_map(|key| {
+                draft
+                    .get_keyword(key)
+                    .or_else(|| schemas::get_next_keyword(key))
+                    .map(|keyword| (key.to_string(), keyword as Box<dyn Keyword + 'a>))
+            })
+            .collect();
         Ok(Config {
+            resolver: Resolver::from_schema_for_draft(&schema, draft.uses_legacy_id())?,
             schema,
-            resolver: Resolver::from_schema(schema)?,
-            draft: draft.unwrap_or_else(|| {
-                schemas::draft_from_schema(schema).unwrap_or_else(|| &schemas::Draft7)
-            }),
+            draft,
+            keywords,
+            formats: HashMap::new(),
+            scheme_resolvers: HashMap::new(),
+            pinned_hashes: HashMap::new(),
+            docs_url: None,
+            keyword_docs_urls: HashMap::new(),
+            formatter: None,
+            error_context: None,
+            redaction: None,
+            error_values: false,
+            epsilon: 0.0,
+            format_mode: if draft.get_draft_number() >= 8 {
+                FormatMode::Annotation
+            } else {
+                FormatMode::default()
+            },
+            date_time_options: DateTimeOptions::default(),
+            email_options: EmailOptions::default(),
+            hostname_options: HostnameOptions::default(),
+            check_examples: false,
+            data_references: false,
+            profile: None,
+            result_cache: None,
+            regexes: RwLock::new(HashMap::new()),
+            vocabularies: HashMap::new(),
+            vocabulary_warnings: Vec::new(),
         })
     }
 
+    /// Resolve relative references and ids in the schema against `url`, as
+    /// if the schema had been loaded from there, rather than against a
+    /// placeholder. This is needed for schemas loaded from strings or memory
+    /// without an absolute `$id`, that refer to documents next to them.
+    ///
+    /// Documents already added with `with_document` are kept.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// # use serde_json::json;
+    /// # use jsonschema_valid::{schemas, Config};
+    /// let schema = json!({"properties": {"id": {"$ref": "common.json#/definitions/id"}}});
+    /// let base = url::Url::parse("https://example.com/schemas/order.json").unwrap();
+    /// let common = url::Url::parse("https://example.com/schemas/common.json").unwrap();
+    ///
+    /// let cfg = Config::from_schema(&schema, Some(&schemas::Draft7))
+    ///     .unwrap()
+    ///     .with_base_url(&base)
+    ///     .unwrap()
+    ///     .with_document(&common, json!({"definitions": {"id": {"type": "integer"}}}))
+    ///     .unwrap();
+    /// assert!(cfg.validate(&json!({"id": 1})).is_ok());
+    /// assert!(cfg.validate(&json!({"id": "1"})).is_err());
+    /// ```
+    pub fn with_base_url(mut self, url: &url::Url) -> Result<Self, ValidationError> {
+        self.resolver = self.resolver.rebase(&self.schema, url)?;
+        Ok(self)
+    }
+
+    /// Make the schema document `document` available to references by the
+    /// URL `url`, and by the ids in it.
+    ///
+    /// From draft 2020-12 on, if `document` is the metaschema that the
+    /// `$schema` of the schema names, only the keywords of the vocabularies
+    /// it requires in `$vocabulary` take part in validation, and `format` is
+    /// asserted if it requires the format-assertion vocabulary; see the
+    /// `vocabulary` module. This fails if the metaschema requires a
+    /// vocabulary that is neither a standard one nor registered with
+    /// `with_vocabulary`.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// # use serde_json::json;
+    /// # use jsonschema_valid::{schemas, Config};
+    /// let schema = json!({
+    ///     "$schema": "https://example.com/structure",
+    ///     "properties": {"id": {"type": "integer"}}
+    /// });
+    /// let metaschema = json!({
+    ///     "$schema": "https://json-schema.org/draft/2020-12/schema",
+    ///     "$vocabulary": {
+    ///         "https://json-schema.org/draft/2020-12/vocab/core": true,
+    ///         "https://json-schema.org/draft/2020-12/vocab/applicator": true
+    ///     }
+    /// });
+    /// let url = url::Url::parse("https://example.com/structure").unwrap();
+    /// let cfg = Config::from_schema(&schema, Some(&schemas::Draft202012))
+    ///     .unwrap()
+    ///     .with_document(&url, metaschema)
+    ///     .unwrap();
+    /// // `type` is in the validation vocabulary, which isn't used.
+    /// assert!(cfg.validate(&json!({"id": "1"})).is_ok());
+    /// ```
+    pub fn with_document(
+        mut self,
+        url: &url::Url,
+        document: Value,
+    ) -> Result<Self, ValidationError> {
+        if self.get_draft_number() >= 9 && self.is_metaschema(url) {
+            if let Some(Value::Object(vocabularies)) = document.get("$vocabulary") {
+                self.use_vocabularies(vocabularies)?;
+            }
+        }
+        self.resolver.add_document(url, document)?;
+        Ok(self)
+    }
+
+    /// Whether `url` is the `$schema` of the schema.
+    fn is_metaschema(&self, url: &url::Url) -> bool {
+        let metaschema = self.schema.get("$schema").and_then(Value::as_str);
+        match metaschema.and_then(|metaschema| url::Url::parse(metaschema).ok()) {
+            Some(mut metaschema) => {
+                let mut url = url.clone();
+                metaschema.set_fragment(None);
+                url.set_fragment(None);
+                metaschema == url
+            }
+            None => false,
+        }
+    }
+
+    /// Leave only the keywords of the draft that the vocabularies required
+    /// by a metaschema define, given its `$vocabulary`, and add those of the
+    /// required vocabularies registered with `with_vocabulary`.
+    fn use_vocabularies(
+        &mut self,
+        vocabularies: &serde_json::Map<String, Value>,
+    ) -> Result<(), ValidationError> {
+        let mut used: Vec<&str> = Vec::new();
+        for (uri, required) in vocabularies {
+            if required != &Value::Bool(true) {
+                self.vocabulary_warnings.push(ValidationError::new(&format!(
+                    "Optional vocabulary {} is not used",
+                    uri
+                )));
+                continue;
+            }
+            let standard = schemas::DRAFT202012_VOCABULARIES
+                .iter()
+                .find(|(vocabulary, _)| vocabulary == uri);
+            if let Some((_, keywords)) = standard {
+                used.extend(keywords.iter());
+            } else if let Some(vocabulary) = self.vocabularies.remove(uri) {
+                self.keywords.extend(vocabulary.into_keywords());
+            } else {
+                return Err(ValidationError::new(&format!(
+                    "Unsupported vocabulary {} is required",
+                    uri
+                )));
+            }
+            if uri == "https://json-schema.org/draft/2020-12/vocab/format-assertion" {
+                self.format_mode = FormatMode::Assertion;
+            }
+        }
+        let draft_keywords = self.draft.get_keywords();
+        let vocabulary_keywords: Vec<&str> = schemas::DRAFT202012_VOCABULARIES
+            .iter()
+            .flat_map(|(_, keywords)| keywords.iter().copied())
+            .collect();
+        self.keywords.retain(|key, _| {
+            let key = key.as_str();
+            !(draft_keywords.contains(&key) || vocabulary_keywords.contains(&key))
+                || used.contains(&key)
+        });
+        Ok(())
+    }
+
+    /// Make the custom vocabulary `vocabulary` available to metaschemas that
+    /// require it in their `$vocabulary`. It must be registered before the
+    /// metaschema is added with `with_document`; see the `vocabulary`
+    /// module.
+    pub fn with_vocabulary(mut self, vocabulary: Vocabulary<'a>) -> Self {
+        self.vocabularies
+            .insert(vocabulary.get_uri().to_string(), vocabulary);
+        self
+    }
+
+    /// Get the warnings about the vocabularies of the metaschema that
+    /// aren't used, because they are optional.
+    pub fn get_vocabulary_warnings(&self) -> &[ValidationError] {
+        &self.vocabulary_warnings
+    }
+
+    /// Fetch the documents that the schema references by URLs of the given
+    /// scheme with `resolver`, and the documents they reference in turn,
+    /// with this or the previously registered resolvers.
+    ///
+    /// Fails if any of them can't be fetched, or doesn't match the hash
+    /// pinned with `with_pinned_hash`. References to documents with URLs of
+    /// other schemes fail to resolve, naming the scheme.
+    pub fn with_scheme_resolver<R: SchemeResolver + 'a>(
+        mut self,
+        scheme: &str,
+        resolver: R,
+    ) -> Result<Self, ValidationError> {
+        self.scheme_resolvers
+            .insert(scheme.to_string(), Box::new(resolver));
+        self.resolver.add_scheme(scheme);
+        loop {
+            let missing: Vec<(url::Url, &dyn SchemeResolver)> = self
+                .resolver
+                .missing_documents()
+                .into_iter()
+                .filter_map(|url| {
+                    let resolver = self.scheme_resolvers.get(url.scheme())?;
+                    Some((url, resolver.as_ref() as &dyn SchemeResolver))
+                })
+                .collect();
+            if missing.is_empty() {
+                return Ok(self);
+            }
+            let documents = missing
+                .into_iter()
+                .map(|(url, resolver)| Ok((resolver.resolve(&url)?, url)))
+                .collect::<Result<Vec<_>, ValidationError>>()?;
+            for (document, url) in documents {
+                if let Some(expected) = self.pinned_hashes.get(url.as_str()) {
+                    let actual = cache::content_hash(&document);
+                    if actual != *expected {
+                        return Err(ValidationError::new(&format!(
+                            "Document {} doesn't match its pinned hash: expected {}, got {}",
+                            url, expected, actual
+                        )));
+                    }
+                }
+                self.resolver.add_document(&url, document)?;
+            }
+        }
+    }
+
+    /// Require the document at `url`, when fetched by a `SchemeResolver`, to
+    /// have the SHA-256 `hash` computed by `cache::content_hash`, so a change
+    /// to an external schema can't silently change what's valid.
+    ///
+    /// Documents are fetched by `with_scheme_resolver`, so their hashes must
+    /// be pinned before it's called.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// # use serde_json::{json, Value};
+    /// # use jsonschema_valid::{schemas, Config, ValidationError};
+    /// use jsonschema_valid::cache;
+    ///
+    /// let schema = json!({"$ref": "registry://user.json"});
+    /// let fetch = |_: &url::Url| -> Result<Value, ValidationError> {
+    ///     Ok(json!({"type": "object", "required": ["name"]}))
+    /// };
+    /// let url = url::Url::parse("registry://user.json").unwrap();
+    /// let pinned = cache::content_hash(&json!({"required": ["name"], "type": "object"}));
+    ///
+    /// let cfg = Config::from_schema(&schema, Some(&schemas::Draft7))
+    ///     .unwrap()
+    ///     .with_pinned_hash(&url, &pinned)
+    ///     .with_scheme_resolver("registry", fetch);
+    /// assert!(cfg.is_ok());
+    ///
+    /// let cfg = Config::from_schema(&schema, Some(&schemas::Draft7))
+    ///     .unwrap()
+    ///     .with_pinned_hash(&url, &cache::content_hash(&json!({"type": "object"})))
+    ///     .with_scheme_resolver("registry", fetch);
+    /// assert!(cfg.is_err());
+    /// ```
+    pub fn with_pinned_hash(mut self, url: &url::Url, hash: &str) -> Self {
+        let mut url = url.clone();
+        url.set_fragment(None);
+        self.pinned_hashes
+            .insert(url.to_string(), hash.to_ascii_lowercase());
+        self
+    }
+
+    /// Fetch the documents that the schema references by `http` and `https`
+    /// URLs with `resolver`; see `with_scheme_resolver`.
+    #[cfg(feature = "remote")]
+    pub fn with_http_resolver(self, resolver: &'a HttpResolver) -> Result<Self, ValidationError> {
+        self.with_scheme_resolver("http", resolver)?
+            .with_scheme_resolver("https", resolver)
+    }
+
+    /// Tolerate a difference of up to `epsilon` when comparing numbers in
+    /// `minimum`, `maximum`, `const` and `enum`, for instances produced by
+    /// systems that introduce rounding errors, e.g. `0.30000000000000004`
+    /// for `0.3`.
+    ///
+    /// By default, numbers are compared exactly, as the specification
+    /// requires.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// # use serde_json::json;
+    /// # use jsonschema_valid::{schemas, Config};
+    /// let schema = json!({"maximum": 0.3, "enum": [0.3, 0.5]});
+    /// let instance = json!(0.1 + 0.2);
+    ///
+    /// let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+    /// assert!(cfg.validate(&instance).is_err());
+    /// let cfg = cfg.with_epsilon(1e-9);
+    /// assert!(cfg.validate(&instance).is_ok());
+    /// ```
+    pub fn with_epsilon(mut self, epsilon: f64) -> Self {
+        self.epsilon = epsilon.abs();
+        self
+    }
+
+    /// Check the `date-time` and `time` formats as strictly as `options`
+    /// say, rather than as RFC 3339 does.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// # use serde_json::json;
+    /// # use jsonschema_valid::{schemas, Config};
+    /// use jsonschema_valid::formats::DateTimeOptions;
+    ///
+    /// let schema = json!({"format": "date-time"});
+    /// let instance = json!("2024-02-29 12:30:00.123456Z");
+    ///
+    /// let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+    /// assert!(cfg.validate(&instance).is_err());
+    /// let cfg = cfg.with_date_time_options(DateTimeOptions::new().with_space_separator(true));
+    /// assert!(cfg.validate(&instance).is_ok());
+    /// let cfg = cfg.with_date_time_options(
+    ///     DateTimeOptions::new()
+    ///         .with_space_separator(true)
+    ///         .with_max_fraction_digits(3),
+    /// );
+    /// assert!(cfg.validate(&instance).is_err());
+    /// ```
+    pub fn with_date_time_options(mut self, options: DateTimeOptions) -> Self {
+        self.date_time_options = options;
+        self
+    }
+
+    /// Choose whether values that fail the check of their `format` are
+    /// invalid, only reported as warnings, or not checked at all, without
+    /// changing the schema. `ValidationOptions::with_format_assertion`
+    /// overrides this for a single validation.
+    ///
+    /// Formats are asserted by default up to draft 7, and only annotations
+    /// from draft 2019-09 on, as that draft specifies.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// # use serde_json::json;
+    /// # use jsonschema_valid::{schemas, Config};
+    /// use jsonschema_valid::formats::FormatMode;
+    ///
+    /// let schema = json!({"properties": {"email": {"format": "email"}}});
+    /// let instance = json!({"email": "nobody"});
+    /// let cfg = Config::from_schema(&schema, Some(&schemas::Draft7))
+    ///     .unwrap()
+    ///     .with_format_mode(FormatMode::Annotation);
+    /// assert!(cfg.validate(&instance).is_ok());
+    /// let warnings = cfg.format_warnings(&instance);
+    /// assert_eq!(warnings[0].instance_path(), ["email"]);
+    /// ```
+    pub fn with_format_mode(mut self, mode: FormatMode) -> Self {
+        self.format_mode = mode;
+        self
+    }
+
+    /// Check the `email` format as strictly as `options` say.
+    pub fn with_email_options(mut self, options: EmailOptions) -> Self {
+        self.email_options = options;
+        self
+    }
+
+    /// Check the `hostname` format, and the domains of `email` values, as
+    /// strictly as `options` say.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// # use serde_json::json;
+    /// # use jsonschema_valid::{schemas, Config};
+    /// use jsonschema_valid::formats::HostnameOptions;
+    ///
+    /// let schema = json!({"format": "hostname"});
+    /// let instance = json!("_dmarc.example.com.");
+    ///
+    /// let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+    /// assert!(cfg.validate(&instance).is_err());
+    /// let cfg = cfg.with_hostname_options(
+    ///     HostnameOptions::new()
+    ///         .with_underscores(true)
+    ///         .with_trailing_dot(true),
+    /// );
+    /// assert!(cfg.validate(&instance).is_ok());
+    /// ```
+    pub fn with_hostname_options(mut self, options: HostnameOptions) -> Self {
+        self.hostname_options = options;
+        self
+    }
+
+    /// Only validate the keywords selected by `profile`; see the `profile`
+    /// module.
+    pub fn with_profile(mut self, profile: Profile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Remember the results of validating the last `capacity` distinct
+    /// payloads with `validate_payload`, so that validating a byte-identical
+    /// payload again, e.g. a retried webhook delivery, returns the same
+    /// result without parsing or validating it.
+    ///
+    /// A `capacity` of 0 disables the cache.
+    pub fn with_result_cache(mut self, capacity: usize) -> Self {
+        self.result_cache = NonZeroUsize::new(capacity).map(|x| Mutex::new(LruCache::new(x)));
+        self
+    }
+
+    /// Validate the keyword `name` with the given implementation, replacing
+    /// the one from the draft in use, if any.
+    ///
+    /// Errors from the keyword get the same paths and keyword information
+    /// as errors from the built-in keywords.
+    pub fn with_keyword<K: Keyword + 'a>(mut self, name: &str, keyword: K) -> Self {
+        self.keywords.insert(name.to_string(), Box::new(keyword));
+        self
+    }
+
+    /// Check the format `name` with `checker`, replacing the one from the
+    /// draft in use, if any. Other formats are unaffected.
+    ///
+    /// This also adds formats that no draft defines, such as identifiers
+    /// specific to an application. Formats without a checker are ignored,
+    /// as the specification requires.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// # use serde_json::json;
+    /// # use jsonschema_valid::{schemas, Config};
+    /// let schema = json!({"format": "uri"});
+    /// let instance = json!("http://example.com");
+    ///
+    /// let cfg = Config::from_schema(&schema, Some(&schemas::Draft7))
+    ///     .unwrap()
+    ///     .with_format("uri", |_cfg, value| value.starts_with("https://"));
+    /// assert!(cfg.validate(&instance).is_err());
+    ///
+    /// let cfg = cfg.without_format("uri");
+    /// assert!(cfg.validate(&json!("not a uri")).is_ok());
+    /// ```
+    pub fn with_format(mut self, name: &str, checker: FormatChecker) -> Self {
+        self.formats.insert(name.to_string(), Some(checker));
+        self
+    }
+
+    /// Don't check the format `name`, even if the draft in use does: every
+    /// string satisfies it.
+    pub fn without_format(mut self, name: &str) -> Self {
+        self.formats.insert(name.to_string(), None);
+        self
+    }
+
+    /// Attach a documentation URL to every validation error.
+    ///
+    /// The string `{keyword}` in `template` is replaced by the keyword that
+    /// failed, e.g. `https://example.com/schema-errors/{keyword}`.
+    pub fn with_docs_url(mut self, template: &str) -> Self {
+        self.docs_url = Some(template.to_string());
+        self
+    }
+
+    /// Attach the given documentation URL to validation errors from `keyword`,
+    /// taking precedence over the template set with `with_docs_url`.
+    pub fn with_keyword_docs_url(mut self, keyword: &str, url: &str) -> Self {
+        self.keyword_docs_urls
+            .insert(keyword.to_string(), url.to_string());
+        self
+    }
+
+    /// Use the given `MessageFormatter` to produce the messages of all
+    /// validation errors.
+    pub fn with_formatter<F: MessageFormatter + 'a>(mut self, formatter: F) -> Self {
+        self.formatter = Some(Box::new(formatter));
+        self
+    }
+
+    /// Attach information about the enclosing object to errors that occur
+    /// inside objects, so that they can be understood without the original
+    /// instance at hand.
+    pub fn with_error_context(mut self, mode: ContextMode) -> Self {
+        self.error_context = Some(mode);
+        self
+    }
+
+    /// Keep instance values out of error messages and error details, so
+    /// that errors about user-submitted data can be logged without leaking
+    /// personal information. Errors still contain the paths to the failing
+    /// values and the schema constraints they failed.
+    pub fn with_redaction(mut self, redaction: Redaction) -> Self {
+        self.redaction = Some(redaction);
+        self
+    }
+
+    /// Record the value of the failing keyword and the failing part of the
+    /// instance in errors, as returned by `ValidationError::keyword_value`
+    /// and `ValidationError::instance`. Both are copied into each error, so
+    /// they are only recorded when asked for, or with `with_redaction`,
+    /// which needs them.
+    pub fn with_error_values(mut self) -> Self {
+        self.error_values = true;
+        self
+    }
+
+    /// Whether errors record the value of the failing keyword and instance.
+    pub(crate) fn records_error_values(&self) -> bool {
+        self.error_values || self.redaction.is_some()
+    }
+
+    /// Also check every `default` and `examples` entry in the schema against
+    /// the subschema it appears in when calling `validate_schema`.
+    pub fn with_example_checks(mut self) -> Self {
+        self.check_examples = true;
+        self
+    }
+
+    /// Check that strings decode according to their `contentEncoding`,
+    /// parse according to their `contentMediaType` and that the parsed
+    /// contents are valid under their `contentSchema`; see the `content`
+    /// module. Since draft 2019-09 these keywords are annotations, so they
+    /// aren't checked by default.
+    pub fn with_content_checks(mut self) -> Self {
+        self.keywords.insert(
+            "contentEncoding".to_string(),
+            Box::new(keywords::contentEncoding),
+        );
+        self.keywords.insert(
+            "contentMediaType".to_string(),
+            Box::new(keywords::contentMediaType),
+        );
+        self.keywords.insert(
+            "contentSchema".to_string(),
+            Box::new(keywords::contentSchema),
+        );
+        self
+    }
+
+    /// Take the values of keywords written as `{"$data": pointer}` from the
+    /// instance; see the `data` module.
+    pub fn with_data_references(mut self) -> Self {
+        self.data_references = true;
+        self
+    }
+
+    /// Whether `$data` references are resolved.
+    pub fn get_data_references(&self) -> bool {
+        self.data_references
+    }
+
+    /// The regular expression `pattern` from the schema, compiled once and
+    /// reused by later validations.
+    pub(crate) fn regex(&self, pattern: &str) -> Result<Regex, regex::Error> {
+        if let Some(result) = self
+            .regexes
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(pattern)
+        {
+            return result.clone();
+        }
+        let result = Regex::new(pattern);
+        self.regexes
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(pattern.to_string(), result.clone());
+        result
+    }
+
+    /// Apply the settings that affect how errors are reported to an error
+    /// that occurred during validation.
+    pub(crate) fn finish_error(&self, error: ValidationError) -> ValidationError {
+        let error = error.map_causes(|cause| self.finish_error(cause));
+        let error = match self.redaction {
+            Some(redaction) => error.redact(redaction),
+            None => error,
+        };
+        let error = match &self.formatter {
+            Some(formatter) => {
+                let msg = formatter.format_message(&error);
+                error.with_message(msg)
+            }
+            None => error,
+        };
+        let docs_url = match error.keyword() {
+            Some(keyword) => match self.keyword_docs_urls.get(keyword) {
+                Some(url) => Some(url.clone()),
+                None => self
+                    .docs_url
+                    .as_ref()
+                    .map(|template| template.replace("{keyword}", keyword)),
+            },
+            None => None,
+        };
+        match docs_url {
+            Some(docs_url) => error.with_docs_url(docs_url),
+            None => error,
+        }
+    }
+
+    /// Parse `payload` as JSON and validate it against the schema.
+    ///
+    /// If enabled with `with_result_cache`, the result for a payload seen
+    /// recently is returned from the cache instead. Payloads that aren't
+    /// valid JSON aren't cached.
+    pub fn validate_payload(&self, payload: &[u8]) -> Result<(), Errors> {
+        let cache = match &self.result_cache {
+            Some(cache) => cache,
+            None => return self.validate_payload_uncached(payload),
+        };
+        let key: [u8; 32] = Sha256::digest(payload).into();
+        if let Some(result) = cache
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&key)
+        {
+            return result.clone().map_err(Errors::Invalid);
+        }
+        let result = self.validate_payload_uncached(payload);
+        if let Ok(()) | Err(Errors::Invalid(_)) = result {
+            let cached = match &result {
+                Err(Errors::Invalid(errors)) => Err(errors.clone()),
+                _ => Ok(()),
+            };
+            cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .put(key, cached);
+        }
+        result
+    }
+
+    fn validate_payload_uncached(&self, payload: &[u8]) -> Result<(), Errors> {
+        let instance: Value = serde_json::from_slice(payload).map_err(Errors::InstanceSyntax)?;
+        crate::validate(self, &instance).map_err(|errors| Errors::Invalid(errors.collect()))
+    }
+
     /// Validate the given JSON instance against the schema.
     pub fn validate(&'a self, instance: &'a Value) -> Result<(), ErrorIterator<'a>> {
         crate::validate(self, instance)
     }
 
+    /// Look up the subschema that `reference` points to, e.g.
+    /// `#/$defs/Address`, to validate instances against it; see the
+    /// `fragment` module.
+    pub fn fragment(&'a self, reference: &str) -> Result<Fragment<'a>, ValidationError> {
+        Fragment::new(self, reference)
+    }
+
+    /// Validate each of `instances` against the schema, and report which are
+    /// invalid and why; see the `batch` module.
+    pub fn validate_batch(&self, instances: &[Value]) -> BatchReport {
+        BatchReport::new(
+            instances
+                .iter()
+                .map(|instance| match crate::validate(self, instance) {
+                    Ok(()) => Vec::new(),
+                    Err(errors) => errors.collect(),
+                })
+                .collect(),
+        )
+    }
+
+    /// Validate the given JSON instance against the schema, with only the
+    /// keywords selected by `profile`, instead of the profile of the
+    /// `Config`, if any.
+    pub fn validate_with_profile(
+        &'a self,
+        instance: &'a Value,
+        profile: &'a Profile,
+    ) -> Result<(), ErrorIterator<'a>> {
+        crate::validate_value(
+            self,
+            instance,
+            Context::new_from(self.get_schema()).with_profile(profile),
+        )
+    }
+
+    /// The values of the given JSON instance that fail the check of their
+    /// `format`, as errors, if formats are annotations according to
+    /// `with_format_mode`. Checks in subschemas that don't apply, such as
+    /// those of `anyOf` branches the instance is invalid against, are left
+    /// out. Always empty in the other modes.
+    pub fn format_warnings(&'a self, instance: &'a Value) -> Vec<ValidationError> {
+        if self.format_mode != FormatMode::Annotation {
+            return Vec::new();
+        }
+        let context = Context::new_from(self.get_schema()).with_options(&options::ASSERT_FORMATS);
+        match crate::validate_value(self, instance, context) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .filter(|error| error.keyword() == Some("format"))
+                .collect(),
+        }
+    }
+
+    /// Validate the given JSON instance against the schema, with `options`
+    /// overriding those of the `Config` for this call; see the `options`
+    /// module.
+    pub fn validate_with_options(
+        &'a self,
+        instance: &'a Value,
+        options: &'a ValidationOptions,
+    ) -> Result<(), ErrorIterator<'a>> {
+        let context = Context::new_from(self.get_schema()).with_options(options);
+        match crate::validate_value(self, instance, context) {
+            Ok(()) => Ok(()),
+            Err(errors) => Err(Box::new(errors.take(options.error_limit()))),
+        }
+    }
+
+    /// Validate the given JSON instance of any type implementing `JsonNode`
+    /// against the schema.
+    pub fn validate_node(&'a self, instance: &'a dyn JsonNode) -> Result<(), ErrorIterator<'a>> {
+        crate::validate_node(self, instance)
+    }
+
     /// Validate the schema in this Config object against the metaschema.
+    ///
+    /// If enabled with `with_example_checks`, this also reports the errors
+    /// from `validate_examples`.
     pub fn validate_schema(&'a self) -> Result<(), ErrorIterator<'a>> {
-        let mut errors = validators::descend(
+        let errors = keywords::descend(
             self,
             self.get_schema(),
             self.get_metaschema(),
             None,
             Context::new_from(self.get_metaschema()),
-        )
-        .peekable();
+        );
+        let errors: ErrorIterator<'a> = if self.check_examples {
+            Box::new(errors.chain(self.example_errors()))
+        } else {
+            errors
+        };
+        let mut errors = errors.map(move |err| self.finish_error(err)).peekable();
+
+        if errors.peek().is_none() {
+            Ok(())
+        } else {
+            Err(Box::new(errors))
+        }
+    }
+
+    /// Check that every `default` and every entry in `examples` in the
+    /// schema is valid against the subschema it appears in.
+    ///
+    /// The instance path of each error is the location of the offending
+    /// value within the schema, e.g. `properties/port/default`.
+    pub fn validate_examples(&'a self) -> Result<(), ErrorIterator<'a>> {
+        let mut errors = self
+            .example_errors()
+            .map(move |err| self.finish_error(err))
+            .peekable();
 
         if errors.peek().is_none() {
             Ok(())
@@ -87,4 +1039,238 @@ impl<'a> Config<'a> {
             Err(Box::new(errors))
         }
     }
+
+    /// Check that every `$ref` in the schema can be resolved.
+    ///
+    /// The instance path of each error is the location of the `$ref` within
+    /// the schema.
+    pub fn validate_refs(&'a self) -> Result<(), ErrorIterator<'a>> {
+        let errors = self.ref_errors();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Box::new(errors.into_iter()))
+        }
+    }
+
+    /// Do all the work that doesn't depend on the instance up front, so that
+    /// the first validation doesn't pay for it, and broken schemas are found
+    /// before any instance is validated.
+    ///
+    /// This reports the documents that are referenced, by the schema or by
+    /// other documents, but weren't loaded; validates the schema and each
+    /// loaded document against the metaschema of its draft; checks that
+    /// every `$ref` in them resolves; and compiles the regular expressions of
+    /// every `pattern` and `patternProperties`, which later validations
+    /// reuse.
+    ///
+    /// The instance path of each error is the location of the offending value
+    /// within its document. The messages of errors in a loaded document start
+    /// with its URL.
+    ///
+    /// ## Example:
+    ///
+    /// ```rust
+    /// # use serde_json::json;
+    /// # use jsonschema_valid::{schemas, Config};
+    /// let schema = json!({"properties": {"a": {"$ref": "other.json"}}});
+    /// let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+    /// assert!(cfg.warm_up().is_err());
+    ///
+    /// let url = url::Url::parse("document:///other.json").unwrap();
+    /// let other = json!({"patternProperties": {"^x-": {"type": "string"}}});
+    /// let cfg = cfg.with_document(&url, other).unwrap();
+    /// assert!(cfg.warm_up().is_ok());
+    /// ```
+    pub fn warm_up(&'a self) -> Result<(), ErrorIterator<'a>> {
+        let resolver = self.get_resolver();
+        let mut errors: Vec<ValidationError> = resolver
+            .missing_documents()
+            .into_iter()
+            .map(|url| {
+                ValidationError::new(&format!("Document {} is referenced but not loaded", url))
+            })
+            .collect();
+        errors.extend(keywords::descend(
+            self,
+            self.get_schema(),
+            self.get_metaschema(),
+            None,
+            Context::new_from(self.get_metaschema()),
+        ));
+        errors.extend(self.ref_errors());
+        self.compile_patterns(self.get_schema());
+
+        let mut documents: Vec<(&str, &Value)> = resolver.documents().collect();
+        documents.sort_by_key(|(url, _)| *url);
+        for (url, document) in documents {
+            let metaschema = schemas::draft_from_schema(document)
+                .unwrap_or(self.draft)
+                .get_schema();
+            let mut document_errors: Vec<ValidationError> = keywords::descend(
+                self,
+                document,
+                metaschema,
+                None,
+                Context::new_from(metaschema),
+            )
+            .collect();
+            if let Ok(base_url) = url::Url::parse(url) {
+                let mut refs = Vec::new();
+                let legacy_ids = resolver::uses_legacy_ids(document, self.draft.uses_legacy_id());
+                resolver::find_refs(
+                    document,
+                    &base_url,
+                    legacy_ids,
+                    &mut String::new(),
+                    &mut refs,
+                );
+                for (pointer, ref_, target) in refs {
+                    let err = match target {
+                        Ok(target) => {
+                            match resolver.resolve_fragment(
+                                target.as_str(),
+                                &Context::new(),
+                                &Value::Null,
+                            ) {
+                                Ok(_) => continue,
+                                Err(err) => err.message().to_string(),
+                            }
+                        }
+                        Err(err) => err.to_string(),
+                    };
+                    document_errors.push(at_pointer(
+                        ValidationError::new(&format!("Can't resolve reference {}: {}", ref_, err)),
+                        &pointer,
+                    ));
+                }
+            }
+            self.compile_patterns(document);
+            errors.extend(document_errors.into_iter().map(|err| {
+                let message = format!("In {}: {}", url, err.message());
+                err.with_message(message)
+            }));
+        }
+        let errors: Vec<ValidationError> = errors
+            .into_iter()
+            .map(|err| self.finish_error(err))
+            .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Box::new(errors.into_iter()))
+        }
+    }
+
+    fn ref_errors(&self) -> Vec<ValidationError> {
+        let mut refs = Vec::new();
+        find_refs(self.get_schema(), &mut String::new(), &mut refs);
+        let context = Context::new_from(self.get_schema());
+        refs.into_iter()
+            .filter_map(|(pointer, ref_)| {
+                let err = self
+                    .get_resolver()
+                    .resolve_fragment(ref_, &context, self.get_schema())
+                    .err()?;
+                let err = ValidationError::new(&format!(
+                    "Can't resolve reference {}: {}",
+                    ref_,
+                    err.message()
+                ));
+                Some(at_pointer(err, &pointer))
+            })
+            .collect()
+    }
+
+    /// Compile the regular expressions of `schema` for later validations.
+    fn compile_patterns(&self, schema: &Value) {
+        let mut patterns = Vec::new();
+        find_patterns(schema, &mut patterns);
+        for pattern in patterns {
+            let _ = self.regex(pattern);
+        }
+    }
+
+    fn example_errors(&'a self) -> ErrorIterator<'a> {
+        let mut examples = Vec::new();
+        find_examples(self.get_schema(), &mut String::new(), &mut examples);
+        Box::new(
+            examples
+                .into_iter()
+                .flat_map(move |(pointer, subschema, example)| {
+                    keywords::descend(
+                        self,
+                        example,
+                        subschema,
+                        None,
+                        Context::new_from(self.get_schema()),
+                    )
+                    .map(move |err| at_pointer(err, &pointer))
+                }),
+        )
+    }
+}
+
+/// Place `err` at the JSON pointer `pointer` of the instance.
+fn at_pointer(err: ValidationError, pointer: &str) -> ValidationError {
+    util::pointer_tokens(pointer)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .fold(err, |err, token| err.instance_ctx(token))
+}
+
+/// Collect the `$ref` of every subschema, along with its JSON pointer.
+fn find_refs<'a>(schema: &'a Value, pointer: &mut String, refs: &mut Vec<(String, &'a str)>) {
+    if let Some(Value::String(ref_)) = schema.get("$ref") {
+        refs.push((format!("{}/$ref", pointer), ref_));
+    }
+    util::for_each_subschema(schema, &mut |path, subschema| {
+        let len = pointer.len();
+        pointer.push('/');
+        pointer.push_str(&path);
+        find_refs(subschema, pointer, refs);
+        pointer.truncate(len);
+    });
+}
+
+/// Collect the regular expressions of every `pattern` and
+/// `patternProperties`.
+fn find_patterns<'a>(schema: &'a Value, patterns: &mut Vec<&'a str>) {
+    if let Some(Value::String(pattern)) = schema.get("pattern") {
+        patterns.push(pattern);
+    }
+    if let Some(Value::Object(properties)) = schema.get("patternProperties") {
+        patterns.extend(properties.keys().map(String::as_str));
+    }
+    util::for_each_subschema(schema, &mut |_, subschema| {
+        find_patterns(subschema, patterns)
+    });
+}
+
+/// Collect the `default` and `examples` values of every subschema, along
+/// with their JSON pointer and the subschema they belong to.
+fn find_examples<'a>(
+    schema: &'a Value,
+    pointer: &mut String,
+    examples: &mut Vec<(String, &'a Value, &'a Value)>,
+) {
+    if let Value::Object(object) = schema {
+        if let Some(default) = object.get("default") {
+            examples.push((format!("{}/default", pointer), schema, default));
+        }
+        if let Some(Value::Array(values)) = object.get("examples") {
+            for (index, example) in values.iter().enumerate() {
+                examples.push((format!("{}/examples/{}", pointer, index), schema, example));
+            }
+        }
+    }
+    util::for_each_subschema(schema, &mut |path, subschema| {
+        let len = pointer.len();
+        pointer.push('/');
+        pointer.push_str(&path);
+        find_examples(subschema, pointer, examples);
+        pointer.truncate(len);
+    });
 }
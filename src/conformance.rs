@@ -0,0 +1,151 @@
+//! Running the official [JSON Schema Test
+//! Suite](https://github.com/json-schema-org/JSON-Schema-Test-Suite) against
+//! this crate.
+//!
+//! The report lists every test case along with whether this crate gave the
+//! expected result, so it's easy to see which optional behaviors (formats,
+//! remote references, ...) a given draft and configuration support.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Map, Value};
+
+use crate::config::Config;
+use crate::schemas;
+
+/// The result of running a single test case from the test suite.
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    /// The test file, relative to the directory the suite was run from.
+    pub file: String,
+    /// The description of the group of tests sharing a schema.
+    pub group: String,
+    /// The description of the individual test.
+    pub description: String,
+    /// Whether the test expects the data to be valid.
+    pub expected_valid: bool,
+    /// Whether this crate agreed with the expected result.
+    pub passed: bool,
+}
+
+/// The results of running a directory of the test suite.
+#[derive(Debug, Clone)]
+pub struct ConformanceReport {
+    /// The draft number the tests were run with.
+    pub draft: u8,
+    /// The result of each individual test, in file order.
+    pub results: Vec<TestResult>,
+}
+
+impl ConformanceReport {
+    /// The number of tests for which this crate gave the expected result.
+    pub fn passed(&self) -> usize {
+        self.results.iter().filter(|x| x.passed).count()
+    }
+
+    /// The number of tests for which this crate gave the wrong result.
+    pub fn failed(&self) -> usize {
+        self.results.len() - self.passed()
+    }
+
+    /// Render the report as a machine-readable JSON document, with per-file
+    /// pass/fail counts and the outcome of every test.
+    pub fn to_json(&self) -> Value {
+        let mut files = Map::new();
+        for result in &self.results {
+            let entry = files
+                .entry(result.file.clone())
+                .or_insert_with(|| json!({"passed": 0, "failed": 0, "tests": []}));
+            let counter = if result.passed { "passed" } else { "failed" };
+            entry[counter] = json!(entry[counter].as_u64().unwrap_or(0) + 1);
+            if let Some(tests) = entry["tests"].as_array_mut() {
+                tests.push(json!({
+                    "group": result.group,
+                    "description": result.description,
+                    "valid": result.expected_valid,
+                    "passed": result.passed,
+                }));
+            }
+        }
+        json!({
+            "draft": self.draft,
+            "passed": self.passed(),
+            "failed": self.failed(),
+            "files": files,
+        })
+    }
+}
+
+/// Run all of the test files in the given directory of the test suite (e.g.
+/// `JSON-Schema-Test-Suite/tests/draft7`), including those in
+/// subdirectories such as `optional`.
+pub fn run_test_suite(dir: &Path, draft: &dyn schemas::Draft) -> io::Result<ConformanceReport> {
+    run_test_suite_with(dir, draft, &|cfg| cfg)
+}
+
+/// Like [`run_test_suite`](fn.run_test_suite.html), but `configure` is
+/// applied to every `Config` before it is used, so the suite can be run
+/// against a specific configuration.
+pub fn run_test_suite_with(
+    dir: &Path,
+    draft: &dyn schemas::Draft,
+    configure: &dyn for<'a> Fn(Config<'a>) -> Config<'a>,
+) -> io::Result<ConformanceReport> {
+    let mut paths = Vec::new();
+    find_test_files(dir, &mut paths)?;
+    paths.sort();
+
+    let mut results = Vec::new();
+    for path in paths {
+        let file = path
+            .strip_prefix(dir)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let json: Value = serde_json::from_reader(fs::File::open(&path)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        for group in json.as_array().into_iter().flatten() {
+            let schema = &group["schema"];
+            for test in group["tests"].as_array().into_iter().flatten() {
+                let expected_valid = test["valid"].as_bool().unwrap_or(false);
+                let valid = match Config::from_schema(schema, Some(draft)) {
+                    Ok(cfg) => configure(cfg).validate(&test["data"]).is_ok(),
+                    Err(_) => !expected_valid,
+                };
+                results.push(TestResult {
+                    file: file.clone(),
+                    group: description(group),
+                    description: description(test),
+                    expected_valid,
+                    passed: valid == expected_valid,
+                });
+            }
+        }
+    }
+
+    Ok(ConformanceReport {
+        draft: draft.get_draft_number(),
+        results,
+    })
+}
+
+fn description(value: &Value) -> String {
+    value["description"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn find_test_files(dir: &Path, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_test_files(&path, paths)?;
+        } else if path.extension().and_then(|x| x.to_str()) == Some("json") {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,117 @@
+//! Checking the encoded contents of strings.
+//!
+//! Draft 7 describes strings that carry other data with `contentEncoding`,
+//! e.g. `base64`, and `contentMediaType`, e.g. `application/json`, and
+//! 2019-09 adds `contentSchema` for the parsed contents. Later drafts treat
+//! these as annotations, so they are only checked after
+//! `Config::with_content_checks`:
+//!
+//! - `contentEncoding`: the string decodes cleanly. `base64` is checked, and
+//!   `7bit`, `8bit` and `binary` leave the string as it is; other encodings
+//!   are not checked.
+//! - `contentMediaType`: the contents, decoded according to the
+//!   `contentEncoding` next to it, parse as the media type. JSON media types,
+//!   i.e. `application/json` and those ending in `+json`, are checked; other
+//!   media types are not.
+//! - `contentSchema`: the parsed contents are valid under the subschema. Its
+//!   errors are the causes of a single error about the string, so that their
+//!   paths are relative to the decoded document.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::{schemas, Config};
+//! let schema = json!({
+//!     "properties": {
+//!         "payload": {
+//!             "contentEncoding": "base64",
+//!             "contentMediaType": "application/json",
+//!             "contentSchema": {"properties": {"id": {"type": "integer"}}}
+//!         }
+//!     }
+//! });
+//! let cfg = Config::from_schema(&schema, Some(&schemas::Draft7))
+//!     .unwrap()
+//!     .with_content_checks();
+//!
+//! // {"id": 1}
+//! assert!(cfg.validate(&json!({"payload": "eyJpZCI6IDF9"})).is_ok());
+//! // {"id":
+//! let errors: Vec<_> = cfg.validate(&json!({"payload": "eyJpZCI6"})).unwrap_err().collect();
+//! assert_eq!(errors[0].keyword(), Some("contentMediaType"));
+//! let errors: Vec<_> = cfg.validate(&json!({"payload": "not base64!"})).unwrap_err().collect();
+//! assert_eq!(errors[0].keyword(), Some("contentEncoding"));
+//!
+//! // {"id": "1"}
+//! let errors: Vec<_> = cfg.validate(&json!({"payload": "eyJpZCI6ICIxIn0="})).unwrap_err().collect();
+//! assert_eq!(errors[0].instance_path(), ["payload"]);
+//! assert_eq!(errors[0].schema_path(), ["properties", "payload", "contentSchema"]);
+//! assert_eq!(errors[0].causes()[0].instance_path(), ["id"]);
+//! ```
+
+use base64::Engine;
+use serde_json::Value;
+
+/// Decode `value` according to `encoding`, a value of `contentEncoding`.
+/// Returns `None` for encodings that aren't checked.
+pub fn decode(encoding: &str, value: &str) -> Option<Result<Vec<u8>, String>> {
+    match encoding.to_ascii_lowercase().as_str() {
+        "base64" => Some(
+            base64::engine::general_purpose::STANDARD
+                .decode(value)
+                .map_err(|err| err.to_string()),
+        ),
+        "7bit" | "8bit" | "binary" => Some(Ok(value.as_bytes().to_vec())),
+        _ => None,
+    }
+}
+
+/// Parse `content` as `media_type`, a value of `contentMediaType`. Returns
+/// `None` for media types that aren't checked.
+pub fn parse(media_type: &str, content: &[u8]) -> Option<Result<Value, String>> {
+    let essence = media_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    if essence == "application/json" || essence.ends_with("+json") {
+        Some(serde_json::from_slice(content).map_err(|err| err.to_string()))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::schemas;
+    use serde_json::json;
+
+    #[test]
+    fn test_content_checks() {
+        assert_eq!(decode("BASE64", "aGk="), Some(Ok(b"hi".to_vec())));
+        assert!(decode("base64", "aGk").unwrap().is_err());
+        assert_eq!(decode("quoted-printable", "=3D"), None);
+        assert_eq!(
+            parse("application/geo+json; charset=utf-8", b"{}"),
+            Some(Ok(json!({})))
+        );
+        assert!(parse("application/json", b"{").unwrap().is_err());
+        assert_eq!(parse("image/png", b"{"), None);
+
+        let schema = json!({"contentMediaType": "application/json"});
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        assert!(cfg.validate(&json!("{")).is_ok());
+        let cfg = cfg.with_content_checks();
+        assert!(cfg.validate(&json!("{\"a\": [1]}")).is_ok());
+        assert!(cfg.validate(&json!(1)).is_ok());
+        let errors: Vec<_> = cfg.validate(&json!("{")).unwrap_err().collect();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0]
+            .message()
+            .starts_with("\"{\" is not valid application/json: "));
+    }
+}
@@ -2,46 +2,119 @@
 
 use serde_json::Value;
 
+use crate::data;
+use crate::options::ValidationOptions;
+use crate::profile::Profile;
+
+/// The stack of schema documents in which `$ref`s are resolved during
+/// validation.
+///
+/// Keywords that validate subschemas need to pass it on; see `Keyword`.
 #[derive(Copy, Clone)]
 pub struct Context<'a> {
+    /// The innermost schema document.
     pub x: &'a Value,
+    /// The enclosing context, if any.
     pub parent: Option<&'a Context<'a>>,
+    /// The keywords to validate in this validation run, if not those of the
+    /// `Config`.
+    profile: Option<&'a Profile>,
+    /// The options overridden for this validation run, if any.
+    options: Option<&'a ValidationOptions>,
+    /// The instance being validated, if it is a `Value` with `$data`
+    /// references to resolve.
+    data: Option<&'a data::Instance<'a>>,
 }
 
 impl<'a> Context<'a> {
+    /// An empty context.
     pub fn new() -> Context<'static> {
         Context {
             x: &Value::Null,
             parent: None,
+            profile: None,
+            options: None,
+            data: None,
         }
     }
 
+    /// A context with the single schema document `x`.
     pub fn new_from(x: &'a Value) -> Context<'a> {
-        Context { x, parent: None }
+        Context {
+            x,
+            parent: None,
+            profile: None,
+            options: None,
+            data: None,
+        }
+    }
+
+    /// Validate the keywords selected by `profile` in this context and the
+    /// ones entered from it.
+    pub(crate) fn with_profile(mut self, profile: &'a Profile) -> Context<'a> {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// The keywords to validate, if selected for this validation run.
+    pub(crate) fn profile(&self) -> Option<&'a Profile> {
+        self.profile
+    }
+
+    /// Validate with `options` in this context and the ones entered from it.
+    pub(crate) fn with_options(mut self, options: &'a ValidationOptions) -> Context<'a> {
+        self.options = Some(options);
+        self
+    }
+
+    /// The options overridden for this validation run, if any.
+    pub(crate) fn options(&self) -> Option<&'a ValidationOptions> {
+        self.options
+    }
+
+    /// Resolve `$data` references in `data` in this context and the ones
+    /// entered from it.
+    pub(crate) fn with_data(mut self, data: &'a data::Instance<'a>) -> Context<'a> {
+        self.data = Some(data);
+        self
+    }
+
+    /// The instance in which `$data` references are resolved, if any.
+    pub(crate) fn data(&self) -> Option<&'a data::Instance<'a>> {
+        self.data
     }
 
+    /// Enter the schema document `x`.
     pub fn push(&'a self, x: &'a Value) -> Context<'a> {
         Context {
             x,
             parent: Some(self),
+            profile: self.profile,
+            options: self.options,
+            data: self.data,
         }
     }
 
+    /// Replace the innermost schema document with `x`.
     pub fn replace(&'a self, x: &'a Value) -> Context<'a> {
         Context {
             x,
             parent: self.parent,
+            profile: self.profile,
+            options: self.options,
+            data: self.data,
         }
     }
 
+    /// The schema documents in the context, outermost first.
     pub fn flatten(&'a self) -> Vec<Value> {
         let mut result = Vec::new();
         let mut ptr = self;
         if !ptr.x.is_null() {
             result.push(ptr.x.clone())
         }
-        while ptr.parent.is_some() {
-            ptr = ptr.parent.unwrap();
+        while let Some(parent) = ptr.parent {
+            ptr = parent;
             if !ptr.x.is_null() {
                 result.push(ptr.x.clone())
             }
@@ -0,0 +1,732 @@
+//! Converting schemas to a newer draft.
+//!
+//! Most of the differences between drafts are renamed or restructured
+//! keywords, which can be rewritten mechanically:
+//!
+//! - draft 4: `extends` became `allOf`, `divisibleBy` became `multipleOf`,
+//!   and `required: true` in a property became its name in the `required`
+//!   list next to `properties`.
+//! - draft 6: `id` became `$id`, and `exclusiveMaximum`/`exclusiveMinimum`
+//!   became numbers instead of modifiers of `maximum`/`minimum`.
+//! - 2019-09: `definitions` became `$defs`, `dependencies` was split into
+//!   `dependentRequired` and `dependentSchemas`, and plain-name fragments in
+//!   `$id` became `$anchor`.
+//! - 2020-12: the array form of `items` became `prefixItems`, and
+//!   `additionalItems` became `items`.
+//!
+//! Local references are rewritten to follow renamed keywords. Constructs
+//! whose meaning changed in a way that can't be converted automatically are
+//! reported, so they can be reviewed by hand.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::schemas;
+//! use jsonschema_valid::convert::{convert, Target};
+//!
+//! let schema = json!({
+//!     "$schema": "http://json-schema.org/draft-04/schema#",
+//!     "properties": {"port": {"$ref": "#/definitions/port"}},
+//!     "definitions": {"port": {"type": "integer", "maximum": 65536, "exclusiveMaximum": true}}
+//! });
+//! let conversion = convert(&schema, &schemas::Draft4, Target::Draft202012).unwrap();
+//! assert_eq!(
+//!     conversion.schema,
+//!     json!({
+//!         "$schema": "https://json-schema.org/draft/2020-12/schema",
+//!         "properties": {"port": {"$ref": "#/$defs/port"}},
+//!         "$defs": {"port": {"type": "integer", "exclusiveMaximum": 65536}}
+//!     })
+//! );
+//! assert!(conversion.unconverted.is_empty());
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde_json::{Map, Value};
+
+use crate::schemas;
+use crate::util;
+
+/// A draft to convert schemas to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Target {
+    /// Draft 6.
+    Draft6,
+    /// Draft 7.
+    Draft7,
+    /// Draft 2019-09.
+    Draft201909,
+    /// Draft 2020-12.
+    Draft202012,
+}
+
+impl Target {
+    /// The number of the draft, continuing the numbering of the older drafts
+    /// for 2019-09 (8) and 2020-12 (9).
+    pub fn number(self) -> u8 {
+        match self {
+            Target::Draft6 => 6,
+            Target::Draft7 => 7,
+            Target::Draft201909 => 8,
+            Target::Draft202012 => 9,
+        }
+    }
+
+    /// The URL of the metaschema, as used in `$schema`.
+    pub fn url(self) -> &'static str {
+        match self {
+            Target::Draft6 => "http://json-schema.org/draft-06/schema#",
+            Target::Draft7 => "http://json-schema.org/draft-07/schema#",
+            Target::Draft201909 => "https://json-schema.org/draft/2019-09/schema",
+            Target::Draft202012 => "https://json-schema.org/draft/2020-12/schema",
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Target::Draft6 => "6",
+            Target::Draft7 => "7",
+            Target::Draft201909 => "2019-09",
+            Target::Draft202012 => "2020-12",
+        })
+    }
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Target, String> {
+        match s {
+            "6" => Ok(Target::Draft6),
+            "7" => Ok(Target::Draft7),
+            "2019-09" => Ok(Target::Draft201909),
+            "2020-12" => Ok(Target::Draft202012),
+            other => Err(format!("Unknown target draft {}", other)),
+        }
+    }
+}
+
+/// A construct that could not be converted automatically.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unconverted {
+    /// The JSON pointer to the construct in the original schema.
+    pub pointer: String,
+    /// What needs to be reviewed.
+    pub message: String,
+}
+
+/// The result of converting a schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conversion {
+    /// The converted schema.
+    pub schema: Value,
+    /// The constructs that need to be reviewed by hand, sorted by pointer.
+    pub unconverted: Vec<Unconverted>,
+}
+
+/// Convert `schema`, written for `from`, to the `to` draft.
+///
+/// `$schema` is set to the metaschema of the target draft if the schema is an
+/// object. Returns `None` if `to` is older than `from`: converting to an
+/// older draft is not supported.
+pub fn convert(schema: &Value, from: &dyn schemas::Draft, to: Target) -> Option<Conversion> {
+    let from_number = from.get_draft_number();
+    let to_number = to.number();
+    if to_number < from_number {
+        return None;
+    }
+
+    // References are rewritten first, while subschemas can still be found
+    // by their keywords in `from`.
+    let mut result = schema.clone();
+    let mut unconverted = Vec::new();
+    rewrite_refs(
+        &mut result,
+        &mut String::new(),
+        from_number == 3,
+        &mut |pointer, reference| match rewrite_ref(schema, reference, from_number, to_number) {
+            Ok(Some(rewritten)) => *reference = rewritten,
+            Ok(None) => {}
+            Err(message) => unconverted.push(Unconverted {
+                pointer: format!("{}/$ref", pointer),
+                message,
+            }),
+        },
+    );
+    convert_schema(
+        &mut result,
+        String::new(),
+        from,
+        to_number,
+        &mut unconverted,
+    );
+    if let Value::Object(object) = &mut result {
+        object.insert("$schema".to_string(), Value::String(to.url().to_string()));
+    }
+
+    unconverted.sort_by(|a, b| a.pointer.cmp(&b.pointer));
+    Some(Conversion {
+        schema: result,
+        unconverted,
+    })
+}
+
+/// Keywords next to `$ref` that don't affect validation, so it doesn't
+/// matter that they start to apply in 2019-09.
+const REF_SIBLINGS: &[&str] = &[
+    "$comment",
+    "$defs",
+    "$id",
+    "$schema",
+    "default",
+    "definitions",
+    "description",
+    "examples",
+    "title",
+];
+
+/// Convert a schema and, recursively, all of its subschemas. Subschemas are
+/// converted first, since they are found by their keywords in `from`.
+fn convert_schema(
+    schema: &mut Value,
+    pointer: String,
+    from: &dyn schemas::Draft,
+    to: u8,
+    unconverted: &mut Vec<Unconverted>,
+) {
+    if from.get_draft_number() == 3 {
+        if let Value::Object(object) = schema {
+            convert_required(object);
+        }
+        match schema.get_mut("extends") {
+            Some(extends @ Value::Object(_)) => convert_schema(
+                extends,
+                format!("{}/extends", pointer),
+                from,
+                to,
+                unconverted,
+            ),
+            Some(Value::Array(array)) => {
+                for (index, item) in array.iter_mut().enumerate() {
+                    convert_schema(
+                        item,
+                        format!("{}/extends/{}", pointer, index),
+                        from,
+                        to,
+                        unconverted,
+                    )
+                }
+            }
+            _ => {}
+        }
+    }
+    util::for_each_subschema_mut(schema, &mut |path, subschema| {
+        convert_schema(
+            subschema,
+            format!("{}/{}", pointer, path),
+            from,
+            to,
+            unconverted,
+        )
+    });
+    let object = match schema {
+        Value::Object(object) => object,
+        _ => return,
+    };
+    let mut report = |message: String| {
+        unconverted.push(Unconverted {
+            pointer: pointer.clone(),
+            message,
+        })
+    };
+
+    let exclusive_bounds = from.boolean_exclusive_bounds();
+    let legacy_id = from.uses_legacy_id();
+    let from = from.get_draft_number();
+    if from == 3 {
+        convert_draft3(object, &mut report);
+    }
+    if legacy_id && to >= 6 {
+        if let Some(Value::String(id)) = object.get("id").cloned() {
+            object.remove("id");
+            object.insert("$id".to_string(), Value::String(id));
+        }
+    }
+    if exclusive_bounds && to >= 6 {
+        convert_exclusive_bound(object, "exclusiveMaximum", "maximum");
+        convert_exclusive_bound(object, "exclusiveMinimum", "minimum");
+    }
+
+    if from <= 7 && to >= 8 {
+        if let Some(Value::String(id)) = object.get("$id").cloned() {
+            match id.find('#') {
+                Some(0) if id.len() > 1 => {
+                    object.remove("$id");
+                    object.insert("$anchor".to_string(), Value::String(id[1..].to_string()));
+                }
+                Some(index) if index == id.len() - 1 => {
+                    object.insert("$id".to_string(), Value::String(id[..index].to_string()));
+                }
+                Some(_) => report(format!(
+                    "$id {} has a fragment, which must be split into $id and $anchor",
+                    id
+                )),
+                None => {}
+            }
+        }
+        if let Some(definitions) = object.remove("definitions") {
+            match object.get_mut("$defs") {
+                Some(Value::Object(defs)) => {
+                    if let Value::Object(definitions) = definitions {
+                        for (name, definition) in definitions {
+                            if defs.contains_key(&name) {
+                                report(format!(
+                                    "definitions/{} conflicts with the entry in $defs of the same name",
+                                    name
+                                ));
+                            } else {
+                                defs.insert(name, definition);
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    object.insert("$defs".to_string(), definitions);
+                }
+            }
+        }
+        if let Some(Value::Object(dependencies)) = object.remove("dependencies") {
+            let mut required = Map::new();
+            let mut schemas = Map::new();
+            for (name, dependency) in dependencies {
+                if dependency.is_array() {
+                    required.insert(name, dependency);
+                } else {
+                    schemas.insert(name, dependency);
+                }
+            }
+            if !required.is_empty() {
+                object.insert("dependentRequired".to_string(), Value::Object(required));
+            }
+            if !schemas.is_empty() {
+                object.insert("dependentSchemas".to_string(), Value::Object(schemas));
+            }
+        }
+        if object.contains_key("$ref") {
+            let siblings: Vec<&str> = object
+                .keys()
+                .map(String::as_str)
+                .filter(|key| *key != "$ref" && !REF_SIBLINGS.contains(key))
+                .collect();
+            if !siblings.is_empty() {
+                report(format!(
+                    "{} next to $ref used to be ignored, but apply from 2019-09 on",
+                    siblings.join(", ")
+                ));
+            }
+        }
+    }
+
+    if from <= 8 && to >= 9 {
+        match object.remove("items") {
+            Some(items @ Value::Array(_)) => {
+                object.insert("prefixItems".to_string(), items);
+                if let Some(additional) = object.remove("additionalItems") {
+                    object.insert("items".to_string(), additional);
+                }
+            }
+            Some(items) => {
+                object.insert("items".to_string(), items);
+                // Without an array in `items`, `additionalItems` never applied.
+                object.remove("additionalItems");
+            }
+            None => {
+                object.remove("additionalItems");
+            }
+        }
+    }
+}
+
+/// Move `required: true` from the properties of a draft 3 schema to the
+/// `required` list next to `properties`. Anywhere else, it had no effect.
+/// Must be done before converting the properties.
+fn convert_required(object: &mut Map<String, Value>) {
+    if let Some(Value::Bool(_)) = object.get("required") {
+        object.remove("required");
+    }
+    let mut required = Vec::new();
+    if let Some(Value::Object(properties)) = object.get_mut("properties") {
+        for (name, property) in properties.iter_mut() {
+            if let Value::Object(property) = property {
+                if let Some(Value::Bool(is_required)) = property.remove("required") {
+                    if is_required {
+                        required.push(Value::String(name.clone()));
+                    }
+                }
+            }
+        }
+    }
+    if !required.is_empty() {
+        object.insert("required".to_string(), Value::Array(required));
+    }
+}
+
+/// Rewrite the other keywords of draft 3 that changed in draft 4, after
+/// converting the subschemas.
+fn convert_draft3(object: &mut Map<String, Value>, report: &mut dyn FnMut(String)) {
+    if let Some(extends) = object.remove("extends") {
+        let extends = match extends {
+            Value::Array(array) => array,
+            other => vec![other],
+        };
+        match object.get_mut("allOf") {
+            Some(Value::Array(all_of)) => all_of.extend(extends),
+            _ => {
+                object.insert("allOf".to_string(), Value::Array(extends));
+            }
+        }
+    }
+    if let Some(divisor) = object.remove("divisibleBy") {
+        object.insert("multipleOf".to_string(), divisor);
+    }
+    if let Some(Value::Object(dependencies)) = object.get_mut("dependencies") {
+        for dependency in dependencies.values_mut() {
+            if let Value::String(name) = dependency {
+                *dependency = Value::Array(vec![Value::String(name.clone())]);
+            }
+        }
+    }
+
+    match object.get("type") {
+        Some(Value::Array(types)) if types.iter().any(|x| x == "any") => {
+            object.remove("type");
+        }
+        Some(Value::Array(types)) if types.iter().any(Value::is_object) => {
+            report("type has schemas, which must be rewritten with anyOf".to_string())
+        }
+        Some(kind) if kind == "any" => {
+            object.remove("type");
+        }
+        _ => {}
+    }
+    match object.get("disallow") {
+        Some(Value::String(disallow)) if disallow != "any" && !object.contains_key("not") => {
+            let not = serde_json::json!({ "type": disallow });
+            object.remove("disallow");
+            object.insert("not".to_string(), not);
+        }
+        Some(_) => report("disallow must be rewritten with not".to_string()),
+        None => {}
+    }
+}
+
+/// Turn a draft 4 boolean `exclusiveMaximum`/`exclusiveMinimum` into the
+/// numeric form of later drafts.
+fn convert_exclusive_bound(object: &mut Map<String, Value>, exclusive: &str, bound: &str) {
+    match object.get(exclusive) {
+        Some(Value::Bool(true)) => match object.remove(bound) {
+            Some(value) => {
+                object.insert(exclusive.to_string(), value);
+            }
+            None => {
+                object.remove(exclusive);
+            }
+        },
+        Some(Value::Bool(false)) => {
+            object.remove(exclusive);
+        }
+        _ => {}
+    }
+}
+
+/// Call `f` with the location and value of every `$ref` in the schema,
+/// including those under the `extends` of draft 3 if `draft3` is set.
+fn rewrite_refs(
+    schema: &mut Value,
+    pointer: &mut String,
+    draft3: bool,
+    f: &mut dyn FnMut(&str, &mut String),
+) {
+    if let Some(Value::String(reference)) = schema.get_mut("$ref") {
+        f(pointer, reference);
+    }
+    let mut rewrite = |path: String, subschema: &mut Value| {
+        let len = pointer.len();
+        pointer.push('/');
+        pointer.push_str(&path);
+        rewrite_refs(subschema, pointer, draft3, f);
+        pointer.truncate(len);
+    };
+    if draft3 {
+        match schema.get_mut("extends") {
+            Some(extends @ Value::Object(_)) => rewrite("extends".to_string(), extends),
+            Some(Value::Array(array)) => {
+                for (index, item) in array.iter_mut().enumerate() {
+                    rewrite(format!("extends/{}", index), item);
+                }
+            }
+            _ => {}
+        }
+    }
+    util::for_each_subschema_mut(schema, &mut rewrite);
+}
+
+/// Where a JSON pointer is within a schema document, to tell which tokens
+/// are keywords.
+#[derive(Clone, Copy, PartialEq)]
+enum Position {
+    Schema,
+    SchemaArray,
+    SchemaMap,
+    Other,
+}
+
+/// Rewrite a reference into `original` so it points to the same subschema
+/// after converting. Returns `Ok(None)` if the reference doesn't change, and
+/// an error if it can't be rewritten.
+fn rewrite_ref(
+    original: &Value,
+    reference: &str,
+    from: u8,
+    to: u8,
+) -> Result<Option<String>, String> {
+    const RENAMED: &[&str] = &["additionalItems", "definitions", "dependencies", "items"];
+
+    let index = match reference.find('#') {
+        Some(index) => index,
+        None => return Ok(None),
+    };
+    let fragment = &reference[index + 1..];
+    if !fragment.starts_with('/') {
+        return Ok(None);
+    }
+    if index > 0 {
+        return if util::pointer_tokens(fragment).any(|x| RENAMED.contains(&x.as_str())) {
+            Err(format!(
+                "{} points into another document, which may need to be converted as well",
+                reference
+            ))
+        } else {
+            Ok(None)
+        };
+    }
+
+    let mut value = Some(original);
+    let mut position = Position::Schema;
+    let mut tokens: Vec<String> = Vec::new();
+    let mut changed = false;
+    for token in util::pointer_tokens(fragment) {
+        let parent = value;
+        value = match parent {
+            Some(Value::Object(object)) => object.get(&token),
+            Some(Value::Array(array)) => token.parse::<usize>().ok().and_then(|x| array.get(x)),
+            _ => None,
+        };
+        let mut renamed = token.clone();
+        let next = match position {
+            Position::Schema => {
+                let keyword = token.as_str();
+                match (keyword, value) {
+                    ("extends", Some(Value::Object(_))) if from == 3 => {
+                        tokens.push("allOf".to_string());
+                        renamed = "0".to_string();
+                    }
+                    ("extends", Some(Value::Array(_))) if from == 3 => {
+                        renamed = "allOf".to_string()
+                    }
+                    ("definitions", _) if from <= 7 && to >= 8 => renamed = "$defs".to_string(),
+                    ("dependencies", _) if from <= 7 && to >= 8 => {
+                        // Which keyword a dependency ends up in depends on
+                        // its form, which is only known one level further down.
+                    }
+                    ("items", Some(Value::Array(_))) if from <= 8 && to >= 9 => {
+                        renamed = "prefixItems".to_string()
+                    }
+                    ("additionalItems", _) if from <= 8 && to >= 9 => {
+                        match parent.and_then(|x| x.get("items")) {
+                            Some(Value::Array(_)) => renamed = "items".to_string(),
+                            _ => {
+                                return Err(format!(
+                                    "{} points to additionalItems, which is removed",
+                                    reference
+                                ))
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                match value {
+                    Some(Value::Object(_)) if keyword == "extends" && from == 3 => Position::Schema,
+                    Some(Value::Array(_)) if keyword == "extends" && from == 3 => {
+                        Position::SchemaArray
+                    }
+                    Some(Value::Object(_)) | Some(Value::Bool(_))
+                        if util::SCHEMA_KEYWORDS.contains(&keyword) =>
+                    {
+                        Position::Schema
+                    }
+                    Some(Value::Array(_)) if util::SCHEMA_ARRAY_KEYWORDS.contains(&keyword) => {
+                        Position::SchemaArray
+                    }
+                    Some(Value::Object(_)) if util::SCHEMA_MAP_KEYWORDS.contains(&keyword) => {
+                        Position::SchemaMap
+                    }
+                    _ => Position::Other,
+                }
+            }
+            Position::SchemaArray | Position::SchemaMap => Position::Schema,
+            Position::Other => Position::Other,
+        };
+        if position == Position::SchemaMap
+            && tokens.last().map(String::as_str) == Some("dependencies")
+            && from <= 7
+            && to >= 8
+        {
+            let keyword = match value {
+                Some(Value::Array(_)) => "dependentRequired",
+                _ => "dependentSchemas",
+            };
+            if let Some(last) = tokens.last_mut() {
+                *last = keyword.to_string();
+            }
+            changed = true;
+        }
+        changed |= renamed != token;
+        tokens.push(renamed);
+        position = next;
+    }
+
+    if !changed {
+        return Ok(None);
+    }
+    Ok(Some(tokens.iter().fold("#".to_string(), |acc, token| {
+        acc + "/" + &util::escape_pointer_token(token)
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_items_and_dependencies() {
+        let schema = json!({
+            "items": [{"type": "string"}, {"$ref": "#/dependencies/b"}],
+            "additionalItems": {"$ref": "#/items/0"},
+            "dependencies": {"a": ["b"], "b": {"required": ["c"]}}
+        });
+        let conversion = convert(&schema, &schemas::Draft7, Target::Draft202012).unwrap();
+        assert_eq!(
+            conversion.schema,
+            json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "prefixItems": [{"type": "string"}, {"$ref": "#/dependentSchemas/b"}],
+                "items": {"$ref": "#/prefixItems/0"},
+                "dependentRequired": {"a": ["b"]},
+                "dependentSchemas": {"b": {"required": ["c"]}}
+            })
+        );
+        assert!(conversion.unconverted.is_empty());
+        assert!(convert(&schema, &schemas::Draft7, Target::Draft6).is_none());
+    }
+
+    #[test]
+    fn test_unconverted() {
+        let schema = json!({
+            "id": "http://example.com/root.json#foo",
+            "properties": {
+                "a": {"$ref": "#/definitions/a", "type": "string", "description": "A"},
+                "b": {"$ref": "other.json#/definitions/b"}
+            },
+            "definitions": {"a": {"id": "#a"}}
+        });
+        let conversion = convert(&schema, &schemas::Draft4, Target::Draft201909).unwrap();
+        assert_eq!(conversion.schema["$defs"], json!({"a": {"$anchor": "a"}}));
+        let pointers: Vec<_> = conversion
+            .unconverted
+            .iter()
+            .map(|x| x.pointer.as_str())
+            .collect();
+        assert_eq!(pointers, vec!["", "/properties/a", "/properties/b/$ref"]);
+    }
+
+    #[test]
+    fn test_exclusive_bounds() {
+        let schema = json!({
+            "properties": {
+                "ratio": {"type": "number", "maximum": 5, "exclusiveMaximum": true, "nullable": true}
+            }
+        });
+        let conversion = convert(&schema, &schemas::OpenApi30, Target::Draft202012).unwrap();
+        assert_eq!(
+            conversion.schema["properties"]["ratio"],
+            json!({"type": "number", "exclusiveMaximum": 5, "nullable": true})
+        );
+        let conversion = convert(&schema, &schemas::Draft6, Target::Draft202012).unwrap();
+        assert_eq!(conversion.schema["properties"], schema["properties"]);
+    }
+
+    #[test]
+    fn test_openapi_id() {
+        let schema = json!({"properties": {"a": {"id": "a.json", "type": "string"}}});
+        let conversion = convert(&schema, &schemas::OpenApi30, Target::Draft7).unwrap();
+        assert_eq!(conversion.schema["properties"], schema["properties"]);
+        let conversion = convert(&schema, &schemas::Draft4, Target::Draft7).unwrap();
+        assert_eq!(
+            conversion.schema["properties"],
+            json!({"a": {"$id": "a.json", "type": "string"}})
+        );
+    }
+
+    #[test]
+    fn test_draft3() {
+        let schema = json!({
+            "extends": {"$ref": "#/definitions/named"},
+            "properties": {
+                "size": {"type": ["integer", "null"], "divisibleBy": 2, "required": true},
+                "color": {"type": "any", "disallow": "null", "required": false},
+                "shape": {"type": ["string", {"enum": [0]}]}
+            },
+            "dependencies": {"size": "color"},
+            "definitions": {"named": {"properties": {"name": {"required": true}}}}
+        });
+        let conversion = convert(&schema, &schemas::Draft3, Target::Draft202012).unwrap();
+        assert_eq!(
+            conversion.schema,
+            json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "allOf": [{"$ref": "#/$defs/named"}],
+                "properties": {
+                    "size": {"type": ["integer", "null"], "multipleOf": 2},
+                    "color": {"not": {"type": "null"}},
+                    "shape": {"type": ["string", {"enum": [0]}]}
+                },
+                "required": ["size"],
+                "dependentRequired": {"size": ["color"]},
+                "$defs": {"named": {"properties": {"name": {}}, "required": ["name"]}}
+            })
+        );
+        assert_eq!(
+            conversion.unconverted,
+            vec![Unconverted {
+                pointer: "/properties/shape".to_string(),
+                message: "type has schemas, which must be rewritten with anyOf".to_string(),
+            }]
+        );
+
+        let schema = json!({
+            "extends": [{"properties": {"a": {"$ref": "#/extends/1"}}}, {"type": "string"}]
+        });
+        let conversion = convert(&schema, &schemas::Draft3, Target::Draft7).unwrap();
+        assert_eq!(
+            conversion.schema["allOf"][0]["properties"]["a"],
+            json!({"$ref": "#/allOf/1"})
+        );
+    }
+}
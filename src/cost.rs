@@ -0,0 +1,285 @@
+//! Estimating how expensive a schema is to validate against.
+//!
+//! Services that accept schemas from tenants need to reject those that are
+//! too expensive to validate with before deploying them. `estimate` scores
+//! the worst-case cost of validating an instance against a schema, and lists
+//! the factors that make it expensive: regular expressions, applicators with
+//! many branches, references that recurse without bound, and `uniqueItems`
+//! on large arrays.
+//!
+//! The score is a relative measure, roughly the number of keyword checks:
+//! every keyword counts 1, a regular expression counts `REGEX_COST`, the
+//! subschemas that apply to each element of an array or each property of an
+//! object count once per element or property, and `uniqueItems` counts the
+//! square of the number of elements. Arrays and objects without `maxItems`
+//! or `maxProperties` are assumed to have `ASSUMED_SIZE` elements or
+//! properties. Recursion through `$ref` is only followed once, and reported
+//! as a factor.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::{schemas, Config};
+//! use jsonschema_valid::cost::estimate;
+//!
+//! let cheap = json!({"type": "array", "items": {"type": "integer"}, "maxItems": 10});
+//! let cfg = Config::from_schema(&cheap, Some(&schemas::Draft7)).unwrap();
+//! let report = estimate(&cfg);
+//! assert!(report.factors.is_empty());
+//!
+//! let expensive = json!({"items": {"pattern": "^(a+)+$"}, "uniqueItems": true});
+//! let cfg = Config::from_schema(&expensive, Some(&schemas::Draft7)).unwrap();
+//! let expensive_report = estimate(&cfg);
+//! assert!(expensive_report.score > report.score);
+//! assert_eq!(expensive_report.regexes, 1);
+//! assert_eq!(expensive_report.factors[0].kind, "unique-items");
+//! ```
+
+use std::collections::HashMap;
+
+use serde_json::{Map, Value};
+
+use crate::config::Config;
+use crate::context::Context;
+use crate::util;
+
+/// The cost of matching a regular expression, relative to other keywords.
+pub const REGEX_COST: u64 = 10;
+
+/// The number of elements or properties assumed for arrays and objects
+/// whose size isn't bounded by the schema.
+pub const ASSUMED_SIZE: u64 = 100;
+
+/// Applicators with more branches than this are reported as a factor.
+pub const FAN_OUT_LIMIT: usize = 16;
+
+/// `uniqueItems` on arrays that can have more elements than this is
+/// reported as a factor.
+pub const UNIQUE_ITEMS_LIMIT: u64 = 1000;
+
+/// Something that makes a schema expensive to validate against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Factor {
+    /// The kind of factor: `fan-out`, `unbounded-recursion` or
+    /// `unique-items`.
+    pub kind: &'static str,
+    /// The path of keywords that leads from the root schema to the
+    /// offending keyword, as a JSON pointer. References are followed, and
+    /// show up as a `$ref` token.
+    pub pointer: String,
+    /// A description of the factor.
+    pub message: String,
+}
+
+/// The estimated cost of validating against a schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CostReport {
+    /// The worst-case cost score; see the module documentation.
+    pub score: u64,
+    /// The number of regular expressions in `pattern` and
+    /// `patternProperties` that validation may match.
+    pub regexes: usize,
+    /// The largest number of branches of an `allOf`, `anyOf` or `oneOf`.
+    pub max_fan_out: usize,
+    /// The factors that make the schema expensive.
+    pub factors: Vec<Factor>,
+}
+
+impl CostReport {
+    /// Whether the score is above `limit`, or a reference recurses without
+    /// bound.
+    pub fn exceeds(&self, limit: u64) -> bool {
+        self.score > limit || self.factors.iter().any(|x| x.kind == "unbounded-recursion")
+    }
+}
+
+/// Estimate the worst-case cost of validating against the schema in `cfg`.
+pub fn estimate(cfg: &Config) -> CostReport {
+    let mut estimator = Estimator {
+        cfg,
+        report: CostReport {
+            score: 0,
+            regexes: 0,
+            max_fan_out: 0,
+            factors: Vec::new(),
+        },
+        stack: Vec::new(),
+        costs: HashMap::new(),
+    };
+    estimator.report.score = estimator.cost(cfg.get_schema(), "");
+    estimator.report
+}
+
+struct Estimator<'a> {
+    cfg: &'a Config<'a>,
+    report: CostReport,
+    /// The subschemas whose `$ref` is being followed.
+    stack: Vec<*const Value>,
+    /// The cost of the subschemas reached through `$ref` so far.
+    costs: HashMap<*const Value, u64>,
+}
+
+/// The number of elements or properties bounded by `keyword`.
+fn size(object: &Map<String, Value>, keyword: &str) -> u64 {
+    object
+        .get(keyword)
+        .and_then(Value::as_u64)
+        .unwrap_or(ASSUMED_SIZE)
+}
+
+impl<'a> Estimator<'a> {
+    fn factor(&mut self, kind: &'static str, pointer: String, message: String) {
+        self.report.factors.push(Factor {
+            kind,
+            pointer,
+            message,
+        });
+    }
+
+    fn cost(&mut self, schema: &'a Value, pointer: &str) -> u64 {
+        let object = match schema {
+            Value::Object(object) => object,
+            _ => return 1,
+        };
+
+        // In the supported drafts, all keywords next to `$ref` are ignored.
+        if let Some(Value::String(ref_)) = object.get("$ref") {
+            let root = self.cfg.get_schema();
+            let resolved =
+                match self
+                    .cfg
+                    .get_resolver()
+                    .resolve_fragment(ref_, &Context::new_from(root), root)
+                {
+                    Ok((_, resolved)) => resolved,
+                    Err(_) => return 1,
+                };
+            let key = resolved as *const Value;
+            if self.stack.contains(&key) {
+                self.factor(
+                    "unbounded-recursion",
+                    format!("{}/$ref", pointer),
+                    format!("{} refers to a schema it is part of", ref_),
+                );
+                return 1;
+            }
+            if let Some(cost) = self.costs.get(&key) {
+                return 1u64.saturating_add(*cost);
+            }
+            self.stack.push(key);
+            let cost = self.cost(resolved, &format!("{}/$ref", pointer));
+            self.stack.pop();
+            self.costs.insert(key, cost);
+            return 1u64.saturating_add(cost);
+        }
+
+        let mut total = object.len() as u64;
+
+        if let Some(Value::String(_)) = object.get("pattern") {
+            self.report.regexes += 1;
+            total = total.saturating_add(REGEX_COST);
+        }
+
+        let items = size(object, "maxItems");
+        let properties = size(object, "maxProperties");
+        for keyword in &["allOf", "anyOf", "oneOf"] {
+            if let Some(Value::Array(branches)) = object.get(*keyword) {
+                self.report.max_fan_out = self.report.max_fan_out.max(branches.len());
+                if branches.len() > FAN_OUT_LIMIT {
+                    self.factor(
+                        "fan-out",
+                        format!("{}/{}", pointer, keyword),
+                        format!("{} has {} branches", keyword, branches.len()),
+                    );
+                }
+            }
+        }
+        if object.get("uniqueItems") == Some(&Value::Bool(true)) {
+            total = total.saturating_add(items.saturating_mul(items));
+            if !object.contains_key("maxItems") || items > UNIQUE_ITEMS_LIMIT {
+                let bound = match object.get("maxItems") {
+                    Some(_) => format!("up to {}", items),
+                    None => "any number of".to_string(),
+                };
+                self.factor(
+                    "unique-items",
+                    format!("{}/uniqueItems", pointer),
+                    format!(
+                        "uniqueItems compares every pair of elements of arrays with {} elements",
+                        bound
+                    ),
+                );
+            }
+        }
+
+        let mut subschemas = Vec::new();
+        util::for_each_subschema(schema, &mut |path, subschema| {
+            subschemas.push((path, subschema))
+        });
+        for (path, subschema) in subschemas {
+            let keyword = util::pointer_tokens(&format!("/{}", path))
+                .next()
+                .unwrap_or_default();
+            let times = match keyword.as_str() {
+                "items" if !object["items"].is_array() => items,
+                "additionalItems" | "contains" => items,
+                "additionalProperties" | "propertyNames" => properties,
+                "patternProperties" => {
+                    self.report.regexes += 1;
+                    total = total.saturating_add(properties.saturating_mul(REGEX_COST));
+                    properties
+                }
+                "definitions" => 0,
+                _ => 1,
+            };
+            if times > 0 {
+                let cost = self.cost(subschema, &format!("{}/{}", pointer, path));
+                total = total.saturating_add(times.saturating_mul(cost));
+            }
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas;
+    use serde_json::json;
+
+    #[test]
+    fn test_recursion_and_fan_out() {
+        let branches: Vec<Value> = (0..20).map(|i| json!({ "const": i })).collect();
+        let schema = json!({
+            "definitions": {
+                "node": {
+                    "properties": {"children": {"items": {"$ref": "#/definitions/node"}}}
+                }
+            },
+            "properties": {
+                "root": {"$ref": "#/definitions/node"},
+                "kind": {"oneOf": branches}
+            }
+        });
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let report = estimate(&cfg);
+        assert_eq!(report.max_fan_out, 20);
+        let factors: Vec<_> = report
+            .factors
+            .iter()
+            .map(|x| (x.kind, x.pointer.as_str()))
+            .collect();
+        assert_eq!(
+            factors,
+            [
+                ("fan-out", "/properties/kind/oneOf"),
+                (
+                    "unbounded-recursion",
+                    "/properties/root/$ref/properties/children/items/$ref"
+                ),
+            ]
+        );
+        assert!(report.exceeds(u64::MAX));
+    }
+}
@@ -0,0 +1,326 @@
+//! Finding the parts of a schema that a corpus of instances never exercises.
+//!
+//! Contract schemas accumulate branches that no real data goes through any
+//! more, and tests that never reach part of a schema don't test it. A
+//! `Coverage` records, for a corpus of instances, how often each subschema is
+//! reached by a value of an instance, how often that value is valid against
+//! it, and how often each of its keywords applies to a value of the type the
+//! keyword checks. `Coverage::report` renders the counts as text, marking
+//! what was never reached, matched or exercised.
+//!
+//! Subschemas are those of the root schema, found by their location in it,
+//! including those only reached through a `$ref`. Keywords next to a `$ref`
+//! are ignored, as they are by validation.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::{schemas, Config};
+//! use jsonschema_valid::coverage::Coverage;
+//!
+//! let schema = json!({
+//!     "properties": {"id": {"anyOf": [{"type": "integer"}, {"type": "string", "minLength": 1}]}}
+//! });
+//! let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+//!
+//! let mut coverage = Coverage::new(&cfg);
+//! coverage.record(&json!({"id": 1}));
+//! coverage.record(&json!({"id": 2}));
+//!
+//! let string = coverage.subschema("/properties/id/anyOf/1").unwrap();
+//! assert_eq!((string.reached, string.matched), (2, 0));
+//! assert_eq!(coverage.unmatched().collect::<Vec<_>>(), ["/properties/id/anyOf/1"]);
+//! assert_eq!(coverage.keyword("/properties/id/anyOf/1/minLength"), Some(0));
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::explain::{children, expand, is_valid};
+use crate::util;
+
+/// How often a subschema was reached and matched by the values of the
+/// recorded instances.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    /// The number of values the subschema applied to.
+    pub reached: usize,
+    /// The number of those values that were valid against it.
+    pub matched: usize,
+}
+
+struct Entry {
+    path: String,
+    counts: Counts,
+    keywords: Vec<(String, usize)>,
+}
+
+/// The coverage of the schema of a `Config` by the instances recorded so
+/// far.
+pub struct Coverage<'a> {
+    cfg: &'a Config<'a>,
+    instances: usize,
+    entries: Vec<Entry>,
+    /// The index in `entries` of each subschema, by its address.
+    index: HashMap<*const Value, usize>,
+}
+
+/// The type of values that `keyword` checks, or `None` if it checks values
+/// of any type.
+fn checked_type(keyword: &str) -> Option<&'static str> {
+    Some(match keyword {
+        "minLength" | "maxLength" | "pattern" | "format" | "contentEncoding"
+        | "contentMediaType" => "string",
+        "minimum" | "maximum" | "exclusiveMinimum" | "exclusiveMaximum" | "multipleOf" => "number",
+        "items" | "additionalItems" | "minItems" | "maxItems" | "uniqueItems" | "contains" => {
+            "array"
+        }
+        "properties"
+        | "patternProperties"
+        | "additionalProperties"
+        | "required"
+        | "minProperties"
+        | "maxProperties"
+        | "dependencies"
+        | "propertyNames" => "object",
+        _ => return None,
+    })
+}
+
+fn type_of(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+impl<'a> Coverage<'a> {
+    /// Start recording the coverage of the schema in `cfg`.
+    pub fn new(cfg: &'a Config<'a>) -> Coverage<'a> {
+        let mut coverage = Coverage {
+            cfg,
+            instances: 0,
+            entries: Vec::new(),
+            index: HashMap::new(),
+        };
+        coverage.add(cfg.get_schema(), &mut String::new());
+        coverage
+    }
+
+    fn add(&mut self, schema: &Value, path: &mut String) {
+        let keywords = match schema {
+            Value::Object(object) => object
+                .keys()
+                .filter(|key| self.cfg.get_keyword(key).is_some())
+                .filter(|key| *key == "$ref" || !object.contains_key("$ref"))
+                .map(|key| (key.clone(), 0))
+                .collect(),
+            _ => Vec::new(),
+        };
+        self.index
+            .insert(schema as *const Value, self.entries.len());
+        self.entries.push(Entry {
+            path: path.clone(),
+            counts: Counts::default(),
+            keywords,
+        });
+        util::for_each_subschema(schema, &mut |subpath, subschema| {
+            let len = path.len();
+            path.push('/');
+            path.push_str(&subpath);
+            self.add(subschema, path);
+            path.truncate(len);
+        });
+    }
+
+    /// Record the subschemas and keywords exercised by `instance`.
+    pub fn record(&mut self, instance: &Value) {
+        self.instances += 1;
+        let mut subschemas = Vec::new();
+        let cfg: &Config = self.cfg;
+        expand(
+            cfg,
+            instance,
+            String::new(),
+            cfg.get_schema(),
+            0,
+            &mut subschemas,
+        );
+        self.visit(instance, subschemas);
+    }
+
+    fn visit<'b>(&mut self, value: &'b Value, subschemas: Vec<(String, &'b Value)>)
+    where
+        'a: 'b,
+    {
+        let cfg: &'b Config<'b> = self.cfg;
+        for (_, schema) in &subschemas {
+            let entry = match self.index.get(&(*schema as *const Value)) {
+                Some(index) => &mut self.entries[*index],
+                None => continue,
+            };
+            entry.counts.reached += 1;
+            if is_valid(cfg, value, schema) {
+                entry.counts.matched += 1;
+            }
+            for (keyword, count) in &mut entry.keywords {
+                let applies = match checked_type(keyword) {
+                    Some(typename) => typename == type_of(value),
+                    None => true,
+                };
+                if applies {
+                    *count += 1;
+                }
+            }
+        }
+
+        let tokens: Vec<String> = match value {
+            Value::Object(object) => object.keys().cloned().collect(),
+            Value::Array(array) => (0..array.len()).map(|i| i.to_string()).collect(),
+            _ => return,
+        };
+        for token in tokens {
+            let child = match value {
+                Value::Object(object) => &object[&token],
+                Value::Array(array) => &array[token.parse::<usize>().unwrap_or(0)],
+                _ => continue,
+            };
+            let mut next = Vec::new();
+            for (path, schema) in &subschemas {
+                for (child_path, child_schema) in children(value, &token, schema) {
+                    let path = format!("{}/{}", path, child_path);
+                    expand(cfg, child, path, child_schema, 0, &mut next);
+                }
+            }
+            self.visit(child, next);
+        }
+    }
+
+    /// The number of instances recorded.
+    pub fn instances(&self) -> usize {
+        self.instances
+    }
+
+    /// The counts of the subschema at `path`, a JSON pointer into the root
+    /// schema. Returns `None` if there's no subschema there.
+    pub fn subschema(&self, path: &str) -> Option<Counts> {
+        self.entries
+            .iter()
+            .find(|entry| entry.path == path)
+            .map(|entry| entry.counts)
+    }
+
+    /// The number of values the keyword at `path`, a JSON pointer into the
+    /// root schema, applied to. Returns `None` if there's no keyword there.
+    pub fn keyword(&self, path: &str) -> Option<usize> {
+        self.entries.iter().find_map(|entry| {
+            let keyword = path.strip_prefix(entry.path.as_str())?.strip_prefix('/')?;
+            entry
+                .keywords
+                .iter()
+                .find(|(name, _)| util::escape_pointer_token(name) == keyword)
+                .map(|(_, count)| *count)
+        })
+    }
+
+    /// The paths of the subschemas that no value reached, in document order.
+    pub fn unreached(&self) -> impl Iterator<Item = &str> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.counts.reached == 0)
+            .map(|entry| entry.path.as_str())
+    }
+
+    /// The paths of the subschemas that values reached, but none was valid
+    /// against, in document order.
+    pub fn unmatched(&self) -> impl Iterator<Item = &str> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.counts.reached > 0 && entry.counts.matched == 0)
+            .map(|entry| entry.path.as_str())
+    }
+
+    /// Render the coverage as text: a summary line, then a line for each
+    /// subschema with its counts, followed by an indented line for each of
+    /// its keywords. Subschemas that were never reached or matched, and
+    /// keywords that were never exercised, are marked with `!`.
+    pub fn report(&self) -> String {
+        let count = |f: &dyn Fn(&Entry) -> bool| self.entries.iter().filter(|x| f(x)).count();
+        let keywords: Vec<usize> = self
+            .entries
+            .iter()
+            .flat_map(|entry| entry.keywords.iter().map(|(_, count)| *count))
+            .collect();
+        let mut result = format!(
+            "{} instances: {}/{} subschemas reached, {}/{} matched, {}/{} keywords exercised\n",
+            self.instances,
+            count(&|x| x.counts.reached > 0),
+            self.entries.len(),
+            count(&|x| x.counts.matched > 0),
+            self.entries.len(),
+            keywords.iter().filter(|x| **x > 0).count(),
+            keywords.len(),
+        );
+        for entry in &self.entries {
+            let mark = if entry.counts.matched == 0 { "!" } else { " " };
+            writeln!(
+                result,
+                "{} #{}: reached {}, matched {}",
+                mark, entry.path, entry.counts.reached, entry.counts.matched
+            )
+            .unwrap();
+            for (keyword, count) in &entry.keywords {
+                let mark = if *count == 0 { "!" } else { " " };
+                writeln!(result, "{}     {}: exercised {}", mark, keyword, count).unwrap();
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas;
+    use serde_json::json;
+
+    #[test]
+    fn test_report() {
+        let schema = json!({
+            "items": {"$ref": "#/definitions/item"},
+            "definitions": {"item": {"type": "string", "maxLength": 2}}
+        });
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let mut coverage = Coverage::new(&cfg);
+        coverage.record(&json!(["a", 1]));
+        assert_eq!(
+            coverage.subschema("/definitions/item"),
+            Some(Counts {
+                reached: 2,
+                matched: 1
+            })
+        );
+        assert_eq!(coverage.unreached().count(), 0);
+        assert_eq!(
+            coverage.report().lines().collect::<Vec<_>>(),
+            [
+                "1 instances: 3/3 subschemas reached, 2/3 matched, 4/4 keywords exercised",
+                "! #: reached 1, matched 0",
+                "      items: exercised 1",
+                "  #/definitions/item: reached 2, matched 1",
+                "      maxLength: exercised 1",
+                "      type: exercised 2",
+                "  #/items: reached 2, matched 1",
+                "      $ref: exercised 2",
+            ]
+        );
+    }
+}
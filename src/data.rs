@@ -0,0 +1,189 @@
+//! The `$data` extension: keyword values taken from the instance.
+//!
+//! Schemas written for ajv often compare values within the same document,
+//! e.g. that a minimum is at most the maximum next to it. With
+//! `Config::with_data_references`, the value of a keyword can be an object
+//! `{"$data": pointer}`, which is replaced by the value that `pointer` leads
+//! to in the instance:
+//!
+//! - a relative JSON pointer, like `1/limit`, goes up from the value being
+//!   validated the given number of levels, and then follows the JSON
+//!   pointer after it;
+//! - an absolute JSON pointer, like `/limit`, starts at the root of the
+//!   instance.
+//!
+//! If the pointer leads nowhere, the keyword is skipped, as in ajv. Only the
+//! keywords in `DATA_KEYWORDS` take `$data`, and only when validating a
+//! `serde_json::Value`: with other `JsonNode`s, they fail. Such schemas
+//! aren't valid against the metaschema of their draft.
+//!
+//! The instance is indexed once for each validation, and its errors are
+//! collected before they are returned.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::{schemas, Config};
+//! let schema = json!({
+//!     "properties": {
+//!         "limit": {"type": "integer"},
+//!         "used": {"maximum": {"$data": "1/limit"}}
+//!     }
+//! });
+//! let cfg = Config::from_schema(&schema, Some(&schemas::Draft7))
+//!     .unwrap()
+//!     .with_data_references();
+//!
+//! assert!(cfg.validate(&json!({"limit": 5, "used": 5})).is_ok());
+//! assert!(cfg.validate(&json!({"limit": 5, "used": 6})).is_err());
+//! assert!(cfg.validate(&json!({"used": 6})).is_ok());
+//! ```
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::node::JsonNode;
+
+/// The keywords whose value can be a `$data` reference, as in ajv.
+pub const DATA_KEYWORDS: &[&str] = &[
+    "const",
+    "enum",
+    "exclusiveMaximum",
+    "exclusiveMinimum",
+    "format",
+    "maxItems",
+    "maxLength",
+    "maxProperties",
+    "maximum",
+    "minItems",
+    "minLength",
+    "minProperties",
+    "minimum",
+    "multipleOf",
+    "pattern",
+    "required",
+    "uniqueItems",
+];
+
+/// The pointer of `value`, the value of `keyword`, if it is a `$data`
+/// reference.
+pub(crate) fn reference<'v>(keyword: &str, value: &'v Value) -> Option<&'v str> {
+    match value {
+        Value::Object(object) if object.len() == 1 && DATA_KEYWORDS.contains(&keyword) => {
+            object.get("$data").and_then(Value::as_str)
+        }
+        _ => None,
+    }
+}
+
+/// The instance being validated, indexed so that `$data` references can be
+/// resolved from any value within it.
+pub(crate) struct Instance<'a> {
+    root: &'a Value,
+    /// Each value within `root`, and the address of its parent, by its
+    /// address.
+    nodes: HashMap<usize, (&'a Value, Option<usize>)>,
+}
+
+impl<'a> Instance<'a> {
+    pub(crate) fn new(root: &'a Value) -> Instance<'a> {
+        let mut nodes = HashMap::new();
+        let mut pending = vec![(root, None)];
+        while let Some((value, parent)) = pending.pop() {
+            let address = value as *const Value as usize;
+            nodes.insert(address, (value, parent));
+            match value {
+                Value::Object(object) => {
+                    pending.extend(object.values().map(|child| (child, Some(address))))
+                }
+                Value::Array(array) => {
+                    pending.extend(array.iter().map(|child| (child, Some(address))))
+                }
+                _ => {}
+            }
+        }
+        Instance { root, nodes }
+    }
+
+    /// Resolve `pointer` for `instance`, which must be within the root.
+    /// Returns `None` if it leads nowhere.
+    pub(crate) fn resolve(
+        &self,
+        instance: &dyn JsonNode,
+        pointer: &str,
+    ) -> Result<Option<&'a Value>, String> {
+        let address = instance as *const dyn JsonNode as *const u8 as usize;
+        let mut node = *self.nodes.get(&address).ok_or_else(|| {
+            format!(
+                "$data reference {} can't be resolved outside of the validated instance",
+                pointer
+            )
+        })?;
+        if pointer.is_empty() || pointer.starts_with('/') {
+            return Ok(self.root.pointer(pointer));
+        }
+        let digits = pointer
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(pointer.len());
+        let (up, rest) = pointer.split_at(digits);
+        let up: usize = match up.parse() {
+            Ok(up) if rest.is_empty() || rest.starts_with('/') => up,
+            _ => return Ok(None),
+        };
+        for _ in 0..up {
+            node = match node.1 {
+                Some(parent) => self.nodes[&parent],
+                None => return Ok(None),
+            };
+        }
+        Ok(node.0.pointer(rest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::schemas;
+    use serde_json::json;
+
+    #[test]
+    fn test_data_references() {
+        let root = json!({"limits": {"max": 3}, "items": [{"n": 1, "max": 2}]});
+        let data = Instance::new(&root);
+        let item = &root["items"][0];
+        assert_eq!(data.resolve(item, "0/max"), Ok(Some(&json!(2))));
+        assert_eq!(data.resolve(&item["n"], "2/1"), Ok(None));
+        assert_eq!(
+            data.resolve(&item["n"], "3/limits/max"),
+            Ok(Some(&json!(3)))
+        );
+        assert_eq!(data.resolve(item, "/limits/max"), Ok(Some(&json!(3))));
+        assert_eq!(data.resolve(item, "9/limits"), Ok(None));
+        assert!(data.resolve(&json!({"max": 2}), "0/max").is_err());
+
+        let schema = json!({
+            "items": {"properties": {"n": {"maximum": {"$data": "1/max"}}}},
+            "maxItems": {"$data": "/count"}
+        });
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        assert!(cfg.validate(&json!([{"n": 3, "max": 2}])).is_ok());
+        let cfg = cfg.with_data_references().with_error_values();
+        let errors: Vec<_> = cfg
+            .validate(&json!([{"n": 3, "max": 2}, {"n": 1, "max": 2}]))
+            .unwrap_err()
+            .collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].instance_path(), ["0", "n"]);
+        assert_eq!(errors[0].keyword_value(), Some(&json!(2)));
+
+        let errors: Vec<_> = cfg
+            .validate_node(&json!([{"n": 3, "max": 2}]))
+            .unwrap_err()
+            .collect();
+        assert_eq!(errors[0].keyword(), Some("maximum"));
+        assert!(errors[0].message().contains("serde_json::Value"));
+    }
+}
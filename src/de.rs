@@ -0,0 +1,602 @@
+//! Validating JSON while deserializing it into a typed value.
+//!
+//! The usual way to validate input before deserializing it into a struct is
+//! to parse it into a `serde_json::Value`, validate that, and convert it into
+//! the struct in a second pass. The helpers here instead deserialize the
+//! struct directly from the input, recording the parsed JSON alongside as it
+//! goes by, and validate the recording once deserialization is complete. The
+//! input is only parsed once.
+//!
+//! Values that the struct ignores (e.g. unknown fields) are recorded as well,
+//! so a schema can still reject them.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::{schemas, Config};
+//! use jsonschema_valid::de;
+//!
+//! #[derive(serde::Deserialize)]
+//! struct Server {
+//!     host: String,
+//!     port: u16,
+//! }
+//!
+//! let schema = json!({
+//!     "properties": {"host": {"type": "string"}, "port": {"minimum": 1024}},
+//!     "additionalProperties": false
+//! });
+//! let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+//!
+//! let server: Server = de::from_str(&cfg, r#"{"host": "localhost", "port": 8080}"#).unwrap();
+//! assert_eq!(server.port, 8080);
+//!
+//! match de::from_str::<Server>(&cfg, r#"{"host": "localhost", "port": 80, "tls": true}"#) {
+//!     Err(de::Error::Invalid(errors)) => assert_eq!(errors.len(), 2),
+//!     _ => panic!("expected validation errors"),
+//! }
+//! ```
+
+use std::error;
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{
+    self, Deserialize, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use serde_json::{Map, Number, Value};
+
+use crate::config::Config;
+use crate::error::ValidationError;
+
+/// An error from `from_str` or `from_slice`.
+#[derive(Debug)]
+pub enum Error {
+    /// The input is not valid JSON, or doesn't have the shape of the target
+    /// type.
+    Json(serde_json::Error),
+    /// The input is invalid against the schema.
+    Invalid(Vec<ValidationError>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Json(err) => write!(f, "{}", err),
+            Error::Invalid(errors) => {
+                write!(f, "{} validation error(s)", errors.len())?;
+                for err in errors {
+                    write!(f, "\n{}", err)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// Deserialize a `T` from a string of JSON, and validate the JSON against
+/// the schema in `cfg`.
+pub fn from_str<'de, T: Deserialize<'de>>(cfg: &Config, s: &'de str) -> Result<T, Error> {
+    finish(cfg, &mut serde_json::Deserializer::from_str(s))
+}
+
+/// Deserialize a `T` from bytes of JSON, and validate the JSON against the
+/// schema in `cfg`.
+pub fn from_slice<'de, T: Deserialize<'de>>(cfg: &Config, v: &'de [u8]) -> Result<T, Error> {
+    finish(cfg, &mut serde_json::Deserializer::from_slice(v))
+}
+
+fn finish<'de, R, T>(
+    cfg: &Config,
+    deserializer: &mut serde_json::Deserializer<R>,
+) -> Result<T, Error>
+where
+    R: serde_json::de::Read<'de>,
+    T: Deserialize<'de>,
+{
+    let result = Validate::new(cfg)
+        .deserialize(&mut *deserializer)
+        .map_err(Error::Json)?;
+    deserializer.end().map_err(Error::Json)?;
+    result.map_err(Error::Invalid)
+}
+
+/// A `DeserializeSeed` that deserializes a `T` and validates the
+/// deserialized JSON against the schema in a `Config`, for use with any
+/// serde deserializer.
+///
+/// Deserialization errors are returned as the error of the deserializer;
+/// validation errors as the `Err` of the deserialized value.
+pub struct Validate<'c, T> {
+    cfg: &'c Config<'c>,
+    marker: PhantomData<T>,
+}
+
+impl<'c, T> Validate<'c, T> {
+    /// Create a seed that validates against the schema in `cfg`.
+    pub fn new(cfg: &'c Config<'c>) -> Self {
+        Validate {
+            cfg,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<'de, 'c, T: Deserialize<'de>> DeserializeSeed<'de> for Validate<'c, T> {
+    type Value = Result<T, Vec<ValidationError>>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let mut recorded = Value::Null;
+        let value = T::deserialize(Tee {
+            inner: deserializer,
+            out: &mut recorded,
+        })?;
+        let errors: Vec<ValidationError> = match self.cfg.validate(&recorded) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors.collect(),
+        };
+        if errors.is_empty() {
+            Ok(Ok(value))
+        } else {
+            Ok(Err(errors))
+        }
+    }
+}
+
+/// A deserializer that records everything the wrapped deserializer produces
+/// into `out`.
+struct Tee<'r, D> {
+    inner: D,
+    out: &'r mut Value,
+}
+
+macro_rules! forward {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+                self.inner.$method(TeeVisitor { inner: visitor, out: self.out })
+            }
+        )*
+    };
+}
+
+impl<'de, 'r, D: Deserializer<'de>> Deserializer<'de> for Tee<'r, D> {
+    type Error = D::Error;
+
+    forward! {
+        deserialize_any deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32
+        deserialize_i64 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_f32 deserialize_f64 deserialize_char deserialize_str deserialize_string
+        deserialize_bytes deserialize_byte_buf deserialize_option deserialize_unit
+        deserialize_seq deserialize_map deserialize_identifier
+    }
+
+    /// Ignored values are recorded too, so they can be validated.
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, D::Error> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        let visitor = TeeVisitor {
+            inner: visitor,
+            out: self.out,
+        };
+        self.inner.deserialize_unit_struct(name, visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        let visitor = TeeVisitor {
+            inner: visitor,
+            out: self.out,
+        };
+        self.inner.deserialize_newtype_struct(name, visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        let visitor = TeeVisitor {
+            inner: visitor,
+            out: self.out,
+        };
+        self.inner.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        let visitor = TeeVisitor {
+            inner: visitor,
+            out: self.out,
+        };
+        self.inner.deserialize_tuple_struct(name, len, visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        let visitor = TeeVisitor {
+            inner: visitor,
+            out: self.out,
+        };
+        self.inner.deserialize_struct(name, fields, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, D::Error> {
+        let visitor = TeeVisitor {
+            inner: visitor,
+            out: self.out,
+        };
+        self.inner.deserialize_enum(name, variants, visitor)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+/// A `DeserializeSeed` that records what it deserializes into `out`.
+struct TeeSeed<'r, S> {
+    inner: S,
+    out: &'r mut Value,
+}
+
+impl<'de, 'r, S: DeserializeSeed<'de>> DeserializeSeed<'de> for TeeSeed<'r, S> {
+    type Value = S::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<S::Value, D::Error> {
+        self.inner.deserialize(Tee {
+            inner: deserializer,
+            out: self.out,
+        })
+    }
+}
+
+/// A visitor that records every value it visits into `out` before passing
+/// it on.
+struct TeeVisitor<'r, V> {
+    inner: V,
+    out: &'r mut Value,
+}
+
+impl<'de, 'r, V: Visitor<'de>> Visitor<'de> for TeeVisitor<'r, V> {
+    type Value = V::Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.expecting(formatter)
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<V::Value, E> {
+        *self.out = Value::Bool(v);
+        self.inner.visit_bool(v)
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<V::Value, E> {
+        *self.out = Value::from(v);
+        self.inner.visit_i64(v)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<V::Value, E> {
+        *self.out = Value::from(v);
+        self.inner.visit_u64(v)
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<V::Value, E> {
+        *self.out = Number::from_f64(v).map_or(Value::Null, Value::Number);
+        self.inner.visit_f64(v)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<V::Value, E> {
+        *self.out = Value::String(v.to_string());
+        self.inner.visit_str(v)
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<V::Value, E> {
+        *self.out = Value::String(v.to_string());
+        self.inner.visit_borrowed_str(v)
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<V::Value, E> {
+        *self.out = Value::String(v.clone());
+        self.inner.visit_string(v)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<V::Value, E> {
+        *self.out = Value::Null;
+        self.inner.visit_unit()
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<V::Value, E> {
+        *self.out = Value::Null;
+        self.inner.visit_none()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<V::Value, D::Error> {
+        self.inner.visit_some(Tee {
+            inner: deserializer,
+            out: self.out,
+        })
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<V::Value, D::Error> {
+        self.inner.visit_newtype_struct(Tee {
+            inner: deserializer,
+            out: self.out,
+        })
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<V::Value, A::Error> {
+        let mut items = Vec::new();
+        let result = self.inner.visit_seq(TeeSeq {
+            inner: seq,
+            items: &mut items,
+        });
+        *self.out = Value::Array(items);
+        result
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<V::Value, A::Error> {
+        let mut entries = Map::new();
+        let result = self.inner.visit_map(TeeMap {
+            inner: map,
+            entries: &mut entries,
+            key: None,
+        });
+        *self.out = Value::Object(entries);
+        result
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<V::Value, A::Error> {
+        self.inner.visit_enum(TeeEnum {
+            inner: data,
+            out: self.out,
+        })
+    }
+}
+
+struct TeeSeq<'r, A> {
+    inner: A,
+    items: &'r mut Vec<Value>,
+}
+
+impl<'de, 'r, A: SeqAccess<'de>> SeqAccess<'de> for TeeSeq<'r, A> {
+    type Error = A::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, A::Error> {
+        let mut item = Value::Null;
+        let result = self.inner.next_element_seed(TeeSeed {
+            inner: seed,
+            out: &mut item,
+        })?;
+        if result.is_some() {
+            self.items.push(item);
+        }
+        Ok(result)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+struct TeeMap<'r, A> {
+    inner: A,
+    entries: &'r mut Map<String, Value>,
+    key: Option<String>,
+}
+
+impl<'de, 'r, A: MapAccess<'de>> MapAccess<'de> for TeeMap<'r, A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, A::Error> {
+        let mut key = Value::Null;
+        let result = self.inner.next_key_seed(TeeSeed {
+            inner: seed,
+            out: &mut key,
+        })?;
+        if result.is_some() {
+            self.key = Some(match key {
+                Value::String(key) => key,
+                other => other.to_string(),
+            });
+        }
+        Ok(result)
+    }
+
+    fn next_value_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<T::Value, A::Error> {
+        let mut value = Value::Null;
+        let result = self.inner.next_value_seed(TeeSeed {
+            inner: seed,
+            out: &mut value,
+        })?;
+        self.entries
+            .insert(self.key.take().unwrap_or_default(), value);
+        Ok(result)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+/// Records an enum as JSON represents it: a unit variant as a string, and
+/// any other variant as an object with a single member named after the
+/// variant.
+struct TeeEnum<'r, A> {
+    inner: A,
+    out: &'r mut Value,
+}
+
+impl<'de, 'r, A: EnumAccess<'de>> EnumAccess<'de> for TeeEnum<'r, A> {
+    type Error = A::Error;
+    type Variant = TeeVariant<'r, A::Variant>;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<(S::Value, Self::Variant), A::Error> {
+        let mut name = Value::Null;
+        let (value, variant) = self.inner.variant_seed(TeeSeed {
+            inner: seed,
+            out: &mut name,
+        })?;
+        let name = match name {
+            Value::String(name) => name,
+            other => other.to_string(),
+        };
+        Ok((
+            value,
+            TeeVariant {
+                inner: variant,
+                out: self.out,
+                name,
+            },
+        ))
+    }
+}
+
+struct TeeVariant<'r, A> {
+    inner: A,
+    out: &'r mut Value,
+    name: String,
+}
+
+impl<'r, A> TeeVariant<'r, A> {
+    fn wrap(name: String, out: &mut Value, content: Value) {
+        let mut object = Map::new();
+        object.insert(name, content);
+        *out = Value::Object(object);
+    }
+}
+
+impl<'de, 'r, A: VariantAccess<'de>> VariantAccess<'de> for TeeVariant<'r, A> {
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), A::Error> {
+        *self.out = Value::String(self.name);
+        self.inner.unit_variant()
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, A::Error> {
+        let mut content = Value::Null;
+        let result = self.inner.newtype_variant_seed(TeeSeed {
+            inner: seed,
+            out: &mut content,
+        });
+        Self::wrap(self.name, self.out, content);
+        result
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, A::Error> {
+        let mut content = Value::Null;
+        let result = self.inner.tuple_variant(
+            len,
+            TeeVisitor {
+                inner: visitor,
+                out: &mut content,
+            },
+        );
+        Self::wrap(self.name, self.out, content);
+        result
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, A::Error> {
+        let mut content = Value::Null;
+        let result = self.inner.struct_variant(
+            fields,
+            TeeVisitor {
+                inner: visitor,
+                out: &mut content,
+            },
+        );
+        Self::wrap(self.name, self.out, content);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas;
+    use serde::Deserialize;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Shape {
+        Point,
+        Circle(f64),
+        Rect { w: u32, h: u32 },
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Drawing {
+        name: Option<String>,
+        shapes: Vec<Shape>,
+        tags: HashMap<String, (i8, bool)>,
+    }
+
+    #[test]
+    fn test_records_everything() {
+        let text = r#"{
+            "name": null,
+            "shapes": ["Point", {"Circle": 1.5}, {"Rect": {"w": 2, "h": 3}}],
+            "tags": {"a": [-1, true]},
+            "extra": {"ignored": [1, 2, {}]}
+        }"#;
+        // A schema that only accepts exactly the input.
+        let schema = json!({"const": serde_json::from_str::<Value>(text).unwrap()});
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+
+        let drawing: Drawing = from_str(&cfg, text).unwrap();
+        assert_eq!(drawing.shapes[2], Shape::Rect { w: 2, h: 3 });
+
+        let other = text.replace("1.5", "2.5");
+        match from_str::<Drawing>(&cfg, &other) {
+            Err(Error::Invalid(errors)) => assert_eq!(errors.len(), 1),
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(matches!(
+            from_str::<Drawing>(&cfg, "{} x"),
+            Err(Error::Json(_))
+        ));
+    }
+}
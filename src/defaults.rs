@@ -0,0 +1,262 @@
+//! Layering of defaults and overrides onto a JSON instance before validation.
+//!
+//! Layered configuration systems often keep defaults (and environment
+//! specific overrides) in documents separate from the user-provided
+//! configuration. A [`Layered`](struct.Layered.html) instance merges those
+//! documents together and remembers where each value came from, so that
+//! validation errors can be traced back to the layer that introduced them.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! use jsonschema_valid::defaults::{Layered, Origin};
+//!
+//! let instance = json!({"name": "service", "port": 80});
+//! let defaults = json!({"port": 8080, "log": {"level": "info"}});
+//! let overrides = json!({"name": "staging-service"});
+//!
+//! let layered = Layered::new(&instance)
+//!     .with_defaults(&defaults)
+//!     .with_overrides(&overrides);
+//!
+//! assert_eq!(
+//!     layered.value(),
+//!     &json!({"name": "staging-service", "port": 80, "log": {"level": "info"}})
+//! );
+//! assert_eq!(layered.origin("/log/level"), Origin::Default);
+//! assert_eq!(layered.origin("/name"), Origin::Override);
+//! assert_eq!(layered.origin("/port"), Origin::Instance);
+//! ```
+//!
+//! The origin of the value a validation error is about can be looked up
+//! with `origin_of`:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::Config;
+//! use jsonschema_valid::defaults::{Layered, Origin};
+//!
+//! let schema = json!({"properties": {"port": {"type": "integer"}}});
+//! let layered = Layered::new(&json!({})).with_defaults(&json!({"port": "80"}));
+//!
+//! let cfg = Config::from_schema(&schema, None).unwrap();
+//! let errors: Vec<_> = cfg.validate(layered.value()).unwrap_err().collect();
+//! assert_eq!(layered.origin_of(&errors[0]), Origin::Default);
+//! ```
+
+use serde_json::Value;
+
+use crate::error::ValidationError;
+use crate::util;
+
+/// The layer a value in a [`Layered`](struct.Layered.html) instance came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    /// The value was present in the original instance.
+    Instance,
+    /// The value was filled in from a defaults document.
+    Default,
+    /// The value was replaced by an overrides document.
+    Override,
+}
+
+/// A JSON instance with defaults and overrides merged into it.
+#[derive(Debug, Clone)]
+pub struct Layered {
+    value: Value,
+    from_defaults: Vec<String>,
+    from_overrides: Vec<String>,
+}
+
+impl Layered {
+    /// Start layering on top of the given instance.
+    pub fn new(instance: &Value) -> Layered {
+        Layered {
+            value: instance.clone(),
+            from_defaults: Vec::new(),
+            from_overrides: Vec::new(),
+        }
+    }
+
+    /// Fill in any values missing from the instance with those from
+    /// `defaults`. Objects are merged recursively; any other existing value
+    /// is left untouched.
+    pub fn with_defaults(mut self, defaults: &Value) -> Self {
+        merge_defaults(
+            &mut self.value,
+            defaults,
+            &mut String::new(),
+            &mut self.from_defaults,
+        );
+        self
+    }
+
+    /// Replace values in the instance with those from `overrides`. Objects are
+    /// merged recursively; any other value replaces the existing one.
+    pub fn with_overrides(mut self, overrides: &Value) -> Self {
+        merge_overrides(
+            &mut self.value,
+            overrides,
+            &mut String::new(),
+            &mut self.from_overrides,
+        );
+        // A value that has been overridden no longer comes from the defaults.
+        let from_overrides = &self.from_overrides;
+        self.from_defaults
+            .retain(|x| !from_overrides.iter().any(|y| is_within(x, y)));
+        self
+    }
+
+    /// Get the merged JSON document, ready to be validated.
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// Consume the layering and return the merged JSON document.
+    pub fn into_value(self) -> Value {
+        self.value
+    }
+
+    /// The JSON pointers of all values that were filled in from defaults.
+    pub fn from_defaults(&self) -> &[String] {
+        &self.from_defaults
+    }
+
+    /// The JSON pointers of all values that were replaced by overrides.
+    pub fn from_overrides(&self) -> &[String] {
+        &self.from_overrides
+    }
+
+    /// Determine which layer the value at the given JSON pointer came from.
+    pub fn origin(&self, pointer: &str) -> Origin {
+        if self.from_overrides.iter().any(|x| is_within(pointer, x)) {
+            Origin::Override
+        } else if self.from_defaults.iter().any(|x| is_within(pointer, x)) {
+            Origin::Default
+        } else {
+            Origin::Instance
+        }
+    }
+
+    /// Determine which layer the value that failed validation with `error`
+    /// came from, given the error's instance path.
+    pub fn origin_of(&self, error: &ValidationError) -> Origin {
+        let pointer: String = error
+            .instance_path()
+            .iter()
+            .map(|token| format!("/{}", util::escape_pointer_token(token)))
+            .collect();
+        self.origin(&pointer)
+    }
+}
+
+/// Is the JSON pointer `pointer` equal to or nested within `parent`?
+fn is_within(pointer: &str, parent: &str) -> bool {
+    pointer == parent || (pointer.starts_with(parent) && pointer[parent.len()..].starts_with('/'))
+}
+
+fn merge_defaults(
+    target: &mut Value,
+    defaults: &Value,
+    pointer: &mut String,
+    origins: &mut Vec<String>,
+) {
+    if let (Value::Object(target), Value::Object(defaults)) = (target, defaults) {
+        for (key, default) in defaults {
+            let len = pointer.len();
+            pointer.push('/');
+            pointer.push_str(&util::escape_pointer_token(key));
+            match target.get_mut(key) {
+                Some(existing) => merge_defaults(existing, default, pointer, origins),
+                None => {
+                    target.insert(key.clone(), default.clone());
+                    origins.push(pointer.clone());
+                }
+            }
+            pointer.truncate(len);
+        }
+    }
+}
+
+fn merge_overrides(
+    target: &mut Value,
+    overrides: &Value,
+    pointer: &mut String,
+    origins: &mut Vec<String>,
+) {
+    match (target, overrides) {
+        (Value::Object(target), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                let len = pointer.len();
+                pointer.push('/');
+                pointer.push_str(&util::escape_pointer_token(key));
+                match target.get_mut(key) {
+                    Some(existing) => merge_overrides(existing, value, pointer, origins),
+                    None => {
+                        target.insert(key.clone(), value.clone());
+                        origins.push(pointer.clone());
+                    }
+                }
+                pointer.truncate(len);
+            }
+        }
+        (target, overrides) => {
+            *target = overrides.clone();
+            origins.push(pointer.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_defaults_do_not_replace() {
+        let layered = Layered::new(&json!({"a": 1, "b": {"c": null}}))
+            .with_defaults(&json!({"a": 2, "b": {"c": 3, "d/e": 4}}));
+        assert_eq!(
+            layered.value(),
+            &json!({"a": 1, "b": {"c": null, "d/e": 4}})
+        );
+        assert_eq!(layered.from_defaults(), &["/b/d~1e".to_string()]);
+    }
+
+    #[test]
+    fn test_overrides_take_precedence_over_defaults() {
+        let layered = Layered::new(&json!({}))
+            .with_defaults(&json!({"a": {"b": 1}}))
+            .with_overrides(&json!({"a": 2}));
+        assert_eq!(layered.value(), &json!({"a": 2}));
+        assert!(layered.from_defaults().is_empty());
+        assert_eq!(layered.origin("/a"), Origin::Override);
+    }
+
+    #[test]
+    fn test_origin_of_errors() {
+        use crate::config::Config;
+
+        let schema = json!({
+            "properties": {"a/b": {"type": "string"}, "c": {"maximum": 1}, "d": false}
+        });
+        let layered = Layered::new(&json!({"d": 0}))
+            .with_defaults(&json!({"a/b": 1}))
+            .with_overrides(&json!({"c": 2}));
+        let cfg = Config::from_schema(&schema, None).unwrap();
+        let origins: Vec<_> = cfg
+            .validate(layered.value())
+            .unwrap_err()
+            .map(|err| (err.instance_path().join("/"), layered.origin_of(&err)))
+            .collect();
+        assert_eq!(
+            origins,
+            vec![
+                ("a/b".to_string(), Origin::Default),
+                ("c".to_string(), Origin::Override),
+                ("d".to_string(), Origin::Instance),
+            ]
+        );
+    }
+}
@@ -0,0 +1,414 @@
+//! Comparing two versions of a schema.
+//!
+//! A change is considered *breaking* if the new schema may reject instances
+//! that the old schema accepted, e.g. because a property became required or
+//! a bound became stricter. Changes that only relax the schema, or only touch
+//! annotations such as `description`, are not breaking.
+//!
+//! The comparison is structural: subschemas are compared keyword by keyword
+//! at the same location, and references are not followed. Changes the
+//! comparison can't reason about (e.g. a different `$ref` or a reordered
+//! `anyOf`) are conservatively reported as breaking.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! use jsonschema_valid::diff::diff;
+//!
+//! let old = json!({"properties": {"age": {"type": "integer", "minimum": 0}}});
+//! let new = json!({
+//!     "properties": {"age": {"type": "integer", "minimum": 18}},
+//!     "description": "A person"
+//! });
+//!
+//! let changes = diff(&old, &new);
+//! assert_eq!(changes.len(), 2);
+//! assert!(changes.iter().any(|x| x.breaking && x.pointer == "/properties/age/minimum"));
+//! assert!(changes.iter().any(|x| !x.breaking && x.keyword == "description"));
+//! ```
+
+use serde_json::{json, Map, Value};
+
+use crate::util;
+
+/// A single difference between two versions of a schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    /// JSON pointer to the changed keyword in the new schema (or in the old
+    /// schema, if the keyword was removed).
+    pub pointer: String,
+    /// The keyword that changed.
+    pub keyword: String,
+    /// Whether the new schema may reject instances the old schema accepted.
+    pub breaking: bool,
+    /// A description of the change.
+    pub message: String,
+}
+
+impl Change {
+    /// Represent the change as a JSON object, for machine-readable output.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "pointer": self.pointer,
+            "keyword": self.keyword,
+            "breaking": self.breaking,
+            "message": self.message,
+        })
+    }
+}
+
+/// Keywords that only annotate a schema and never affect validation.
+const ANNOTATIONS: &[&str] = &[
+    "$comment",
+    "$schema",
+    "default",
+    "description",
+    "examples",
+    "readOnly",
+    "title",
+    "writeOnly",
+];
+
+/// Lower bounds: increasing them is breaking.
+const LOWER_BOUNDS: &[&str] = &[
+    "exclusiveMinimum",
+    "minItems",
+    "minLength",
+    "minProperties",
+    "minimum",
+];
+
+/// Upper bounds: decreasing them is breaking.
+const UPPER_BOUNDS: &[&str] = &[
+    "exclusiveMaximum",
+    "maxItems",
+    "maxLength",
+    "maxProperties",
+    "maximum",
+];
+
+/// Compare two versions of a schema, returning all changes from `old` to
+/// `new`.
+pub fn diff(old: &Value, new: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_schemas(old, new, &mut String::new(), &mut changes);
+    changes
+}
+
+fn as_object(schema: &Value) -> Map<String, Value> {
+    match schema {
+        Value::Object(object) => object.clone(),
+        Value::Bool(false) => {
+            let mut object = Map::new();
+            object.insert("not".to_string(), json!({}));
+            object
+        }
+        _ => Map::new(),
+    }
+}
+
+fn diff_schemas(old: &Value, new: &Value, pointer: &mut String, changes: &mut Vec<Change>) {
+    if old == new {
+        return;
+    }
+    let (old, new) = (as_object(old), as_object(new));
+
+    let mut keywords: Vec<&String> = old.keys().chain(new.keys()).collect();
+    keywords.sort();
+    keywords.dedup();
+    for keyword in keywords {
+        let len = pointer.len();
+        pointer.push('/');
+        pointer.push_str(&util::escape_pointer_token(keyword));
+        diff_keyword(keyword, &old, &new, pointer, changes);
+        pointer.truncate(len);
+    }
+}
+
+fn diff_keyword(
+    keyword: &str,
+    old_schema: &Map<String, Value>,
+    new_schema: &Map<String, Value>,
+    pointer: &mut String,
+    changes: &mut Vec<Change>,
+) {
+    let (old, new) = (old_schema.get(keyword), new_schema.get(keyword));
+    if old == new {
+        return;
+    }
+    let mut change = |breaking: bool, message: String| {
+        changes.push(Change {
+            pointer: pointer.clone(),
+            keyword: keyword.to_string(),
+            breaking,
+            message,
+        })
+    };
+
+    if ANNOTATIONS.contains(&keyword) {
+        change(false, format!("{} changed", keyword));
+        return;
+    }
+
+    match (keyword, old, new) {
+        // Definitions only take effect once referenced.
+        ("definitions", None, Some(_)) | ("$defs", None, Some(_)) => {
+            change(false, format!("{} added", keyword))
+        }
+        (_, None, Some(new)) if !is_subschema_keyword(keyword) || !is_trivial(new) => {
+            change(true, format!("{} added", keyword))
+        }
+        (_, Some(_), None) => {
+            // Removing a keyword relaxes the schema, unless it declared
+            // properties that are now rejected by `additionalProperties`.
+            let breaking = (keyword == "properties" || keyword == "patternProperties")
+                && new_schema.get("additionalProperties") == Some(&Value::Bool(false));
+            change(breaking, format!("{} removed", keyword))
+        }
+        ("type", Some(old), Some(new)) => {
+            let (old_types, new_types) = (type_set(old), type_set(new));
+            let removed: Vec<&str> = old_types
+                .iter()
+                .filter(|x| !allows_type(&new_types, x))
+                .cloned()
+                .collect();
+            let added: Vec<&str> = new_types
+                .iter()
+                .filter(|x| !allows_type(&old_types, x))
+                .cloned()
+                .collect();
+            if !removed.is_empty() {
+                change(
+                    true,
+                    format!("type no longer allows {}", removed.join(", ")),
+                );
+            }
+            if !added.is_empty() {
+                change(false, format!("type now allows {}", added.join(", ")));
+            }
+        }
+        ("required", Some(old), Some(new)) | ("enum", Some(old), Some(new)) => {
+            let (old_items, new_items) = (array_items(old), array_items(new));
+            let added: Vec<String> = new_items
+                .iter()
+                .filter(|x| !old_items.contains(x))
+                .map(|x| x.to_string())
+                .collect();
+            let removed: Vec<String> = old_items
+                .iter()
+                .filter(|x| !new_items.contains(x))
+                .map(|x| x.to_string())
+                .collect();
+            // Requiring more properties is breaking, but allowing more
+            // values is not.
+            let (stricter, looser) = if keyword == "required" {
+                (added, removed)
+            } else {
+                (removed, added)
+            };
+            if !stricter.is_empty() {
+                let verb = if keyword == "required" {
+                    "now requires"
+                } else {
+                    "no longer allows"
+                };
+                change(
+                    true,
+                    format!("{} {} {}", keyword, verb, stricter.join(", ")),
+                );
+            }
+            if !looser.is_empty() {
+                let verb = if keyword == "required" {
+                    "no longer requires"
+                } else {
+                    "now allows"
+                };
+                change(false, format!("{} {} {}", keyword, verb, looser.join(", ")));
+            }
+        }
+        (_, Some(old), Some(new))
+            if LOWER_BOUNDS.contains(&keyword) || UPPER_BOUNDS.contains(&keyword) =>
+        {
+            match (old.as_f64(), new.as_f64()) {
+                (Some(x), Some(y)) => {
+                    let stricter = if LOWER_BOUNDS.contains(&keyword) {
+                        y > x
+                    } else {
+                        y < x
+                    };
+                    change(
+                        stricter,
+                        format!("{} changed from {} to {}", keyword, old, new),
+                    )
+                }
+                // Draft 4 style boolean exclusiveMinimum/exclusiveMaximum.
+                _ => change(
+                    new == &Value::Bool(true),
+                    format!("{} changed from {} to {}", keyword, old, new),
+                ),
+            }
+        }
+        ("multipleOf", Some(old), Some(new)) => {
+            let looser = match (old.as_f64(), new.as_f64()) {
+                (Some(x), Some(y)) => (x / y).fract() == 0.0,
+                _ => false,
+            };
+            change(
+                !looser,
+                format!("multipleOf changed from {} to {}", old, new),
+            )
+        }
+        ("uniqueItems", Some(_), Some(new)) => change(
+            new == &Value::Bool(true),
+            format!("uniqueItems changed to {}", new),
+        ),
+        ("properties", Some(old), Some(new))
+        | ("patternProperties", Some(old), Some(new))
+        | ("definitions", Some(old), Some(new))
+        | ("$defs", Some(old), Some(new))
+        | ("dependencies", Some(old), Some(new)) => {
+            let (old, new) = (as_object(old), as_object(new));
+            let mut names: Vec<&String> = old.keys().chain(new.keys()).collect();
+            names.sort();
+            names.dedup();
+            let closed = new_schema.get("additionalProperties") == Some(&Value::Bool(false));
+            let is_definitions = keyword == "definitions" || keyword == "$defs";
+            for name in names {
+                let len = pointer.len();
+                pointer.push('/');
+                pointer.push_str(&util::escape_pointer_token(name));
+                match (old.get(name), new.get(name)) {
+                    (Some(old), Some(new)) if old.is_array() || new.is_array() => {
+                        // Property dependencies in `dependencies`.
+                        if old != new {
+                            changes.push(Change {
+                                pointer: pointer.clone(),
+                                keyword: keyword.to_string(),
+                                breaking: true,
+                                message: format!("{} of {} changed", keyword, name),
+                            });
+                        }
+                    }
+                    (Some(old), Some(new)) => diff_schemas(old, new, pointer, changes),
+                    (None, Some(new)) => changes.push(Change {
+                        pointer: pointer.clone(),
+                        keyword: keyword.to_string(),
+                        breaking: !is_definitions && !is_trivial(new),
+                        message: format!("{} {} added", keyword, name),
+                    }),
+                    (Some(_), None) => changes.push(Change {
+                        pointer: pointer.clone(),
+                        keyword: keyword.to_string(),
+                        breaking: keyword == "properties" && closed,
+                        message: format!("{} {} removed", keyword, name),
+                    }),
+                    (None, None) => {}
+                }
+                pointer.truncate(len);
+            }
+        }
+        (_, Some(Value::Array(old)), Some(Value::Array(new)))
+            if util::SCHEMA_ARRAY_KEYWORDS.contains(&keyword) && old.len() == new.len() =>
+        {
+            for (index, (old, new)) in old.iter().zip(new.iter()).enumerate() {
+                let len = pointer.len();
+                pointer.push('/');
+                pointer.push_str(&index.to_string());
+                diff_schemas(old, new, pointer, changes);
+                pointer.truncate(len);
+            }
+        }
+        (_, Some(old), Some(new))
+            if util::SCHEMA_KEYWORDS.contains(&keyword)
+                && keyword != "not"
+                && !old.is_array()
+                && !new.is_array() =>
+        {
+            diff_schemas(old, new, pointer, changes)
+        }
+        (_, Some(old), Some(new)) => {
+            change(true, format!("{} changed from {} to {}", keyword, old, new))
+        }
+        (_, None, Some(_)) => change(false, format!("{} added", keyword)),
+        (_, None, None) => {}
+    }
+}
+
+fn is_subschema_keyword(keyword: &str) -> bool {
+    util::SCHEMA_KEYWORDS.contains(&keyword) && keyword != "not"
+}
+
+/// Is the schema `true` or `{}`, which accepts everything?
+fn is_trivial(schema: &Value) -> bool {
+    match schema {
+        Value::Bool(b) => *b,
+        Value::Object(object) => object.is_empty(),
+        _ => false,
+    }
+}
+
+fn type_set(types: &Value) -> Vec<&str> {
+    util::iter_or_once(types)
+        .filter_map(Value::as_str)
+        .collect()
+}
+
+fn allows_type(types: &[&str], typename: &str) -> bool {
+    types.contains(&typename) || (typename == "integer" && types.contains(&"number"))
+}
+
+fn array_items(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(items) => items.iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaking(old: Value, new: Value) -> Vec<(String, bool)> {
+        diff(&old, &new)
+            .into_iter()
+            .map(|x| (x.pointer, x.breaking))
+            .collect()
+    }
+
+    #[test]
+    fn test_relaxing_is_not_breaking() {
+        assert_eq!(
+            breaking(
+                json!({"type": "integer", "enum": [1, 2], "required": ["a", "b"], "maxLength": 3}),
+                json!({"type": "number", "enum": [1, 2, 3], "required": ["a"]})
+            ),
+            vec![
+                ("/enum".to_string(), false),
+                ("/maxLength".to_string(), false),
+                ("/required".to_string(), false),
+                ("/type".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tightening_is_breaking() {
+        assert_eq!(
+            breaking(
+                json!({"properties": {"a": {"type": "number"}, "b": {}}, "items": [{}]}),
+                json!({
+                    "properties": {"a": {"type": "integer"}},
+                    "additionalProperties": false,
+                    "items": [{"minimum": 1}]
+                })
+            ),
+            vec![
+                ("/additionalProperties".to_string(), true),
+                ("/items/0/minimum".to_string(), true),
+                ("/properties/a/type".to_string(), true),
+                ("/properties/b".to_string(), true),
+            ]
+        );
+    }
+}
@@ -1,7 +1,17 @@
+//! Validation errors.
+//!
 use std::fmt;
 use std::iter::{empty, once};
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
 use url;
 
+use crate::config::Config;
+use crate::node::JsonNode;
+use crate::output::{ContextMode, ParentContext, Redaction};
+use crate::util;
+
 /// An error that can occur during validation.
 ///
 /// It holds:
@@ -14,9 +24,23 @@ pub struct ValidationError {
     msg: String,
     instance_path: Vec<String>,
     schema_path: Vec<String>,
+    details: Box<ErrorDetails>,
 }
 
-fn path_to_string(path: &[String]) -> String {
+/// Information about an error that isn't needed to construct it, kept out of
+/// line to keep `ValidationError` small.
+#[derive(Default, Debug, Clone)]
+struct ErrorDetails {
+    keyword: Option<String>,
+    keyword_value: Option<Value>,
+    instance: Option<Value>,
+    docs_url: Option<String>,
+    parent_context: Option<ParentContext>,
+    causes: Vec<ValidationError>,
+    fix: Option<Vec<Value>>,
+}
+
+fn path_to_string(path: &[&str]) -> String {
     if path.is_empty() {
         "/".to_string()
     } else {
@@ -29,13 +53,95 @@ impl fmt::Display for ValidationError {
         write!(
             f,
             "At {} with schema at {}: {}",
-            path_to_string(&self.instance_path),
-            path_to_string(&self.schema_path),
+            path_to_string(&self.instance_path()),
+            path_to_string(&self.schema_path()),
             self.msg
         )
     }
 }
 
+fn pointer(path: &[&str]) -> String {
+    if path.is_empty() {
+        "/".to_string()
+    } else {
+        path.iter()
+            .map(|token| format!("/{}", util::escape_pointer_token(token)))
+            .collect()
+    }
+}
+
+fn fragment(path: &[&str]) -> String {
+    let mut result = "#".to_string();
+    for token in path {
+        result.push('/');
+        result.push_str(&util::escape_pointer_token(token));
+    }
+    result
+}
+
+/// The single-line rendering of an error returned by
+/// `ValidationError::compact`.
+pub struct Compact<'a>(&'a ValidationError);
+
+impl fmt::Display for Compact<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} (at {})",
+            pointer(&self.0.instance_path()),
+            self.0.msg,
+            fragment(&self.0.schema_path())
+        )
+    }
+}
+
+/// The multi-line rendering of an error and its causes returned by
+/// `ValidationError::pretty`.
+pub struct Pretty<'a>(&'a ValidationError);
+
+impl Pretty<'_> {
+    fn fmt_causes(
+        f: &mut fmt::Formatter,
+        error: &ValidationError,
+        instance_path: &[&str],
+        schema_path: &[&str],
+        indent: &str,
+    ) -> fmt::Result {
+        for (i, cause) in error.causes().iter().enumerate() {
+            let last = i + 1 == error.causes().len();
+            let mut cause_instance_path = instance_path.to_vec();
+            cause_instance_path.extend(cause.instance_path());
+            let mut cause_schema_path = schema_path.to_vec();
+            cause_schema_path.extend(cause.schema_path());
+            writeln!(
+                f,
+                "{}{} {}: {} (at {})",
+                indent,
+                if last { "└─" } else { "├─" },
+                pointer(&cause_instance_path),
+                cause.msg,
+                fragment(&cause_schema_path)
+            )?;
+            let indent = format!("{}{}", indent, if last { "   " } else { "│  " });
+            Pretty::fmt_causes(f, cause, &cause_instance_path, &cause_schema_path, &indent)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Pretty<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.0.compact())?;
+        Pretty::fmt_causes(
+            f,
+            self.0,
+            &self.0.instance_path(),
+            &self.0.schema_path(),
+            "  ",
+        )
+    }
+}
+
 impl From<url::ParseError> for ValidationError {
     fn from(err: url::ParseError) -> ValidationError {
         ValidationError::new(&format!("Invalid URL: {:?}", err))
@@ -73,16 +179,334 @@ impl ValidationError {
         self.schema_path.push(schema_context);
         self
     }
+
+    /// Record the schema keyword that failed, unless the error already has a
+    /// keyword or is nested within another keyword's schema. If `cfg`
+    /// records error values, its value and the instance it failed on, if
+    /// known, are recorded as well.
+    pub(crate) fn keyword_ctx(
+        mut self,
+        cfg: &Config,
+        keyword: &str,
+        value: &Value,
+        instance: Option<&dyn JsonNode>,
+    ) -> Self {
+        if self.details.keyword.is_none() && self.schema_path.is_empty() {
+            self.details.keyword = Some(keyword.to_string());
+            if cfg.records_error_values() {
+                self.details.keyword_value = Some(value.clone());
+                self.details.instance = instance.map(JsonNode::to_value);
+            }
+        }
+        self
+    }
+
+    /// Record the object enclosing the failing part of the instance, unless
+    /// the error already has a closer enclosing object.
+    pub(crate) fn parent_ctx(mut self, mode: ContextMode, parent: &dyn JsonNode) -> Self {
+        if self.details.parent_context.is_none() {
+            self.details.parent_context = Some(match mode {
+                ContextMode::Keys => {
+                    ParentContext::Keys(parent.members().map(|(key, _)| key.to_string()).collect())
+                }
+                ContextMode::Snippet(max_chars) => {
+                    let json = parent.to_value().to_string();
+                    if json.chars().count() > max_chars {
+                        ParentContext::Snippet(
+                            json.chars().take(max_chars).collect::<String>() + "…",
+                        )
+                    } else {
+                        ParentContext::Snippet(json)
+                    }
+                }
+            });
+        }
+        self
+    }
+
+    /// Remove the failing instance value from the error, so that only paths
+    /// and schema constraints remain.
+    pub(crate) fn redact(mut self, redaction: Redaction) -> Self {
+        let instance = self.details.instance.take();
+        self.details.fix = None;
+        if let Some(ParentContext::Snippet(_)) = self.details.parent_context {
+            self.details.parent_context = None;
+        }
+        let (keyword, constraint) = match (&self.details.keyword, &self.details.keyword_value) {
+            (Some(keyword), Some(value)) if keyword != "$ref" => (keyword, value),
+            _ => return self,
+        };
+        self.msg = match (redaction, instance) {
+            (Redaction::Hash, Some(instance)) => {
+                let digest = Sha256::digest(instance.to_string().as_bytes());
+                let hex: String = digest.iter().map(|x| format!("{:02x}", x)).collect();
+                let hashed = format!("sha256:{}", hex);
+                let msg = format!(
+                    "Value {} does not satisfy {} {}",
+                    hashed, keyword, constraint
+                );
+                self.details.instance = Some(Value::String(hashed));
+                msg
+            }
+            _ => format!("Value does not satisfy {} {}", keyword, constraint),
+        };
+        self
+    }
+
+    /// Replace the error message.
+    pub(crate) fn with_message(mut self, msg: String) -> Self {
+        self.msg = msg;
+        self
+    }
+
+    /// The message describing the validation failure.
+    pub fn message(&self) -> &str {
+        &self.msg
+    }
+
+    /// The path to the failing part of the instance, from the root of the
+    /// instance.
+    pub fn instance_path(&self) -> Vec<&str> {
+        self.instance_path
+            .iter()
+            .rev()
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// The path to the failing keyword, from the root of the schema.
+    pub fn schema_path(&self) -> Vec<&str> {
+        self.schema_path.iter().rev().map(String::as_str).collect()
+    }
+
+    /// Attach a URL to documentation explaining the error.
+    pub fn with_docs_url(mut self, docs_url: String) -> Self {
+        self.details.docs_url = Some(docs_url);
+        self
+    }
+
+    /// The schema keyword that failed validation (e.g. `"type"` or
+    /// `"required"`), if any. Errors from `false` schemas have no keyword.
+    pub fn keyword(&self) -> Option<&str> {
+        self.details.keyword.as_deref()
+    }
+
+    /// The value of the failing keyword in the schema, e.g. `"integer"` for a
+    /// failing `"type": "integer"`. Only recorded with
+    /// `Config::with_error_values`.
+    pub fn keyword_value(&self) -> Option<&Value> {
+        self.details.keyword_value.as_ref()
+    }
+
+    /// The part of the instance that failed validation. Only recorded with
+    /// `Config::with_error_values`.
+    pub fn instance(&self) -> Option<&Value> {
+        self.details.instance.as_ref()
+    }
+
+    /// Information about the object enclosing the failing part of the
+    /// instance, if enabled with `Config::with_error_context`.
+    pub fn parent_context(&self) -> Option<&ParentContext> {
+        self.details.parent_context.as_ref()
+    }
+
+    /// The URL to documentation explaining the error, if the `Config` has one
+    /// configured for the failing keyword.
+    pub fn docs_url(&self) -> Option<&str> {
+        self.details.docs_url.as_deref()
+    }
+
+    /// The errors that made an `anyOf` or `oneOf` fail: those of each of
+    /// its subschemas. Their paths are relative to the paths of this error,
+    /// starting with the index of the subschema in the schema path. For
+    /// `contentSchema`, these are the errors of the decoded contents, with
+    /// paths relative to the decoded document.
+    pub fn causes(&self) -> &[ValidationError] {
+        &self.details.causes
+    }
+
+    /// Attach the errors that made an applicator keyword fail.
+    pub(crate) fn with_causes(mut self, causes: Vec<ValidationError>) -> Self {
+        self.details.causes = causes;
+        self
+    }
+
+    /// Apply `f` to each of the causes of the error.
+    pub(crate) fn map_causes<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(ValidationError) -> ValidationError,
+    {
+        self.details.causes = std::mem::take(&mut self.details.causes)
+            .into_iter()
+            .map(f)
+            .collect();
+        self
+    }
+
+    /// A JSON Patch (RFC 6902) that fixes the failure, if there is an
+    /// obvious one: adding the defaults of missing required properties,
+    /// converting a value to the required type without loss (e.g. `"42"` to
+    /// `42`), or removing properties that aren't allowed.
+    ///
+    /// The paths of the operations are from the root of the instance.
+    /// Errors with redacted instance values have no fix.
+    pub fn fix(&self) -> Option<Value> {
+        let prefix: String = self
+            .instance_path()
+            .iter()
+            .map(|token| format!("/{}", util::escape_pointer_token(token)))
+            .collect();
+        let fix = self.details.fix.as_ref()?;
+        Some(Value::Array(
+            fix.iter()
+                .map(|operation| {
+                    let mut operation = operation.clone();
+                    if let Some(Value::String(path)) = operation.get_mut("path") {
+                        path.insert_str(0, &prefix);
+                    }
+                    operation
+                })
+                .collect(),
+        ))
+    }
+
+    /// Attach the operations that fix the failure, with paths relative to
+    /// the failing part of the instance.
+    pub(crate) fn with_fix(mut self, fix: Vec<Value>) -> Self {
+        self.details.fix = Some(fix);
+        self
+    }
+
+    /// Render the error on a single line for logs, with the location of the
+    /// failing part of the instance, the message, and the location of the
+    /// failing keyword in the schema:
+    /// `/a/b: "x" is not of type "integer" (at #/properties/a/type)`.
+    pub fn compact(&self) -> Compact<'_> {
+        Compact(self)
+    }
+
+    /// Render the error in the compact form, followed by a tree of its
+    /// causes, one per line with their full paths, for terminals.
+    pub fn pretty(&self) -> Pretty<'_> {
+        Pretty(self)
+    }
 }
 
 /// An `Iterator` over `ValidationError` objects. The main method by which
 /// validation errors are returned to the user.
 pub type ErrorIterator<'a> = Box<dyn Iterator<Item = ValidationError> + 'a>;
 
+/// The ways one-shot validation with `validate_str` or `validate_bytes` can
+/// fail.
+#[derive(Debug)]
+pub enum Errors {
+    /// The schema is not valid JSON.
+    SchemaSyntax(serde_json::Error),
+    /// The instance is not valid JSON.
+    InstanceSyntax(serde_json::Error),
+    /// The schema is valid JSON but not a valid schema: it can't be
+    /// compiled, or doesn't validate against the metaschema of its draft.
+    InvalidSchema(Vec<ValidationError>),
+    /// The instance is invalid against the schema.
+    Invalid(Vec<ValidationError>),
+}
+
+impl fmt::Display for Errors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (what, errors) = match self {
+            Errors::SchemaSyntax(err) => return write!(f, "Schema is not valid JSON: {}", err),
+            Errors::InstanceSyntax(err) => return write!(f, "Instance is not valid JSON: {}", err),
+            Errors::InvalidSchema(errors) => ("Schema is invalid", errors),
+            Errors::Invalid(errors) => ("Instance is invalid", errors),
+        };
+        write!(f, "{}", what)?;
+        for error in errors {
+            write!(f, "\n{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Errors {}
+
+/// An `ErrorIterator` with a single error with the given message.
 pub fn make_error<'a, O: Into<String>>(message: O) -> ErrorIterator<'a> {
     Box::new(once(ValidationError::new(&message.into())))
 }
 
+/// An `ErrorIterator` without any errors.
 pub fn no_error<'a>() -> ErrorIterator<'a> {
     Box::new(empty())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::schemas;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_rendering() {
+        let schema = json!({
+            "properties": {"a": {"anyOf": [{"type": "integer"}, {"properties": {"b": {"type": "null"}}}]}}
+        });
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let instance = json!({"a": {"b": "x"}});
+        let error = cfg.validate(&instance).unwrap_err().next().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "At /a with schema at /properties/a/anyOf: anyOf failed"
+        );
+        assert_eq!(
+            error.compact().to_string(),
+            "/a: anyOf failed (at #/properties/a/anyOf)"
+        );
+        assert_eq!(
+            error.pretty().to_string(),
+            "/a: anyOf failed (at #/properties/a/anyOf)\n\
+             \x20 ├─ /a: {\"b\":\"x\"} is not of type \"integer\" (at #/properties/a/anyOf/0/type)\n\
+             \x20 └─ /a/b: \"x\" is not of type \"null\" (at #/properties/a/anyOf/1/properties/b/type)\n"
+        );
+    }
+
+    #[test]
+    fn test_fix() {
+        let schema = json!({
+            "properties": {
+                "a": {"properties": {"n": {"type": ["boolean", "integer"]}}, "additionalProperties": false},
+                "b": {"type": "integer", "default": 1},
+                "c/d": {"default": "x"}
+            },
+            "required": ["b", "c/d"]
+        });
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let instance = json!({"a": {"n": "42", "x": 1, "y": 2}});
+        let fixes: Vec<Value> = cfg
+            .validate(&instance)
+            .unwrap_err()
+            .filter_map(|err| err.fix())
+            .collect();
+        assert_eq!(
+            fixes,
+            vec![
+                json!([{"op": "remove", "path": "/a/x"}, {"op": "remove", "path": "/a/y"}]),
+                json!([{"op": "replace", "path": "/a/n", "value": 42}]),
+                json!([
+                    {"op": "add", "path": "/b", "value": 1},
+                    {"op": "add", "path": "/c~1d", "value": "x"}
+                ]),
+            ]
+        );
+
+        let schema = json!({"properties": {"b": {}}, "required": ["a", "b"]});
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let instance = json!({});
+        assert!(cfg
+            .validate(&instance)
+            .unwrap_err()
+            .next()
+            .unwrap()
+            .fix()
+            .is_none());
+    }
+}
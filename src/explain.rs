@@ -0,0 +1,335 @@
+//! Explaining how a schema applies to one location in an instance.
+//!
+//! When an instance fails validation deep inside a schema built from many
+//! `$ref`s and `anyOf`/`oneOf` branches, it's often unclear which parts of
+//! the schema even apply to the offending value. `explain` lists every
+//! subschema that applies to the value at a given JSON pointer, whether the
+//! value is valid against it, and the errors reported at or below that
+//! location.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::{schemas, Config};
+//! use jsonschema_valid::explain::explain;
+//!
+//! let schema = json!({
+//!     "properties": {"id": {"anyOf": [{"type": "integer"}, {"$ref": "#/definitions/uuid"}]}},
+//!     "definitions": {"uuid": {"type": "string", "format": "uuid"}}
+//! });
+//! let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+//! let instance = json!({"id": 1.5});
+//!
+//! let explanation = explain(&cfg, &instance, "/id").unwrap();
+//! let paths: Vec<_> = explanation.subschemas.iter().map(|x| x.schema_path.as_str()).collect();
+//! assert_eq!(
+//!     paths,
+//!     vec!["/properties/id", "/properties/id/anyOf/0", "/properties/id/anyOf/1", "/properties/id/anyOf/1/$ref"]
+//! );
+//! assert!(explanation.subschemas.iter().all(|x| !x.valid));
+//! assert_eq!(explanation.errors.len(), 1);
+//! ```
+
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::context::Context;
+use crate::error::ValidationError;
+use crate::keywords;
+use crate::util;
+
+/// Subschemas reached through `$ref` are only followed this deep, so that
+/// a schema that refers to itself in place can't loop forever.
+pub(crate) const MAX_REF_DEPTH: usize = 32;
+
+/// A subschema that applies to the explained location.
+#[derive(Debug, Clone)]
+pub struct Applicable<'a> {
+    /// The path of keywords that leads from the root schema to this
+    /// subschema, as a JSON pointer. References are followed, and show up as
+    /// a `$ref` token.
+    pub schema_path: String,
+    /// The subschema itself.
+    pub schema: &'a Value,
+    /// Whether the value at the explained location is valid against the
+    /// subschema on its own.
+    pub valid: bool,
+}
+
+/// How a schema applies to one location in an instance.
+#[derive(Debug, Clone)]
+pub struct Explanation<'a> {
+    /// The value at the explained location.
+    pub instance: &'a Value,
+    /// All subschemas that apply to the value, in the order they are reached
+    /// from the root schema.
+    pub subschemas: Vec<Applicable<'a>>,
+    /// The errors from validating the whole instance whose instance path is
+    /// at or below the explained location.
+    pub errors: Vec<ValidationError>,
+}
+
+/// Explain how the schema in `cfg` applies to the value at `pointer` (a JSON
+/// pointer) in `instance`.
+///
+/// Returns `None` if `instance` has no value at `pointer`.
+pub fn explain<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a Value,
+    pointer: &str,
+) -> Option<Explanation<'a>> {
+    let target = instance.pointer(pointer)?;
+    let tokens: Vec<String> = util::pointer_tokens(pointer).collect();
+
+    let mut current = Vec::new();
+    expand(
+        cfg,
+        instance,
+        String::new(),
+        cfg.get_schema(),
+        0,
+        &mut current,
+    );
+    let mut value = instance;
+    for token in &tokens {
+        let child = match value {
+            Value::Object(object) => object.get(token.as_str())?,
+            Value::Array(array) => array.get(token.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+        let mut next = Vec::new();
+        for (path, schema) in current {
+            for (child_path, child_schema) in children(value, token, schema) {
+                expand(
+                    cfg,
+                    child,
+                    format!("{}/{}", path, child_path),
+                    child_schema,
+                    0,
+                    &mut next,
+                );
+            }
+        }
+        current = next;
+        value = child;
+    }
+
+    let subschemas = current
+        .into_iter()
+        .map(|(schema_path, schema)| Applicable {
+            valid: is_valid(cfg, target, schema),
+            schema_path,
+            schema,
+        })
+        .collect();
+    let errors = match cfg.validate(instance) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors
+            .filter(|err| {
+                let path = err.instance_path();
+                path.len() >= tokens.len() && tokens.iter().zip(path).all(|(a, b)| a == b)
+            })
+            .collect(),
+    };
+    Some(Explanation {
+        instance: target,
+        subschemas,
+        errors,
+    })
+}
+
+pub(crate) fn is_valid<'a>(cfg: &'a Config<'a>, instance: &'a Value, schema: &'a Value) -> bool {
+    keywords::descend(
+        cfg,
+        instance,
+        schema,
+        None,
+        Context::new_from(cfg.get_schema()),
+    )
+    .next()
+    .is_none()
+}
+
+/// Add `schema` and every subschema that applies in place to the same
+/// instance (through `$ref`, `allOf`, `anyOf`, `oneOf`, `not`,
+/// `if`/`then`/`else` and schema `dependencies`) to `out`.
+pub(crate) fn expand<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a Value,
+    path: String,
+    schema: &'a Value,
+    ref_depth: usize,
+    out: &mut Vec<(String, &'a Value)>,
+) {
+    let object = match schema {
+        Value::Object(object) => object,
+        _ => {
+            out.push((path, schema));
+            return;
+        }
+    };
+    out.push((path.clone(), schema));
+
+    // In the supported drafts, all keywords next to `$ref` are ignored.
+    if let Some(Value::String(ref_)) = object.get("$ref") {
+        if ref_depth < MAX_REF_DEPTH {
+            let root = cfg.get_schema();
+            if let Ok((_, resolved)) =
+                cfg.get_resolver()
+                    .resolve_fragment(ref_, &Context::new_from(root), root)
+            {
+                expand(
+                    cfg,
+                    instance,
+                    format!("{}/$ref", path),
+                    resolved,
+                    ref_depth + 1,
+                    out,
+                );
+            }
+        }
+        return;
+    }
+
+    for keyword in &["allOf", "anyOf", "oneOf"] {
+        if let Some(Value::Array(branches)) = object.get(*keyword) {
+            for (index, branch) in branches.iter().enumerate() {
+                let branch_path = format!("{}/{}/{}", path, keyword, index);
+                expand(cfg, instance, branch_path, branch, ref_depth, out);
+            }
+        }
+    }
+    if let Some(not) = object.get("not") {
+        expand(cfg, instance, format!("{}/not", path), not, ref_depth, out);
+    }
+    if cfg.get_draft_number() >= 7 {
+        if let Some(if_) = object.get("if") {
+            expand(cfg, instance, format!("{}/if", path), if_, ref_depth, out);
+            let branch = if is_valid(cfg, instance, if_) {
+                "then"
+            } else {
+                "else"
+            };
+            if let Some(subschema) = object.get(branch) {
+                let branch_path = format!("{}/{}", path, branch);
+                expand(cfg, instance, branch_path, subschema, ref_depth, out);
+            }
+        }
+    }
+    if let (Some(Value::Object(dependencies)), Value::Object(instance_object)) =
+        (object.get("dependencies"), instance)
+    {
+        for (property, dependency) in dependencies {
+            if instance_object.contains_key(property) && !dependency.is_array() {
+                let dependency_path = format!(
+                    "{}/dependencies/{}",
+                    path,
+                    util::escape_pointer_token(property)
+                );
+                expand(cfg, instance, dependency_path, dependency, ref_depth, out);
+            }
+        }
+    }
+}
+
+/// The subschemas of `schema` that apply to the child `token` of
+/// `instance`, along with their paths relative to `schema`.
+pub(crate) fn children<'a>(
+    instance: &Value,
+    token: &str,
+    schema: &'a Value,
+) -> Vec<(String, &'a Value)> {
+    let object = match schema {
+        Value::Object(object) => object,
+        _ => return Vec::new(),
+    };
+    if object.contains_key("$ref") {
+        return Vec::new();
+    }
+    let escaped = util::escape_pointer_token(token);
+    let mut result = Vec::new();
+    match instance {
+        Value::Object(_) => {
+            if let Some(subschema) = object.get("properties").and_then(|x| x.get(token)) {
+                result.push((format!("properties/{}", escaped), subschema));
+            }
+            if let Some(Value::Object(patterns)) = object.get("patternProperties") {
+                for (pattern, subschema) in patterns {
+                    if let Ok(re) = regex::Regex::new(pattern) {
+                        if re.is_match(token) {
+                            let pattern = util::escape_pointer_token(pattern);
+                            result.push((format!("patternProperties/{}", pattern), subschema));
+                        }
+                    }
+                }
+            }
+            if result.is_empty() {
+                if let Some(subschema) = object.get("additionalProperties") {
+                    result.push(("additionalProperties".to_string(), subschema));
+                }
+            }
+        }
+        Value::Array(_) => {
+            let index = token.parse::<usize>().unwrap_or(0);
+            match object.get("items") {
+                Some(Value::Array(items)) => match items.get(index) {
+                    Some(subschema) => result.push((format!("items/{}", index), subschema)),
+                    None => {
+                        if let Some(subschema) = object.get("additionalItems") {
+                            result.push(("additionalItems".to_string(), subschema));
+                        }
+                    }
+                },
+                Some(subschema) => result.push(("items".to_string(), subschema)),
+                None => {}
+            }
+            if let Some(subschema) = object.get("contains") {
+                result.push(("contains".to_string(), subschema));
+            }
+        }
+        _ => {}
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas;
+    use serde_json::json;
+
+    #[test]
+    fn test_branches_and_items() {
+        let schema = json!({
+            "items": [{"type": "string"}],
+            "additionalItems": {
+                "if": {"type": "object"},
+                "then": {"properties": {"a": {"minimum": 3}}},
+                "else": {"type": "null"}
+            }
+        });
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let instance = json!(["x", {"a": 1}]);
+
+        let explanation = explain(&cfg, &instance, "/1/a").unwrap();
+        assert_eq!(explanation.instance, &json!(1));
+        let subschemas: Vec<_> = explanation
+            .subschemas
+            .iter()
+            .map(|x| (x.schema_path.as_str(), x.valid))
+            .collect();
+        assert_eq!(
+            subschemas,
+            vec![("/additionalItems/then/properties/a", false)]
+        );
+        assert_eq!(explanation.errors.len(), 1);
+
+        let explanation = explain(&cfg, &instance, "/0").unwrap();
+        assert_eq!(explanation.subschemas[0].schema_path, "/items/0");
+        assert!(explanation.subschemas[0].valid);
+        assert!(explanation.errors.is_empty());
+
+        assert!(explain(&cfg, &instance, "/2").is_none());
+    }
+}
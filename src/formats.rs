@@ -0,0 +1,652 @@
+//! Checks for the values of the `format` keyword.
+//!
+//! Which formats are checked depends on the draft; see
+//! `Draft::get_format_checker`. Individual formats can be replaced or
+//! disabled with `Config::with_format` and `Config::without_format`, and
+//! whether failed checks make an instance invalid at all with
+//! `Config::with_format_mode`.
+
+use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use regex::Regex;
+use url::{Host, Url};
+
+use crate::config::Config;
+use crate::content;
+
+/// The type of the functions checking a string against a `format`.
+pub type FormatChecker = fn(cfg: &Config, value: &str) -> bool;
+
+/// Check for an RFC 5322 `email` address, as strict as the `EmailOptions` of
+/// `cfg`. The domain is checked as a `hostname`, or can be an IP address in
+/// brackets.
+pub fn email(cfg: &Config, value: &str) -> bool {
+    let options = cfg.get_email_options();
+    let at = match value.rfind('@') {
+        Some(index) => index,
+        None => return false,
+    };
+    let (local, domain) = (&value[..at], &value[at + 1..]);
+    if options.length_limits
+        && (local.len() > MAX_LOCAL_PART_LENGTH || value.len() > MAX_EMAIL_LENGTH)
+    {
+        return false;
+    }
+
+    let local_ok = if local.len() >= 2 && local.starts_with('"') && local.ends_with('"') {
+        options.quoted_local_part && quoted_string(&local[1..local.len() - 1])
+    } else {
+        local
+            .split('.')
+            .all(|atom| !atom.is_empty() && atom.chars().all(atext))
+    };
+    let domain_ok = match domain.strip_prefix('[').and_then(|x| x.strip_suffix(']')) {
+        Some(literal) => match literal.strip_prefix("IPv6:") {
+            Some(address) => Ipv6Addr::from_str(address).is_ok(),
+            None => Ipv4Addr::from_str(literal).is_ok(),
+        },
+        None => domain_name(cfg.get_hostname_options(), domain, true),
+    };
+    local_ok && domain_ok
+}
+
+/// Check for an `ipv4` address in dotted-quad notation.
+pub fn ipv4(_cfg: &Config, value: &str) -> bool {
+    Ipv4Addr::from_str(value).is_ok()
+}
+
+/// Check for an `ipv6` address.
+pub fn ipv6(_cfg: &Config, value: &str) -> bool {
+    Ipv6Addr::from_str(value).is_ok()
+}
+
+/// Check for an RFC 1123 `hostname`, as strict as the `HostnameOptions` of
+/// `cfg`.
+pub fn hostname(cfg: &Config, value: &str) -> bool {
+    domain_name(cfg.get_hostname_options(), value, false) && Host::parse(value).is_ok()
+}
+
+/// Check for an `idn-hostname`: a hostname whose labels may be
+/// internationalized, which must convert to ASCII according to IDNA (UTS
+/// #46). The converted hostname is checked as a `hostname`.
+#[cfg(feature = "idn")]
+pub fn idn_hostname(cfg: &Config, value: &str) -> bool {
+    let options = cfg.get_hostname_options();
+    let punycode = |label: &str| label.to_ascii_lowercase().starts_with("xn--");
+    if !options.punycode && value.split('.').any(punycode) {
+        return false;
+    }
+    let ascii = idna::uts46::Uts46::new().to_ascii(
+        value.as_bytes(),
+        idna::uts46::AsciiDenyList::EMPTY,
+        idna::uts46::Hyphens::CheckFirstLast,
+        idna::uts46::DnsLength::Ignore,
+    );
+    match ascii {
+        Ok(ascii) => {
+            let options = HostnameOptions {
+                punycode: true,
+                ..*options
+            };
+            domain_name(&options, &ascii, false)
+        }
+        Err(_) => false,
+    }
+}
+
+/// Check for an RFC 6531 `idn-email` address: an `email` address whose domain
+/// is checked as an `idn-hostname`.
+#[cfg(feature = "idn")]
+pub fn idn_email(cfg: &Config, value: &str) -> bool {
+    match value.rfind('@') {
+        Some(at) if !value[at + 1..].starts_with('[') => {
+            email(cfg, value) && idn_hostname(cfg, &value[at + 1..])
+        }
+        _ => email(cfg, value),
+    }
+}
+
+/// The longest local part of an email address RFC 5321 allows.
+const MAX_LOCAL_PART_LENGTH: usize = 64;
+
+/// The longest email address RFC 5321 allows in a path.
+const MAX_EMAIL_LENGTH: usize = 254;
+
+/// How strictly `email` values are checked, to match what the systems
+/// exchanging them accept. See `Config::with_email_options`.
+///
+/// By default, local parts can be quoted, as in `"john doe"@example.com`,
+/// and the lengths RFC 5321 limits local parts and addresses to are
+/// enforced. The domain is checked with the `HostnameOptions` of the
+/// `Config`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmailOptions {
+    quoted_local_part: bool,
+    length_limits: bool,
+}
+
+impl Default for EmailOptions {
+    fn default() -> EmailOptions {
+        EmailOptions {
+            quoted_local_part: true,
+            length_limits: true,
+        }
+    }
+}
+
+impl EmailOptions {
+    /// The options following RFC 5321 and RFC 5322.
+    pub fn new() -> EmailOptions {
+        EmailOptions::default()
+    }
+
+    /// Whether the local part can be a quoted string.
+    pub fn with_quoted_local_part(mut self, allow: bool) -> Self {
+        self.quoted_local_part = allow;
+        self
+    }
+
+    /// Whether local parts longer than 64 bytes, and addresses longer than
+    /// 254 bytes, are rejected.
+    pub fn with_length_limits(mut self, enforce: bool) -> Self {
+        self.length_limits = enforce;
+        self
+    }
+}
+
+/// How strictly `hostname` values, and the domains of `email` values, are
+/// checked. See `Config::with_hostname_options`.
+///
+/// By default, labels can't contain underscores, hostnames can't end with a
+/// dot, and punycode labels starting with `xn--` are accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HostnameOptions {
+    underscores: bool,
+    trailing_dot: bool,
+    punycode: bool,
+}
+
+impl Default for HostnameOptions {
+    fn default() -> HostnameOptions {
+        HostnameOptions {
+            underscores: false,
+            trailing_dot: false,
+            punycode: true,
+        }
+    }
+}
+
+impl HostnameOptions {
+    /// The options following RFC 1123.
+    pub fn new() -> HostnameOptions {
+        HostnameOptions::default()
+    }
+
+    /// Whether labels can contain underscores, as in `_dmarc.example.com`.
+    pub fn with_underscores(mut self, allow: bool) -> Self {
+        self.underscores = allow;
+        self
+    }
+
+    /// Whether a fully qualified hostname can end with a dot, as in
+    /// `example.com.`.
+    pub fn with_trailing_dot(mut self, allow: bool) -> Self {
+        self.trailing_dot = allow;
+        self
+    }
+
+    /// Whether labels encoded in punycode, starting with `xn--`, are
+    /// accepted.
+    pub fn with_punycode(mut self, allow: bool) -> Self {
+        self.punycode = allow;
+        self
+    }
+}
+
+/// Whether `c` can be part of an atom of the local part of an email address.
+/// Non-ASCII characters are accepted as per RFC 6531.
+fn atext(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~".contains(c) || !c.is_ascii()
+}
+
+/// Check the content of a quoted string: printable characters, where `"`
+/// and `\` are escaped with a `\`.
+fn quoted_string(value: &str) -> bool {
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c) if c == ' ' || c.is_ascii_graphic() => {}
+                _ => return false,
+            },
+            '"' => return false,
+            c if c != ' ' && !c.is_ascii_graphic() && c.is_ascii() => return false,
+            _ => {}
+        }
+    }
+    true
+}
+
+/// Check for a domain name of labels of at most 63 letters, digits and
+/// hyphens, separated by dots. Non-ASCII letters are only accepted if
+/// `unicode` is set.
+fn domain_name(options: &HostnameOptions, value: &str, unicode: bool) -> bool {
+    let value = match value.strip_suffix('.') {
+        Some(value) if options.trailing_dot => value,
+        _ => value,
+    };
+    value.len() <= 253
+        && value.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && (options.punycode || !label.to_ascii_lowercase().starts_with("xn--"))
+                && label.chars().all(|c| {
+                    c.is_ascii_alphanumeric()
+                        || c == '-'
+                        || (c == '_' && options.underscores)
+                        || (unicode && c.is_alphanumeric())
+                })
+        })
+}
+
+/// Check for an absolute `uri`.
+pub fn uri(_cfg: &Config, value: &str) -> bool {
+    Url::parse(value).is_ok()
+}
+
+/// Check for a `uri-reference`: an absolute or relative URI (RFC 3986).
+pub fn uri_reference(_cfg: &Config, value: &str) -> bool {
+    iri_string::types::UriReferenceStr::new(value).is_ok()
+}
+
+/// Check for an absolute `iri`.
+pub fn iri(_cfg: &Config, value: &str) -> bool {
+    iri_string::types::IriAbsoluteStr::new(value).is_ok()
+}
+
+/// Check for an `iri-reference`: an absolute or relative IRI (RFC 3987).
+pub fn iri_reference(_cfg: &Config, value: &str) -> bool {
+    iri_string::types::IriReferenceStr::new(value).is_ok()
+}
+
+/// Check for an RFC 3339 `date-time`, as strict as the `DateTimeOptions` of
+/// `cfg`.
+pub fn datetime(cfg: &Config, value: &str) -> bool {
+    let options = cfg.get_date_time_options();
+    let separator = |c: char| {
+        c == 'T' || (c == 't' && options.lowercase) || (c == ' ' && options.space_separator)
+    };
+    match value.get(10..) {
+        Some(rest) if value.is_char_boundary(10) => {
+            let mut chars = rest.chars();
+            chars.next().is_some_and(separator)
+                && full_date(&value[..10])
+                && full_time(options, chars.as_str())
+        }
+        _ => false,
+    }
+}
+
+/// Check for a `regex` the `regex` crate can compile.
+pub fn regex(_cfg: &Config, value: &str) -> bool {
+    Regex::new(value).is_ok()
+}
+
+/// Check for an RFC 3339 `date` of the form `YYYY-MM-DD`.
+pub fn date(_cfg: &Config, value: &str) -> bool {
+    full_date(value)
+}
+
+/// Check for the `byte` format of OpenAPI: base64-encoded data.
+pub fn byte(_cfg: &Config, value: &str) -> bool {
+    matches!(content::decode("base64", value), Some(Ok(_)))
+}
+
+/// Check for an RFC 3339 `time` of the form `HH:MM:SS` with a time zone
+/// offset, as strict as the `DateTimeOptions` of `cfg`.
+pub fn time(cfg: &Config, value: &str) -> bool {
+    full_time(cfg.get_date_time_options(), value)
+}
+
+/// Whether `format` takes part in validation; see `Config::with_format_mode`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FormatMode {
+    /// Values that fail the check of their format are invalid. This is the
+    /// default up to draft 7.
+    #[default]
+    Assertion,
+    /// Values that fail the check of their format are valid, but reported
+    /// by `Config::format_warnings`. This is the default from draft 2019-09
+    /// on.
+    Annotation,
+    /// Formats aren't checked.
+    Disabled,
+}
+
+/// How strictly `date-time` and `time` values are checked, to match what
+/// the systems producing them actually emit. See
+/// `Config::with_date_time_options`.
+///
+/// The default follows RFC 3339: the `T` and `Z` may be lowercase, but the
+/// date and time can't be separated by a space, seconds are required, their
+/// fraction may have any number of digits, and leap seconds are accepted at
+/// 23:59:60 UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateTimeOptions {
+    lowercase: bool,
+    space_separator: bool,
+    optional_seconds: bool,
+    max_fraction_digits: Option<usize>,
+    leap_seconds: bool,
+}
+
+impl Default for DateTimeOptions {
+    fn default() -> DateTimeOptions {
+        DateTimeOptions {
+            lowercase: true,
+            space_separator: false,
+            optional_seconds: false,
+            max_fraction_digits: None,
+            leap_seconds: true,
+        }
+    }
+}
+
+impl DateTimeOptions {
+    /// The options following RFC 3339.
+    pub fn new() -> DateTimeOptions {
+        DateTimeOptions::default()
+    }
+
+    /// Whether a lowercase `t` and `z` are accepted in place of `T` and `Z`.
+    pub fn with_lowercase(mut self, allow: bool) -> Self {
+        self.lowercase = allow;
+        self
+    }
+
+    /// Whether the date and time of a `date-time` can be separated by a
+    /// space instead of a `T`.
+    pub fn with_space_separator(mut self, allow: bool) -> Self {
+        self.space_separator = allow;
+        self
+    }
+
+    /// Whether times can leave out the seconds, as in `12:30Z`.
+    pub fn with_optional_seconds(mut self, allow: bool) -> Self {
+        self.optional_seconds = allow;
+        self
+    }
+
+    /// Limit the number of digits of the fraction of seconds, e.g. to 3 for
+    /// millisecond precision.
+    pub fn with_max_fraction_digits(mut self, digits: usize) -> Self {
+        self.max_fraction_digits = Some(digits);
+        self
+    }
+
+    /// Whether a leap second, `23:59:60` in UTC, is accepted.
+    pub fn with_leap_seconds(mut self, allow: bool) -> Self {
+        self.leap_seconds = allow;
+        self
+    }
+}
+
+/// The number made of the ASCII digits `digits`, if they all are.
+fn number(digits: &str) -> Option<u32> {
+    if !digits.is_empty() && digits.bytes().all(|x| x.is_ascii_digit()) {
+        digits.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Check for an RFC 3339 `full-date`: `YYYY-MM-DD`.
+fn full_date(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    if bytes.len() != 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return false;
+    }
+    match (
+        number(&value[..4]),
+        number(&value[5..7]),
+        number(&value[8..]),
+    ) {
+        (Some(year), Some(month), Some(day)) => {
+            NaiveDate::from_ymd_opt(year as i32, month, day).is_some()
+        }
+        _ => false,
+    }
+}
+
+/// Check for an RFC 3339 `full-time`: `HH:MM:SS`, an optional fraction of
+/// seconds, and `Z` or an offset of the form `+HH:MM`.
+fn full_time(options: &DateTimeOptions, value: &str) -> bool {
+    let offset_start = match value.find(['Z', 'z', '+', '-']) {
+        Some(index) => index,
+        None => return false,
+    };
+    let (time, offset) = value.split_at(offset_start);
+    let offset_minutes = match offset {
+        "Z" => 0,
+        "z" if options.lowercase => 0,
+        _ => {
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            match offset.get(1..).map(|x| x.split(':').collect::<Vec<_>>()) {
+                Some(parts) if parts.len() == 2 && parts.iter().all(|x| x.len() == 2) => {
+                    match (number(parts[0]), number(parts[1])) {
+                        (Some(hours), Some(minutes)) if hours < 24 && minutes < 60 => {
+                            sign * (hours * 60 + minutes) as i32
+                        }
+                        _ => return false,
+                    }
+                }
+                _ => return false,
+            }
+        }
+    };
+
+    let (time, fraction) = match time.find('.') {
+        Some(index) => (&time[..index], Some(&time[index + 1..])),
+        None => (time, None),
+    };
+    let parts: Vec<&str> = time.split(':').collect();
+    if !(parts.len() == 3 || (parts.len() == 2 && options.optional_seconds && fraction.is_none()))
+        || parts.iter().any(|x| x.len() != 2)
+    {
+        return false;
+    }
+    if let Some(fraction) = fraction {
+        let too_long = options
+            .max_fraction_digits
+            .is_some_and(|max| fraction.len() > max);
+        if number(fraction).is_none() || too_long {
+            return false;
+        }
+    }
+    let second = match parts.get(2) {
+        Some(second) => number(second),
+        None => Some(0),
+    };
+    match (number(parts[0]), number(parts[1]), second) {
+        (Some(hour), Some(minute), Some(second)) if hour < 24 && minute < 60 => {
+            if second < 60 {
+                return true;
+            }
+            // A leap second is only inserted at the end of a UTC day.
+            let utc = (hour * 60 + minute) as i32 - offset_minutes;
+            second == 60 && options.leap_seconds && utc.rem_euclid(24 * 60) == 23 * 60 + 59
+        }
+        _ => false,
+    }
+}
+
+/// Check for a `json-pointer`.
+pub fn json_pointer(_cfg: &Config, value: &str) -> bool {
+    value.parse::<json_pointer::JsonPointer<_, _>>().is_ok()
+}
+
+/// Check for a `uri-template`. Every string is accepted.
+pub fn uri_template(_cfg: &Config, _value: &str) -> bool {
+    // It seems like pretty much anything can be a URI template
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas;
+    use serde_json::json;
+
+    #[test]
+    fn test_builtin_formats() {
+        let cases = [
+            ("date-time", "2020-01-01T12:00:00+01:00", "2020-01-01 12:00"),
+            ("date", "2020-01-01", "2020-13-01"),
+            ("time", "12:00:00Z", "25:00:00Z"),
+            ("email", "joe@example.com", "joe"),
+            ("hostname", "example.com", "-example.com"),
+            ("ipv4", "127.0.0.1", "127.0.0.256"),
+            ("ipv6", "::1", "::g"),
+            ("uri", "https://example.com/a?b#c", "example.com"),
+            ("uri-reference", "../a#b", "a b"),
+            ("iri", "https://例え.jp/パス", "パス"),
+            ("iri-reference", "../パス#b", "a b"),
+            ("json-pointer", "/a~1b/0", "a"),
+            ("regex", "^a+$", "(a"),
+        ];
+        for (format, valid, invalid) in cases.iter() {
+            let schema = json!({ "format": format });
+            let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+            assert!(cfg.validate(&json!(valid)).is_ok(), "{} {}", format, valid);
+            assert!(
+                cfg.validate(&json!(invalid)).is_err(),
+                "{} {}",
+                format,
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_custom_format() {
+        let schema = json!({
+            "properties": {"ticket": {"format": "ticket-id"}, "sku": {"format": "sku"}}
+        });
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7))
+            .unwrap()
+            .with_format("ticket-id", |_cfg, value| {
+                value.len() > 4
+                    && value.starts_with("TK-")
+                    && value[3..].bytes().all(|b| b.is_ascii_digit())
+            });
+        assert!(cfg
+            .validate(&json!({"ticket": "TK-42", "sku": "?"}))
+            .is_ok());
+        let errors: Vec<_> = cfg
+            .validate(&json!({"ticket": "42"}))
+            .unwrap_err()
+            .collect();
+        assert_eq!(errors[0].message(), "42 invalid for ticket-id format");
+    }
+
+    #[test]
+    fn test_format_modes() {
+        use crate::options::ValidationOptions;
+
+        let schema = json!({
+            "properties": {
+                "at": {"format": "date"},
+                "contact": {"anyOf": [{"format": "email"}, {"type": "integer"}]}
+            }
+        });
+        let instance = json!({"at": "tomorrow", "contact": 5, "other": "x"});
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        assert_eq!(cfg.get_format_mode(), FormatMode::Assertion);
+        assert!(cfg.validate(&instance).is_err());
+        assert!(cfg.format_warnings(&instance).is_empty());
+
+        let cfg = cfg.with_format_mode(FormatMode::Annotation);
+        assert!(cfg.validate(&instance).is_ok());
+        let warnings = cfg.format_warnings(&instance);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].instance_path(), ["at"]);
+        let strict = ValidationOptions::new().with_format_assertion(true);
+        assert!(cfg.validate_with_options(&instance, &strict).is_err());
+
+        let cfg = cfg.with_format_mode(FormatMode::Disabled);
+        assert!(cfg.validate(&instance).is_ok());
+        assert!(cfg.format_warnings(&instance).is_empty());
+    }
+
+    #[test]
+    fn test_date_time_options() {
+        let schema = json!({});
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        assert!(date(&cfg, "2024-02-29"));
+        assert!(!date(&cfg, "2023-02-29"));
+        assert!(datetime(&cfg, "2016-12-31t23:59:60z"));
+        assert!(datetime(&cfg, "2016-12-31T15:59:60.5-08:00"));
+        assert!(!datetime(&cfg, "2016-12-31T12:59:60Z"));
+        assert!(!time(&cfg, "12:30Z"));
+
+        let cfg = cfg.with_date_time_options(
+            DateTimeOptions::new()
+                .with_lowercase(false)
+                .with_optional_seconds(true)
+                .with_leap_seconds(false),
+        );
+        assert!(!datetime(&cfg, "2016-12-31t23:59:59Z"));
+        assert!(!datetime(&cfg, "2016-12-31T23:59:60Z"));
+        assert!(time(&cfg, "12:30+01:00"));
+        assert!(!time(&cfg, "12:30.5Z"));
+    }
+    #[test]
+    fn test_email_and_hostname_options() {
+        let schema = json!({});
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        assert!(email(&cfg, "john.doe@example.com"));
+        assert!(email(&cfg, "\"john doe\"@[127.0.0.1]"));
+        assert!(!email(&cfg, "john..doe@example.com"));
+        assert!(!email(&cfg, &format!("{}@example.com", "a".repeat(65))));
+        assert!(hostname(&cfg, "xn--bcher-kva.example"));
+        assert!(!hostname(&cfg, "-example.com"));
+        assert!(!hostname(&cfg, "example.com."));
+
+        let cfg = cfg
+            .with_email_options(
+                EmailOptions::new()
+                    .with_quoted_local_part(false)
+                    .with_length_limits(false),
+            )
+            .with_hostname_options(HostnameOptions::new().with_punycode(false));
+        assert!(!email(&cfg, "\"john doe\"@example.com"));
+        assert!(email(&cfg, &format!("{}@example.com", "a".repeat(65))));
+        assert!(!hostname(&cfg, "xn--bcher-kva.example"));
+        assert!(!email(&cfg, "john@xn--bcher-kva.example"));
+    }
+
+    #[cfg(feature = "idn")]
+    #[test]
+    fn test_idn_formats() {
+        let schema = json!({});
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        assert!(idn_hostname(&cfg, "bücher.example"));
+        assert!(idn_hostname(&cfg, "xn--bcher-kva.example"));
+        assert!(idn_hostname(&cfg, "例え.テスト"));
+        assert!(!idn_hostname(&cfg, "-bücher.example"));
+        assert!(!idn_hostname(&cfg, "xn--x.example"));
+        assert!(!idn_hostname(&cfg, "bü cher.example"));
+        assert!(idn_email(&cfg, "用户@例子.广告"));
+        assert!(idn_email(&cfg, "user@[127.0.0.1]"));
+        assert!(!idn_email(&cfg, "user@bü cher.example"));
+
+        let cfg = cfg.with_hostname_options(HostnameOptions::new().with_punycode(false));
+        assert!(idn_hostname(&cfg, "bücher.example"));
+        assert!(!idn_hostname(&cfg, "xn--bcher-kva.example"));
+    }
+}
@@ -0,0 +1,119 @@
+//! Validating against parts of a compiled schema.
+//!
+//! A single schema document often defines the types of many payloads, e.g.
+//! under `$defs` or `definitions`. `Config::fragment` looks up a subschema of
+//! a compiled schema by reference, so that instances can be validated
+//! against it without compiling the subschema on its own. References within
+//! the subschema are resolved as they are when it is reached through a
+//! `$ref` from the root.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::{schemas, Config};
+//! let schema = json!({
+//!     "$defs": {
+//!         "Address": {"required": ["city"], "properties": {"zip": {"$ref": "#/$defs/Zip"}}},
+//!         "Zip": {"type": "string", "pattern": "^[0-9]{5}$"}
+//!     }
+//! });
+//! let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+//!
+//! let address = cfg.fragment("#/$defs/Address").unwrap();
+//! assert!(address.validate(&json!({"city": "Paris", "zip": "75001"})).is_ok());
+//! let errors: Vec<_> = address.validate(&json!({"city": "Paris", "zip": "7500"})).unwrap_err().collect();
+//! assert_eq!(errors[0].schema_path(), ["properties", "zip", "pattern"]);
+//! assert!(cfg.fragment("#/$defs/Phone").is_err());
+//! ```
+
+use serde_json::{json, Value};
+
+use crate::config::Config;
+use crate::context::Context;
+use crate::error::{ErrorIterator, ValidationError};
+use crate::keywords;
+
+/// A subschema of a compiled schema, to validate instances against; see
+/// `Config::fragment`.
+pub struct Fragment<'a> {
+    cfg: &'a Config<'a>,
+    schema: &'a Value,
+    /// A schema with the id that references within the subschema are
+    /// resolved against.
+    scope: Value,
+}
+
+impl<'a> Fragment<'a> {
+    pub(crate) fn new(
+        cfg: &'a Config<'a>,
+        reference: &str,
+    ) -> Result<Fragment<'a>, ValidationError> {
+        let root = cfg.get_schema();
+        let (scope, schema) = cfg
+            .get_resolver()
+            .resolve_fragment(reference, &Context::new_from(root), root)
+            .map_err(|err| {
+                ValidationError::new(&format!("Can't resolve {}: {}", reference, err.message()))
+            })?;
+        Ok(Fragment {
+            cfg,
+            schema,
+            scope: json!({"$id": scope.to_string()}),
+        })
+    }
+
+    /// Get the subschema.
+    pub fn get_schema(&self) -> &'a Value {
+        self.schema
+    }
+
+    /// Validate the given JSON instance against the subschema. The schema
+    /// paths of errors start at the subschema.
+    pub fn validate(&self, instance: &Value) -> Result<(), ErrorIterator<'a>> {
+        let root = Context::new_from(self.cfg.get_schema());
+        let errors: Vec<ValidationError> = keywords::descend(
+            self.cfg,
+            instance,
+            self.schema,
+            None,
+            root.push(&self.scope),
+        )
+        .map(|err| self.cfg.finish_error(err))
+        .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Box::new(errors.into_iter()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::schemas;
+    use serde_json::json;
+
+    #[test]
+    fn test_fragment_with_id() {
+        let schema = json!({
+            "$id": "http://example.com/root.json",
+            "definitions": {
+                "item": {
+                    "$id": "item.json",
+                    "properties": {"price": {"$ref": "#/definitions/price"}},
+                    "definitions": {"price": {"type": "number"}}
+                }
+            }
+        });
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let item = cfg.fragment("item.json").unwrap();
+        assert_eq!(item.get_schema(), &schema["definitions"]["item"]);
+        assert!(item.validate(&json!({"price": 1.5})).is_ok());
+        assert_eq!(
+            item.validate(&json!({"price": "1.5"})).unwrap_err().count(),
+            1
+        );
+    }
+}
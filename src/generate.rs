@@ -0,0 +1,904 @@
+//! Generating sample instances from a schema, and random schemas.
+//!
+//! The generator produces instances that are valid against a schema, e.g. for
+//! test fixtures or mock API responses, as well as invalid counterexamples
+//! derived from them. Every generated instance is checked against the schema
+//! before it is returned, so constraints the generator doesn't understand
+//! (such as `pattern` or `not`) only make generation fail, never produce a
+//! wrong result.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! use jsonschema_valid::{generate::Generator, Config};
+//!
+//! let schema = json!({
+//!     "type": "object",
+//!     "required": ["id"],
+//!     "properties": {"id": {"type": "integer", "minimum": 1}}
+//! });
+//! let cfg = Config::from_schema(&schema, None).unwrap();
+//! let mut generator = Generator::new(&cfg).with_seed(42);
+//!
+//! let valid = generator.valid().unwrap();
+//! assert!(cfg.validate(&valid).is_ok());
+//! let invalid = generator.invalid().unwrap();
+//! assert!(cfg.validate(&invalid).is_err());
+//! ```
+//!
+//! `SchemaGenerator` goes the other way and produces random schemas for a
+//! draft, bounded in depth and size, to fuzz the validator or test
+//! properties that must hold for any schema:
+//!
+//! ```rust
+//! # use jsonschema_valid::{schemas, Config};
+//! use jsonschema_valid::generate::SchemaGenerator;
+//!
+//! let mut generator = SchemaGenerator::new(&schemas::Draft7).with_seed(1).with_max_depth(2);
+//! for _ in 0..10 {
+//!     let schema = generator.schema();
+//!     let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+//!     assert!(cfg.validate_schema().is_ok());
+//! }
+//! ```
+
+use serde_json::{json, Map, Number, Value};
+
+use crate::algebra;
+use crate::config::Config;
+use crate::context::Context;
+use crate::keywords;
+use crate::schemas::Draft;
+use crate::util;
+
+/// Beyond this depth, only the parts of a schema that are required are
+/// generated, so recursive schemas terminate.
+const MAX_DEPTH: usize = 8;
+
+/// The most characters, items or properties in a generated value. Larger
+/// minimums are treated as unsatisfiable.
+const MAX_SIZE: u64 = 1024;
+
+const TYPES: &[&str] = &[
+    "null", "boolean", "integer", "number", "string", "array", "object",
+];
+
+const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+/// The property names in generated schemas.
+const NAMES: &[&str] = &["a", "b", "id", "kind", "name", "tags"];
+
+/// The regular expressions in generated schemas.
+const PATTERNS: &[&str] = &["^a", "[0-9]+", "^[a-z]*$", "b$", "."];
+
+/// The formats in generated schemas.
+const FORMATS: &[&str] = &["date-time", "email", "hostname", "ipv4", "uri"];
+
+/// A xorshift64* pseudo-random number generator: fast, reproducible and good
+/// enough for test data.
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn new() -> Rng {
+        Rng {
+            state: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    fn seeded(seed: u64) -> Rng {
+        // The xorshift state must never be zero.
+        Rng {
+            state: seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1,
+        }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// A random number in `low..=high`.
+    fn range(&mut self, low: u64, high: u64) -> u64 {
+        if high <= low {
+            low
+        } else {
+            low + self.next() % (high - low + 1)
+        }
+    }
+
+    fn chance(&mut self) -> bool {
+        self.next() & 1 == 1
+    }
+
+    fn choose<'b, T>(&mut self, items: &'b [T]) -> Option<&'b T> {
+        if items.is_empty() {
+            None
+        } else {
+            let index = self.range(0, items.len() as u64 - 1) as usize;
+            items.get(index)
+        }
+    }
+}
+
+/// Generates instances for the schema of a `Config`.
+pub struct Generator<'a> {
+    cfg: &'a Config<'a>,
+    rng: Rng,
+    attempts: usize,
+}
+
+impl<'a> Generator<'a> {
+    /// Create a generator for the schema of the given `Config`.
+    pub fn new(cfg: &'a Config<'a>) -> Generator<'a> {
+        Generator {
+            cfg,
+            rng: Rng::new(),
+            attempts: 100,
+        }
+    }
+
+    /// Seed the generator, so that different runs produce different
+    /// instances. The same seed always produces the same instances.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::seeded(seed);
+        self
+    }
+
+    /// Set how many candidates are tried before giving up on producing an
+    /// instance. The default is 100.
+    pub fn with_attempts(mut self, attempts: usize) -> Self {
+        self.attempts = attempts;
+        self
+    }
+
+    /// Generate an instance that is valid against the schema. Returns `None`
+    /// if no valid instance was found.
+    pub fn valid(&mut self) -> Option<Value> {
+        let schema = self.cfg.get_schema();
+        let ctx = Context::new_from(schema);
+        for _ in 0..self.attempts {
+            if let Some(instance) = self.generate(schema, &ctx, 0) {
+                if self.cfg.validate(&instance).is_ok() {
+                    return Some(instance);
+                }
+            }
+        }
+        None
+    }
+
+    /// Generate an instance that is invalid against the schema, by making a
+    /// small change to a valid instance: replacing a value with one of a
+    /// different type, removing a property or adding an unexpected one.
+    /// Returns `None` if no invalid instance was found.
+    pub fn invalid(&mut self) -> Option<Value> {
+        for _ in 0..self.attempts {
+            let mut instance = self.valid().unwrap_or(Value::Null);
+            self.mutate(&mut instance);
+            if self.cfg.validate(&instance).is_err() {
+                return Some(instance);
+            }
+        }
+        None
+    }
+
+    /// Follow `$ref` until reaching a schema without one, resolving each
+    /// reference in the scope of `ctx` and the resources entered so far, as
+    /// `ref_` does. Returns that schema, and the scope to push for it if a
+    /// reference was followed. In draft 7 and earlier, all keywords next to
+    /// `$ref` are ignored.
+    fn resolve<'b>(
+        &self,
+        mut schema: &'b Value,
+        ctx: &Context,
+    ) -> Option<(&'b Value, Option<Value>)>
+    where
+        'a: 'b,
+    {
+        let root = self.cfg.get_schema();
+        let mut scope = None;
+        for _ in 0..MAX_DEPTH {
+            let ref_ = match schema.get("$ref").and_then(Value::as_str) {
+                Some(ref_) => ref_,
+                None => return Some((schema, scope)),
+            };
+            let pushed;
+            let ref_context = match &scope {
+                Some(scope) => {
+                    pushed = ctx.push(scope);
+                    &pushed
+                }
+                None => ctx,
+            };
+            let (url, resolved) = self
+                .cfg
+                .get_resolver()
+                .resolve_fragment(ref_, ref_context, root)
+                .ok()?;
+            scope = Some(keywords::scope_schema(self.cfg, &url));
+            schema = resolved;
+        }
+        None
+    }
+
+    fn generate(&mut self, schema: &Value, ctx: &Context, depth: usize) -> Option<Value> {
+        if depth > 2 * MAX_DEPTH {
+            return None;
+        }
+        let (schema, scope) = self.resolve(schema, ctx)?;
+        let pushed;
+        let ctx = match &scope {
+            Some(scope) => {
+                pushed = ctx.push(scope);
+                &pushed
+            }
+            None => ctx,
+        };
+        let object = match schema {
+            Value::Bool(false) => return None,
+            Value::Bool(true) => return Some(self.scalar()),
+            Value::Object(object) => object,
+            _ => return None,
+        };
+
+        // Fold applicators into a single schema, so that all constraints
+        // apply to the same value.
+        if object.contains_key("allOf")
+            || object.contains_key("anyOf")
+            || object.contains_key("oneOf")
+        {
+            let mut combined = object.clone();
+            let all_of = combined.remove("allOf");
+            let any_of = combined.remove("anyOf");
+            let one_of = combined.remove("oneOf");
+            let draft = self.cfg.get_draft();
+            let mut result = Value::Object(combined);
+            if let Some(Value::Array(branches)) = all_of {
+                for branch in &branches {
+                    let (branch, _) = self.resolve(branch, ctx)?;
+                    result = algebra::intersection(&result, branch, draft);
+                }
+            }
+            for branches in [any_of, one_of].iter() {
+                if let Some(Value::Array(branches)) = branches {
+                    let branch = self.rng.choose(branches)?;
+                    let (branch, _) = self.resolve(branch, ctx)?;
+                    result = algebra::intersection(&result, branch, draft);
+                }
+            }
+            return match result.get("allOf") {
+                // Branches that couldn't be merged are only checked after
+                // generation.
+                Some(Value::Array(branches)) if !branches.is_empty() => {
+                    let mut rest = result.clone();
+                    if let Value::Object(rest) = &mut rest {
+                        rest.remove("allOf");
+                    }
+                    let (first, _) = self.resolve(&branches[0], ctx)?;
+                    self.generate(&algebra::intersection(&rest, first, draft), ctx, depth + 1)
+                }
+                _ => self.generate(&result, ctx, depth + 1),
+            };
+        }
+
+        if let Some(value) = object.get("const") {
+            return Some(value.clone());
+        }
+        if let Some(Value::Array(values)) = object.get("enum") {
+            return self.rng.choose(values).cloned();
+        }
+
+        let mut types = allowed_types(object);
+        if depth >= MAX_DEPTH && types.iter().any(|x| x != &"object" && x != &"array") {
+            types.retain(|x| x != &"object" && x != &"array");
+        }
+        let typename = *self.rng.choose(&types)?;
+        match typename {
+            "null" => Some(Value::Null),
+            "boolean" => Some(Value::Bool(self.rng.chance())),
+            "integer" => self.number(object, true),
+            "number" => self.number(object, false),
+            "string" => self.string(object).map(Value::String),
+            "array" => self.array(object, ctx, depth),
+            "object" => self.object(object, ctx, depth),
+            _ => None,
+        }
+    }
+
+    fn scalar(&mut self) -> Value {
+        match self.rng.range(0, 3) {
+            0 => Value::Null,
+            1 => Value::Bool(self.rng.chance()),
+            2 => json!(self.rng.range(0, 100)),
+            _ => Value::String(self.random_string(0, 8)),
+        }
+    }
+
+    fn number(&mut self, object: &Map<String, Value>, integer: bool) -> Option<Value> {
+        let minimum = object.get("minimum").and_then(Value::as_f64);
+        let maximum = object.get("maximum").and_then(Value::as_f64);
+        let (mut low, mut high) = match (minimum, maximum) {
+            (Some(low), Some(high)) => (low, high),
+            (Some(low), None) => (low, low + 200.0),
+            (None, Some(high)) => (high - 200.0, high),
+            (None, None) => (-100.0, 100.0),
+        };
+        // Draft 4 uses booleans to make `minimum` and `maximum` exclusive.
+        let mut low_exclusive =
+            minimum.is_some() && object.get("exclusiveMinimum") == Some(&Value::Bool(true));
+        let mut high_exclusive =
+            maximum.is_some() && object.get("exclusiveMaximum") == Some(&Value::Bool(true));
+        if let Some(minimum) = object.get("exclusiveMinimum").and_then(Value::as_f64) {
+            if minimum >= low {
+                low = minimum;
+                low_exclusive = true;
+            }
+        }
+        if let Some(maximum) = object.get("exclusiveMaximum").and_then(Value::as_f64) {
+            if maximum <= high {
+                high = maximum;
+                high_exclusive = true;
+            }
+        }
+
+        let step = match object.get("multipleOf").and_then(Value::as_f64) {
+            Some(step) if step > 0.0 => step,
+            _ if integer => 1.0,
+            _ => 0.25,
+        };
+        let mut first = (low / step).ceil();
+        if low_exclusive && first * step <= low {
+            first += 1.0;
+        }
+        let mut last = (high / step).floor();
+        if high_exclusive && last * step >= high {
+            last -= 1.0;
+        }
+        if first > last || !first.is_finite() || !last.is_finite() {
+            return None;
+        }
+        let span = (last - first).min(u32::MAX as f64) as u64;
+        let value = (first + self.rng.range(0, span) as f64) * step;
+        if value.fract() == 0.0 && value.abs() < (1u64 << 53) as f64 {
+            Some(json!(value as i64))
+        } else if integer {
+            None
+        } else {
+            Number::from_f64(value).map(Value::Number)
+        }
+    }
+
+    fn string(&mut self, object: &Map<String, Value>) -> Option<String> {
+        if let Some(format) = object.get("format").and_then(Value::as_str) {
+            if let Some(example) = format_example(format) {
+                return Some(example.to_string());
+            }
+        }
+        let (min, max) = size_bounds(object, "minLength", "maxLength", 8)?;
+        Some(self.random_string(min, max))
+    }
+
+    fn random_string(&mut self, min: u64, max: u64) -> String {
+        let len = self.rng.range(min, max);
+        (0..len)
+            .map(|_| *self.rng.choose(CHARS).unwrap_or(&b'a') as char)
+            .collect()
+    }
+
+    fn array(&mut self, object: &Map<String, Value>, ctx: &Context, depth: usize) -> Option<Value> {
+        let extra = if depth >= MAX_DEPTH { 0 } else { 3 };
+        let (min, mut max) = size_bounds(object, "minItems", "maxItems", extra)?;
+        let tuple = object.get("items").and_then(Value::as_array);
+        if let (Some(tuple), Some(Value::Bool(false))) = (tuple, object.get("additionalItems")) {
+            max = max.min(tuple.len() as u64);
+        }
+        let len = self.rng.range(min, max) as usize;
+
+        let mut items = Vec::with_capacity(len);
+        for index in 0..len {
+            let item_schema = match (tuple, object.get("items")) {
+                (Some(tuple), _) => tuple
+                    .get(index)
+                    .or_else(|| object.get("additionalItems"))
+                    .unwrap_or(&Value::Bool(true)),
+                (None, Some(items)) => items,
+                (None, None) => &Value::Bool(true),
+            };
+            items.push(self.generate(item_schema, ctx, depth + 1)?);
+        }
+        if let Some(contains) = object.get("contains") {
+            if !items.is_empty() && tuple.is_none() {
+                let index = self.rng.range(0, items.len() as u64 - 1) as usize;
+                items[index] = self.generate(contains, ctx, depth + 1)?;
+            }
+        }
+        Some(Value::Array(items))
+    }
+
+    fn object(
+        &mut self,
+        object: &Map<String, Value>,
+        ctx: &Context,
+        depth: usize,
+    ) -> Option<Value> {
+        let empty = Map::new();
+        let properties = object
+            .get("properties")
+            .and_then(Value::as_object)
+            .unwrap_or(&empty);
+        let mut names: Vec<String> = object
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|required| {
+                required
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        if depth < MAX_DEPTH {
+            for name in properties.keys() {
+                if !names.contains(name) && self.rng.chance() {
+                    names.push(name.clone());
+                }
+            }
+        }
+        let min = size_bounds(object, "minProperties", "maxProperties", 0)?.0 as usize;
+        let mut extra = 0;
+        while names.len() < min {
+            names.push(format!("property{}", extra));
+            extra += 1;
+        }
+
+        let mut result = Map::new();
+        for name in names {
+            let property_schema = match properties.get(&name) {
+                Some(schema) => schema,
+                None => object
+                    .get("additionalProperties")
+                    .unwrap_or(&Value::Bool(true)),
+            };
+            let value = self.generate(property_schema, ctx, depth + 1)?;
+            result.insert(name, value);
+        }
+        Some(Value::Object(result))
+    }
+
+    fn mutate(&mut self, instance: &mut Value) {
+        let mut pointers = Vec::new();
+        collect_pointers(instance, &mut String::new(), &mut pointers);
+        let pointer = self.rng.choose(&pointers).cloned().unwrap_or_default();
+        let mutation = self.rng.range(0, 2);
+        let replacement = match instance.pointer_mut(&pointer) {
+            Some(target) => target,
+            None => return,
+        };
+        match (mutation, &mut *replacement) {
+            (0, Value::Object(object)) if !object.is_empty() => {
+                let keys: Vec<String> = object.keys().cloned().collect();
+                if let Some(key) = self.rng.choose(&keys) {
+                    object.remove(key);
+                }
+            }
+            (1, Value::Object(object)) => {
+                object.insert("unexpectedProperty".to_string(), Value::Null);
+            }
+            (_, target) => *target = different_type(target),
+        }
+    }
+}
+
+/// Generates random schemas that are valid against the metaschema of a
+/// draft, e.g. to fuzz the validator or to test properties that must hold
+/// for every schema.
+///
+/// Schemas use the keywords of the draft with values of the right shape,
+/// but are not necessarily satisfiable. `$ref` only refers to definitions
+/// in the root schema, which don't refer to each other, so validation
+/// against a generated schema always terminates.
+pub struct SchemaGenerator<'a> {
+    draft: &'a dyn Draft,
+    rng: Rng,
+    max_depth: usize,
+    max_keywords: usize,
+    /// The names of the definitions that `$ref` can refer to.
+    definitions: Vec<String>,
+}
+
+impl<'a> SchemaGenerator<'a> {
+    /// Create a generator of schemas for the given draft.
+    pub fn new(draft: &'a dyn Draft) -> SchemaGenerator<'a> {
+        SchemaGenerator {
+            draft,
+            rng: Rng::new(),
+            max_depth: 3,
+            max_keywords: 4,
+            definitions: Vec::new(),
+        }
+    }
+
+    /// Seed the generator, so that different runs produce different
+    /// schemas. The same seed always produces the same schemas.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::seeded(seed);
+        self
+    }
+
+    /// Set how deeply subschemas are nested at most. The default is 3.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Set how many keywords a schema or subschema has at most. The default
+    /// is 4.
+    pub fn with_max_keywords(mut self, max_keywords: usize) -> Self {
+        self.max_keywords = max_keywords;
+        self
+    }
+
+    /// Generate a schema.
+    pub fn schema(&mut self) -> Value {
+        self.definitions.clear();
+        let mut definitions = Map::new();
+        if self.max_depth > 0 {
+            for _ in 0..self.rng.range(0, 2) {
+                let name = self.name();
+                let definition = self.subschema(1);
+                definitions.insert(name, definition);
+            }
+        }
+        self.definitions = definitions.keys().cloned().collect();
+        let mut schema = self.subschema(0);
+        if let Value::Object(object) = &mut schema {
+            if !definitions.is_empty() {
+                object.insert("definitions".to_string(), Value::Object(definitions));
+            }
+        }
+        schema
+    }
+
+    fn subschema(&mut self, depth: usize) -> Value {
+        if self.draft.get_draft_number() >= 6 && self.rng.range(0, 9) == 0 {
+            return Value::Bool(self.rng.chance());
+        }
+        let mut object = Map::new();
+        for _ in 0..self.rng.range(0, self.max_keywords as u64) {
+            let keyword = match self.rng.choose(self.draft.get_keywords()) {
+                Some(keyword) => *keyword,
+                None => break,
+            };
+            if let Some(value) = self.keyword(&mut object, keyword, depth) {
+                object.insert(keyword.to_string(), value);
+            }
+        }
+        Value::Object(object)
+    }
+
+    /// A value for `keyword` in `object`, or `None` if the keyword can't be
+    /// used there. Keywords that depend on another one add it to `object`.
+    fn keyword(
+        &mut self,
+        object: &mut Map<String, Value>,
+        keyword: &str,
+        depth: usize,
+    ) -> Option<Value> {
+        let deeper = depth < self.max_depth;
+        let value = match keyword {
+            "$ref" => {
+                let name = self.rng.choose(&self.definitions)?;
+                Value::String(format!(
+                    "#/definitions/{}",
+                    util::escape_pointer_token(name)
+                ))
+            }
+            "items" if deeper && self.rng.chance() => self.subschemas(depth + 1),
+            "additionalItems"
+            | "additionalProperties"
+            | "contains"
+            | "else"
+            | "if"
+            | "items"
+            | "not"
+            | "propertyNames"
+            | "then"
+                if deeper =>
+            {
+                self.subschema(depth + 1)
+            }
+            "allOf" | "anyOf" | "oneOf" if deeper => self.subschemas(depth + 1),
+            "properties" | "patternProperties" | "dependencies" if deeper => {
+                let mut map = Map::new();
+                for _ in 0..self.rng.range(1, 3) {
+                    let name = match keyword {
+                        "patternProperties" => self.rng.choose(PATTERNS)?.to_string(),
+                        _ => self.name(),
+                    };
+                    let value = match keyword {
+                        "dependencies" if self.rng.chance() => self.names(),
+                        _ => self.subschema(depth + 1),
+                    };
+                    map.insert(name, value);
+                }
+                Value::Object(map)
+            }
+            "dependencies" => json!({ self.name(): self.names() }),
+            "const" => self.value(),
+            "enum" => {
+                let mut values = Vec::new();
+                for _ in 0..self.rng.range(1, 3) {
+                    let value = self.value();
+                    if !values.contains(&value) {
+                        values.push(value);
+                    }
+                }
+                Value::Array(values)
+            }
+            "type" => {
+                let mut types: Vec<&str> = (0..self.rng.range(1, 2))
+                    .filter_map(|_| self.rng.choose(TYPES).copied())
+                    .collect();
+                types.sort_unstable();
+                types.dedup();
+                match types.as_slice() {
+                    [single] => json!(single),
+                    _ => json!(types),
+                }
+            }
+            "exclusiveMaximum" | "exclusiveMinimum" if self.draft.boolean_exclusive_bounds() => {
+                // Drafts 3 and 4 and OpenAPI 3.0 only allow the flags next to
+                // the bound they apply to.
+                let bound = match keyword {
+                    "exclusiveMaximum" => "maximum",
+                    _ => "minimum",
+                };
+                if !object.contains_key(bound) {
+                    object.insert(bound.to_string(), self.number());
+                }
+                Value::Bool(self.rng.chance())
+            }
+            "maximum" | "minimum" | "exclusiveMaximum" | "exclusiveMinimum" => self.number(),
+            "multipleOf" => json!(*self.rng.choose(&[0.5, 1.0, 2.0, 3.0])?),
+            "maxItems" | "maxLength" | "maxProperties" | "minItems" | "minLength"
+            | "minProperties" => json!(self.rng.range(0, 5)),
+            "pattern" => json!(self.rng.choose(PATTERNS)?),
+            "format" => json!(self.rng.choose(FORMATS)?),
+            "uniqueItems" => Value::Bool(self.rng.chance()),
+            "required" if self.draft.get_draft_number() == 3 => Value::Bool(self.rng.chance()),
+            "required" => self.names(),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    fn subschemas(&mut self, depth: usize) -> Value {
+        let count = self.rng.range(1, 3);
+        Value::Array((0..count).map(|_| self.subschema(depth)).collect())
+    }
+
+    fn name(&mut self) -> String {
+        self.rng.choose(NAMES).unwrap_or(&"a").to_string()
+    }
+
+    /// A non-empty array of distinct property names.
+    fn names(&mut self) -> Value {
+        let mut names: Vec<&str> = (0..self.rng.range(1, 3))
+            .filter_map(|_| self.rng.choose(NAMES).copied())
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        json!(names)
+    }
+
+    fn number(&mut self) -> Value {
+        json!(self.rng.range(0, 200) as i64 - 100)
+    }
+
+    /// A value for `const` and `enum`.
+    fn value(&mut self) -> Value {
+        match self.rng.range(0, 4) {
+            0 => Value::Null,
+            1 => Value::Bool(self.rng.chance()),
+            2 => self.number(),
+            3 => Value::String(self.name()),
+            _ => json!([self.number()]),
+        }
+    }
+}
+
+/// The JSON types allowed by the schema, either explicitly with `type` or
+/// implied by the keywords it uses.
+fn allowed_types(object: &Map<String, Value>) -> Vec<&'static str> {
+    if let Some(types) = object.get("type") {
+        let names: Vec<&str> = util::iter_or_once(types)
+            .filter_map(Value::as_str)
+            .collect();
+        return TYPES
+            .iter()
+            .cloned()
+            .filter(|x| names.contains(x) || (*x == "integer" && names.contains(&"number")))
+            .collect();
+    }
+    let implied: &[(&str, &[&str])] = &[
+        (
+            "object",
+            &["properties", "required", "additionalProperties"],
+        ),
+        ("array", &["items", "minItems", "maxItems", "contains"]),
+        ("string", &["minLength", "maxLength", "pattern", "format"]),
+        ("number", &["minimum", "maximum", "multipleOf"]),
+    ];
+    for (typename, keywords) in implied {
+        if keywords.iter().any(|x| object.contains_key(*x)) {
+            return vec![typename];
+        }
+    }
+    TYPES.to_vec()
+}
+
+/// The bounds on the size of a string, array or object from the `min` and
+/// `max` keywords, up to `MAX_SIZE`. Without `max`, up to `extra` more than
+/// the minimum. Returns `None` if the bounds can't be met.
+fn size_bounds(
+    object: &Map<String, Value>,
+    min: &str,
+    max: &str,
+    extra: u64,
+) -> Option<(u64, u64)> {
+    let min = object_f64(object, min, 0.0) as u64;
+    let max = object_f64(object, max, min.saturating_add(extra) as f64) as u64;
+    if min > max || min > MAX_SIZE {
+        return None;
+    }
+    Some((min, max.min(MAX_SIZE)))
+}
+
+fn object_f64(object: &Map<String, Value>, keyword: &str, default: f64) -> f64 {
+    object
+        .get(keyword)
+        .and_then(Value::as_f64)
+        .unwrap_or(default)
+}
+
+fn format_example(format: &str) -> Option<&'static str> {
+    Some(match format {
+        "date-time" => "2020-01-01T12:00:00Z",
+        "date" => "2020-01-01",
+        "time" => "12:00:00Z",
+        "email" | "idn-email" => "user@example.com",
+        "hostname" | "idn-hostname" => "example.com",
+        "ipv4" => "192.0.2.1",
+        "ipv6" => "2001:db8::1",
+        "uri" | "iri" => "https://example.com/",
+        "uri-reference" | "iri-reference" => "/path",
+        "uri-template" => "https://example.com/{id}",
+        "json-pointer" => "/a/b",
+        "relative-json-pointer" => "0/a",
+        "regex" => "^[a-z]+$",
+        "uuid" => "123e4567-e89b-12d3-a456-426614174000",
+        _ => return None,
+    })
+}
+
+fn collect_pointers(value: &Value, pointer: &mut String, pointers: &mut Vec<String>) {
+    pointers.push(pointer.clone());
+    match value {
+        Value::Object(object) => {
+            for (key, child) in object {
+                let len = pointer.len();
+                pointer.push('/');
+                pointer.push_str(&util::escape_pointer_token(key));
+                collect_pointers(child, pointer, pointers);
+                pointer.truncate(len);
+            }
+        }
+        Value::Array(array) => {
+            for (index, child) in array.iter().enumerate() {
+                let len = pointer.len();
+                pointer.push('/');
+                pointer.push_str(&index.to_string());
+                collect_pointers(child, pointer, pointers);
+                pointer.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub(crate) fn different_type(value: &Value) -> Value {
+    match value {
+        Value::Null => json!(0),
+        Value::Bool(_) => json!("true"),
+        Value::Number(_) => json!("0"),
+        Value::String(_) => json!(0),
+        Value::Array(_) => json!({}),
+        Value::Object(_) => json!([]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas;
+
+    #[test]
+    fn test_generated_instances_match_schema() {
+        let schema = json!({
+            "definitions": {"tag": {"type": "string", "minLength": 2, "maxLength": 4}},
+            "type": "object",
+            "required": ["id", "tags", "kind"],
+            "properties": {
+                "id": {"type": "integer", "exclusiveMinimum": 0, "multipleOf": 5},
+                "tags": {"type": "array", "items": {"$ref": "#/definitions/tag"}, "minItems": 1},
+                "kind": {"oneOf": [{"const": "a"}, {"enum": ["b", "c"]}]},
+                "ratio": {"type": "number", "minimum": 0, "maximum": 1}
+            },
+            "additionalProperties": false
+        });
+        let cfg = Config::from_schema(&schema, None).unwrap();
+        let mut generator = Generator::new(&cfg).with_seed(7);
+        for _ in 0..20 {
+            let instance = generator.valid().unwrap();
+            assert!(cfg.validate(&instance).is_ok(), "{}", instance);
+            let invalid = generator.invalid().unwrap();
+            assert!(cfg.validate(&invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_refs_in_nested_ids() {
+        let schema = json!({
+            "$id": "http://example.com/root.json",
+            "type": "object",
+            "required": ["a"],
+            "properties": {"a": {"$ref": "nested/b.json"}},
+            "definitions": {
+                "b": {
+                    "$id": "nested/b.json",
+                    "type": "object",
+                    "required": ["c"],
+                    "properties": {"c": {"$ref": "c.json"}}
+                },
+                "c": {"$id": "nested/c.json", "type": "integer", "minimum": 5}
+            }
+        });
+        let cfg = Config::from_schema(&schema, None).unwrap();
+        let instance = Generator::new(&cfg).with_seed(7).valid().unwrap();
+        assert!(instance["a"]["c"].as_i64().unwrap() >= 5, "{}", instance);
+    }
+
+    #[test]
+    fn test_unsatisfiable_schema() {
+        for schema in &[
+            json!({"type": "integer", "minimum": 3, "maximum": 2}),
+            json!({"type": "string", "minLength": 1e20}),
+            json!({"type": "array", "minItems": 18446744073709551615u64}),
+            json!({"type": "object", "minProperties": 1e9}),
+        ] {
+            let cfg = Config::from_schema(schema, None).unwrap();
+            assert_eq!(Generator::new(&cfg).with_attempts(5).valid(), None);
+        }
+    }
+
+    #[test]
+    fn test_generated_schemas_are_valid() {
+        for draft in &[
+            &schemas::Draft4 as &dyn Draft,
+            &schemas::Draft6,
+            &schemas::Draft7,
+        ] {
+            let mut generator = SchemaGenerator::new(*draft).with_seed(3);
+            for _ in 0..200 {
+                let schema = generator.schema();
+                let cfg = Config::from_schema(&schema, Some(*draft)).unwrap();
+                assert!(cfg.validate_schema().is_ok(), "{}", schema);
+                let _ = cfg.validate(&json!({"a": [1, "b"], "id": null}));
+            }
+        }
+    }
+}
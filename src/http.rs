@@ -0,0 +1,265 @@
+//! Validating the body of HTTP messages, e.g. in an API gateway.
+//!
+//! `validate_body` takes the headers and the raw body of a request or
+//! response, checks that the body is JSON, undoes any content coding,
+//! validates it against a schema, and reports any failure as an RFC 9457
+//! problem details object that can be sent back to the client as is.
+//!
+//! The `gzip` and `deflate` content codings are supported with the `gzip`
+//! feature; without it, only `identity` is.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::{schemas, Config};
+//! use jsonschema_valid::http::validate_body;
+//!
+//! let schema = json!({"required": ["name"]});
+//! let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+//! let headers = [("Content-Type", "application/json; charset=utf-8")];
+//!
+//! assert!(validate_body(&cfg, &headers, br#"{"name": "x"}"#).is_ok());
+//!
+//! let problem = validate_body(&cfg, &headers, b"{}").unwrap_err();
+//! assert_eq!(problem.status, 422);
+//! assert_eq!(problem.to_json()["errors"][0]["pointer"], "");
+//! assert_eq!(
+//!     validate_body(&cfg, &[("content-type", "text/plain")], b"{}").unwrap_err().status,
+//!     415
+//! );
+//! ```
+
+use serde_json::{json, Value};
+
+use crate::config::Config;
+use crate::error::ValidationError;
+use crate::util;
+
+/// The media type of problem details objects.
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+/// Why an HTTP message was rejected, in the shape of RFC 9457 problem
+/// details.
+#[derive(Debug, Clone)]
+pub struct Problem {
+    /// The HTTP status code to respond with.
+    pub status: u16,
+    /// A short summary of the problem.
+    pub title: String,
+    /// An explanation specific to this occurrence of the problem.
+    pub detail: String,
+    /// The validation errors, if the body is invalid against the schema.
+    pub errors: Vec<ValidationError>,
+}
+
+impl Problem {
+    fn new(status: u16, detail: String) -> Problem {
+        let title = match status {
+            400 => "Bad Request",
+            415 => "Unsupported Media Type",
+            _ => "Unprocessable Content",
+        };
+        Problem {
+            status,
+            title: title.to_string(),
+            detail,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Represent the problem as a problem details JSON object, to be sent
+    /// with the content type `PROBLEM_CONTENT_TYPE`.
+    ///
+    /// Validation errors are listed in an `errors` extension member, each
+    /// with the JSON pointer to the offending value in the body.
+    pub fn to_json(&self) -> Value {
+        let mut result = json!({
+            "type": "about:blank",
+            "status": self.status,
+            "title": self.title,
+            "detail": self.detail,
+        });
+        if !self.errors.is_empty() {
+            let errors: Vec<Value> = self
+                .errors
+                .iter()
+                .map(|err| {
+                    let pointer: String = err
+                        .instance_path()
+                        .iter()
+                        .map(|token| format!("/{}", util::escape_pointer_token(token)))
+                        .collect();
+                    let mut error = json!({"detail": err.message(), "pointer": pointer});
+                    if let Some(keyword) = err.keyword() {
+                        error["keyword"] = json!(keyword);
+                    }
+                    error
+                })
+                .collect();
+            result["errors"] = Value::Array(errors);
+        }
+        result
+    }
+}
+
+/// Validate the body of an HTTP message against the schema in `cfg`.
+///
+/// `headers` are the message's header fields as name/value pairs; names are
+/// matched case-insensitively. The `Content-Type` must be
+/// `application/json` or end in `+json`, with a UTF-8 charset if one is
+/// given. Content codings listed in `Content-Encoding` are undone in
+/// reverse order.
+///
+/// Returns the parsed body if it is valid. Otherwise, the problem has
+/// status 415 for an unsupported content type or coding, 400 for a body
+/// that can't be decoded or parsed, and 422 for a body that is invalid
+/// against the schema.
+pub fn validate_body(
+    cfg: &Config,
+    headers: &[(&str, &str)],
+    body: &[u8],
+) -> Result<Value, Problem> {
+    let header = |name: &str| {
+        headers
+            .iter()
+            .rev()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| *value)
+    };
+
+    match header("content-type") {
+        Some(content_type) => check_content_type(content_type)?,
+        None => return Err(Problem::new(415, "Missing Content-Type".to_string())),
+    }
+    let mut decoded = body.to_vec();
+    if let Some(encodings) = header("content-encoding") {
+        for encoding in encodings.rsplit(',').map(str::trim) {
+            decoded = decode(&encoding.to_ascii_lowercase(), decoded)?;
+        }
+    }
+    let instance: Value = serde_json::from_slice(&decoded)
+        .map_err(|err| Problem::new(400, format!("The body is not valid JSON: {}", err)))?;
+
+    let errors: Vec<ValidationError> = match cfg.validate(&instance) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.collect(),
+    };
+    if errors.is_empty() {
+        Ok(instance)
+    } else {
+        let mut problem = Problem::new(
+            422,
+            format!("The body is invalid: {} error(s)", errors.len()),
+        );
+        problem.errors = errors;
+        Err(problem)
+    }
+}
+
+fn check_content_type(content_type: &str) -> Result<(), Problem> {
+    let mut parts = content_type.split(';').map(str::trim);
+    let media_type = parts.next().unwrap_or("").to_ascii_lowercase();
+    if media_type != "application/json" && !media_type.ends_with("+json") {
+        return Err(Problem::new(
+            415,
+            format!("Unsupported Content-Type {}", content_type),
+        ));
+    }
+    for parameter in parts {
+        if let Some(index) = parameter.find('=') {
+            let (name, value) = (&parameter[..index], &parameter[index + 1..]);
+            let value = value.trim_matches('"');
+            if name.trim().eq_ignore_ascii_case("charset")
+                && !value.eq_ignore_ascii_case("utf-8")
+                && !value.eq_ignore_ascii_case("utf8")
+            {
+                return Err(Problem::new(415, format!("Unsupported charset {}", value)));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn decode(encoding: &str, body: Vec<u8>) -> Result<Vec<u8>, Problem> {
+    match encoding {
+        "" | "identity" => Ok(body),
+        #[cfg(feature = "gzip")]
+        "gzip" | "x-gzip" | "deflate" => {
+            use std::io::Read;
+
+            let mut decoded = Vec::new();
+            let result = if encoding == "deflate" {
+                flate2::read::ZlibDecoder::new(&body[..]).read_to_end(&mut decoded)
+            } else {
+                flate2::read::GzDecoder::new(&body[..]).read_to_end(&mut decoded)
+            };
+            result.map_err(|err| {
+                Problem::new(400, format!("Can't decode {} body: {}", encoding, err))
+            })?;
+            Ok(decoded)
+        }
+        other => Err(Problem::new(
+            415,
+            format!("Unsupported Content-Encoding {}", other),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas;
+
+    #[test]
+    fn test_content_type_and_encoding() {
+        let schema = json!({"type": "array"});
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let check = |headers: &[(&str, &str)], body: &[u8]| match validate_body(&cfg, headers, body)
+        {
+            Ok(_) => 200,
+            Err(problem) => problem.status,
+        };
+
+        assert_eq!(
+            check(&[("Content-Type", "application/geo+json")], b"[]"),
+            200
+        );
+        assert_eq!(
+            check(
+                &[("Content-Type", "application/json; charset=latin1")],
+                b"[]"
+            ),
+            415
+        );
+        assert_eq!(check(&[], b"[]"), 415);
+        assert_eq!(check(&[("Content-Type", "application/json")], b"[1,"), 400);
+        assert_eq!(
+            check(
+                &[
+                    ("Content-Type", "application/json"),
+                    ("Content-Encoding", "br")
+                ],
+                b"[]"
+            ),
+            415
+        );
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip() {
+        use std::io::Write;
+
+        let schema = json!({"type": "array"});
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"[1, 2]").unwrap();
+        let body = encoder.finish().unwrap();
+        let headers = [
+            ("Content-Type", "application/json"),
+            ("Content-Encoding", "gzip"),
+        ];
+        assert_eq!(validate_body(&cfg, &headers, &body).unwrap(), json!([1, 2]));
+    }
+}
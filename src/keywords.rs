@@ -0,0 +1,2159 @@
+//! The keywords of JSON schema, and the trait to implement custom ones.
+//!
+#![allow(non_snake_case)]
+#![allow(clippy::too_many_arguments)]
+
+use std::convert::TryFrom;
+
+use itertools::Itertools;
+
+use serde_json::{json, Map, Value, Value::Array, Value::Bool, Value::Object};
+
+use crate::config::Config;
+use crate::content;
+pub use crate::context::Context;
+use crate::data;
+use crate::error::{make_error, no_error, ErrorIterator, ValidationError};
+use crate::formats::FormatMode;
+use crate::node::{self, JsonNode, NodeKind};
+use crate::unique;
+use crate::util;
+
+/// The implementation of a schema keyword.
+///
+/// Every keyword the validator understands is an object implementing this
+/// trait. The `Config` holds a registry of them, built when the schema is
+/// compiled, so implementations may carry state such as settings or caches.
+/// Plain functions with the signature of `validate` implement it, which is
+/// how the keywords defined in the JSON schema specification are provided.
+///
+/// Custom keywords can be added with `Config::with_keyword`.
+pub trait Keyword: Send + Sync {
+    /// Validate an instance against the value of the keyword.
+    ///
+    /// # Arguments
+    ///
+    /// * `cfg`: Settings for the current validation run that don't change
+    ///   during the run.
+    /// * `instance`: The part of the JSON document being validated.
+    /// * `schema`: The value of the keyword in the JSON schema that the JSON
+    ///   document is being validated against.
+    /// * `parent_schema`: The parent node of the `schema`.  Used to look up
+    ///   sibling attributes, such as `if`/`then`/`else`.
+    /// * `ref_context`: The context in which to look up `$ref` elements. This is a
+    ///   stack that is pushed/popped when entering `$ref` contexts.  It is always
+    ///   the top element in which JSON path references are resolved.
+    ///
+    /// # Returns
+    ///
+    /// * `Iterator` over `ValidationError`
+    fn validate<'a>(
+        &'a self,
+        cfg: &'a Config<'a>,
+        instance: &'a dyn JsonNode,
+        schema: &'a Value,
+        parent_schema: Option<&'a Value>,
+        ref_context: Context<'a>,
+    ) -> ErrorIterator<'a>;
+}
+
+impl<F> Keyword for F
+where
+    F: for<'a> Fn(
+            &'a Config<'a>,
+            &'a dyn JsonNode,
+            &'a Value,
+            Option<&'a Value>,
+            Context<'a>,
+        ) -> ErrorIterator<'a>
+        + Send
+        + Sync,
+{
+    fn validate<'a>(
+        &'a self,
+        cfg: &'a Config<'a>,
+        instance: &'a dyn JsonNode,
+        schema: &'a Value,
+        parent_schema: Option<&'a Value>,
+        ref_context: Context<'a>,
+    ) -> ErrorIterator<'a> {
+        self(cfg, instance, schema, parent_schema, ref_context)
+    }
+}
+
+/// The top-level validation function that performs all of the concrete
+/// validation functions at a given instance/schema pair.
+///
+/// Keywords that hold subschemas, such as `properties`, call this to
+/// validate the parts of the instance they apply to.
+pub fn descend<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    match schema {
+        Bool(b) => {
+            if *b {
+                no_error()
+            } else {
+                make_error("false schema always fails")
+            }
+        }
+        Object(schema_object) => {
+            let profile = ref_context.profile().or_else(|| cfg.get_profile());
+            let options = ref_context.options();
+            let format_mode = options
+                .and_then(|options| options.format_mode())
+                .unwrap_or_else(|| cfg.get_format_mode());
+            let enabled = move |keyword: &str| {
+                (keyword != "format" || format_mode == FormatMode::Assertion)
+                    && match profile {
+                        Some(profile) => profile.enables(keyword),
+                        None => true,
+                    }
+            };
+            if let (Some(ref_), Some(validator), true) = (
+                schema_object.get("$ref"),
+                cfg.get_keyword("$ref"),
+                ignores_ref_siblings(cfg),
+            ) {
+                if !enabled("$ref") {
+                    return no_error();
+                }
+                Box::new(
+                    validator
+                        .validate(cfg, instance, ref_, Some(schema), ref_context)
+                        .map(move |err| err.keyword_ctx(cfg, "$ref", ref_, Some(instance))),
+                )
+            } else {
+                let forbidden = options
+                    .and_then(|options| options.direction())
+                    .map(|direction| direction.forbidden_keyword())
+                    .and_then(|keyword| schema_object.get_key_value(keyword))
+                    .filter(|(_, value)| **value == Bool(true));
+                let direction_errors: ErrorIterator<'a> = match forbidden {
+                    Some((k, v)) => Box::new(
+                        make_error(format!("{} value is not allowed here", k)).map(move |err| {
+                            err.keyword_ctx(cfg, k, v, Some(instance))
+                                .schema_ctx(k.to_string())
+                        }),
+                    ),
+                    None => no_error(),
+                };
+                Box::new(direction_errors.chain(schema_object.iter().flat_map(
+                    move |(k, v)| -> ErrorIterator<'a> {
+                        if let (Some(validator), true) = (cfg.get_keyword(k), enabled(k)) {
+                            let v = match data_reference(cfg, instance, k, v, ref_context) {
+                                None => v,
+                                Some(Ok(Some(value))) => value,
+                                Some(Ok(None)) => return no_error(),
+                                Some(Err(message)) => {
+                                    return Box::new(make_error(message).map(move |err| {
+                                        err.keyword_ctx(cfg, k, v, Some(instance))
+                                            .schema_ctx(k.to_string())
+                                    }))
+                                }
+                            };
+                            Box::new(
+                                validator
+                                    .validate(cfg, instance, v, Some(schema), ref_context)
+                                    .map(move |err| {
+                                        err.keyword_ctx(cfg, k, v, Some(instance))
+                                            .schema_ctx(k.to_string())
+                                    }),
+                            )
+                        } else {
+                            no_error()
+                        }
+                    },
+                )))
+            }
+        }
+        _ => make_error(format!(
+            "Invalid schema. Must be Bool or Object, got '{:?}'",
+            schema
+        )),
+    }
+}
+
+/// The value that `v`, the value of `k`, refers to if it is a `$data`
+/// reference, or `None` if it isn't one. Inner `None` means the reference
+/// leads nowhere, and the keyword is skipped.
+fn data_reference<'a>(
+    cfg: &Config,
+    instance: &dyn JsonNode,
+    k: &str,
+    v: &Value,
+    ref_context: Context<'a>,
+) -> Option<Result<Option<&'a Value>, String>> {
+    if !cfg.get_data_references() {
+        return None;
+    }
+    let pointer = data::reference(k, v)?;
+    Some(match ref_context.data() {
+        Some(data) => data.resolve(instance, pointer),
+        None => Err(format!(
+            "$data reference {} can only be resolved when validating a serde_json::Value",
+            pointer
+        )),
+    })
+}
+
+/// Whether the keywords next to `$ref` are ignored, as they are before draft
+/// 2019-09.
+fn ignores_ref_siblings(cfg: &Config) -> bool {
+    cfg.get_draft_number() < 8
+}
+
+/// Attach information about the enclosing object to an error that occurred
+/// inside it, if configured.
+fn parent_ctx(cfg: &Config, err: ValidationError, parent: &dyn JsonNode) -> ValidationError {
+    match cfg.get_error_context() {
+        Some(mode) => err.parent_ctx(mode, parent),
+        None => err,
+    }
+}
+
+// The validation functions below all correspond to individual schema checks
+// defined in the JSON schema specification.
+
+pub(crate) fn patternProperties<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let (NodeKind::Object, Object(schema_object)) = (instance.kind(), schema) {
+        Box::new(
+            schema_object
+                .iter()
+                .flat_map(move |(pattern, subschema)| -> ErrorIterator<'a> {
+                    if let Ok(re) = cfg.regex(pattern) {
+                        Box::new(
+                            instance
+                                .members()
+                                .flat_map(move |(k, v)| {
+                                    if re.is_match(k) {
+                                        Box::new(
+                                            descend(cfg, v, subschema, Some(schema), ref_context)
+                                                .map(move |err| {
+                                                    parent_ctx(cfg, err, instance)
+                                                        .instance_ctx(k.to_string())
+                                                }),
+                                        )
+                                    } else {
+                                        no_error()
+                                    }
+                                })
+                                .map(move |err| err.schema_ctx(pattern.clone())),
+                        )
+                    } else {
+                        Box::new(std::iter::once(
+                            ValidationError::new(&format!("Invalid pattern '{}'", pattern))
+                                .schema_ctx(pattern.clone()),
+                        ))
+                    }
+                }),
+        )
+    } else {
+        no_error()
+    }
+}
+
+pub(crate) fn propertyNames<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    struct PropertyNameIter<'a> {
+        instance_cursor: Box<dyn Iterator<Item = &'a str> + 'a>,
+        cfg: &'a Config<'a>,
+        schema: &'a Value,
+        parent_schema: Option<&'a Value>,
+        ref_context: Context<'a>,
+        collected_errors: std::vec::IntoIter<ValidationError>,
+    }
+
+    impl<'a> Iterator for PropertyNameIter<'a> {
+        type Item = ValidationError;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            loop {
+                if let Some(error) = self.collected_errors.next() {
+                    return Some(error);
+                } else if let Some(instance) = self.instance_cursor.next() {
+                    // The key is only borrowed for the duration of the
+                    // validation, so its errors are collected right away.
+                    let key = node::StrNode(instance);
+                    self.collected_errors = descend(
+                        self.cfg,
+                        &key,
+                        self.schema,
+                        self.parent_schema,
+                        self.ref_context,
+                    )
+                    .collect::<Vec<_>>()
+                    .into_iter();
+                } else {
+                    return None;
+                }
+            }
+        }
+    }
+
+    if instance.is_object() {
+        Box::new(PropertyNameIter {
+            instance_cursor: Box::new(instance.members().map(|(key, _)| key)),
+            cfg,
+            schema,
+            parent_schema,
+            ref_context,
+            collected_errors: Vec::new().into_iter(),
+        })
+    } else {
+        no_error()
+    }
+}
+
+/// Find the properties of `instance` that are matched by neither
+/// `properties` nor `patternProperties` in `schema`.
+///
+/// Fails if one of the `patternProperties` isn't a valid regular expression,
+/// since it's then unknown which properties it matches.
+fn find_additional_properties<'a>(
+    cfg: &Config,
+    instance: &'a dyn JsonNode,
+    schema: &Map<String, Value>,
+) -> Result<Vec<(&'a str, &'a dyn JsonNode)>, ValidationError> {
+    let properties = schema.get("properties").and_then(Value::as_object);
+    let mut pattern_regexes = Vec::new();
+    if let Some(Object(patterns)) = schema.get("patternProperties") {
+        for pattern in patterns.keys() {
+            let re = cfg.regex(pattern).map_err(|err| {
+                ValidationError::new(&format!(
+                    "Can't check for additional properties: invalid pattern '{}' in patternProperties: {}",
+                    pattern, err
+                ))
+            })?;
+            pattern_regexes.push(re);
+        }
+    }
+    Ok(instance
+        .members()
+        .filter(|(property, _)| match properties {
+            Some(properties) => !properties.contains_key(*property),
+            None => true,
+        })
+        .filter(|(property, _)| !pattern_regexes.iter().any(|re| re.is_match(property)))
+        .collect())
+}
+
+pub(crate) fn additionalProperties<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    let parent = match parent_schema {
+        Some(Object(parent)) if instance.is_object() => parent,
+        _ => return no_error(),
+    };
+    let extras = match find_additional_properties(cfg, instance, parent) {
+        Ok(extras) => extras,
+        Err(err) => return Box::new(std::iter::once(err)),
+    };
+
+    match schema {
+        Object(_) => Box::new(extras.into_iter().flat_map(move |(extra, value)| {
+            descend(cfg, value, schema, parent_schema, ref_context)
+                .map(move |err| parent_ctx(cfg, err, instance).instance_ctx(extra.to_string()))
+        })),
+        Bool(false) if !extras.is_empty() => {
+            let fix = extras
+                .iter()
+                .map(|(extra, _)| {
+                    json!({
+                        "op": "remove",
+                        "path": format!("/{}", util::escape_pointer_token(extra)),
+                    })
+                })
+                .collect();
+            Box::new(std::iter::once(
+                ValidationError::new(&format!(
+                    "Additional properties are not allowed. Found {}",
+                    extras.iter().map(|(extra, _)| extra).join(", ")
+                ))
+                .with_fix(fix),
+            ))
+        }
+        _ => no_error(),
+    }
+}
+
+pub(crate) fn items<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if instance.is_array() {
+        let items = if cfg.get_draft_number() >= 6 {
+            util::bool_to_object_schema(schema)
+        } else {
+            schema
+        };
+        // Next to `prefixItems`, as in draft 2020-12, `items` only applies to
+        // the items after the prefix.
+        let prefix = match parent_schema.and_then(|parent| parent.get("prefixItems")) {
+            Some(Array(prefix)) if cfg.get_keyword("prefixItems").is_some() => prefix.len(),
+            _ => 0,
+        };
+
+        match items {
+            Object(_) => Box::new(instance.elements().enumerate().skip(prefix).flat_map(
+                move |(index, item)| {
+                    Box::new(
+                        descend(cfg, item, items, Some(schema), ref_context)
+                            .map(move |err| err.instance_ctx(index.to_string())),
+                    )
+                },
+            )),
+            Array(items) => Box::new(instance.elements().enumerate().zip(items.iter()).flat_map(
+                move |((index, item), subschema)| {
+                    Box::new(
+                        descend(cfg, item, subschema, Some(schema), ref_context)
+                            .map(move |err| err.add_ctx(index.to_string(), index.to_string())),
+                    )
+                },
+            )),
+            _ => no_error(),
+        }
+    } else {
+        no_error()
+    }
+}
+
+/// The `prefixItems` keyword of draft 2020-12, which replaces the array form
+/// of `items`.
+pub(crate) fn prefixItems<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    match (instance.kind(), schema) {
+        (NodeKind::Array, Array(prefix)) => {
+            Box::new(instance.elements().enumerate().zip(prefix.iter()).flat_map(
+                move |((index, item), subschema)| {
+                    descend(cfg, item, subschema, parent_schema, ref_context)
+                        .map(move |err| err.add_ctx(index.to_string(), index.to_string()))
+                },
+            ))
+        }
+        _ => no_error(),
+    }
+}
+
+pub(crate) fn additionalItems<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let Some(parent_schema) = parent_schema {
+        if let (NodeKind::Array, Some(Array(items))) = (instance.kind(), parent_schema.get("items"))
+        {
+            match schema {
+                Object(_) => {
+                    return Box::new(instance.elements().enumerate().skip(items.len()).flat_map(
+                        move |(index, item)| {
+                            Box::new(
+                                descend(cfg, item, schema, Some(parent_schema), ref_context)
+                                    .map(move |err| err.instance_ctx(index.to_string())),
+                            )
+                        },
+                    ))
+                }
+                Bool(false) if instance.len() > items.len() => {
+                    return make_error("Additional items are not allowed");
+                }
+                _ => {}
+            }
+        }
+    }
+    no_error()
+}
+
+pub(crate) fn const_<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if !node::equals_within(instance, schema, cfg.get_epsilon()) {
+        make_error(format!(
+            "const doesn't match. Got {}, expected {}",
+            instance.to_value(),
+            schema
+        ))
+    } else {
+        no_error()
+    }
+}
+
+pub(crate) fn contains<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if instance.is_array() {
+        // `minContains` sets the number of matching items instead, and may
+        // allow none.
+        if cfg.get_keyword("minContains").is_some()
+            && parent_schema.is_some_and(|parent| parent.get("minContains").is_some())
+        {
+            return no_error();
+        }
+        for item in instance.elements() {
+            if descend(cfg, item, schema, parent_schema, ref_context)
+                .next()
+                .is_none()
+            {
+                return no_error();
+            }
+        }
+        return make_error("No items in array valid under the given schema");
+    }
+    no_error()
+}
+
+/// The number of items of `instance` that are valid under `contains`.
+fn count_contained<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    contains: &'a Value,
+    parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> usize {
+    instance
+        .elements()
+        .filter(|item| {
+            descend(cfg, *item, contains, parent_schema, ref_context)
+                .next()
+                .is_none()
+        })
+        .count()
+}
+
+/// The `minContains` keyword of draft 2019-09: the least number of items
+/// that must be valid under `contains`. With `0`, `contains` always passes.
+pub(crate) fn minContains<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    let contains = parent_schema.and_then(|parent| parent.get("contains"));
+    if let (NodeKind::Array, Value::Number(schema), Some(contains)) =
+        (instance.kind(), schema, contains)
+    {
+        let limit = match non_negative_integer(schema) {
+            Some(limit) => limit,
+            None => return make_error(format!("Invalid minContains: {}", schema)),
+        };
+        let count = count_contained(cfg, instance, contains, parent_schema, ref_context);
+        if count < limit {
+            return make_error(format!(
+                "{} items valid under contains < minContains {}",
+                count, schema
+            ));
+        }
+    }
+    no_error()
+}
+
+/// The `maxContains` keyword of draft 2019-09: the greatest number of items
+/// that may be valid under `contains`.
+pub(crate) fn maxContains<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    let contains = parent_schema.and_then(|parent| parent.get("contains"));
+    if let (NodeKind::Array, Value::Number(schema), Some(contains)) =
+        (instance.kind(), schema, contains)
+    {
+        let limit = match non_negative_integer(schema) {
+            Some(limit) => limit,
+            None => return make_error(format!("Invalid maxContains: {}", schema)),
+        };
+        let count = count_contained(cfg, instance, contains, parent_schema, ref_context);
+        if count > limit {
+            return make_error(format!(
+                "{} items valid under contains > maxContains {}",
+                count, schema
+            ));
+        }
+    }
+    no_error()
+}
+
+pub(crate) fn exclusiveMinimum<'a>(
+    _cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let (Some(instance), Value::Number(schema)) = (instance.as_number(), schema) {
+        if instance.as_f64() <= schema.as_f64() {
+            return make_error(format!("{} <= exclusiveMinimum {}", instance, schema));
+        }
+    }
+    no_error()
+}
+
+pub(crate) fn exclusiveMaximum<'a>(
+    _cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let (Some(instance), Value::Number(schema)) = (instance.as_number(), schema) {
+        if instance.as_f64() >= schema.as_f64() {
+            return make_error(format!("{} >= exclusiveMaximum {}", instance, schema));
+        }
+    }
+    no_error()
+}
+
+pub(crate) fn minimum_draft4<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let (Some(instance), Value::Number(minimum)) = (instance.as_number(), schema) {
+        if parent_schema
+            .and_then(|x| x.get("exclusiveMinimum"))
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+        {
+            if instance.as_f64() <= minimum.as_f64() {
+                return make_error(format!("{} <= exclusiveMinimum {}", instance, schema));
+            }
+        } else if below(cfg, &instance, minimum) {
+            return make_error(format!("{} <= minimum {}", instance, schema));
+        }
+    }
+    no_error()
+}
+
+/// Whether `a` is less than `b`, by more than the tolerance of `cfg`.
+fn below(cfg: &Config, a: &serde_json::Number, b: &serde_json::Number) -> bool {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(a), Some(b)) => a < b - cfg.get_epsilon(),
+        _ => false,
+    }
+}
+
+pub(crate) fn minimum<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let (Some(instance), Value::Number(schema)) = (instance.as_number(), schema) {
+        if below(cfg, &instance, schema) {
+            return make_error(format!("{} < minimum {}", instance, schema));
+        }
+    }
+    no_error()
+}
+
+pub(crate) fn maximum_draft4<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let (Some(instance), Value::Number(maximum)) = (instance.as_number(), schema) {
+        if parent_schema
+            .and_then(|x| x.get("exclusiveMaximum"))
+            .and_then(Value::as_bool)
+            .unwrap_or(false)
+        {
+            if instance.as_f64() >= maximum.as_f64() {
+                return make_error(format!("{} >= exclusiveMaximum {}", instance, schema));
+            }
+        } else if below(cfg, maximum, &instance) {
+            return make_error(format!("{} > maximum {}", instance, schema));
+        }
+    }
+    no_error()
+}
+
+pub(crate) fn maximum<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let (Some(instance), Value::Number(schema)) = (instance.as_number(), schema) {
+        if below(cfg, schema, &instance) {
+            return make_error(format!("{} > maximum {}", instance, schema));
+        }
+    }
+    no_error()
+}
+
+/// The value of a keyword that must be a non-negative integer, such as
+/// `minItems`. Floats with an integer value, like `2.0`, are accepted.
+#[allow(clippy::float_cmp)]
+fn non_negative_integer(schema: &serde_json::Number) -> Option<usize> {
+    let value = match schema.as_u64() {
+        Some(value) => value,
+        None => match schema.as_f64() {
+            Some(value) if value >= 0.0 && value.trunc() == value => value as u64,
+            _ => return None,
+        },
+    };
+    Some(usize::try_from(value).unwrap_or(usize::MAX))
+}
+
+pub(crate) fn multipleOf<'a>(
+    _cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    multiple(instance, schema, "multipleOf")
+}
+
+/// The `divisibleBy` keyword of draft 3, which `multipleOf` replaced.
+pub(crate) fn divisibleBy<'a>(
+    _cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    multiple(instance, schema, "divisibleBy")
+}
+
+/// Check that `instance` is a multiple of `schema`, the value of `keyword`.
+#[allow(clippy::float_cmp)]
+fn multiple<'a>(instance: &dyn JsonNode, schema: &Value, keyword: &str) -> ErrorIterator<'a> {
+    if let (Some(instance), Value::Number(schema)) = (instance.as_number(), schema) {
+        let divisor = match schema.as_f64() {
+            Some(divisor) if divisor > 0.0 => divisor,
+            _ => return make_error(format!("Invalid {}: {}", keyword, schema)),
+        };
+        let failed = if let (Some(instance), Some(schema)) = (instance.as_u64(), schema.as_u64()) {
+            instance % schema != 0
+        } else if let (Some(instance), Some(schema)) = (instance.as_i64(), schema.as_i64()) {
+            instance % schema != 0
+        } else {
+            let quotient = instance.as_f64().unwrap_or(f64::NAN) / divisor;
+            quotient.trunc() != quotient
+        };
+        if failed {
+            return make_error(format!("{} not {} {}", instance, keyword, schema));
+        }
+    }
+    no_error()
+}
+
+pub(crate) fn minItems<'a>(
+    _cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let (NodeKind::Array, Value::Number(schema)) = (instance.kind(), schema) {
+        let limit = match non_negative_integer(schema) {
+            Some(limit) => limit,
+            None => return make_error(format!("Invalid minItems: {}", schema)),
+        };
+        if instance.len() < limit {
+            return make_error(format!("{} < minItems {}", instance.len(), schema));
+        }
+    }
+    no_error()
+}
+
+pub(crate) fn maxItems<'a>(
+    _cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let (NodeKind::Array, Value::Number(schema)) = (instance.kind(), schema) {
+        let limit = match non_negative_integer(schema) {
+            Some(limit) => limit,
+            None => return make_error(format!("Invalid maxItems: {}", schema)),
+        };
+        if instance.len() > limit {
+            return make_error(format!("{} > maxItems {}", instance.len(), schema));
+        }
+    }
+    no_error()
+}
+
+pub(crate) fn uniqueItems<'a>(
+    _cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let (NodeKind::Array, Bool(schema)) = (instance.kind(), schema) {
+        if *schema && !unique::has_unique_elements(&mut instance.elements()) {
+            return make_error("items are not unique");
+        }
+    }
+    no_error()
+}
+
+pub(crate) fn pattern<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let (Some(instance), Value::String(schema)) = (instance.as_str(), schema) {
+        if let Ok(re) = cfg.regex(schema) {
+            if !re.is_match(instance) {
+                return make_error(format!("{} does not match pattern {}", instance, schema));
+            }
+        } else {
+            return make_error(format!("Invalid regex: {}", schema));
+        }
+    }
+    no_error()
+}
+
+/// The `contentEncoding` keyword, checked after `Config::with_content_checks`;
+/// see the `content` module.
+pub(crate) fn contentEncoding<'a>(
+    _cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let (Some(instance), Value::String(schema)) = (instance.as_str(), schema) {
+        if let Some(Err(err)) = content::decode(schema, instance) {
+            return make_error(format!("{:?} is not valid {}: {}", instance, schema, err));
+        }
+    }
+    no_error()
+}
+
+/// The `contentMediaType` keyword, checked after
+/// `Config::with_content_checks`; see the `content` module.
+pub(crate) fn contentMediaType<'a>(
+    _cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let (Some(instance), Value::String(schema)) = (instance.as_str(), schema) {
+        if let Some(Err(err)) = decoded_content(instance, parent_schema)
+            .and_then(|decoded| content::parse(schema, &decoded))
+        {
+            return make_error(format!("{:?} is not valid {}: {}", instance, schema, err));
+        }
+    }
+    no_error()
+}
+
+/// The `contentSchema` keyword, checked after `Config::with_content_checks`;
+/// see the `content` module.
+pub(crate) fn contentSchema<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    let media_type = parent_schema
+        .and_then(|parent| parent.get("contentMediaType"))
+        .and_then(Value::as_str);
+    if let (Some(instance), Some(media_type)) = (instance.as_str(), media_type) {
+        // Contents that can't be parsed are reported by `contentMediaType`.
+        if let Some(Ok(parsed)) = decoded_content(instance, parent_schema)
+            .and_then(|decoded| content::parse(media_type, &decoded))
+        {
+            let causes: Vec<ValidationError> =
+                descend(cfg, &parsed, schema, parent_schema, ref_context).collect();
+            if !causes.is_empty() {
+                return Box::new(std::iter::once(
+                    ValidationError::new("The decoded content is not valid under contentSchema")
+                        .with_causes(causes),
+                ));
+            }
+        }
+    }
+    no_error()
+}
+
+/// The contents of the string `instance`, decoded according to the
+/// `contentEncoding` in `parent_schema`, if any, or `None` if they can't be
+/// decoded, which `contentEncoding` reports if it can tell.
+fn decoded_content(instance: &str, parent_schema: Option<&Value>) -> Option<Vec<u8>> {
+    match parent_schema
+        .and_then(|parent| parent.get("contentEncoding"))
+        .and_then(Value::as_str)
+    {
+        Some(encoding) => content::decode(encoding, instance)?.ok(),
+        None => Some(instance.as_bytes().to_vec()),
+    }
+}
+
+pub(crate) fn format<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let (Some(instance), Value::String(schema)) = (instance.as_str(), schema) {
+        if let Some(checker) = cfg.get_format_checker(schema) {
+            if !checker(cfg, instance) {
+                return make_error(format!("{} invalid for {} format", instance, schema));
+            }
+        }
+    }
+    no_error()
+}
+
+pub(crate) fn minLength<'a>(
+    _cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let (Some(instance), Value::Number(schema)) = (instance.as_str(), schema) {
+        let count = instance.chars().count();
+        let limit = match non_negative_integer(schema) {
+            Some(limit) => limit,
+            None => return make_error(format!("Invalid minLength: {}", schema)),
+        };
+        if count < limit {
+            return make_error(format!(
+                "{} < minLength {}",
+                instance.chars().count(),
+                schema
+            ));
+        }
+    }
+    no_error()
+}
+
+pub(crate) fn maxLength<'a>(
+    _cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let (Some(instance), Value::Number(schema)) = (instance.as_str(), schema) {
+        let count = instance.chars().count();
+        let limit = match non_negative_integer(schema) {
+            Some(limit) => limit,
+            None => return make_error(format!("Invalid maxLength: {}", schema)),
+        };
+        if count > limit {
+            return make_error(format!("{} < maxLength {}", count, schema));
+        }
+    }
+    no_error()
+}
+
+pub(crate) fn dependencies<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    dependents(cfg, instance, schema, ref_context, |_| true)
+}
+
+/// The `dependentRequired` keyword of draft 2019-09: the form of
+/// `dependencies` with arrays of property names.
+pub(crate) fn dependentRequired<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    dependents(cfg, instance, schema, ref_context, Value::is_array)
+}
+
+/// The `dependentSchemas` keyword of draft 2019-09: the form of
+/// `dependencies` with subschemas.
+pub(crate) fn dependentSchemas<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    dependents(cfg, instance, schema, ref_context, |dependency| {
+        dependency.is_object() || dependency.is_boolean()
+    })
+}
+
+/// Check the dependencies in `schema` of the properties of `instance`, as in
+/// `dependencies`, skipping those that `accept` returns `false` for.
+fn dependents<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    ref_context: Context<'a>,
+    accept: fn(&Value) -> bool,
+) -> ErrorIterator<'a> {
+    if let (NodeKind::Object, Object(schema_object)) = (instance.kind(), schema) {
+        Box::new(
+            schema_object
+                .iter()
+                .filter(move |(property, dependency)| {
+                    accept(dependency) && instance.get(property).is_some()
+                })
+                .flat_map(move |(property, dependency)| -> ErrorIterator<'a> {
+                    let dep = util::bool_to_object_schema(dependency);
+                    if let Object(_) = dep {
+                        return Box::new(
+                            descend(cfg, instance, dep, Some(schema), ref_context)
+                                .map(move |err| err.schema_ctx(property.clone())),
+                        );
+                    } else {
+                        for dep0 in util::iter_or_once(dep) {
+                            if let Value::String(key) = dep0 {
+                                if instance.get(key).is_none() {
+                                    return make_error("dependency");
+                                }
+                            }
+                        }
+                    }
+                    no_error()
+                }),
+        )
+    } else {
+        no_error()
+    }
+}
+
+/// The proposed `propertyDependencies` keyword: for each property of the
+/// instance with a string value, the schema for that value, if any, applies
+/// to the whole instance.
+#[cfg(feature = "draft-next")]
+pub(crate) fn propertyDependencies<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let (NodeKind::Object, Object(schema_object)) = (instance.kind(), schema) {
+        Box::new(
+            schema_object
+                .iter()
+                .filter_map(move |(property, dependencies)| {
+                    let value = instance.get(property)?.as_str()?;
+                    let (value, dependency) = dependencies.as_object()?.get_key_value(value)?;
+                    Some((property, value, dependency))
+                })
+                .flat_map(move |(property, value, dependency)| {
+                    descend(cfg, instance, dependency, Some(schema), ref_context)
+                        .map(move |err| err.schema_ctx(value.clone()).schema_ctx(property.clone()))
+                }),
+        )
+    } else {
+        no_error()
+    }
+}
+
+/// The `unevaluatedProperties` keyword of draft 2019-09: applies to the
+/// properties of the instance that no `properties`, `patternProperties` or
+/// `additionalProperties` evaluated, next to it or in subschemas that apply
+/// in place and that the instance is valid against.
+pub(crate) fn unevaluatedProperties<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    let parent = match parent_schema {
+        Some(Object(parent)) if instance.is_object() => parent,
+        _ => return no_error(),
+    };
+    let mut evaluated = std::collections::HashSet::new();
+    evaluated_in_place(
+        cfg,
+        instance,
+        parent,
+        false,
+        ref_context,
+        0,
+        &|schema, nested, _, evaluated| {
+            evaluated_properties(cfg, instance, schema, nested, evaluated)
+        },
+        &mut evaluated,
+    );
+    let unevaluated: Vec<(&'a str, &'a dyn JsonNode)> = instance
+        .members()
+        .filter(|(property, _)| !evaluated.contains(*property))
+        .collect();
+
+    match schema {
+        Object(_) => Box::new(unevaluated.into_iter().flat_map(move |(property, value)| {
+            descend(cfg, value, schema, parent_schema, ref_context)
+                .map(move |err| parent_ctx(cfg, err, instance).instance_ctx(property.to_string()))
+        })),
+        Bool(false) if !unevaluated.is_empty() => make_error(format!(
+            "Unevaluated properties are not allowed. Found {}",
+            unevaluated.iter().map(|(property, _)| property).join(", ")
+        )),
+        _ => no_error(),
+    }
+}
+
+/// The `unevaluatedItems` keyword of draft 2019-09: applies to the items of
+/// the instance that no `prefixItems`, `items`, `additionalItems` or
+/// `contains` evaluated, next to it or in subschemas that apply in place and
+/// that the instance is valid against.
+pub(crate) fn unevaluatedItems<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    let parent = match parent_schema {
+        Some(Object(parent)) if instance.is_array() => parent,
+        _ => return no_error(),
+    };
+    let mut evaluated = std::collections::HashSet::new();
+    evaluated_in_place(
+        cfg,
+        instance,
+        parent,
+        false,
+        ref_context,
+        0,
+        &|schema, nested, ref_context, evaluated| {
+            evaluated_items(cfg, instance, schema, nested, ref_context, evaluated)
+        },
+        &mut evaluated,
+    );
+    let unevaluated: Vec<(usize, &'a dyn JsonNode)> = instance
+        .elements()
+        .enumerate()
+        .filter(|(index, _)| !evaluated.contains(index))
+        .collect();
+
+    match schema {
+        Object(_) => Box::new(unevaluated.into_iter().flat_map(move |(index, item)| {
+            descend(cfg, item, schema, parent_schema, ref_context)
+                .map(move |err| parent_ctx(cfg, err, instance).instance_ctx(index.to_string()))
+        })),
+        Bool(false) if !unevaluated.is_empty() => make_error(format!(
+            "Unevaluated items are not allowed. Found items {}",
+            unevaluated.iter().map(|(index, _)| index).join(", ")
+        )),
+        _ => no_error(),
+    }
+}
+
+/// Add the properties of `instance` that the keywords of `schema` evaluate
+/// to `evaluated`, and return whether these are all of its properties.
+/// `nested` is whether `schema` is a subschema of the one with the
+/// `unevaluatedProperties` keyword.
+fn evaluated_properties(
+    cfg: &Config,
+    instance: &dyn JsonNode,
+    schema: &Map<String, Value>,
+    nested: bool,
+    evaluated: &mut std::collections::HashSet<String>,
+) -> bool {
+    if schema.contains_key("additionalProperties")
+        || (nested && schema.contains_key("unevaluatedProperties"))
+    {
+        evaluated.extend(instance.members().map(|(property, _)| property.to_string()));
+        return true;
+    }
+    if let Some(Object(properties)) = schema.get("properties") {
+        evaluated.extend(
+            properties
+                .keys()
+                .filter(|property| instance.get(property).is_some())
+                .cloned(),
+        );
+    }
+    if let Some(Object(patterns)) = schema.get("patternProperties") {
+        let regexes: Vec<_> = patterns
+            .keys()
+            .filter_map(|pattern| cfg.regex(pattern).ok())
+            .collect();
+        evaluated.extend(
+            instance
+                .members()
+                .filter(|(property, _)| regexes.iter().any(|re| re.is_match(property)))
+                .map(|(property, _)| property.to_string()),
+        );
+    }
+    false
+}
+
+/// Add the indices of the items of `instance` that the keywords of `schema`
+/// evaluate to `evaluated`, and return whether these are all of its items.
+/// `nested` is whether `schema` is a subschema of the one with the
+/// `unevaluatedItems` keyword.
+fn evaluated_items(
+    cfg: &Config,
+    instance: &dyn JsonNode,
+    schema: &Map<String, Value>,
+    nested: bool,
+    ref_context: Context,
+    evaluated: &mut std::collections::HashSet<usize>,
+) -> bool {
+    if let Some(Array(prefix)) = schema.get("prefixItems") {
+        evaluated.extend(0..prefix.len().min(instance.len()));
+    }
+    match schema.get("items") {
+        Some(Array(items))
+            if !(schema.contains_key("additionalItems")
+                && cfg.get_keyword("additionalItems").is_some()) =>
+        {
+            evaluated.extend(0..items.len().min(instance.len()));
+        }
+        Some(_) => {
+            evaluated.extend(0..instance.len());
+            return true;
+        }
+        None => {}
+    }
+    if nested && schema.contains_key("unevaluatedItems") {
+        evaluated.extend(0..instance.len());
+        return true;
+    }
+    if let Some(contains) = schema.get("contains") {
+        evaluated.extend(
+            instance
+                .elements()
+                .enumerate()
+                .filter(|(_, item)| {
+                    descend(cfg, *item, contains, None, ref_context)
+                        .next()
+                        .is_none()
+                })
+                .map(|(index, _)| index),
+        );
+    }
+    false
+}
+
+/// Adds what the keywords of a single schema evaluate to a set, and returns
+/// whether that is everything; see `evaluated_in_place`.
+type EvaluatedLocally<'s, K> =
+    dyn Fn(&Map<String, Value>, bool, Context, &mut std::collections::HashSet<K>) -> bool + 's;
+
+/// Add what the keywords of `schema` evaluate in `instance`, according to
+/// `local`, to `evaluated`, following `$ref` and the subschemas that apply in
+/// place and that the instance is valid against. `local` adds what the
+/// keywords of a single schema evaluate, and returns whether that is
+/// everything; `nested` and `ref_context` are passed on to it.
+fn evaluated_in_place<K>(
+    cfg: &Config,
+    instance: &dyn JsonNode,
+    schema: &Map<String, Value>,
+    nested: bool,
+    ref_context: Context,
+    ref_depth: usize,
+    local: &EvaluatedLocally<'_, K>,
+    evaluated: &mut std::collections::HashSet<K>,
+) {
+    let valid = |subschema: &Value, ref_context: Context| {
+        descend(cfg, instance, subschema, None, ref_context)
+            .next()
+            .is_none()
+    };
+
+    for keyword in &["$ref", "$recursiveRef", "$dynamicRef"] {
+        let reference = match schema.get(*keyword) {
+            Some(Value::String(reference)) if cfg.get_keyword(keyword).is_some() => reference,
+            _ => continue,
+        };
+        if ref_depth >= crate::explain::MAX_REF_DEPTH {
+            continue;
+        }
+        let target = match *keyword {
+            "$recursiveRef" => resolve_recursive_ref(cfg, reference, ref_context),
+            "$dynamicRef" => resolve_dynamic_ref(cfg, reference, ref_context),
+            _ => cfg
+                .get_resolver()
+                .resolve_fragment(reference, &ref_context, cfg.get_schema()),
+        };
+        if let Ok((scope, resolved)) = target {
+            let scope_schema = scope_schema(cfg, &scope);
+            let ref_context = ref_context.push(&scope_schema);
+            if let (Object(resolved_object), true) = (resolved, valid(resolved, ref_context)) {
+                evaluated_in_place(
+                    cfg,
+                    instance,
+                    resolved_object,
+                    true,
+                    ref_context,
+                    ref_depth + 1,
+                    local,
+                    evaluated,
+                );
+            }
+        }
+    }
+    if schema.contains_key("$ref") && ignores_ref_siblings(cfg) {
+        return;
+    }
+
+    if local(schema, nested, ref_context, evaluated) {
+        return;
+    }
+
+    let mut in_place: Vec<&Value> = Vec::new();
+    for keyword in &["allOf", "anyOf", "oneOf"] {
+        if let Some(Array(branches)) = schema.get(*keyword) {
+            in_place.extend(branches);
+        }
+    }
+    if let Some(if_) = schema.get("if") {
+        if valid(if_, ref_context) {
+            in_place.push(if_);
+            in_place.extend(schema.get("then"));
+        } else {
+            in_place.extend(schema.get("else"));
+        }
+    }
+    for keyword in &["dependencies", "dependentSchemas"] {
+        if let Some(Object(dependencies)) = schema.get(*keyword) {
+            in_place.extend(
+                dependencies
+                    .iter()
+                    .filter(|(property, _)| instance.get(property).is_some())
+                    .map(|(_, dependency)| dependency),
+            );
+        }
+    }
+    if let Some(Object(dependencies)) = schema.get("propertyDependencies") {
+        for (property, dependencies) in dependencies {
+            if let Some(value) = instance.get(property).and_then(|value| value.as_str()) {
+                in_place.extend(dependencies.get(value));
+            }
+        }
+    }
+
+    for subschema in in_place {
+        if let Object(object) = subschema {
+            if valid(subschema, ref_context) {
+                evaluated_in_place(
+                    cfg,
+                    instance,
+                    object,
+                    true,
+                    ref_context,
+                    ref_depth,
+                    local,
+                    evaluated,
+                );
+            }
+        }
+    }
+}
+
+pub(crate) fn enum_<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let Array(enums) = schema {
+        let epsilon = cfg.get_epsilon();
+        if !enums
+            .iter()
+            .any(|val| node::equals_within(val, instance, epsilon))
+        {
+            return make_error(format!(
+                "{} is not one of enum {}",
+                instance.to_value(),
+                schema
+            ));
+        }
+    }
+    no_error()
+}
+
+#[allow(clippy::float_cmp)]
+fn single_type(instance: &dyn JsonNode, schema: &Value) -> bool {
+    if let Value::String(typename) = schema {
+        return match typename.as_ref() {
+            "array" => instance.kind() == NodeKind::Array,
+            "object" => instance.kind() == NodeKind::Object,
+            "null" => instance.kind() == NodeKind::Null,
+            "number" => instance.kind() == NodeKind::Number,
+            "string" => instance.kind() == NodeKind::String,
+            "integer" => {
+                if let Some(number) = instance.as_number() {
+                    number.is_i64()
+                        || number.is_u64()
+                        || number
+                            .as_f64()
+                            .is_some_and(|number| number.trunc() == number)
+                } else {
+                    false
+                }
+            }
+            "boolean" => instance.kind() == NodeKind::Bool,
+            _ => true,
+        };
+    }
+    true
+}
+
+pub(crate) fn type_<'a>(
+    _cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if !util::iter_or_once(schema).any(|x| single_type(instance, x)) {
+        let err = ValidationError::new(&format!(
+            "{} is not of type {}",
+            instance.to_value(),
+            schema
+        ));
+        let err = match util::iter_or_once(schema).find_map(|x| coerce(instance, x)) {
+            Some(value) => err.with_fix(vec![json!({"op": "replace", "path": "", "value": value})]),
+            None => err,
+        };
+        return Box::new(std::iter::once(err));
+    }
+    no_error()
+}
+
+/// The `type` keyword of OpenAPI 3.0, which also allows `null` if
+/// `nullable` is `true` next to it.
+pub(crate) fn type_openapi<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if instance.kind() == NodeKind::Null && nullable(cfg, parent_schema) {
+        no_error()
+    } else {
+        type_(cfg, instance, schema, parent_schema, ref_context)
+    }
+}
+
+/// Whether `schema` allows `null` regardless of its `type`, with the
+/// `nullable` of OpenAPI 3.0.
+pub(crate) fn nullable(cfg: &Config, schema: Option<&Value>) -> bool {
+    cfg.get_draft().is_openapi()
+        && schema.and_then(|schema| schema.get("nullable")) == Some(&Bool(true))
+}
+
+/// The `type` keyword of draft 3, whose types may also be schemas, which
+/// an instance is of if it is valid against them.
+pub(crate) fn type_draft3<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if !util::iter_or_once(schema).any(|x| draft3_type(cfg, instance, x, ref_context)) {
+        return make_error(format!("{} is not of type {}", instance.to_value(), schema));
+    }
+    no_error()
+}
+
+/// The `disallow` keyword of draft 3: the instance must not be of any of the
+/// types, as in the draft 3 `type`.
+pub(crate) fn disallow<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if util::iter_or_once(schema).any(|x| draft3_type(cfg, instance, x, ref_context)) {
+        return make_error(format!(
+            "{} is disallowed for {}",
+            schema,
+            instance.to_value()
+        ));
+    }
+    no_error()
+}
+
+/// Whether `instance` is of `typename`, a type name or a schema, in draft 3.
+fn draft3_type(
+    cfg: &Config,
+    instance: &dyn JsonNode,
+    typename: &Value,
+    ref_context: Context,
+) -> bool {
+    match typename {
+        Object(_) => descend(cfg, instance, typename, None, ref_context)
+            .next()
+            .is_none(),
+        _ => single_type(instance, typename),
+    }
+}
+
+/// Convert a scalar `instance` to the type `typename` without loss, e.g.
+/// `"42"` to `42` or `true` to `"true"`, if possible.
+fn coerce(instance: &dyn JsonNode, typename: &Value) -> Option<Value> {
+    let value = match (instance.kind(), typename.as_str()?) {
+        (NodeKind::String, "integer") => json!(instance.as_str()?.parse::<i64>().ok()?),
+        (NodeKind::String, "number") => {
+            let text = instance.as_str()?;
+            match text.parse::<i64>() {
+                Ok(number) => json!(number),
+                Err(_) => Value::Number(serde_json::Number::from_f64(text.parse().ok()?)?),
+            }
+        }
+        (NodeKind::String, "boolean") => json!(instance.as_str()?.parse::<bool>().ok()?),
+        (NodeKind::String, "null") if instance.as_str()? == "null" => Value::Null,
+        (NodeKind::Number, "string") => json!(instance.as_number()?.to_string()),
+        (NodeKind::Bool, "string") => json!(instance.as_bool()?.to_string()),
+        _ => return None,
+    };
+    Some(value)
+}
+
+pub(crate) fn properties<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let (NodeKind::Object, Object(schema_object)) = (instance.kind(), schema) {
+        Box::new(schema_object.iter().flat_map(move |(property, subschema)| {
+            if let Some(property_value) = instance.get(property) {
+                Box::new(
+                    descend(cfg, property_value, subschema, Some(schema), ref_context).map(
+                        move |err| {
+                            parent_ctx(cfg, err, instance)
+                                .add_ctx(property.clone(), property.clone())
+                        },
+                    ),
+                )
+            } else {
+                no_error()
+            }
+        }))
+    } else {
+        no_error()
+    }
+}
+
+/// The `properties` keyword of draft 3, where a property is also required
+/// if its subschema has `"required": true`.
+pub(crate) fn properties_draft3<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    let errors = properties(cfg, instance, schema, parent_schema, ref_context);
+    if let (NodeKind::Object, Object(schema_object)) = (instance.kind(), schema) {
+        let missing: Vec<ValidationError> = schema_object
+            .iter()
+            .filter_map(|(property, subschema)| {
+                let required = subschema.get("required").filter(|x| **x == Bool(true))?;
+                if instance.get(property).is_some() {
+                    return None;
+                }
+                Some(
+                    ValidationError::new(&format!("required property {} is missing", property))
+                        .keyword_ctx(cfg, "required", required, Some(instance))
+                        .schema_ctx("required".to_string())
+                        .schema_ctx(property.clone()),
+                )
+            })
+            .collect();
+        return Box::new(errors.chain(missing));
+    }
+    errors
+}
+
+pub(crate) fn required<'a>(
+    _cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let (NodeKind::Object, Array(schema)) = (instance.kind(), schema) {
+        let missing_properties: Vec<&str> = schema
+            .iter()
+            .filter_map(Value::as_str)
+            .filter(|&x| instance.get(x).is_none())
+            .collect();
+
+        if !missing_properties.is_empty() {
+            let err = ValidationError::new(&format!(
+                "required properties {} are missing",
+                missing_properties.join(", ")
+            ));
+            // Only suggest adding the defaults if all of the missing
+            // properties have one.
+            let fix: Option<Vec<Value>> = missing_properties
+                .iter()
+                .map(|property| {
+                    let default = parent_schema?
+                        .get("properties")?
+                        .get(property)?
+                        .get("default")?;
+                    Some(json!({
+                        "op": "add",
+                        "path": format!("/{}", util::escape_pointer_token(property)),
+                        "value": default,
+                    }))
+                })
+                .collect();
+            let err = match fix {
+                Some(fix) => err.with_fix(fix),
+                None => err,
+            };
+            return Box::new(std::iter::once(err));
+        }
+    }
+    no_error()
+}
+
+pub(crate) fn minProperties<'a>(
+    _cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let (NodeKind::Object, Value::Number(schema)) = (instance.kind(), schema) {
+        let limit = match non_negative_integer(schema) {
+            Some(limit) => limit,
+            None => return make_error(format!("Invalid minProperties: {}", schema)),
+        };
+        if instance.len() < limit {
+            return make_error(format!("{} < minProperties {}", instance.len(), schema));
+        }
+    }
+    no_error()
+}
+
+pub(crate) fn maxProperties<'a>(
+    _cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    _ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let (NodeKind::Object, Value::Number(schema)) = (instance.kind(), schema) {
+        let limit = match non_negative_integer(schema) {
+            Some(limit) => limit,
+            None => return make_error(format!("Invalid maxProperties: {}", schema)),
+        };
+        if instance.len() > limit {
+            return make_error(format!("{} > maxProperties {}", instance.len(), schema));
+        }
+    }
+    no_error()
+}
+
+pub(crate) fn allOf<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let Array(schema_array) = schema {
+        Box::new(
+            schema_array
+                .iter()
+                .enumerate()
+                .flat_map(move |(index, subschema)| {
+                    let subschema0 = if cfg.get_draft_number() >= 6 {
+                        util::bool_to_object_schema(subschema)
+                    } else {
+                        subschema
+                    };
+                    Box::new(
+                        descend(cfg, instance, subschema0, Some(schema), ref_context)
+                            .map(move |err| err.schema_ctx(index.to_string())),
+                    )
+                }),
+        )
+    } else {
+        no_error()
+    }
+}
+
+/// The `extends` keyword of draft 3: the instance must be valid against the
+/// subschema, or against each of an array of subschemas, as with `allOf`.
+pub(crate) fn extends<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    match schema {
+        Array(_) => allOf(cfg, instance, schema, parent_schema, ref_context),
+        _ => descend(cfg, instance, schema, parent_schema, ref_context),
+    }
+}
+
+/// The errors from validating `instance` against each of the subschemas of
+/// the applicator `schema`, to attach as the causes of the applicator
+/// failing. Their schema paths start at the index of the subschema.
+fn subschema_errors<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    schema_array: &'a [Value],
+    ref_context: Context<'a>,
+) -> Vec<ValidationError> {
+    let mut causes = Vec::new();
+    for (index, subschema) in schema_array.iter().enumerate() {
+        let subschema0 = if cfg.get_draft_number() >= 6 {
+            util::bool_to_object_schema(subschema)
+        } else {
+            subschema
+        };
+        causes.extend(
+            descend(cfg, instance, subschema0, Some(schema), ref_context)
+                .map(|err| err.schema_ctx(index.to_string())),
+        );
+    }
+    causes
+}
+
+pub(crate) fn anyOf<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let Array(schema_array) = schema {
+        for subschema in schema_array.iter() {
+            let subschema0 = if cfg.get_draft_number() >= 6 {
+                util::bool_to_object_schema(subschema)
+            } else {
+                subschema
+            };
+            if descend(cfg, instance, subschema0, Some(schema), ref_context)
+                .next()
+                .is_none()
+            {
+                return no_error();
+            }
+        }
+        let causes = subschema_errors(cfg, instance, schema, schema_array, ref_context);
+        return Box::new(std::iter::once(
+            ValidationError::new("anyOf failed").with_causes(causes),
+        ));
+    }
+    no_error()
+}
+
+/// The `oneOf` keyword of OpenAPI 3.0. With a `discriminator` next to it,
+/// the value of the discriminating property picks the one subschema to
+/// validate against, and its errors are reported as they are; the other
+/// subschemas aren't tried. Otherwise every subschema is tried.
+pub(crate) fn oneOf_openapi<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let Array(schema_array) = schema {
+        if let Some(index) = discriminated(instance, schema_array, parent_schema) {
+            return Box::new(
+                descend(
+                    cfg,
+                    instance,
+                    &schema_array[index],
+                    Some(schema),
+                    ref_context,
+                )
+                .map(move |err| err.schema_ctx(index.to_string())),
+            );
+        }
+    }
+    oneOf(cfg, instance, schema, parent_schema, ref_context)
+}
+
+pub(crate) fn oneOf<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let Array(schema_array) = schema {
+        let matched: Vec<usize> = schema_array
+            .iter()
+            .enumerate()
+            .filter(|(_, subschema)| {
+                let subschema0 = if cfg.get_draft_number() >= 6 {
+                    util::bool_to_object_schema(subschema)
+                } else {
+                    subschema
+                };
+                descend(cfg, instance, subschema0, Some(schema), ref_context)
+                    .next()
+                    .is_none()
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        match matched.len() {
+            0 => {
+                let causes = subschema_errors(cfg, instance, schema, schema_array, ref_context);
+                return Box::new(std::iter::once(
+                    ValidationError::new("nothing matched in oneOf").with_causes(causes),
+                ));
+            }
+            1 => {}
+            _ => return make_error(more_than_one_matched(&matched)),
+        }
+    }
+    no_error()
+}
+
+/// The index of the subschema among `subschemas` that the `discriminator`
+/// in `parent_schema` picks for `instance`: the one whose `$ref` is the
+/// `mapping` of the value of the discriminating property, or, without a
+/// mapping for it, ends in the value as the name of a schema.
+fn discriminated(
+    instance: &dyn JsonNode,
+    subschemas: &[Value],
+    parent_schema: Option<&Value>,
+) -> Option<usize> {
+    let discriminator = parent_schema?.get("discriminator")?;
+    let value = instance
+        .get(discriminator.get("propertyName")?.as_str()?)?
+        .as_str()?;
+    let target = discriminator
+        .get("mapping")
+        .and_then(|mapping| mapping.get(value))
+        .and_then(Value::as_str)
+        .unwrap_or(value);
+    subschemas.iter().position(
+        |subschema| match subschema.get("$ref").and_then(Value::as_str) {
+            Some(ref_) => {
+                ref_ == target || (!target.contains('/') && ref_.rsplit('/').next() == Some(target))
+            }
+            None => false,
+        },
+    )
+}
+
+/// The message of a `oneOf` error, naming the indices of the subschemas that
+/// matched.
+pub(crate) fn more_than_one_matched(matched: &[usize]) -> String {
+    format!(
+        "More than one matched in oneOf: subschemas {} matched",
+        matched.iter().join(", ")
+    )
+}
+
+pub(crate) fn not<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if descend(cfg, instance, schema, parent_schema, ref_context)
+        .next()
+        .is_none()
+    {
+        make_error("not")
+    } else {
+        no_error()
+    }
+}
+
+pub(crate) fn ref_<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    if let Value::String(sref) = schema {
+        struct RefIter {
+            collected_errors: Vec<ValidationError>,
+            error_i: usize,
+        }
+
+        impl Iterator for RefIter {
+            type Item = ValidationError;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                if self.error_i < self.collected_errors.len() {
+                    self.error_i += 1;
+                    Some(self.collected_errors[self.error_i - 1].clone())
+                } else {
+                    None
+                }
+            }
+        }
+
+        match cfg
+            .get_resolver()
+            .resolve_fragment(sref, &ref_context, cfg.get_schema())
+        {
+            Ok((scope, resolved)) => {
+                let scope_schema = scope_schema(cfg, &scope);
+                return Box::new(RefIter {
+                    collected_errors: descend(
+                        cfg,
+                        instance,
+                        resolved,
+                        Some(schema),
+                        ref_context.push(&scope_schema),
+                    )
+                    .collect(),
+                    error_i: 0,
+                });
+            }
+            Err(_err) => return make_error(format!("Couldn't resolve reference {}", sref)),
+        }
+    }
+    no_error()
+}
+
+/// The `$recursiveRef` keyword of draft 2019-09; see
+/// `resolve_recursive_ref`.
+pub(crate) fn recursiveRef<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    dynamic_ref(cfg, instance, schema, ref_context, resolve_recursive_ref)
+}
+
+/// The `$dynamicRef` keyword of draft 2020-12; see `resolve_dynamic_ref`.
+pub(crate) fn dynamicRef<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    _parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    dynamic_ref(cfg, instance, schema, ref_context, resolve_dynamic_ref)
+}
+
+/// Resolves a reference that depends on the schema resources entered during
+/// validation.
+type DynamicResolve<'a> =
+    fn(&'a Config<'a>, &str, Context) -> Result<(url::Url, &'a Value), ValidationError>;
+
+/// Validate against the schema that the reference `schema` leads to, as
+/// resolved by `resolve`.
+fn dynamic_ref<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    ref_context: Context<'a>,
+    resolve: DynamicResolve<'a>,
+) -> ErrorIterator<'a> {
+    if let Value::String(reference) = schema {
+        return match resolve(cfg, reference, ref_context) {
+            Ok((scope, resolved)) => {
+                let scope_schema = scope_schema(cfg, &scope);
+                let errors: Vec<ValidationError> = descend(
+                    cfg,
+                    instance,
+                    resolved,
+                    Some(schema),
+                    ref_context.push(&scope_schema),
+                )
+                .collect();
+                Box::new(errors.into_iter())
+            }
+            Err(_) => make_error(format!("Couldn't resolve reference {}", reference)),
+        };
+    }
+    no_error()
+}
+
+/// Resolve the `$recursiveRef` `reference` like a `$ref`. If the schema it
+/// leads to has `"$recursiveAnchor": true`, it leads instead to the outermost
+/// schema resource entered during validation that has one too, as in draft
+/// 2019-09, so that extensions of recursive schemas apply at every level.
+fn resolve_recursive_ref<'a>(
+    cfg: &'a Config<'a>,
+    reference: &str,
+    ref_context: Context,
+) -> Result<(url::Url, &'a Value), ValidationError> {
+    let resolver = cfg.get_resolver();
+    let target = resolver.resolve_fragment(reference, &ref_context, cfg.get_schema())?;
+    if !has_recursive_anchor(target.1) {
+        return Ok(target);
+    }
+    let mut outermost = None;
+    let mut frame = Some(&ref_context);
+    while let Some(context) = frame {
+        if has_recursive_anchor(context.x) {
+            outermost = Some(context);
+        }
+        frame = context.parent;
+    }
+    match outermost {
+        Some(context) => resolver.resolve_fragment("#", context, cfg.get_schema()),
+        None => Ok(target),
+    }
+}
+
+fn has_recursive_anchor(schema: &Value) -> bool {
+    schema.get("$recursiveAnchor") == Some(&Bool(true))
+}
+
+/// Resolve the `$dynamicRef` `reference` like a `$ref`. If its fragment is a
+/// name, and the schema it leads to has a `$dynamicAnchor` of that name, it
+/// leads instead to the schema with that `$dynamicAnchor` in the outermost
+/// schema resource entered during validation that has one, as in draft
+/// 2020-12.
+fn resolve_dynamic_ref<'a>(
+    cfg: &'a Config<'a>,
+    reference: &str,
+    ref_context: Context,
+) -> Result<(url::Url, &'a Value), ValidationError> {
+    let resolver = cfg.get_resolver();
+    let target = resolver.resolve_fragment(reference, &ref_context, cfg.get_schema())?;
+    let anchor = match reference.split_once('#') {
+        Some((_, anchor)) if !anchor.is_empty() && !anchor.starts_with('/') => anchor,
+        _ => return Ok(target),
+    };
+    let has_dynamic_anchor =
+        |schema: &Value| schema.get("$dynamicAnchor").and_then(Value::as_str) == Some(anchor);
+    if !has_dynamic_anchor(target.1) {
+        return Ok(target);
+    }
+    let fragment = format!("#{}", anchor);
+    let mut outermost = None;
+    let mut frame = Some(&ref_context);
+    while let Some(context) = frame {
+        match resolver.resolve_fragment(&fragment, context, cfg.get_schema()) {
+            Ok(found) if has_dynamic_anchor(found.1) => outermost = Some(found),
+            _ => {}
+        }
+        frame = context.parent;
+    }
+    Ok(outermost.unwrap_or(target))
+}
+
+/// The schema entered into the context when a reference leads into the
+/// schema resource at `scope`: its id, and, since draft 2019-09, whether the
+/// resource has a `$recursiveAnchor`.
+pub(crate) fn scope_schema(cfg: &Config, scope: &url::Url) -> Value {
+    let recursive = cfg.get_draft_number() >= 8
+        && cfg
+            .get_resolver()
+            .resolve_url(scope, cfg.get_schema())
+            .is_ok_and(has_recursive_anchor);
+    if recursive {
+        json!({"$id": scope.to_string(), "$recursiveAnchor": true})
+    } else {
+        json!({"$id": scope.to_string()})
+    }
+}
+
+/// Validate against `schema`, the value of `then` or `else`, if the
+/// instance is valid against the sibling `if` or not, respectively. `if`
+/// itself never fails, so errors point at the branch that applied.
+fn conditional<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+    applies_if_valid: bool,
+) -> ErrorIterator<'a> {
+    let if_ = match parent_schema.and_then(|x| x.get("if")) {
+        Some(if_) => if_,
+        None => return no_error(),
+    };
+    let valid = descend(cfg, instance, if_, parent_schema, ref_context)
+        .next()
+        .is_none();
+    if valid == applies_if_valid {
+        descend(cfg, instance, schema, parent_schema, ref_context)
+    } else {
+        no_error()
+    }
+}
+
+pub(crate) fn then_<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    conditional(cfg, instance, schema, parent_schema, ref_context, true)
+}
+
+pub(crate) fn else_<'a>(
+    cfg: &'a Config<'a>,
+    instance: &'a dyn JsonNode,
+    schema: &'a Value,
+    parent_schema: Option<&'a Value>,
+    ref_context: Context<'a>,
+) -> ErrorIterator<'a> {
+    conditional(cfg, instance, schema, parent_schema, ref_context, false)
+}
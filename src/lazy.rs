@@ -0,0 +1,189 @@
+//! Compiling slices of very large schema documents.
+//!
+//! Bundled schema documents, such as OpenAPI descriptions, can be tens of
+//! megabytes, of which a service often only needs a few definitions.
+//! `compile` takes the text of such a document and the references of the
+//! definitions that are needed, and only parses these definitions, the parts
+//! of the document that their `$ref`s reach, and the `$schema` and `$id` of
+//! the root. The rest of the document is skipped over without building any
+//! values, which cuts both the compile time and the memory of the schema.
+//!
+//! Only references that are JSON pointers into the document itself, like
+//! `#/components/schemas/Order`, are followed. Documents that identify their
+//! subschemas with `$id` should be compiled as a whole.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::schemas;
+//! use jsonschema_valid::lazy::compile;
+//!
+//! let document = br##"{
+//!     "components": {"schemas": {
+//!         "Order": {"properties": {"total": {"$ref": "#/components/schemas/Money"}}},
+//!         "Money": {"type": "number", "minimum": 0},
+//!         "Report": {"description": "Never parsed", "maxLength": 1e400}
+//!     }}
+//! }"##;
+//! let cfg = compile(document, &["#/components/schemas/Order"], Some(&schemas::Draft7)).unwrap();
+//!
+//! let order = cfg.fragment("#/components/schemas/Order").unwrap();
+//! assert!(order.validate(&json!({"total": 12.5})).is_ok());
+//! assert!(order.validate(&json!({"total": -1})).is_err());
+//! assert!(cfg.get_schema().pointer("/components/schemas/Report").is_none());
+//! ```
+
+use serde_json::{Map, Value};
+
+use crate::config::Config;
+use crate::error::ValidationError;
+use crate::lsp;
+use crate::schemas;
+use crate::util;
+
+/// Compile the parts of the JSON schema document `document` that are
+/// reached from `references`, as described in the module documentation.
+///
+/// The schema of the returned `Config` is the document with only these
+/// parts; use `Config::fragment` with one of `references` to validate
+/// against it. The draft is determined as in `Config::from_schema`.
+///
+/// The document must be valid JSON; the parts that are skipped over are not
+/// checked.
+pub fn compile<'a>(
+    document: &[u8],
+    references: &[&str],
+    draft: Option<&'a dyn schemas::Draft>,
+) -> Result<Config<'a>, ValidationError> {
+    let text = std::str::from_utf8(document)
+        .map_err(|err| ValidationError::new(&format!("Invalid schema document: {}", err)))?;
+
+    let mut partial = Value::Object(Map::new());
+    for keyword in &["$schema", "$id", "id"] {
+        if let Some((start, end)) = lsp::span(text, &[keyword]) {
+            insert(&mut partial, &[keyword], parse(text, start, end)?);
+        }
+    }
+
+    let mut pending = references
+        .iter()
+        .map(|reference| {
+            local_pointer(reference).ok_or_else(|| {
+                ValidationError::new(&format!(
+                    "{} is not a JSON pointer into the document",
+                    reference
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut parsed: Vec<String> = Vec::new();
+    while let Some(pointer) = pending.pop() {
+        if parsed
+            .iter()
+            .any(|parent| pointer == *parent || pointer.starts_with(&format!("{}/", parent)))
+        {
+            continue;
+        }
+        let tokens: Vec<String> = util::pointer_tokens(&pointer).collect();
+        let tokens: Vec<&str> = tokens.iter().map(String::as_str).collect();
+        let (start, end) = lsp::span(text, &tokens)
+            .ok_or_else(|| ValidationError::new(&format!("Can't resolve #{}", pointer)))?;
+        let value = parse(text, start, end)?;
+        find_refs(&value, &mut pending);
+        insert(&mut partial, &tokens, value);
+        parsed.push(pointer);
+    }
+
+    Config::from_owned_schema(partial, draft)
+}
+
+fn parse(text: &str, start: usize, end: usize) -> Result<Value, ValidationError> {
+    serde_json::from_str(&text[start..end])
+        .map_err(|err| ValidationError::new(&format!("Invalid schema document: {}", err)))
+}
+
+/// The JSON pointer of a reference to a part of the same document, e.g.
+/// `/definitions/a` for `#/definitions/a`.
+fn local_pointer(reference: &str) -> Option<String> {
+    let fragment = reference.strip_prefix('#')?;
+    if !fragment.is_empty() && !fragment.starts_with('/') {
+        return None;
+    }
+    percent_encoding::percent_decode(fragment.as_bytes())
+        .decode_utf8()
+        .ok()
+        .map(|pointer| pointer.into_owned())
+}
+
+/// Add the pointers of the references to parts of the same document in
+/// `value` to `pending`.
+fn find_refs(value: &Value, pending: &mut Vec<String>) {
+    match value {
+        Value::Object(object) => {
+            if let Some(pointer) = object
+                .get("$ref")
+                .and_then(Value::as_str)
+                .and_then(local_pointer)
+            {
+                pending.push(pointer);
+            }
+            object.values().for_each(|value| find_refs(value, pending));
+        }
+        Value::Array(array) => array.iter().for_each(|value| find_refs(value, pending)),
+        _ => {}
+    }
+}
+
+/// Put `value` at `path` in `document`, creating the objects along the way.
+/// Arrays along the way are represented as objects with the indices as
+/// keys, which JSON pointers resolve the same way.
+fn insert(document: &mut Value, path: &[&str], value: Value) {
+    let mut target = document;
+    for token in path {
+        if !target.is_object() {
+            *target = Value::Object(Map::new());
+        }
+        target = target
+            .as_object_mut()
+            .unwrap()
+            .entry(token.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+    *target = value;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_compile_follows_refs() {
+        let document = br##"{
+            "$schema": "http://json-schema.org/draft-06/schema",
+            "definitions": {
+                "list": {"type": "array", "items": {"$ref": "#/definitions/node"}},
+                "node": {"anyOf": [{"$ref": "#/definitions/list"}, {"$ref": "#/definitions/le%61f"}]},
+                "leaf": {"type": "integer"},
+                "unused": [1e400, {"$ref": "#/definitions/leaf"}]
+            }
+        }"##;
+        let cfg = compile(document, &["#/definitions/list"], None).unwrap();
+        assert_eq!(cfg.get_draft_number(), 6);
+        assert_eq!(
+            cfg.get_schema()["definitions"]
+                .as_object()
+                .unwrap()
+                .keys()
+                .collect::<Vec<_>>(),
+            ["leaf", "list", "node"]
+        );
+        let list = cfg.fragment("#/definitions/list").unwrap();
+        assert!(list.validate(&json!([1, [2, [3]]])).is_ok());
+        assert!(list.validate(&json!([1, ["2"]])).is_err());
+
+        assert!(compile(document, &["#/definitions/tree"], None).is_err());
+        assert!(compile(document, &["other.json#/definitions/list"], None).is_err());
+    }
+}
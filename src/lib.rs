@@ -2,7 +2,8 @@
 //!
 //! A simple crate to perform [JSON Schema](https://json-schema.org/) validation.
 //!
-//! Supports JSON Schema drafts 4, 6, and 7.
+//! Supports JSON Schema drafts 3, 4, 6, 7, 2019-09 and 2020-12, and the Schema
+//! Object of OpenAPI 3.0.
 //!
 //! ## Example:
 //!
@@ -24,24 +25,90 @@
 //!
 //! # Ok(()) }
 //! ````
+//!
+//! ## Organization:
+//!
+//! `compile`, `validate` and `iter_errors` cover the common case. The
+//! types they use live in these modules:
+//!
+//! * `schema`: compiled schemas (`Config`) and the supported drafts.
+//! * `keywords`: the `Keyword` trait, for custom keywords.
+//! * `formats`: the checks for the `format` keyword.
+//! * `resolver`: resolving `$ref`s.
+//! * `node`: the `JsonNode` trait, for validating other types of values.
+//! * `error`: validation errors.
+//! * `output`: settings for how errors are presented.
 
 #![warn(missing_docs)]
 
 use serde_json::Value;
 
+mod algebra;
+pub mod annotations;
+pub mod batch;
+pub mod builder;
+pub mod bundle;
+pub mod cache;
+pub mod compat;
 mod config;
+pub mod conformance;
+pub mod content;
 mod context;
-mod error;
-mod format;
-mod resolver;
+pub mod convert;
+pub mod cost;
+pub mod coverage;
+pub mod data;
+pub mod de;
+pub mod defaults;
+pub mod diff;
+pub mod error;
+pub mod explain;
+pub mod formats;
+pub mod fragment;
+pub mod generate;
+pub mod http;
+pub mod keywords;
+pub mod lazy;
+pub mod lint;
+pub mod lsp;
+#[doc(hidden)]
+pub mod macros;
+mod merge;
+pub mod multi;
+pub mod mutate;
+pub mod node;
+mod normalize;
+pub mod openapi;
+mod optimize;
+pub mod options;
+pub mod output;
+pub mod profile;
+pub mod reload;
+#[cfg(feature = "remote")]
+pub mod remote;
+pub mod resolver;
+pub mod schema;
 pub mod schemas;
+pub mod snapshot;
+pub mod snippet;
+pub mod stream;
 mod unique;
 mod util;
-mod validators;
+pub mod vocabulary;
+pub mod workspace;
 
+pub use crate::algebra::{intersection, union};
 pub use crate::config::Config;
-use crate::context::Context;
-pub use crate::error::{ErrorIterator, ValidationError};
+pub use crate::context::Context;
+pub use crate::error::{ErrorIterator, Errors, ValidationError};
+pub use crate::keywords::Keyword;
+pub use crate::merge::{merge_allOf, MergeError};
+pub use crate::node::JsonNode;
+pub use crate::normalize::normalize;
+pub use crate::optimize::optimize;
+pub use crate::output::{
+    ContextMode, EnglishFormatter, MessageFormatter, ParentContext, Redaction,
+};
 
 /// Validates a given JSON instance against a given JSON schema, returning the
 /// errors, if any. draft may provide the schema draft to use. If not provided,
@@ -85,14 +152,50 @@ pub fn validate<'a>(
     cfg: &'a config::Config<'a>,
     instance: &'a Value,
 ) -> Result<(), ErrorIterator<'a>> {
-    let mut errors = validators::descend(
-        cfg,
-        instance,
-        cfg.get_schema(),
-        None,
-        Context::new_from(cfg.get_schema()),
-    )
-    .peekable();
+    validate_value(cfg, instance, Context::new_from(cfg.get_schema()))
+}
+
+/// Validates a JSON instance of any type implementing `JsonNode` against
+/// the schema in `cfg`, like `validate`.
+///
+/// This allows validating values from other JSON libraries without
+/// converting them to a `serde_json::Value` first; see the `node` module.
+/// Keywords with `$data` references fail.
+pub fn validate_node<'a>(
+    cfg: &'a config::Config<'a>,
+    instance: &'a dyn JsonNode,
+) -> Result<(), ErrorIterator<'a>> {
+    validate_in(cfg, instance, Context::new_from(cfg.get_schema()))
+}
+
+/// Validate `instance` against the schema in `cfg`, starting in `context`,
+/// resolving `$data` references if enabled.
+pub(crate) fn validate_value<'a>(
+    cfg: &'a config::Config<'a>,
+    instance: &'a Value,
+    context: Context<'a>,
+) -> Result<(), ErrorIterator<'a>> {
+    if !cfg.get_data_references() {
+        return validate_in(cfg, instance, context);
+    }
+    // The index only lives for this call, so the errors are collected.
+    let data = data::Instance::new(instance);
+    let errors: Vec<ValidationError> = match validate_in(cfg, instance, context.with_data(&data)) {
+        Ok(()) => return Ok(()),
+        Err(errors) => errors.collect(),
+    };
+    Err(Box::new(errors.into_iter()))
+}
+
+/// Validate `instance` against the schema in `cfg`, starting in `context`.
+pub(crate) fn validate_in<'a>(
+    cfg: &'a config::Config<'a>,
+    instance: &'a dyn JsonNode,
+    context: Context<'a>,
+) -> Result<(), ErrorIterator<'a>> {
+    let mut errors = keywords::descend(cfg, instance, cfg.get_schema(), None, context)
+        .map(move |err| cfg.finish_error(err))
+        .peekable();
 
     if errors.peek().is_none() {
         Ok(())
@@ -101,6 +204,83 @@ pub fn validate<'a>(
     }
 }
 
+/// Compile a JSON schema for validation, detecting the draft from its
+/// `$schema` entry.
+///
+/// This is the same as `Config::from_schema(schema, None)`.
+///
+/// ## Example:
+///
+/// ```rust
+/// # use serde_json::json;
+/// let schema = json!({"type": "integer"});
+/// let cfg = jsonschema_valid::compile(&schema).unwrap();
+/// assert!(jsonschema_valid::validate(&cfg, &json!(1)).is_ok());
+/// assert_eq!(jsonschema_valid::iter_errors(&cfg, &json!("1")).count(), 1);
+/// ```
+pub fn compile(schema: &Value) -> Result<Config<'_>, ValidationError> {
+    Config::from_schema(schema, None)
+}
+
+/// Parse a JSON schema and a JSON instance, and validate the instance
+/// against the schema, in one step.
+///
+/// The schema is checked against the metaschema of its draft first. `draft`
+/// is the draft of the schema, as in `Config::from_schema`. The error tells
+/// which of the steps failed.
+///
+/// ## Example:
+///
+/// ```rust
+/// use jsonschema_valid::{validate_str, Errors};
+///
+/// let schema = r#"{"type": "integer"}"#;
+/// assert!(validate_str(schema, "1", None).is_ok());
+/// assert!(matches!(validate_str(schema, "1.5", None), Err(Errors::Invalid(_))));
+/// assert!(matches!(validate_str(schema, "[", None), Err(Errors::InstanceSyntax(_))));
+/// assert!(matches!(
+///     validate_str(r#"{"type": 1}"#, "1", None),
+///     Err(Errors::InvalidSchema(_))
+/// ));
+/// ```
+pub fn validate_str(
+    schema_json: &str,
+    instance_json: &str,
+    draft: Option<&dyn schemas::Draft>,
+) -> Result<(), Errors> {
+    validate_bytes(schema_json.as_bytes(), instance_json.as_bytes(), draft)
+}
+
+/// Parse a JSON schema and a JSON instance from bytes, and validate the
+/// instance against the schema, like `validate_str`.
+pub fn validate_bytes(
+    schema_json: &[u8],
+    instance_json: &[u8],
+    draft: Option<&dyn schemas::Draft>,
+) -> Result<(), Errors> {
+    let schema: Value = serde_json::from_slice(schema_json).map_err(Errors::SchemaSyntax)?;
+    let instance: Value = serde_json::from_slice(instance_json).map_err(Errors::InstanceSyntax)?;
+    let cfg =
+        Config::from_schema(&schema, draft).map_err(|err| Errors::InvalidSchema(vec![err]))?;
+    if let Err(errors) = cfg.validate_schema() {
+        return Err(Errors::InvalidSchema(errors.collect()));
+    }
+    cfg.validate(&instance)
+        .map_err(|errors| Errors::Invalid(errors.collect()))
+}
+
+/// Validates a given JSON instance against the schema in `cfg`, returning
+/// an iterator over the errors, which is empty if the instance is valid.
+///
+/// This is the same as `validate`, for callers that process errors the same
+/// way whether there are any or not.
+pub fn iter_errors<'a>(cfg: &'a config::Config<'a>, instance: &'a Value) -> ErrorIterator<'a> {
+    match validate(cfg, instance) {
+        Ok(()) => Box::new(std::iter::empty()),
+        Err(errors) => errors,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -173,4 +353,332 @@ mod tests {
     fn test_draft4() {
         test_draft("draft4", &schemas::Draft4);
     }
+
+    #[test]
+    fn test_docs_url() {
+        let schema = serde_json::json!({"properties": {"a": {"type": "string", "minLength": 2}}});
+        let data = serde_json::json!({"a": 1});
+        let cfg = Config::from_schema(&schema, None)
+            .unwrap()
+            .with_docs_url("https://example.com/{keyword}")
+            .with_keyword_docs_url("minLength", "https://example.com/length");
+        let errors: Vec<ValidationError> = cfg.validate(&data).unwrap_err().collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].keyword(), Some("type"));
+        assert_eq!(errors[0].docs_url(), Some("https://example.com/type"));
+
+        let data = serde_json::json!({"a": "x"});
+        let errors: Vec<ValidationError> = cfg.validate(&data).unwrap_err().collect();
+        assert_eq!(errors[0].docs_url(), Some("https://example.com/length"));
+    }
+
+    #[test]
+    fn test_error_context() {
+        let schema =
+            serde_json::json!({"properties": {"a": {"properties": {"b": {"type": "string"}}}}});
+        let data = serde_json::json!({"a": {"b": 1, "c": "secret"}, "d": 2});
+        let cfg = Config::from_schema(&schema, None)
+            .unwrap()
+            .with_error_context(ContextMode::Keys);
+        let errors: Vec<ValidationError> = cfg.validate(&data).unwrap_err().collect();
+        assert_eq!(
+            errors[0].parent_context(),
+            Some(&ParentContext::Keys(vec!["b".to_string(), "c".to_string()]))
+        );
+
+        let cfg = Config::from_schema(&schema, None)
+            .unwrap()
+            .with_error_context(ContextMode::Snippet(10));
+        let errors: Vec<ValidationError> = cfg.validate(&data).unwrap_err().collect();
+        assert_eq!(
+            errors[0].parent_context(),
+            Some(&ParentContext::Snippet("{\"b\":1,\"c\"…".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_redaction() {
+        let schema = serde_json::json!({"properties": {"email": {"format": "email"}}});
+        let data = serde_json::json!({"email": "not-an-email"});
+        let cfg = Config::from_schema(&schema, None)
+            .unwrap()
+            .with_redaction(Redaction::Omit);
+        let errors: Vec<ValidationError> = cfg.validate(&data).unwrap_err().collect();
+        assert_eq!(
+            errors[0].message(),
+            "Value does not satisfy format \"email\""
+        );
+        assert!(errors[0].instance().is_none());
+
+        let cfg = Config::from_schema(&schema, None)
+            .unwrap()
+            .with_redaction(Redaction::Hash);
+        let errors: Vec<ValidationError> = cfg.validate(&data).unwrap_err().collect();
+        assert!(!errors[0].to_string().contains("not-an-email"));
+        assert!(errors[0].message().starts_with("Value sha256:"));
+    }
+
+    #[test]
+    fn test_validate_examples() {
+        let schema = serde_json::json!({
+            "properties": {
+                "port": {"type": "integer", "default": "80", "examples": [8080, -1.5]},
+                "default": {"type": "string"}
+            }
+        });
+        let cfg = Config::from_schema(&schema, None).unwrap();
+        assert!(cfg.validate_schema().is_ok());
+
+        let cfg = cfg.with_example_checks();
+        let errors: Vec<ValidationError> = cfg.validate_schema().unwrap_err().collect();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors[0].instance_path(),
+            vec!["properties", "port", "default"]
+        );
+        assert_eq!(
+            errors[1].instance_path(),
+            vec!["properties", "port", "examples", "1"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_pattern_properties() {
+        let schema = serde_json::json!({
+            "patternProperties": {"^a": {"type": "string"}, "(": {}},
+            "additionalProperties": false
+        });
+        let data = serde_json::json!({"ab": 1});
+        let cfg = Config::from_schema(&schema, None).unwrap();
+        let errors: Vec<ValidationError> = cfg.validate(&data).unwrap_err().collect();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].schema_path(), vec!["additionalProperties"]);
+        assert!(errors[0].message().contains("invalid pattern '('"));
+        assert_eq!(errors[1].schema_path(), vec!["patternProperties", "("]);
+        assert_eq!(errors[2].instance_path(), vec!["ab"]);
+    }
+
+    #[test]
+    fn test_custom_keyword() {
+        struct Divisible {
+            by: u64,
+        }
+
+        impl Keyword for Divisible {
+            fn validate<'a>(
+                &'a self,
+                _cfg: &'a Config<'a>,
+                instance: &'a dyn JsonNode,
+                schema: &'a Value,
+                _parent_schema: Option<&'a Value>,
+                _ref_context: Context<'a>,
+            ) -> ErrorIterator<'a> {
+                match instance.as_number().and_then(|n| n.as_u64()) {
+                    Some(n) if schema == &Value::Bool(true) && n % self.by != 0 => {
+                        Box::new(std::iter::once(ValidationError::new(&format!(
+                            "{} is not divisible by {}",
+                            n, self.by
+                        ))))
+                    }
+                    _ => Box::new(std::iter::empty()),
+                }
+            }
+        }
+
+        let schema = serde_json::json!({"items": {"divisible": true}});
+        let cfg = Config::from_schema(&schema, None)
+            .unwrap()
+            .with_keyword("divisible", Divisible { by: 3 });
+        let errors: Vec<ValidationError> = cfg
+            .validate(&serde_json::json!([6, 4]))
+            .unwrap_err()
+            .collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message(), "4 is not divisible by 3");
+        assert_eq!(errors[0].keyword(), Some("divisible"));
+        assert_eq!(errors[0].schema_path(), vec!["items", "divisible"]);
+    }
+
+    #[test]
+    fn test_malformed_schema_values() {
+        let schema = serde_json::json!({
+            "properties": {
+                "list": {"minItems": -1, "maxItems": 2.0},
+                "name": {"maxLength": 1.5},
+                "count": {"multipleOf": 0},
+                "ratio": {"multipleOf": 2},
+                "ref": {"$ref": "#/%FF"}
+            }
+        });
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let data = serde_json::json!({
+            "list": [1, 2, 3],
+            "name": "ab",
+            "count": 3,
+            "ratio": -4.5,
+            "ref": 1
+        });
+        let mut messages: Vec<String> = cfg
+            .validate(&data)
+            .unwrap_err()
+            .map(|err| err.message().to_string())
+            .collect();
+        messages.sort();
+        assert_eq!(
+            messages,
+            [
+                "-4.5 not multipleOf 2",
+                "3 > maxItems 2.0",
+                "Couldn't resolve reference #/%FF",
+                "Invalid maxLength: 1.5",
+                "Invalid minItems: -1",
+                "Invalid multipleOf: 0",
+            ]
+        );
+        let data = serde_json::json!({"ratio": -4});
+        assert!(cfg.validate(&data).is_ok());
+    }
+
+    #[test]
+    fn test_one_of_diagnostics() {
+        let schema = serde_json::json!({
+            "oneOf": [{"type": "integer"}, {"minimum": 0}, {"type": "string"}]
+        });
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        assert!(cfg.validate(&serde_json::json!(-1)).is_ok());
+
+        let errors: Vec<ValidationError> =
+            cfg.validate(&serde_json::json!(5)).unwrap_err().collect();
+        assert_eq!(
+            errors[0].message(),
+            "More than one matched in oneOf: subschemas 0, 1 matched"
+        );
+
+        let errors: Vec<ValidationError> = cfg
+            .validate(&serde_json::json!(-1.5))
+            .unwrap_err()
+            .collect();
+        assert_eq!(errors[0].message(), "nothing matched in oneOf");
+        let branches: Vec<_> = errors[0]
+            .causes()
+            .iter()
+            .map(|cause| cause.schema_path().join("/"))
+            .collect();
+        assert_eq!(branches, ["0/type", "1/minimum", "2/type"]);
+    }
+
+    #[test]
+    fn test_one_of_discriminator() {
+        let schema = serde_json::json!({
+            "oneOf": [
+                {"$ref": "#/components/schemas/Cat"},
+                {"$ref": "#/components/schemas/Dog"}
+            ],
+            "discriminator": {"propertyName": "pet", "mapping": {"kitten": "Cat"}},
+            "components": {"schemas": {
+                "Cat": {"properties": {"lives": {"type": "integer"}}},
+                "Dog": {"properties": {"bark": {"type": "string"}}}
+            }}
+        });
+        let cfg = Config::from_schema(&schema, Some(&schemas::OpenApi30)).unwrap();
+        assert!(cfg
+            .validate(&serde_json::json!({"pet": "Cat", "lives": 9}))
+            .is_ok());
+        // Both subschemas match, but only the picked one is tried.
+        assert!(cfg.validate(&serde_json::json!({"pet": "Dog"})).is_ok());
+
+        let errors: Vec<ValidationError> = cfg
+            .validate(&serde_json::json!({"pet": "kitten", "lives": "9"}))
+            .unwrap_err()
+            .collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].schema_path().join("/"),
+            "oneOf/0/properties/lives/type"
+        );
+        let errors: Vec<ValidationError> = cfg
+            .validate(&serde_json::json!({"pet": "Bird"}))
+            .unwrap_err()
+            .collect();
+        assert_eq!(
+            errors[0].message(),
+            "More than one matched in oneOf: subschemas 0, 1 matched"
+        );
+
+        // `discriminator` is an unknown keyword in JSON schema drafts.
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let errors: Vec<ValidationError> = cfg
+            .validate(&serde_json::json!({"pet": "Dog"}))
+            .unwrap_err()
+            .collect();
+        assert_eq!(
+            errors[0].message(),
+            "More than one matched in oneOf: subschemas 0, 1 matched"
+        );
+    }
+
+    #[test]
+    fn test_pattern() {
+        let schema = serde_json::json!({
+            "properties": {"code": {"pattern": "^[A-Z]{3}$"}, "bad": {"pattern": "("}}
+        });
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7))
+            .unwrap()
+            .with_error_values();
+        assert!(cfg.validate(&serde_json::json!({"code": "EUR"})).is_ok());
+
+        let errors: Vec<ValidationError> = cfg
+            .validate(&serde_json::json!({"code": "eur", "bad": "x"}))
+            .unwrap_err()
+            .collect();
+        assert_eq!(errors[0].message(), "Invalid regex: (");
+        assert_eq!(errors[1].message(), "eur does not match pattern ^[A-Z]{3}$");
+        assert_eq!(
+            errors[1].keyword_value(),
+            Some(&serde_json::json!("^[A-Z]{3}$"))
+        );
+        assert_eq!(errors[1].instance_path(), vec!["code"]);
+
+        // Values are only recorded when asked for.
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let errors: Vec<ValidationError> = cfg
+            .validate(&serde_json::json!({"code": "eur"}))
+            .unwrap_err()
+            .collect();
+        assert_eq!(errors[0].keyword(), Some("pattern"));
+        assert_eq!(errors[0].keyword_value(), None);
+        assert_eq!(errors[0].instance(), None);
+    }
+
+    #[test]
+    fn test_if_then_else() {
+        let schema = serde_json::json!({
+            "if": {"properties": {"kind": {"const": "card"}}},
+            "then": {"required": ["number"]},
+            "else": {"properties": {"number": false}}
+        });
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        assert!(cfg
+            .validate(&serde_json::json!({"kind": "card", "number": "4111"}))
+            .is_ok());
+        assert!(cfg.validate(&serde_json::json!({"kind": "cash"})).is_ok());
+
+        let errors: Vec<ValidationError> = cfg
+            .validate(&serde_json::json!({"kind": "card"}))
+            .unwrap_err()
+            .collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].schema_path(), vec!["then", "required"]);
+
+        let errors: Vec<ValidationError> = cfg
+            .validate(&serde_json::json!({"kind": "cash", "number": "4111"}))
+            .unwrap_err()
+            .collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].schema_path(),
+            vec!["else", "properties", "number"]
+        );
+        assert_eq!(errors[0].instance_path(), vec!["number"]);
+    }
 }
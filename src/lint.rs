@@ -0,0 +1,481 @@
+//! Checking schemas for likely mistakes.
+//!
+//! Many mistakes in schemas don't make them invalid against the metaschema:
+//! a misspelled keyword is silently ignored, and contradicting bounds just
+//! make every instance invalid. The linter reports those, along with
+//! metaschema violations and unresolvable references.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::schemas;
+//! use jsonschema_valid::lint::{Linter, Severity};
+//!
+//! let schema = json!({"properties": {"name": {"type": "string", "minLenght": 1}}});
+//! let lints: Vec<_> = Linter::new()
+//!     .lint(&schema, &schemas::Draft7)
+//!     .into_iter()
+//!     .filter(|x| x.severity >= Severity::Warning)
+//!     .collect();
+//! assert_eq!(lints.len(), 1);
+//! assert_eq!(lints[0].rule, "unknown-keyword");
+//! assert_eq!(lints[0].severity, Severity::Warning);
+//! assert_eq!(lints[0].pointer, "/properties/name/minLenght");
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use regex::Regex;
+use serde_json::{Map, Value};
+
+use crate::config::Config;
+use crate::error::ValidationError;
+use crate::optimize::TYPE_KEYWORDS;
+use crate::schemas;
+use crate::util;
+
+/// How serious a problem found by the linter is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Worth knowing, but often intentional.
+    Info,
+    /// Probably a mistake.
+    Warning,
+    /// Definitely a mistake: the schema is invalid or can't work as
+    /// intended.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Severity, String> {
+        match s {
+            "info" => Ok(Severity::Info),
+            "warning" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            other => Err(format!("Unknown severity {}", other)),
+        }
+    }
+}
+
+/// A problem found by the linter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lint {
+    /// The name of the rule that found the problem.
+    pub rule: &'static str,
+    /// How serious the problem is.
+    pub severity: Severity,
+    /// JSON pointer to the location of the problem within the schema.
+    pub pointer: String,
+    /// A description of the problem.
+    pub message: String,
+}
+
+/// All rules, with their severity and a short description.
+pub const RULES: &[(&str, Severity, &str)] = &[
+    (
+        "invalid-schema",
+        Severity::Error,
+        "the schema is invalid against its metaschema",
+    ),
+    (
+        "unresolvable-ref",
+        Severity::Error,
+        "a $ref can't be resolved",
+    ),
+    (
+        "invalid-pattern",
+        Severity::Error,
+        "a pattern is not a valid regular expression",
+    ),
+    (
+        "empty-enum",
+        Severity::Error,
+        "an enum has no members, so nothing is valid",
+    ),
+    (
+        "contradictory-bounds",
+        Severity::Error,
+        "a lower bound is greater than the corresponding upper bound",
+    ),
+    (
+        "unsatisfiable-required",
+        Severity::Error,
+        "a required property is forbidden by additionalProperties",
+    ),
+    (
+        "unknown-keyword",
+        Severity::Warning,
+        "a keyword is not known to the draft, and is ignored",
+    ),
+    (
+        "ref-siblings",
+        Severity::Warning,
+        "keywords next to $ref are ignored",
+    ),
+    (
+        "inapplicable-keyword",
+        Severity::Warning,
+        "a keyword can't apply to any of the types allowed by type",
+    ),
+    (
+        "missing-description",
+        Severity::Info,
+        "a property has no description",
+    ),
+];
+
+/// Keywords without a validator of their own that are nevertheless known.
+pub(crate) const OTHER_KEYWORDS: &[&str] = &[
+    "$anchor",
+    "$comment",
+    "$defs",
+    "$dynamicAnchor",
+    "$id",
+    "$recursiveAnchor",
+    "$schema",
+    "$vocabulary",
+    "contentEncoding",
+    "contentMediaType",
+    "contentSchema",
+    "default",
+    "definitions",
+    "deprecated",
+    "description",
+    "discriminator",
+    "else",
+    "example",
+    "examples",
+    "exclusiveMaximum",
+    "exclusiveMinimum",
+    "externalDocs",
+    "id",
+    "nullable",
+    "readOnly",
+    "then",
+    "title",
+    "writeOnly",
+    "xml",
+];
+
+/// Checks schemas with a configurable set of rules.
+pub struct Linter {
+    rules: Vec<&'static str>,
+}
+
+impl Default for Linter {
+    fn default() -> Linter {
+        Linter {
+            rules: RULES.iter().map(|(rule, _, _)| *rule).collect(),
+        }
+    }
+}
+
+impl Linter {
+    /// Create a linter with all rules enabled.
+    pub fn new() -> Linter {
+        Linter::default()
+    }
+
+    /// Only use the given rules. Unknown rule names are an error.
+    pub fn with_rules(mut self, rules: &[&str]) -> Result<Self, String> {
+        self.rules = rules
+            .iter()
+            .map(|rule| find_rule(rule))
+            .collect::<Result<_, _>>()?;
+        Ok(self)
+    }
+
+    /// Disable the given rule. Unknown rule names are an error.
+    pub fn without_rule(mut self, rule: &str) -> Result<Self, String> {
+        let rule = find_rule(rule)?;
+        self.rules.retain(|x| *x != rule);
+        Ok(self)
+    }
+
+    /// Check the schema, returning all problems found, ordered by location.
+    pub fn lint(&self, schema: &Value, draft: &dyn schemas::Draft) -> Vec<Lint> {
+        let mut lints = Vec::new();
+        match Config::from_schema(schema, Some(draft)) {
+            Ok(cfg) => {
+                if let Err(errors) = cfg.validate_schema() {
+                    for error in errors {
+                        push_error(&mut lints, "invalid-schema", &error);
+                    }
+                }
+                if let Err(errors) = cfg.validate_refs() {
+                    for error in errors {
+                        push_error(&mut lints, "unresolvable-ref", &error);
+                    }
+                }
+            }
+            Err(err) => lints.push(Lint {
+                rule: "invalid-schema",
+                severity: Severity::Error,
+                pointer: String::new(),
+                message: err.message().to_string(),
+            }),
+        }
+        self.lint_subschema(schema, draft, &mut String::new(), &mut lints);
+        lints.retain(|lint| self.rules.contains(&lint.rule));
+        lints.sort_by(|a, b| a.pointer.cmp(&b.pointer));
+        lints
+    }
+
+    fn lint_subschema(
+        &self,
+        schema: &Value,
+        draft: &dyn schemas::Draft,
+        pointer: &mut String,
+        lints: &mut Vec<Lint>,
+    ) {
+        if let Value::Object(object) = schema {
+            lint_object(object, draft, pointer, lints);
+        }
+        util::for_each_subschema(schema, &mut |path, subschema| {
+            let len = pointer.len();
+            pointer.push('/');
+            pointer.push_str(&path);
+            self.lint_subschema(subschema, draft, pointer, lints);
+            pointer.truncate(len);
+        });
+    }
+}
+
+fn find_rule(rule: &str) -> Result<&'static str, String> {
+    RULES
+        .iter()
+        .map(|(name, _, _)| *name)
+        .find(|name| *name == rule)
+        .ok_or_else(|| format!("Unknown lint rule {}", rule))
+}
+
+fn push(lints: &mut Vec<Lint>, rule: &'static str, pointer: String, message: String) {
+    let severity = RULES
+        .iter()
+        .find(|(name, _, _)| *name == rule)
+        .map_or(Severity::Error, |(_, severity, _)| *severity);
+    lints.push(Lint {
+        rule,
+        severity,
+        pointer,
+        message,
+    });
+}
+
+/// Report an error found while validating the schema, which refers to the
+/// schema as the instance.
+fn push_error(lints: &mut Vec<Lint>, rule: &'static str, error: &ValidationError) {
+    let pointer: String = error
+        .instance_path()
+        .iter()
+        .map(|token| format!("/{}", util::escape_pointer_token(token)))
+        .collect();
+    push(lints, rule, pointer, error.message().to_string());
+}
+
+fn lint_object(
+    object: &Map<String, Value>,
+    draft: &dyn schemas::Draft,
+    pointer: &str,
+    lints: &mut Vec<Lint>,
+) {
+    let at = |keyword: &str| format!("{}/{}", pointer, util::escape_pointer_token(keyword));
+
+    for keyword in object.keys() {
+        if !schemas::supports(keyword, draft)
+            && !OTHER_KEYWORDS.contains(&keyword.as_str())
+            && !keyword.starts_with("x-")
+        {
+            push(
+                lints,
+                "unknown-keyword",
+                at(keyword),
+                format!("Unknown keyword {}", keyword),
+            );
+        }
+    }
+
+    if object.contains_key("$ref") {
+        for keyword in object.keys() {
+            if keyword != "$ref"
+                && schemas::supports(keyword, draft)
+                && draft.get_draft_number() <= 7
+            {
+                push(
+                    lints,
+                    "ref-siblings",
+                    at(keyword),
+                    format!("{} is ignored next to $ref", keyword),
+                );
+            }
+        }
+    }
+
+    if let Some(Value::String(pattern)) = object.get("pattern") {
+        if let Err(err) = Regex::new(pattern) {
+            push(
+                lints,
+                "invalid-pattern",
+                at("pattern"),
+                format!("Invalid pattern: {}", err),
+            );
+        }
+    }
+
+    if object.get("enum") == Some(&Value::Array(Vec::new())) {
+        push(
+            lints,
+            "empty-enum",
+            at("enum"),
+            "enum has no members".to_string(),
+        );
+    }
+
+    for (min, max) in &[
+        ("minimum", "maximum"),
+        ("minLength", "maxLength"),
+        ("minItems", "maxItems"),
+        ("minProperties", "maxProperties"),
+    ] {
+        if let (Some(low), Some(high)) = (
+            object.get(*min).and_then(Value::as_f64),
+            object.get(*max).and_then(Value::as_f64),
+        ) {
+            if low > high {
+                push(
+                    lints,
+                    "contradictory-bounds",
+                    at(min),
+                    format!("{} {} is greater than {} {}", min, low, max, high),
+                );
+            }
+        }
+    }
+
+    if object.get("additionalProperties") == Some(&Value::Bool(false))
+        && !object.contains_key("patternProperties")
+    {
+        let properties = object.get("properties").and_then(Value::as_object);
+        for name in object
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            let declared = match properties {
+                Some(properties) => properties.contains_key(name),
+                None => false,
+            };
+            if !declared {
+                push(
+                    lints,
+                    "unsatisfiable-required",
+                    at("required"),
+                    format!(
+                        "{} is required, but forbidden by additionalProperties",
+                        name
+                    ),
+                );
+            }
+        }
+    }
+
+    if let Some(types) = object.get("type") {
+        let types: Vec<&str> = util::iter_or_once(types)
+            .filter_map(Value::as_str)
+            .map(|x| if x == "integer" { "number" } else { x })
+            .collect();
+        for (typename, keywords) in TYPE_KEYWORDS {
+            if types.contains(typename) {
+                continue;
+            }
+            for keyword in keywords.iter() {
+                if object.contains_key(*keyword) {
+                    push(
+                        lints,
+                        "inapplicable-keyword",
+                        at(keyword),
+                        format!("{} only applies to {} instances", keyword, typename),
+                    );
+                }
+            }
+        }
+    }
+
+    if let Some(Value::Object(properties)) = object.get("properties") {
+        for (name, property) in properties {
+            if let Value::Object(property) = property {
+                if !property.contains_key("description") && !property.contains_key("$ref") {
+                    push(
+                        lints,
+                        "missing-description",
+                        format!("{}/{}", at("properties"), util::escape_pointer_token(name)),
+                        format!("Property {} has no description", name),
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rules() {
+        let schema = json!({
+            "type": "object",
+            "required": ["a", "b"],
+            "properties": {"a": {"description": "A", "minimum": 2, "maximum": 1}},
+            "additionalProperties": false,
+            "minLength": 1,
+            "definitions": {"x": {"$ref": "#/definitions/y", "type": "string"}}
+        });
+        let lints: Vec<(&str, String)> = Linter::new()
+            .without_rule("missing-description")
+            .unwrap()
+            .lint(&schema, &schemas::Draft7)
+            .into_iter()
+            .map(|x| (x.rule, x.pointer))
+            .collect();
+        assert_eq!(
+            lints,
+            vec![
+                ("unresolvable-ref", "/definitions/x/$ref".to_string()),
+                ("ref-siblings", "/definitions/x/type".to_string()),
+                ("inapplicable-keyword", "/minLength".to_string()),
+                ("contradictory-bounds", "/properties/a/minimum".to_string()),
+                ("unsatisfiable-required", "/required".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_selected_rules() {
+        let schema = json!({"properties": {"a": {"type": 1}}});
+        let lints = Linter::new()
+            .with_rules(&["invalid-schema"])
+            .unwrap()
+            .lint(&schema, &schemas::Draft7);
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].severity, Severity::Error);
+        assert!(Linter::new().with_rules(&["no-such-rule"]).is_err());
+    }
+}
@@ -0,0 +1,341 @@
+//! Reporting validation results as Language Server Protocol diagnostics.
+//!
+//! Editors show problems at a location in the source text, while validation
+//! errors only know the JSON pointer of the offending value. `diagnostics`
+//! validates a JSON document given as text and maps every error back to a
+//! range in that text, in the shape of an LSP `Diagnostic`.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::{schemas, Config};
+//! use jsonschema_valid::lsp::{diagnostics, Position};
+//!
+//! let schema = json!({"properties": {"port": {"type": "integer"}}});
+//! let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+//! let text = "{\n  \"port\": \"80\"\n}\n";
+//!
+//! let diagnostics = diagnostics(&cfg, text);
+//! assert_eq!(diagnostics.len(), 1);
+//! assert_eq!(diagnostics[0].range.start, Position { line: 1, character: 10 });
+//! assert_eq!(diagnostics[0].range.end, Position { line: 1, character: 14 });
+//! assert_eq!(diagnostics[0].code.as_deref(), Some("type"));
+//! ```
+
+use serde_json::{json, Value};
+
+use crate::config::Config;
+use crate::error::ValidationError;
+
+/// A position in a text document: a zero-based line, and a zero-based
+/// offset within the line in UTF-16 code units, as in LSP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    /// The line.
+    pub line: u32,
+    /// The offset within the line.
+    pub character: u32,
+}
+
+/// A range in a text document. `end` is exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Range {
+    /// The start of the range.
+    pub start: Position,
+    /// The end of the range.
+    pub end: Position,
+}
+
+/// The severity of a diagnostic, with the numeric values used by LSP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// An error.
+    Error = 1,
+    /// A warning.
+    Warning = 2,
+    /// Information.
+    Information = 3,
+    /// A hint.
+    Hint = 4,
+}
+
+/// A problem at a range in a text document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// Where the problem is.
+    pub range: Range,
+    /// How serious the problem is.
+    pub severity: Severity,
+    /// The keyword that failed, if known.
+    pub code: Option<String>,
+    /// A link to documentation for the keyword, if configured with
+    /// `Config::with_docs_url`.
+    pub code_description: Option<String>,
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Represent the diagnostic as a JSON object in the shape of an LSP
+    /// `Diagnostic`.
+    pub fn to_json(&self) -> Value {
+        let position = |x: Position| json!({"line": x.line, "character": x.character});
+        let mut result = json!({
+            "range": {"start": position(self.range.start), "end": position(self.range.end)},
+            "severity": self.severity as u8,
+            "source": "jsonschema-valid",
+            "message": self.message,
+        });
+        if let Some(code) = &self.code {
+            result["code"] = json!(code);
+        }
+        if let Some(href) = &self.code_description {
+            result["codeDescription"] = json!({ "href": href });
+        }
+        result
+    }
+}
+
+/// Validate the JSON document in `text` against the schema in `cfg`, and
+/// report every error as a diagnostic at the location of the offending
+/// value.
+///
+/// Scalars are highlighted entirely; for objects and arrays, only the
+/// opening bracket is, so that a single error doesn't cover the whole
+/// document. If `text` isn't valid JSON, a single diagnostic for the syntax
+/// error is returned.
+pub fn diagnostics(cfg: &Config, text: &str) -> Vec<Diagnostic> {
+    let instance: Value = match serde_json::from_str(text) {
+        Ok(instance) => instance,
+        Err(err) => {
+            let line = err.line().saturating_sub(1);
+            let offset = line_offsets(text).get(line).copied().unwrap_or(text.len());
+            let start = position(text, offset + err.column().saturating_sub(1));
+            return vec![Diagnostic {
+                range: Range { start, end: start },
+                severity: Severity::Error,
+                code: None,
+                code_description: None,
+                message: err.to_string(),
+            }];
+        }
+    };
+
+    let diagnostics = match cfg.validate(&instance) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.map(|err| to_diagnostic(text, &err)).collect(),
+    };
+    diagnostics
+}
+
+/// Convert a validation error for the JSON document in `text` into a
+/// diagnostic at the location of the offending value.
+///
+/// This is useful for errors from other sources than `diagnostics`, e.g.
+/// from `Config::validate_schema` with the text of the schema.
+pub fn to_diagnostic(text: &str, error: &ValidationError) -> Diagnostic {
+    let (start, end) = locate(text, &error.instance_path()).unwrap_or((0, 0));
+    Diagnostic {
+        range: Range {
+            start: position(text, start),
+            end: position(text, end),
+        },
+        severity: Severity::Error,
+        code: error.keyword().map(str::to_string),
+        code_description: error.docs_url().map(str::to_string),
+        message: error.message().to_string(),
+    }
+}
+
+/// The byte offsets at which each line of `text` starts.
+fn line_offsets(text: &str) -> Vec<usize> {
+    let mut offsets = vec![0];
+    offsets.extend(text.match_indices('\n').map(|(index, _)| index + 1));
+    offsets
+}
+
+/// Convert a byte offset in `text` into a position.
+fn position(text: &str, offset: usize) -> Position {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+/// Find the byte range of the value at `path` (the unescaped tokens of a
+/// JSON pointer) in the JSON document `text`. For objects and arrays, the
+/// range only covers the opening bracket.
+pub(crate) fn locate(text: &str, path: &[&str]) -> Option<(usize, usize)> {
+    let mut scanner = seek(text, path)?;
+    let start = scanner.pos;
+    match scanner.peek()? {
+        b'{' | b'[' => Some((start, start + 1)),
+        _ => {
+            scanner.skip_value()?;
+            Some((start, scanner.pos))
+        }
+    }
+}
+
+/// Find the byte range of the whole value at `path` in the JSON document
+/// `text`, without parsing the values around it.
+pub(crate) fn span(text: &str, path: &[&str]) -> Option<(usize, usize)> {
+    let mut scanner = seek(text, path)?;
+    let start = scanner.pos;
+    scanner.skip_value()?;
+    Some((start, scanner.pos))
+}
+
+/// Move a scanner over `text` to the start of the value at `path`.
+fn seek<'a>(text: &'a str, path: &[&str]) -> Option<Scanner<'a>> {
+    let mut scanner = Scanner {
+        bytes: text.as_bytes(),
+        pos: 0,
+    };
+    for token in path {
+        scanner.skip_whitespace();
+        match scanner.peek()? {
+            b'{' => {
+                scanner.pos += 1;
+                loop {
+                    scanner.skip_whitespace();
+                    if scanner.peek()? != b'"' {
+                        return None;
+                    }
+                    let key_start = scanner.pos;
+                    scanner.skip_string()?;
+                    let key: String = serde_json::from_str(&text[key_start..scanner.pos]).ok()?;
+                    scanner.skip_whitespace();
+                    scanner.expect(b':')?;
+                    if key == *token {
+                        break;
+                    }
+                    scanner.skip_value()?;
+                    scanner.skip_whitespace();
+                    scanner.expect(b',')?;
+                }
+            }
+            b'[' => {
+                scanner.pos += 1;
+                let index: usize = token.parse().ok()?;
+                for _ in 0..index {
+                    scanner.skip_value()?;
+                    scanner.skip_whitespace();
+                    scanner.expect(b',')?;
+                }
+            }
+            _ => return None,
+        }
+    }
+    scanner.skip_whitespace();
+    Some(scanner)
+}
+
+/// A minimal scanner over a JSON document that is known to be valid.
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        if self.peek()? == byte {
+            self.pos += 1;
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_string(&mut self) -> Option<()> {
+        self.expect(b'"')?;
+        loop {
+            match self.peek()? {
+                b'"' => {
+                    self.pos += 1;
+                    return Some(());
+                }
+                b'\\' => self.pos += 2,
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    fn skip_value(&mut self) -> Option<()> {
+        self.skip_whitespace();
+        match self.peek()? {
+            b'"' => self.skip_string(),
+            b'{' | b'[' => {
+                let mut depth = 0;
+                loop {
+                    match self.peek()? {
+                        b'"' => {
+                            self.skip_string()?;
+                            continue;
+                        }
+                        b'{' | b'[' => depth += 1,
+                        b'}' | b']' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                self.pos += 1;
+                                return Some(());
+                            }
+                        }
+                        _ => {}
+                    }
+                    self.pos += 1;
+                }
+            }
+            _ => {
+                while let Some(byte) = self.peek() {
+                    if byte == b',' || byte == b'}' || byte == b']' || byte.is_ascii_whitespace() {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+                Some(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locate() {
+        let text = r#"{"a\"b": [1, {"x": "ü"}, "€x"], "c": {}}"#;
+        assert_eq!(locate(text, &[]), Some((0, 1)));
+        assert_eq!(locate(text, &["c"]), Some((40, 41)));
+        assert_eq!(locate(text, &["a\"b", "2"]), Some((26, 32)));
+        assert_eq!(&text[19..23], "\"ü\"");
+        assert_eq!(locate(text, &["a\"b", "1", "x"]), Some((19, 23)));
+        assert_eq!(locate(text, &["a\"b", "3"]), None);
+        // "€" is a single UTF-16 code unit, but three bytes.
+        assert_eq!(
+            position(text, 32),
+            Position {
+                line: 0,
+                character: 29
+            }
+        );
+    }
+}
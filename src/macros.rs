@@ -0,0 +1,547 @@
+//! Support for the `schema!` macro.
+//!
+//! The macro checks the schema while the crate using it is compiled, by
+//! evaluating `check` on the text of the schema in a constant. `check` is a
+//! small JSON parser that only uses what's allowed in `const fn`s, and walks
+//! the schema knowing which keyword values are subschemas.
+
+use crate::lint::OTHER_KEYWORDS;
+use crate::schemas::{DRAFT4_KEYWORDS, DRAFT6_KEYWORDS, DRAFT7_KEYWORDS};
+
+pub use serde_json::json;
+
+/// Build a schema as a `serde_json::Value`, like `serde_json::json!`, while
+/// checking at compile time that its keywords exist and their values have
+/// the right types.
+///
+/// The keywords are those of the draft named by the `$schema` member of
+/// the schema, or Draft 7 if there is none, along with annotations such as
+/// `title` and `definitions`, and extensions starting with `x-`. Only
+/// literal keys and values can be checked, so values that are Rust
+/// expressions are accepted as they are.
+///
+/// ## Example:
+///
+/// ```rust
+/// # use serde_json::json;
+/// use jsonschema_valid::schema;
+///
+/// let max = 3;
+/// let schema = schema!({
+///     "type": "object",
+///     "patternProperties": {"^x": {"type": "string", "maxLength": max}},
+///     "x-owner": "billing"
+/// });
+/// assert_eq!(schema["patternProperties"]["^x"]["maxLength"], json!(3));
+/// ```
+///
+/// A misspelled keyword fails to compile:
+///
+/// ```rust,compile_fail
+/// use jsonschema_valid::schema;
+///
+/// let schema = schema!({"type": "object", "pattrnProperties": {}});
+/// ```
+///
+/// As does a keyword with a value of the wrong type:
+///
+/// ```rust,compile_fail
+/// use jsonschema_valid::schema;
+///
+/// let schema = schema!({"properties": {"a": {"minLength": "1"}}});
+/// ```
+#[macro_export]
+macro_rules! schema {
+    ($($schema:tt)+) => {{
+        const _: () = $crate::macros::check(stringify!($($schema)+));
+        $crate::macros::json!($($schema)+)
+    }};
+}
+
+/// What the value of a keyword must be.
+#[derive(Clone, Copy)]
+enum Expect {
+    Schema,
+    SchemaOrSchemas,
+    Schemas,
+    SchemaMap,
+    Dependencies,
+    String,
+    Number,
+    NumberOrBool,
+    Bool,
+    Array,
+    Type,
+    Any,
+}
+
+/// The kinds of values in the text of a schema. Rust expressions can't be
+/// checked.
+#[derive(Clone, Copy)]
+enum Kind {
+    Object,
+    Array,
+    String,
+    Number,
+    Bool,
+    Null,
+    Expr,
+}
+
+const EXPECTATIONS: &[(&str, Expect)] = &[
+    ("$comment", Expect::String),
+    ("$defs", Expect::SchemaMap),
+    ("$id", Expect::String),
+    ("$ref", Expect::String),
+    ("$schema", Expect::String),
+    ("additionalItems", Expect::Schema),
+    ("additionalProperties", Expect::Schema),
+    ("allOf", Expect::Schemas),
+    ("anyOf", Expect::Schemas),
+    ("const", Expect::Any),
+    ("contains", Expect::Schema),
+    ("contentEncoding", Expect::String),
+    ("contentMediaType", Expect::String),
+    ("default", Expect::Any),
+    ("definitions", Expect::SchemaMap),
+    ("dependencies", Expect::Dependencies),
+    ("description", Expect::String),
+    ("else", Expect::Schema),
+    ("enum", Expect::Array),
+    ("examples", Expect::Array),
+    ("exclusiveMaximum", Expect::NumberOrBool),
+    ("exclusiveMinimum", Expect::NumberOrBool),
+    ("format", Expect::String),
+    ("id", Expect::String),
+    ("if", Expect::Schema),
+    ("items", Expect::SchemaOrSchemas),
+    ("maxItems", Expect::Number),
+    ("maxLength", Expect::Number),
+    ("maxProperties", Expect::Number),
+    ("maximum", Expect::Number),
+    ("minItems", Expect::Number),
+    ("minLength", Expect::Number),
+    ("minProperties", Expect::Number),
+    ("minimum", Expect::Number),
+    ("multipleOf", Expect::Number),
+    ("not", Expect::Schema),
+    ("oneOf", Expect::Schemas),
+    ("pattern", Expect::String),
+    ("patternProperties", Expect::SchemaMap),
+    ("properties", Expect::SchemaMap),
+    ("propertyNames", Expect::Schema),
+    ("readOnly", Expect::Bool),
+    ("required", Expect::Array),
+    ("then", Expect::Schema),
+    ("title", Expect::String),
+    ("type", Expect::Type),
+    ("uniqueItems", Expect::Bool),
+    ("writeOnly", Expect::Bool),
+];
+
+/// Fail compilation with a message made of `parts`.
+const fn fail(parts: &[&[u8]]) -> ! {
+    let mut message = [0u8; 256];
+    let mut len = 0;
+    let mut i = 0;
+    while i < parts.len() {
+        let mut j = 0;
+        while j < parts[i].len() && len < message.len() {
+            message[len] = parts[i][j];
+            len += 1;
+            j += 1;
+        }
+        i += 1;
+    }
+    let (message, _) = message.split_at(len);
+    match core::str::from_utf8(message) {
+        Ok(message) => panic!("{}", message),
+        Err(_) => panic!("invalid schema"),
+    }
+}
+
+const fn slice(b: &[u8], start: usize, end: usize) -> &[u8] {
+    let (head, _) = b.split_at(end);
+    let (_, result) = head.split_at(start);
+    result
+}
+
+const fn equals(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    let mut i = 0;
+    while i + needle.len() <= haystack.len() {
+        if equals(slice(haystack, i, i + needle.len()), needle) {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+const fn is_one_of(key: &[u8], keywords: &[&str]) -> bool {
+    let mut i = 0;
+    while i < keywords.len() {
+        if equals(key, keywords[i].as_bytes()) {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+const fn skip_ws(b: &[u8], mut i: usize) -> usize {
+    while i < b.len() && b[i].is_ascii_whitespace() {
+        i += 1;
+    }
+    i
+}
+
+/// The end of the string, raw string or character literal at `i`, or `i`
+/// if there is none.
+const fn skip_literal(b: &[u8], i: usize) -> usize {
+    if i >= b.len() {
+        return i;
+    }
+    if b[i] == b'"' {
+        let mut j = i + 1;
+        while j < b.len() && b[j] != b'"' {
+            if b[j] == b'\\' {
+                j += 1;
+            }
+            j += 1;
+        }
+        return j + 1;
+    }
+    if b[i] == b'r' && i + 1 < b.len() && (b[i + 1] == b'"' || b[i + 1] == b'#') {
+        let mut hashes = 0;
+        let mut j = i + 1;
+        while j < b.len() && b[j] == b'#' {
+            hashes += 1;
+            j += 1;
+        }
+        if j >= b.len() || b[j] != b'"' {
+            return i;
+        }
+        j += 1;
+        while j < b.len() {
+            if b[j] == b'"' {
+                let mut k = 0;
+                while k < hashes && j + 1 + k < b.len() && b[j + 1 + k] == b'#' {
+                    k += 1;
+                }
+                if k == hashes {
+                    return j + 1 + hashes;
+                }
+            }
+            j += 1;
+        }
+        return j;
+    }
+    if b[i] == b'\'' && i + 2 < b.len() {
+        if b[i + 1] == b'\\' {
+            let mut j = i + 2;
+            while j < b.len() && b[j] != b'\'' {
+                j += 1;
+            }
+            return j + 1;
+        }
+        if b[i + 2] == b'\'' {
+            return i + 3;
+        }
+    }
+    i
+}
+
+/// The end of the value starting at `i`: the next `,` or closing bracket
+/// outside of brackets and literals, or `stop` at the top level.
+const fn value_end(b: &[u8], mut i: usize, stop: u8) -> usize {
+    let mut depth = 0;
+    while i < b.len() {
+        let end = skip_literal(b, i);
+        if end > i {
+            i = end;
+            continue;
+        }
+        match b[i] {
+            b'{' | b'[' | b'(' => depth += 1,
+            b'}' | b']' | b')' if depth == 0 => return i,
+            b'}' | b']' | b')' => depth -= 1,
+            b',' if depth == 0 => return i,
+            x if x == stop && depth == 0 => return i,
+            _ => {}
+        }
+        i += 1;
+    }
+    i
+}
+
+const fn trim_end(b: &[u8], start: usize, mut end: usize) -> usize {
+    while end > start && b[end - 1].is_ascii_whitespace() {
+        end -= 1;
+    }
+    end
+}
+
+const fn is_number(b: &[u8], mut i: usize, end: usize) -> bool {
+    if b[i] == b'-' {
+        i = skip_ws(b, i + 1);
+    }
+    if i >= end || !b[i].is_ascii_digit() {
+        return false;
+    }
+    while i < end {
+        let c = b[i];
+        let exponent_sign = (c == b'-' || c == b'+') && (b[i - 1] == b'e' || b[i - 1] == b'E');
+        if !(c.is_ascii_alphanumeric() || c == b'.' || c == b'_' || exponent_sign) {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn classify(b: &[u8], start: usize, end: usize) -> Kind {
+    if start >= end {
+        return Kind::Expr;
+    }
+    let value = slice(b, start, end);
+    match b[start] {
+        b'{' if matching(b, start) == end => Kind::Object,
+        b'[' if matching(b, start) == end => Kind::Array,
+        _ if skip_literal(b, start) == end && b[start] != b'\'' => Kind::String,
+        _ if equals(value, b"true") || equals(value, b"false") => Kind::Bool,
+        _ if equals(value, b"null") => Kind::Null,
+        _ if is_number(b, start, end) => Kind::Number,
+        _ => Kind::Expr,
+    }
+}
+
+/// The end of the brackets starting at `start`, after the closing bracket.
+const fn matching(b: &[u8], start: usize) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+    while i < b.len() {
+        let end = skip_literal(b, i);
+        if end > i {
+            i = end;
+            continue;
+        }
+        match b[i] {
+            b'{' | b'[' | b'(' => depth += 1,
+            b'}' | b']' | b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    i
+}
+
+/// The contents of the string literal at `start` to `end`, without quotes.
+const fn string_contents(b: &[u8], start: usize, end: usize) -> &[u8] {
+    let mut start = start;
+    let mut end = end;
+    if b[start] == b'r' {
+        start += 1;
+        while b[start] == b'#' {
+            start += 1;
+            end -= 1;
+        }
+    }
+    slice(b, start + 1, end - 1)
+}
+
+const fn expectation(key: &[u8], draft: u8) -> Option<Expect> {
+    let known = match draft {
+        4 => is_one_of(key, DRAFT4_KEYWORDS),
+        6 => is_one_of(key, DRAFT6_KEYWORDS),
+        _ => is_one_of(key, DRAFT7_KEYWORDS),
+    };
+    if !known && !is_one_of(key, OTHER_KEYWORDS) {
+        return None;
+    }
+    let mut i = 0;
+    while i < EXPECTATIONS.len() {
+        if equals(key, EXPECTATIONS[i].0.as_bytes()) {
+            return Some(EXPECTATIONS[i].1);
+        }
+        i += 1;
+    }
+    Some(Expect::Any)
+}
+
+const fn check_value(b: &[u8], start: usize, end: usize, expect: Expect, key: &[u8], draft: u8) {
+    let kind = classify(b, start, end);
+    if let Kind::Expr = kind {
+        return;
+    }
+    let (ok, expected): (bool, &[u8]) = match expect {
+        Expect::Schema => (
+            matches!(kind, Kind::Object) || (draft >= 6 && matches!(kind, Kind::Bool)),
+            b"a schema",
+        ),
+        Expect::SchemaOrSchemas => (
+            matches!(kind, Kind::Object | Kind::Array)
+                || (draft >= 6 && matches!(kind, Kind::Bool)),
+            b"a schema or an array of schemas",
+        ),
+        Expect::Schemas => (matches!(kind, Kind::Array), b"an array of schemas"),
+        Expect::SchemaMap | Expect::Dependencies => (matches!(kind, Kind::Object), b"an object"),
+        Expect::String => (matches!(kind, Kind::String), b"a string"),
+        Expect::Number => (matches!(kind, Kind::Number), b"a number"),
+        Expect::NumberOrBool if draft == 4 => (matches!(kind, Kind::Bool), b"a boolean"),
+        Expect::NumberOrBool => (matches!(kind, Kind::Number), b"a number"),
+        Expect::Bool => (matches!(kind, Kind::Bool), b"a boolean"),
+        Expect::Array => (matches!(kind, Kind::Array), b"an array"),
+        Expect::Type => (
+            matches!(kind, Kind::String | Kind::Array),
+            b"a string or an array",
+        ),
+        Expect::Any => (true, b""),
+    };
+    if !ok {
+        fail(&[b"schema!: the value of `", key, b"` must be ", expected]);
+    }
+    match (expect, kind) {
+        (Expect::Schema, Kind::Object) | (Expect::SchemaOrSchemas, Kind::Object) => {
+            check_schema(b, start, draft)
+        }
+        (Expect::Schemas, Kind::Array) | (Expect::SchemaOrSchemas, Kind::Array) => {
+            let mut i = skip_ws(b, start + 1);
+            while i < b.len() && b[i] != b']' {
+                let end = value_end(b, i, 0);
+                check_value(b, i, trim_end(b, i, end), Expect::Schema, key, draft);
+                i = skip_ws(b, if b[end] == b',' { end + 1 } else { end });
+            }
+        }
+        (Expect::SchemaMap, Kind::Object) | (Expect::Dependencies, Kind::Object) => {
+            let mut i = skip_ws(b, start + 1);
+            while i < b.len() && b[i] != b'}' {
+                let colon = value_end(b, i, b':');
+                let value = skip_ws(b, colon + 1);
+                let end = value_end(b, value, 0);
+                let value_end = trim_end(b, value, end);
+                match expect {
+                    Expect::Dependencies => {
+                        if let Kind::Object = classify(b, value, value_end) {
+                            check_value(b, value, value_end, Expect::Schema, key, draft)
+                        }
+                    }
+                    _ => check_value(b, value, value_end, Expect::Schema, key, draft),
+                }
+                i = skip_ws(b, if b[end] == b',' { end + 1 } else { end });
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Check the schema object starting at `start`.
+const fn check_schema(b: &[u8], start: usize, draft: u8) {
+    let mut i = skip_ws(b, start + 1);
+    while i < b.len() && b[i] != b'}' {
+        let colon = value_end(b, i, b':');
+        let key_end = trim_end(b, i, colon);
+        let value = skip_ws(b, colon + 1);
+        let end = value_end(b, value, 0);
+        if let Kind::String = classify(b, i, key_end) {
+            let key = string_contents(b, i, key_end);
+            let extension = key.len() >= 2 && key[0] == b'x' && key[1] == b'-';
+            if !extension {
+                match expectation(key, draft) {
+                    Some(expect) => {
+                        check_value(b, value, trim_end(b, value, end), expect, key, draft)
+                    }
+                    None => fail(&[b"schema!: unknown keyword `", key, b"`"]),
+                }
+            }
+        }
+        i = skip_ws(
+            b,
+            if end < b.len() && b[end] == b',' {
+                end + 1
+            } else {
+                end
+            },
+        );
+    }
+}
+
+/// The draft named by the `$schema` member of the schema object starting
+/// at `start`, or 7.
+const fn draft_of(b: &[u8], start: usize) -> u8 {
+    let mut i = skip_ws(b, start + 1);
+    while i < b.len() && b[i] != b'}' {
+        let colon = value_end(b, i, b':');
+        let key_end = trim_end(b, i, colon);
+        let value = skip_ws(b, colon + 1);
+        let end = value_end(b, value, 0);
+        if let Kind::String = classify(b, i, key_end) {
+            if equals(string_contents(b, i, key_end), b"$schema") {
+                let url = slice(b, value, end);
+                if contains(url, b"draft-04") {
+                    return 4;
+                } else if contains(url, b"draft-06") {
+                    return 6;
+                }
+            }
+        }
+        i = skip_ws(
+            b,
+            if end < b.len() && b[end] == b',' {
+                end + 1
+            } else {
+                end
+            },
+        );
+    }
+    7
+}
+
+/// Check the schema in `text`, the source of the argument of `schema!`,
+/// failing compilation if it has unknown keywords or keyword values of the
+/// wrong type.
+pub const fn check(text: &str) {
+    let b = text.as_bytes();
+    let start = skip_ws(b, 0);
+    let end = trim_end(b, start, b.len());
+    if let Kind::Object = classify(b, start, end) {
+        check_schema(b, start, draft_of(b, start));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    #[test]
+    fn test_schema() {
+        let name = "n";
+        let schema = schema!({
+            "$schema": "http://json-schema.org/draft-04/schema#",
+            "definitions": {"a": {"type": ["string", "null"], "pattern": r#"^\d,"x"$"#}},
+            "properties": {name: {"$ref": "#/definitions/a"}, "b": {"exclusiveMinimum": true}},
+            "dependencies": {"a": ["b"], "b": {"required": ["a"]}},
+            "items": [{"enum": [1, "x", {"maxLength": "y"}]}, {"minimum": -1.5e-3}],
+            "allOf": [{}, {"not": {"format": "date"}}],
+        });
+        assert_eq!(schema["items"][1], json!({"minimum": -1.5e-3}));
+    }
+}
@@ -0,0 +1,599 @@
+//! Flattening of `allOf` into a single schema object.
+//!
+//! Schemas are often authored as an `allOf` over a base schema and a number
+//! of refinements. Each branch of an `allOf` has to be evaluated separately
+//! against the instance, so flattening compatible branches into their parent
+//! both makes the schema easier to read and cheaper to validate against.
+//!
+//! Branches that can't be merged without changing the meaning of the schema
+//! (for example, branches containing a `$ref` or `additionalProperties` that
+//! depend on the properties defined next to them) are left in a residual
+//! `allOf`. So are branches that a local `$ref` points into, since moving
+//! their content would leave the reference dangling.
+
+use std::error::Error;
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use crate::util;
+
+/// An error that can occur when merging `allOf` branches.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeError {
+    /// The branches of an `allOf` contradict each other, so no instance can
+    /// ever be valid against the schema.
+    Unsatisfiable {
+        /// JSON pointer to the schema containing the `allOf`.
+        pointer: String,
+        /// The keyword whose values contradict each other.
+        keyword: String,
+    },
+    /// The value of `allOf` is not an array of schemas.
+    InvalidAllOf {
+        /// JSON pointer to the invalid `allOf`.
+        pointer: String,
+    },
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MergeError::Unsatisfiable { pointer, keyword } => write!(
+                f,
+                "allOf at {} can never be satisfied: conflicting {}",
+                path_or_root(pointer),
+                keyword
+            ),
+            MergeError::InvalidAllOf { pointer } => write!(
+                f,
+                "allOf at {} must be an array of schemas",
+                path_or_root(pointer)
+            ),
+        }
+    }
+}
+
+impl Error for MergeError {}
+
+fn path_or_root(pointer: &str) -> &str {
+    if pointer.is_empty() {
+        "/"
+    } else {
+        pointer
+    }
+}
+
+/// Combine the compatible branches of every `allOf` in the given schema into
+/// their parent schema object.
+///
+/// Bounds are intersected (the strictest `minimum`, `maxLength` etc. wins),
+/// `required` and `definitions` are unioned and `properties` are merged
+/// recursively. Branches that can't be merged safely are kept in a (smaller)
+/// `allOf`.
+///
+/// # Returns
+///
+/// * `Ok(schema)`: The merged schema, which is equivalent to the input.
+/// * `Err(err)`: The branches of an `allOf` contradict each other, or the
+///   `allOf` is malformed.
+///
+/// ## Example:
+///
+/// ```rust
+/// # use serde_json::json;
+/// let schema = json!({
+///     "allOf": [
+///         {"type": "object", "required": ["a"], "properties": {"a": {"minimum": 0}}},
+///         {"required": ["b"], "properties": {"a": {"maximum": 10}}}
+///     ]
+/// });
+/// assert_eq!(
+///     jsonschema_valid::merge_allOf(&schema).unwrap(),
+///     json!({
+///         "type": "object",
+///         "required": ["a", "b"],
+///         "properties": {"a": {"minimum": 0, "maximum": 10}}
+///     })
+/// );
+/// ```
+#[allow(non_snake_case)]
+pub fn merge_allOf(schema: &Value) -> Result<Value, MergeError> {
+    let mut targets = Vec::new();
+    util::local_ref_targets(schema, &mut targets);
+    let mut result = schema.clone();
+    flatten(&mut result, &mut String::new(), &targets)?;
+    Ok(result)
+}
+
+fn flatten(schema: &mut Value, pointer: &mut String, targets: &[String]) -> Result<(), MergeError> {
+    let mut result = Ok(());
+    util::for_each_subschema_mut(schema, &mut |path, subschema| {
+        if result.is_ok() {
+            let len = pointer.len();
+            pointer.push('/');
+            pointer.push_str(&path);
+            result = flatten(subschema, pointer, targets);
+            pointer.truncate(len);
+        }
+    });
+    result?;
+
+    if let Value::Object(object) = schema {
+        if let Some(all_of) = object.remove("allOf") {
+            let branches = match all_of {
+                Value::Array(branches) => branches,
+                _ => {
+                    return Err(MergeError::InvalidAllOf {
+                        pointer: pointer.clone(),
+                    })
+                }
+            };
+            let pinned = pinned_branches(targets, pointer, branches.len());
+            let mut residue = Vec::new();
+            for (index, branch) in branches.into_iter().enumerate() {
+                if index < pinned {
+                    residue.push(branch);
+                    continue;
+                }
+                match branch {
+                    Value::Bool(true) => {}
+                    Value::Bool(false) => {
+                        return Err(MergeError::Unsatisfiable {
+                            pointer: pointer.clone(),
+                            keyword: "allOf".to_string(),
+                        })
+                    }
+                    Value::Object(branch_object) => {
+                        let merged = if is_target(targets, pointer, &branch_object) {
+                            None
+                        } else {
+                            merge_objects(object, &branch_object, pointer)?
+                        };
+                        if let Some(mut merged) = merged {
+                            // What is left of the branch's own allOf after
+                            // flattening it is kept along with the rest.
+                            if let Some(Value::Array(nested)) = merged.remove("allOf") {
+                                residue.extend(nested);
+                            }
+                            *object = merged;
+                        } else {
+                            residue.push(Value::Object(branch_object));
+                        }
+                    }
+                    _ => {
+                        return Err(MergeError::InvalidAllOf {
+                            pointer: pointer.clone(),
+                        })
+                    }
+                }
+            }
+            if !residue.is_empty() {
+                object.insert("allOf".to_string(), Value::Array(residue));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The number of leading branches of the `allOf` at `pointer` that have to
+/// stay where they are, because a local `$ref` points into one of them.
+fn pinned_branches(targets: &[String], pointer: &str, len: usize) -> usize {
+    let all_of = format!("{}/allOf", pointer);
+    targets
+        .iter()
+        .filter_map(|target| {
+            let rest = target.strip_prefix(&all_of)?;
+            if rest.is_empty() {
+                return Some(len);
+            }
+            let index: usize = rest.strip_prefix('/')?.split('/').next()?.parse().ok()?;
+            Some(index + 1)
+        })
+        .max()
+        .unwrap_or(0)
+        .min(len)
+}
+
+/// Does a local `$ref` point into a keyword of the schema at `pointer` that
+/// merging `branch` would change?
+fn is_target(targets: &[String], pointer: &str, branch: &Map<String, Value>) -> bool {
+    branch.keys().any(|key| {
+        let prefix = format!("{}/{}", pointer, util::escape_pointer_token(key));
+        targets
+            .iter()
+            .any(|target| target == &prefix || target.starts_with(&format!("{}/", prefix)))
+    })
+}
+
+/// Intersect two schemas, returning a schema equivalent to validating
+/// against both. Returns `Ok(None)` if the schemas can't be combined into a
+/// single schema object.
+pub(crate) fn merge_schemas(
+    a: &Value,
+    b: &Value,
+    pointer: &str,
+) -> Result<Option<Value>, MergeError> {
+    match (a, b) {
+        (Value::Bool(true), other) | (other, Value::Bool(true)) => Ok(Some(other.clone())),
+        (Value::Bool(false), _) | (_, Value::Bool(false)) => Ok(Some(Value::Bool(false))),
+        (Value::Object(a), Value::Object(b)) => {
+            Ok(merge_objects(a, b, pointer)?.map(Value::Object))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Keywords that change how sibling or nested keywords are interpreted, so a
+/// branch containing them is never merged.
+const UNMERGEABLE: &[&str] = &["$ref", "$id", "id", "$schema", "if", "then", "else"];
+
+/// Keywords that are only annotations; the first value seen wins.
+const ANNOTATIONS: &[&str] = &[
+    "$comment",
+    "default",
+    "description",
+    "examples",
+    "readOnly",
+    "title",
+    "writeOnly",
+];
+
+fn merge_objects(
+    a: &Map<String, Value>,
+    b: &Map<String, Value>,
+    pointer: &str,
+) -> Result<Option<Map<String, Value>>, MergeError> {
+    if UNMERGEABLE
+        .iter()
+        .any(|k| b.contains_key(*k) || (a.contains_key(*k) && *k == "$ref"))
+    {
+        return Ok(None);
+    }
+    if !additional_compatible(a, b, "additionalProperties", same_properties)
+        || !additional_compatible(a, b, "additionalItems", same_items)
+        || !exclusive_bounds_compatible(a, b)
+    {
+        return Ok(None);
+    }
+
+    let mut result = a.clone();
+    for (key, b_value) in b {
+        let a_value = match a.get(key) {
+            Some(a_value) => a_value,
+            None => {
+                result.insert(key.clone(), b_value.clone());
+                continue;
+            }
+        };
+        if a_value == b_value || ANNOTATIONS.contains(&key.as_str()) {
+            continue;
+        }
+        let unsatisfiable = || MergeError::Unsatisfiable {
+            pointer: pointer.to_string(),
+            keyword: key.clone(),
+        };
+        let merged = match key.as_str() {
+            "type" => {
+                let types = intersect_types(a_value, b_value);
+                if types.is_empty() {
+                    return Err(unsatisfiable());
+                } else if types.len() == 1 {
+                    Value::String(types[0].to_string())
+                } else {
+                    Value::Array(types.into_iter().map(Value::from).collect())
+                }
+            }
+            "enum" => match (a_value, b_value) {
+                (Value::Array(a_enum), Value::Array(b_enum)) => {
+                    let both: Vec<Value> = a_enum
+                        .iter()
+                        .filter(|x| b_enum.contains(x))
+                        .cloned()
+                        .collect();
+                    if both.is_empty() {
+                        return Err(unsatisfiable());
+                    }
+                    Value::Array(both)
+                }
+                _ => return Ok(None),
+            },
+            "const" => return Err(unsatisfiable()),
+            "required" => match (a_value, b_value) {
+                (Value::Array(a_required), Value::Array(b_required)) => {
+                    let mut union = a_required.clone();
+                    union.extend(
+                        b_required
+                            .iter()
+                            .filter(|x| !a_required.contains(x))
+                            .cloned(),
+                    );
+                    Value::Array(union)
+                }
+                _ => return Ok(None),
+            },
+            "minimum" | "exclusiveMinimum" | "minLength" | "minItems" | "minProperties" => {
+                match numeric_bound(a_value, b_value, true) {
+                    Some(x) => x,
+                    None => return Ok(None),
+                }
+            }
+            "maximum" | "exclusiveMaximum" | "maxLength" | "maxItems" | "maxProperties" => {
+                match numeric_bound(a_value, b_value, false) {
+                    Some(x) => x,
+                    None => return Ok(None),
+                }
+            }
+            "multipleOf" => match (a_value.as_f64(), b_value.as_f64()) {
+                (Some(x), Some(y)) if (x / y).fract() == 0.0 => a_value.clone(),
+                (Some(x), Some(y)) if (y / x).fract() == 0.0 => b_value.clone(),
+                _ => return Ok(None),
+            },
+            "uniqueItems" => Value::Bool(
+                a_value.as_bool().unwrap_or(false) || b_value.as_bool().unwrap_or(false),
+            ),
+            "not" => serde_json::json!({"anyOf": [a_value, b_value]}),
+            "definitions" | "$defs" => match (a_value, b_value) {
+                (Value::Object(a_definitions), Value::Object(b_definitions)) => {
+                    let mut union = a_definitions.clone();
+                    for (name, b_schema) in b_definitions {
+                        match a_definitions.get(name) {
+                            Some(a_schema) if a_schema != b_schema => return Ok(None),
+                            _ => {
+                                union.insert(name.clone(), b_schema.clone());
+                            }
+                        }
+                    }
+                    Value::Object(union)
+                }
+                _ => return Ok(None),
+            },
+            "properties" | "patternProperties" => {
+                match merge_schema_maps(a_value, b_value, &format!("{}/{}", pointer, key))? {
+                    Some(x) => x,
+                    None => return Ok(None),
+                }
+            }
+            "items" | "additionalItems" | "additionalProperties" | "propertyNames" => {
+                if a_value.is_array() || b_value.is_array() {
+                    return Ok(None);
+                }
+                match merge_schemas(a_value, b_value, &format!("{}/{}", pointer, key))? {
+                    Some(x) => x,
+                    None => return Ok(None),
+                }
+            }
+            _ => return Ok(None),
+        };
+        result.insert(key.clone(), merged);
+    }
+
+    for (min, max) in &[
+        ("minimum", "maximum"),
+        ("minLength", "maxLength"),
+        ("minItems", "maxItems"),
+        ("minProperties", "maxProperties"),
+    ] {
+        if let (Some(low), Some(high)) = (
+            result.get(*min).and_then(Value::as_f64),
+            result.get(*max).and_then(Value::as_f64),
+        ) {
+            if low > high {
+                return Err(MergeError::Unsatisfiable {
+                    pointer: pointer.to_string(),
+                    keyword: (*max).to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(Some(result))
+}
+
+/// `additionalProperties` and `additionalItems` are relative to the
+/// properties/items defined next to them, so they may only be merged if those
+/// stay the same.
+fn additional_compatible(
+    a: &Map<String, Value>,
+    b: &Map<String, Value>,
+    keyword: &str,
+    same: fn(&Map<String, Value>, &Map<String, Value>) -> bool,
+) -> bool {
+    !(a.contains_key(keyword) || b.contains_key(keyword)) || same(a, b)
+}
+
+/// In draft 4, a boolean `exclusiveMinimum` or `exclusiveMaximum` changes the
+/// meaning of the `minimum` or `maximum` next to it, so those bounds may only
+/// be merged if just one side has them.
+fn exclusive_bounds_compatible(a: &Map<String, Value>, b: &Map<String, Value>) -> bool {
+    [
+        ("minimum", "exclusiveMinimum"),
+        ("maximum", "exclusiveMaximum"),
+    ]
+    .iter()
+    .all(|(bound, exclusive)| {
+        let boolean = |x: &Map<String, Value>| matches!(x.get(*exclusive), Some(Value::Bool(_)));
+        let bounded = |x: &Map<String, Value>| x.contains_key(*bound) || x.contains_key(*exclusive);
+        !(boolean(a) && bounded(b) || boolean(b) && bounded(a))
+    })
+}
+
+fn key_set(schema: &Map<String, Value>, keyword: &str) -> Vec<String> {
+    schema
+        .get(keyword)
+        .and_then(Value::as_object)
+        .map(|x| x.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn same_properties(a: &Map<String, Value>, b: &Map<String, Value>) -> bool {
+    key_set(a, "properties") == key_set(b, "properties")
+        && key_set(a, "patternProperties") == key_set(b, "patternProperties")
+}
+
+fn same_items(a: &Map<String, Value>, b: &Map<String, Value>) -> bool {
+    match (a.get("items"), b.get("items")) {
+        (Some(Value::Array(x)), Some(Value::Array(y))) => x.len() == y.len(),
+        (Some(Value::Array(_)), _) | (_, Some(Value::Array(_))) => false,
+        _ => true,
+    }
+}
+
+fn merge_schema_maps(a: &Value, b: &Value, pointer: &str) -> Result<Option<Value>, MergeError> {
+    if let (Value::Object(a), Value::Object(b)) = (a, b) {
+        let mut result = a.clone();
+        for (name, b_schema) in b {
+            let merged = match a.get(name) {
+                Some(a_schema) => {
+                    let subpointer = format!("{}/{}", pointer, util::escape_pointer_token(name));
+                    match merge_schemas(a_schema, b_schema, &subpointer)? {
+                        Some(x) => x,
+                        None => serde_json::json!({"allOf": [a_schema, b_schema]}),
+                    }
+                }
+                None => b_schema.clone(),
+            };
+            result.insert(name.clone(), merged);
+        }
+        Ok(Some(Value::Object(result)))
+    } else {
+        Ok(None)
+    }
+}
+
+fn numeric_bound(a: &Value, b: &Value, lower: bool) -> Option<Value> {
+    let (x, y) = (a.as_f64()?, b.as_f64()?);
+    if (x > y) == lower {
+        Some(a.clone())
+    } else {
+        Some(b.clone())
+    }
+}
+
+fn type_names(value: &Value) -> Vec<&str> {
+    util::iter_or_once(value)
+        .filter_map(Value::as_str)
+        .collect()
+}
+
+fn intersect_types<'a>(a: &'a Value, b: &'a Value) -> Vec<&'a str> {
+    let (a_types, b_types) = (type_names(a), type_names(b));
+    let mut result = Vec::new();
+    for a_type in &a_types {
+        for b_type in &b_types {
+            let common = match (*a_type, *b_type) {
+                (x, y) if x == y => Some(x),
+                ("integer", "number") | ("number", "integer") => Some("integer"),
+                _ => None,
+            };
+            if let Some(common) = common {
+                if !result.contains(&common) {
+                    result.push(common);
+                }
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_merge_bounds_and_types() {
+        let schema = json!({
+            "allOf": [
+                {"type": ["number", "string"], "minimum": 1, "maximum": 10},
+                {"type": "integer", "minimum": 3}
+            ]
+        });
+        assert_eq!(
+            merge_allOf(&schema).unwrap(),
+            json!({"type": "integer", "minimum": 3, "maximum": 10})
+        );
+    }
+
+    #[test]
+    fn test_unmergeable_branches_are_kept() {
+        let schema = json!({
+            "properties": {"a": true},
+            "additionalProperties": false,
+            "allOf": [{"properties": {"b": true}}, {"$ref": "#/definitions/x"}]
+        });
+        assert_eq!(merge_allOf(&schema).unwrap(), schema);
+    }
+
+    #[test]
+    fn test_nested_residue_is_kept() {
+        let schema = json!({
+            "allOf": [{"allOf": [{"$ref": "#/a"}], "type": "string"}, {"$ref": "#/b"}]
+        });
+        assert_eq!(
+            merge_allOf(&schema).unwrap(),
+            json!({"type": "string", "allOf": [{"$ref": "#/a"}, {"$ref": "#/b"}]})
+        );
+    }
+
+    #[test]
+    fn test_boolean_exclusive_bounds() {
+        let schema = json!({"allOf": [{"minimum": 1, "exclusiveMinimum": true}, {"minimum": 3}]});
+        assert_eq!(
+            merge_allOf(&schema).unwrap(),
+            json!({"minimum": 1, "exclusiveMinimum": true, "allOf": [{"minimum": 3}]})
+        );
+        let schema = json!({"allOf": [{"minimum": 1, "exclusiveMinimum": true}, {"maximum": 3}]});
+        assert_eq!(
+            merge_allOf(&schema).unwrap(),
+            json!({"minimum": 1, "exclusiveMinimum": true, "maximum": 3})
+        );
+    }
+
+    #[test]
+    fn test_definitions_are_unioned() {
+        let schema = json!({
+            "allOf": [
+                {"definitions": {"a": {"type": "string"}}},
+                {"definitions": {"b": {"type": "integer"}}},
+                {"definitions": {"a": {"type": "null"}}}
+            ]
+        });
+        assert_eq!(
+            merge_allOf(&schema).unwrap(),
+            json!({
+                "definitions": {"a": {"type": "string"}, "b": {"type": "integer"}},
+                "allOf": [{"definitions": {"a": {"type": "null"}}}]
+            })
+        );
+    }
+
+    #[test]
+    fn test_ref_targets_are_kept() {
+        let schema = json!({
+            "allOf": [{"minimum": 0}, {"properties": {"a": {"maximum": 9}}}, {"type": "object"}],
+            "properties": {"b": {"$ref": "#/allOf/1/properties/a"}}
+        });
+        assert_eq!(
+            merge_allOf(&schema).unwrap(),
+            json!({
+                "type": "object",
+                "allOf": [{"minimum": 0}, {"properties": {"a": {"maximum": 9}}}],
+                "properties": {"b": {"$ref": "#/allOf/1/properties/a"}}
+            })
+        );
+    }
+
+    #[test]
+    fn test_conflicts_are_reported() {
+        let schema =
+            json!({"properties": {"x": {"allOf": [{"type": "string"}, {"type": "null"}]}}});
+        assert_eq!(
+            merge_allOf(&schema),
+            Err(MergeError::Unsatisfiable {
+                pointer: "/properties/x".to_string(),
+                keyword: "type".to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,164 @@
+//! Validating one instance against a set of named schemas.
+//!
+//! This is useful for classification, e.g. to determine which kind of event a
+//! given message is by checking it against the schema of every known event
+//! type.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! use jsonschema_valid::multi::SchemaSet;
+//!
+//! let click = json!({"properties": {"type": {"const": "click"}}, "required": ["x", "y"]});
+//! let key = json!({"properties": {"type": {"const": "key"}}, "required": ["code"]});
+//!
+//! let set = SchemaSet::new()
+//!     .with_schema("click", &click, None)
+//!     .unwrap()
+//!     .with_schema("key", &key, None)
+//!     .unwrap();
+//!
+//! let report = set.validate(&json!({"type": "click", "x": 1, "y": 2}));
+//! assert_eq!(report.matching().collect::<Vec<_>>(), vec!["click"]);
+//! assert_eq!(report.errors("key").unwrap().len(), 2);
+//! ```
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::cache::content_hash;
+use crate::config::Config;
+use crate::error::ValidationError;
+use crate::schemas;
+
+/// A set of named schemas to validate instances against.
+#[derive(Default)]
+pub struct SchemaSet<'a> {
+    entries: Vec<(String, Config<'a>)>,
+    /// For each entry, the index of the first entry with an identical
+    /// schema and draft, whose results can be reused.
+    same_as: Vec<usize>,
+    hashes: HashMap<(String, &'static str), usize>,
+}
+
+/// The result of validating an instance against each schema in a
+/// [`SchemaSet`](struct.SchemaSet.html).
+#[derive(Debug)]
+pub struct MultiReport {
+    results: Vec<(String, Vec<ValidationError>)>,
+}
+
+impl<'a> SchemaSet<'a> {
+    /// Create a new, empty set of schemas.
+    pub fn new() -> SchemaSet<'a> {
+        SchemaSet::default()
+    }
+
+    /// Add a schema to the set under the given name. `draft` works as in
+    /// `Config::from_schema`.
+    pub fn with_schema(
+        self,
+        name: &str,
+        schema: &'a Value,
+        draft: Option<&'a dyn schemas::Draft>,
+    ) -> Result<Self, ValidationError> {
+        Ok(self.with_config(name, Config::from_schema(schema, draft)?))
+    }
+
+    /// Add an already built `Config` to the set under the given name.
+    pub fn with_config(mut self, name: &str, cfg: Config<'a>) -> Self {
+        let key = (
+            content_hash(cfg.get_schema()),
+            cfg.get_draft().get_dialect(),
+        );
+        let index = self.entries.len();
+        self.same_as.push(*self.hashes.entry(key).or_insert(index));
+        self.entries.push((name.to_string(), cfg));
+        self
+    }
+
+    /// The names of the schemas in the set, in the order they were added.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Validate the instance against every schema in the set.
+    ///
+    /// Schemas that are identical to one added earlier (with the same draft)
+    /// are only evaluated once.
+    pub fn validate(&self, instance: &Value) -> MultiReport {
+        let mut results: Vec<(String, Vec<ValidationError>)> =
+            Vec::with_capacity(self.entries.len());
+        for (index, (name, cfg)) in self.entries.iter().enumerate() {
+            let errors = match self.same_as[index] {
+                same if same != index => results[same].1.clone(),
+                _ => match cfg.validate(instance) {
+                    Ok(()) => Vec::new(),
+                    Err(errors) => errors.collect(),
+                },
+            };
+            results.push((name.clone(), errors));
+        }
+        MultiReport { results }
+    }
+}
+
+impl MultiReport {
+    /// The names of the schemas the instance is valid against, in the order
+    /// they were added to the set.
+    pub fn matching(&self) -> impl Iterator<Item = &str> {
+        self.results
+            .iter()
+            .filter(|(_, errors)| errors.is_empty())
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Whether the instance is valid against the schema with the given name.
+    /// Returns `None` if there's no schema with that name.
+    pub fn is_valid(&self, name: &str) -> Option<bool> {
+        self.errors(name).map(|errors| errors.is_empty())
+    }
+
+    /// The validation errors for the schema with the given name. Returns
+    /// `None` if there's no schema with that name.
+    pub fn errors(&self, name: &str) -> Option<&[ValidationError]> {
+        self.results
+            .iter()
+            .find(|(x, _)| x == name)
+            .map(|(_, errors)| errors.as_slice())
+    }
+
+    /// Iterate over the name and errors of each schema, in the order they
+    /// were added to the set.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[ValidationError])> {
+        self.results
+            .iter()
+            .map(|(name, errors)| (name.as_str(), errors.as_slice()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_identical_schemas_share_results() {
+        let a = json!({"type": "string"});
+        let b = json!({"type": "string"});
+        let set = SchemaSet::new()
+            .with_schema("a", &a, None)
+            .unwrap()
+            .with_schema("b", &b, None)
+            .unwrap();
+        assert_eq!(set.same_as, vec![0, 0]);
+
+        let report = set.validate(&json!(1));
+        assert_eq!(report.is_valid("a"), Some(false));
+        assert_eq!(report.errors("b").unwrap().len(), 1);
+        assert_eq!(report.is_valid("c"), None);
+        assert_eq!(report.matching().count(), 0);
+    }
+}
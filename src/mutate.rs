@@ -0,0 +1,374 @@
+//! Deriving invalid instances from a valid one, one keyword at a time.
+//!
+//! To test how consumers of an API handle rejected input, each way an
+//! instance can be invalid should be tried on its own. `mutations` takes an
+//! instance that is valid against a schema and, for each keyword of the
+//! schema that applies to a value in it, makes a single change that
+//! violates the keyword: removing a required property, going one past a bound, breaking a
+//! pattern, changing a type, and so on. Each mutation is labeled with the
+//! keyword it violates and where, and is only returned once validation has
+//! confirmed that the mutated instance fails that keyword at that location.
+//!
+//! Unlike `generate::Generator::invalid`, the mutations are systematic
+//! rather than random: the same schema and instance always give the same
+//! mutations, in the same order.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::{schemas, Config};
+//! use jsonschema_valid::mutate::mutations;
+//!
+//! let schema = json!({
+//!     "required": ["name"],
+//!     "properties": {"name": {"type": "string", "maxLength": 3}}
+//! });
+//! let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+//!
+//! let mutations = mutations(&cfg, &json!({"name": "abc"}));
+//! let labels: Vec<_> = mutations
+//!     .iter()
+//!     .map(|x| (x.keyword.as_str(), x.instance_path.as_str()))
+//!     .collect();
+//! assert_eq!(labels, [("required", ""), ("type", "/name"), ("maxLength", "/name")]);
+//! assert_eq!(mutations[2].instance, json!({"name": "abcx"}));
+//! assert_eq!(mutations[2].schema_path, "/properties/name/maxLength");
+//! ```
+
+use std::collections::HashSet;
+
+use serde_json::{json, Map, Value};
+
+use crate::config::Config;
+use crate::error::ValidationError;
+use crate::explain::{children, expand};
+use crate::generate::different_type;
+use crate::util;
+
+/// An invalid instance derived from a valid one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mutation {
+    /// The keyword the instance violates.
+    pub keyword: String,
+    /// The location of the changed value in the instance, as a JSON pointer.
+    pub instance_path: String,
+    /// The location of the violated keyword in the schema, as a JSON
+    /// pointer.
+    pub schema_path: String,
+    /// The whole mutated instance.
+    pub instance: Value,
+}
+
+/// Derive an invalid instance from `instance` for each keyword of the schema
+/// in `cfg` that applies to a value in it and can be violated on its own.
+///
+/// `instance` is expected to be valid. Errors it already has are not
+/// reported as mutations.
+pub fn mutations(cfg: &Config, instance: &Value) -> Vec<Mutation> {
+    let existing: HashSet<(String, String)> = errors(cfg, instance)
+        .iter()
+        .map(|err| (pointer(&err.instance_path()), pointer(&err.schema_path())))
+        .collect();
+    let mut subschemas = Vec::new();
+    expand(
+        cfg,
+        instance,
+        String::new(),
+        cfg.get_schema(),
+        0,
+        &mut subschemas,
+    );
+    let mut candidates = Vec::new();
+    find_candidates(
+        cfg,
+        instance,
+        &mut String::new(),
+        subschemas,
+        &mut candidates,
+    );
+
+    let mut seen = HashSet::new();
+    let mut result = Vec::new();
+    for (instance_path, keyword, replacement) in candidates {
+        let mut mutated = instance.clone();
+        match mutated.pointer_mut(&instance_path) {
+            Some(value) => *value = replacement,
+            None => continue,
+        }
+        let schema_path = errors(cfg, &mutated).iter().find_map(|err| {
+            let paths = (pointer(&err.instance_path()), pointer(&err.schema_path()));
+            if err.keyword() == Some(keyword)
+                && paths.0 == instance_path
+                && !existing.contains(&paths)
+            {
+                Some(paths.1)
+            } else {
+                None
+            }
+        });
+        if let Some(schema_path) = schema_path {
+            if seen.insert((schema_path.clone(), instance_path.clone())) {
+                result.push(Mutation {
+                    keyword: keyword.to_string(),
+                    instance_path,
+                    schema_path,
+                    instance: mutated,
+                });
+            }
+        }
+    }
+    result
+}
+
+fn errors(cfg: &Config, instance: &Value) -> Vec<ValidationError> {
+    match crate::validate_node(cfg, instance) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.collect(),
+    }
+}
+
+fn pointer(path: &[&str]) -> String {
+    path.iter()
+        .map(|token| format!("/{}", util::escape_pointer_token(token)))
+        .collect()
+}
+
+/// Collect the replacements of the value `value` at `instance_path`, and of
+/// the values within it, that may violate one keyword of `subschemas`, the
+/// subschemas that apply to `value`.
+fn find_candidates<'a>(
+    cfg: &'a Config<'a>,
+    value: &'a Value,
+    instance_path: &mut String,
+    subschemas: Vec<(String, &'a Value)>,
+    candidates: &mut Vec<(String, &'static str, Value)>,
+) {
+    for (_, schema) in &subschemas {
+        if let Value::Object(schema) = schema {
+            for (keyword, replacement) in replacements(cfg, value, schema) {
+                candidates.push((instance_path.clone(), keyword, replacement));
+            }
+        }
+    }
+
+    let tokens: Vec<String> = match value {
+        Value::Object(object) => object.keys().cloned().collect(),
+        Value::Array(array) => (0..array.len()).map(|i| i.to_string()).collect(),
+        _ => return,
+    };
+    for token in tokens {
+        let child = match value {
+            Value::Object(object) => &object[&token],
+            Value::Array(array) => &array[token.parse::<usize>().unwrap_or(0)],
+            _ => continue,
+        };
+        let mut next = Vec::new();
+        for (path, schema) in &subschemas {
+            for (child_path, child_schema) in children(value, &token, schema) {
+                let path = format!("{}/{}", path, child_path);
+                expand(cfg, child, path, child_schema, 0, &mut next);
+            }
+        }
+        let len = instance_path.len();
+        instance_path.push('/');
+        instance_path.push_str(&util::escape_pointer_token(&token));
+        find_candidates(cfg, child, instance_path, next, candidates);
+        instance_path.truncate(len);
+    }
+}
+
+fn number(x: f64) -> Value {
+    if x.fract() == 0.0 && x.abs() < 9_007_199_254_740_992.0 {
+        json!(x as i64)
+    } else {
+        json!(x)
+    }
+}
+
+/// The replacements of `value` that may violate one keyword of `schema`,
+/// along with that keyword.
+fn replacements(
+    cfg: &Config,
+    value: &Value,
+    schema: &Map<String, Value>,
+) -> Vec<(&'static str, Value)> {
+    let mut result = Vec::new();
+    let get_u64 = |keyword: &str| schema.get(keyword).and_then(Value::as_u64);
+    let get_f64 = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+
+    if let Some(Value::Array(required)) = schema.get("required") {
+        if let Value::Object(object) = value {
+            for name in required.iter().filter_map(Value::as_str) {
+                if object.contains_key(name) {
+                    let mut object = object.clone();
+                    object.remove(name);
+                    result.push(("required", Value::Object(object)));
+                }
+            }
+        }
+    }
+    if schema.contains_key("type") {
+        result.push(("type", different_type(value)));
+    }
+    for keyword in &["enum", "const"] {
+        if schema.contains_key(*keyword) {
+            result.push((*keyword, different_type(value)));
+            result.push((*keyword, json!("\u{fffd}")));
+        }
+    }
+
+    if let Some(x) = value.as_f64() {
+        let integers = value.is_i64() || value.is_u64();
+        let step = if integers { 1.0 } else { 0.5 };
+        if let Some(min) = get_f64("minimum") {
+            // Draft 4 has a boolean `exclusiveMinimum`, which makes the
+            // bound itself invalid.
+            let exclusive = schema.get("exclusiveMinimum") == Some(&Value::Bool(true));
+            result.push(("minimum", number(if exclusive { min } else { min - step })));
+        }
+        if let Some(max) = get_f64("maximum") {
+            let exclusive = schema.get("exclusiveMaximum") == Some(&Value::Bool(true));
+            result.push(("maximum", number(if exclusive { max } else { max + step })));
+        }
+        if let Some(min) = get_f64("exclusiveMinimum") {
+            result.push(("exclusiveMinimum", number(min)));
+        }
+        if let Some(max) = get_f64("exclusiveMaximum") {
+            result.push(("exclusiveMaximum", number(max)));
+        }
+        if let Some(factor) = get_f64("multipleOf") {
+            result.push(("multipleOf", number(x + factor / 2.0)));
+        }
+    }
+
+    if let Value::String(s) = value {
+        let length = s.chars().count() as u64;
+        if let Some(min) = get_u64("minLength").filter(|min| *min > 0 && length >= *min) {
+            result.push((
+                "minLength",
+                json!(s.chars().take(min as usize - 1).collect::<String>()),
+            ));
+        }
+        if let Some(max) = get_u64("maxLength").filter(|max| length <= *max) {
+            let padding = "x".repeat((max + 1 - length) as usize);
+            result.push(("maxLength", json!(format!("{}{}", s, padding))));
+        }
+        if let Some(Value::String(pattern)) = schema.get("pattern") {
+            if let Ok(re) = cfg.regex(pattern) {
+                let candidates = [
+                    String::new(),
+                    "!".into(),
+                    format!("{}!", s),
+                    format!("!{}", s),
+                ];
+                if let Some(candidate) = candidates.iter().find(|x| !re.is_match(x)) {
+                    result.push(("pattern", json!(candidate)));
+                }
+            }
+        }
+        if schema.contains_key("format") {
+            result.push(("format", json!("!")));
+        }
+    }
+
+    if let Value::Array(array) = value {
+        let length = array.len() as u64;
+        if let Some(min) = get_u64("minItems").filter(|min| *min > 0 && length >= *min) {
+            result.push(("minItems", json!(array[..min as usize - 1])));
+        }
+        if let Some(max) = get_u64("maxItems").filter(|max| length <= *max) {
+            let mut array = array.clone();
+            let last = array.last().cloned().unwrap_or(Value::Null);
+            array.resize(max as usize + 1, last);
+            result.push(("maxItems", Value::Array(array)));
+        }
+        if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+            if let Some(first) = array.first() {
+                let mut array = array.clone();
+                array.push(first.clone());
+                result.push(("uniqueItems", Value::Array(array)));
+            }
+        }
+    }
+
+    if let Value::Object(object) = value {
+        let length = object.len() as u64;
+        if let Some(min) = get_u64("minProperties").filter(|min| *min > 0 && length >= *min) {
+            let kept: Map<String, Value> = object
+                .iter()
+                .take(min as usize - 1)
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            result.push(("minProperties", Value::Object(kept)));
+        }
+        let unused = |object: &Map<String, Value>| {
+            (0..)
+                .map(|i| format!("unexpected{}", i))
+                .find(|name| !object.contains_key(name))
+                .unwrap_or_default()
+        };
+        if let Some(max) = get_u64("maxProperties").filter(|max| length <= *max) {
+            let mut object = object.clone();
+            while object.len() as u64 <= max {
+                let name = unused(&object);
+                object.insert(name, Value::Null);
+            }
+            result.push(("maxProperties", Value::Object(object)));
+        }
+        if schema.get("additionalProperties") == Some(&Value::Bool(false)) {
+            let mut object = object.clone();
+            let name = unused(&object);
+            object.insert(name, Value::Null);
+            result.push(("additionalProperties", Value::Object(object)));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas;
+
+    #[test]
+    fn test_each_mutation_violates_its_keyword() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "id": {"type": "integer", "minimum": 1, "maximum": 10},
+                "code": {"pattern": "^[A-Z]+$", "minLength": 2},
+                "tags": {"items": {"enum": ["a", "b"]}, "uniqueItems": true, "maxItems": 2}
+            },
+            "additionalProperties": false
+        });
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let instance = json!({"id": 1, "code": "AB", "tags": ["a"]});
+        let mutations = mutations(&cfg, &instance);
+        let labels: Vec<_> = mutations
+            .iter()
+            .map(|x| format!("{} {}", x.instance_path, x.schema_path))
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                " /type",
+                " /additionalProperties",
+                "/code /properties/code/minLength",
+                "/code /properties/code/pattern",
+                "/id /properties/id/type",
+                "/id /properties/id/minimum",
+                "/id /properties/id/maximum",
+                "/tags /properties/tags/maxItems",
+                "/tags /properties/tags/uniqueItems",
+                "/tags/0 /properties/tags/items/enum",
+            ]
+        );
+        for mutation in &mutations {
+            let errors: Vec<_> = cfg.validate(&mutation.instance).unwrap_err().collect();
+            assert!(errors
+                .iter()
+                .any(|err| err.keyword() == Some(&mutation.keyword)));
+        }
+    }
+}
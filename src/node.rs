@@ -0,0 +1,356 @@
+//! Access to the JSON documents being validated.
+//!
+//! The validator only reads instances through the `JsonNode` trait, so
+//! values from other JSON libraries, or custom document representations,
+//! can be validated in place by implementing it, without converting them to
+//! a `serde_json::Value` first. Schemas are always `serde_json::Value`s.
+//!
+//! Only reporting an error converts the failing part of the instance, with
+//! `JsonNode::to_value`, to attach it to the error.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::{json, Value};
+//! use jsonschema_valid::node::{JsonNode, NodeKind};
+//! use jsonschema_valid::Config;
+//!
+//! /// A document that is an array of small integers.
+//! struct Bytes(Vec<Value>);
+//!
+//! impl JsonNode for Bytes {
+//!     fn kind(&self) -> NodeKind {
+//!         NodeKind::Array
+//!     }
+//!     fn as_bool(&self) -> Option<bool> {
+//!         None
+//!     }
+//!     fn as_number(&self) -> Option<serde_json::Number> {
+//!         None
+//!     }
+//!     fn as_str(&self) -> Option<&str> {
+//!         None
+//!     }
+//!     fn len(&self) -> usize {
+//!         self.0.len()
+//!     }
+//!     fn get(&self, _key: &str) -> Option<&dyn JsonNode> {
+//!         None
+//!     }
+//!     fn elements(&self) -> Box<dyn Iterator<Item = &dyn JsonNode> + '_> {
+//!         Box::new(self.0.iter().map(|x| x as &dyn JsonNode))
+//!     }
+//!     fn members(&self) -> Box<dyn Iterator<Item = (&str, &dyn JsonNode)> + '_> {
+//!         Box::new(std::iter::empty())
+//!     }
+//! }
+//!
+//! let schema = json!({"items": {"maximum": 255}, "maxItems": 3});
+//! let cfg = Config::from_schema(&schema, None).unwrap();
+//! assert!(cfg.validate_node(&Bytes(vec![json!(1), json!(2)])).is_ok());
+//! assert!(cfg.validate_node(&Bytes(vec![json!(1), json!(256)])).is_err());
+//! ```
+
+use serde_json::{Map, Number, Value};
+
+/// The type of a JSON value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    /// `null`.
+    Null,
+    /// `true` or `false`.
+    Bool,
+    /// A number.
+    Number,
+    /// A string.
+    String,
+    /// An array.
+    Array,
+    /// An object.
+    Object,
+}
+
+/// A JSON value that can be validated.
+pub trait JsonNode {
+    /// The type of the value.
+    fn kind(&self) -> NodeKind;
+
+    /// The value, if it is a boolean.
+    fn as_bool(&self) -> Option<bool>;
+
+    /// The value, if it is a number.
+    fn as_number(&self) -> Option<Number>;
+
+    /// The value, if it is a string.
+    fn as_str(&self) -> Option<&str>;
+
+    /// The number of elements of an array or members of an object; 0 for
+    /// other values.
+    fn len(&self) -> usize;
+
+    /// The value of the member `key`, if the value is an object that has it.
+    fn get(&self, key: &str) -> Option<&dyn JsonNode>;
+
+    /// The elements of the value, if it is an array; nothing otherwise.
+    fn elements(&self) -> Box<dyn Iterator<Item = &dyn JsonNode> + '_>;
+
+    /// The members of the value, if it is an object; nothing otherwise.
+    fn members(&self) -> Box<dyn Iterator<Item = (&str, &dyn JsonNode)> + '_>;
+
+    /// Whether the value is an array without elements or an object without
+    /// members.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the value is an object.
+    fn is_object(&self) -> bool {
+        self.kind() == NodeKind::Object
+    }
+
+    /// Whether the value is an array.
+    fn is_array(&self) -> bool {
+        self.kind() == NodeKind::Array
+    }
+
+    /// Convert the value to a `serde_json::Value`.
+    fn to_value(&self) -> Value {
+        match self.kind() {
+            NodeKind::Null => Value::Null,
+            NodeKind::Bool => Value::Bool(self.as_bool().unwrap_or(false)),
+            NodeKind::Number => self.as_number().map_or(Value::Null, Value::Number),
+            NodeKind::String => Value::String(self.as_str().unwrap_or("").to_string()),
+            NodeKind::Array => Value::Array(self.elements().map(|x| x.to_value()).collect()),
+            NodeKind::Object => Value::Object(
+                self.members()
+                    .map(|(key, value)| (key.to_string(), value.to_value()))
+                    .collect::<Map<String, Value>>(),
+            ),
+        }
+    }
+}
+
+impl JsonNode for Value {
+    fn kind(&self) -> NodeKind {
+        match self {
+            Value::Null => NodeKind::Null,
+            Value::Bool(_) => NodeKind::Bool,
+            Value::Number(_) => NodeKind::Number,
+            Value::String(_) => NodeKind::String,
+            Value::Array(_) => NodeKind::Array,
+            Value::Object(_) => NodeKind::Object,
+        }
+    }
+
+    fn as_bool(&self) -> Option<bool> {
+        Value::as_bool(self)
+    }
+
+    fn as_number(&self) -> Option<Number> {
+        match self {
+            Value::Number(number) => Some(number.clone()),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        Value::as_str(self)
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Value::Array(array) => array.len(),
+            Value::Object(object) => object.len(),
+            _ => 0,
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&dyn JsonNode> {
+        match self {
+            Value::Object(object) => object.get(key).map(|x| x as &dyn JsonNode),
+            _ => None,
+        }
+    }
+
+    fn elements(&self) -> Box<dyn Iterator<Item = &dyn JsonNode> + '_> {
+        match self {
+            Value::Array(array) => Box::new(array.iter().map(|x| x as &dyn JsonNode)),
+            _ => Box::new(std::iter::empty()),
+        }
+    }
+
+    fn members(&self) -> Box<dyn Iterator<Item = (&str, &dyn JsonNode)> + '_> {
+        match self {
+            Value::Object(object) => Box::new(
+                object
+                    .iter()
+                    .map(|(key, value)| (key.as_str(), value as &dyn JsonNode)),
+            ),
+            _ => Box::new(std::iter::empty()),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        self.clone()
+    }
+}
+
+/// A JSON string borrowed from elsewhere, such as an object key, to validate
+/// it without allocating a `serde_json::Value`.
+pub(crate) struct StrNode<'a>(pub(crate) &'a str);
+
+impl JsonNode for StrNode<'_> {
+    fn kind(&self) -> NodeKind {
+        NodeKind::String
+    }
+
+    fn as_bool(&self) -> Option<bool> {
+        None
+    }
+
+    fn as_number(&self) -> Option<Number> {
+        None
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        Some(self.0)
+    }
+
+    fn len(&self) -> usize {
+        0
+    }
+
+    fn get(&self, _key: &str) -> Option<&dyn JsonNode> {
+        None
+    }
+
+    fn elements(&self) -> Box<dyn Iterator<Item = &dyn JsonNode> + '_> {
+        Box::new(std::iter::empty())
+    }
+
+    fn members(&self) -> Box<dyn Iterator<Item = (&str, &dyn JsonNode)> + '_> {
+        Box::new(std::iter::empty())
+    }
+}
+
+/// The value of `number` if it is an integer, whether it is represented as
+/// one or as a float with a zero fractional part.
+pub(crate) fn integer_value(number: &Number) -> Option<i128> {
+    if let Some(x) = number.as_i64() {
+        Some(i128::from(x))
+    } else if let Some(x) = number.as_u64() {
+        Some(i128::from(x))
+    } else {
+        let x = number.as_f64()?;
+        if x.fract() == 0.0 && x.abs() < 2f64.powi(127) {
+            Some(x as i128)
+        } else {
+            None
+        }
+    }
+}
+
+/// Check whether two JSON numbers have the same mathematical value,
+/// regardless of their representation, so that `1` equals `1.0`.
+#[allow(clippy::float_cmp)]
+pub(crate) fn numbers_equal(a: &Number, b: &Number) -> bool {
+    match (integer_value(a), integer_value(b)) {
+        (Some(x), Some(y)) => x == y,
+        (None, None) => a.as_f64() == b.as_f64(),
+        _ => false,
+    }
+}
+
+/// Check whether two JSON values are equal, as JSON schema defines it for
+/// `enum`, `const` and `uniqueItems`: numbers are equal if they have the same
+/// mathematical value, so `1` equals `1.0`, unlike with `serde_json::Value`.
+pub fn equals(a: &dyn JsonNode, b: &dyn JsonNode) -> bool {
+    equals_within(a, b, 0.0)
+}
+
+/// Like `equals`, but numbers are also equal if they differ by at most
+/// `epsilon`.
+pub(crate) fn equals_within(a: &dyn JsonNode, b: &dyn JsonNode, epsilon: f64) -> bool {
+    match (a.kind(), b.kind()) {
+        (NodeKind::Null, NodeKind::Null) => true,
+        (NodeKind::Bool, NodeKind::Bool) => a.as_bool() == b.as_bool(),
+        (NodeKind::Number, NodeKind::Number) => match (a.as_number(), b.as_number()) {
+            (Some(x), Some(y)) => {
+                numbers_equal(&x, &y)
+                    || (epsilon > 0.0
+                        && match (x.as_f64(), y.as_f64()) {
+                            (Some(x), Some(y)) => (x - y).abs() <= epsilon,
+                            _ => false,
+                        })
+            }
+            _ => false,
+        },
+        (NodeKind::String, NodeKind::String) => a.as_str() == b.as_str(),
+        (NodeKind::Array, NodeKind::Array) => {
+            a.len() == b.len()
+                && a.elements()
+                    .zip(b.elements())
+                    .all(|(x, y)| equals_within(x, y, epsilon))
+        }
+        (NodeKind::Object, NodeKind::Object) => {
+            a.len() == b.len()
+                && a.members().all(|(key, x)| match b.get(key) {
+                    Some(y) => equals_within(x, y, epsilon),
+                    None => false,
+                })
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_equals() {
+        let a = json!({"a": [1, "x", null], "b": {"c": true}});
+        assert!(equals(&a, &a.clone()));
+        assert!(!equals(&a, &json!({"a": [1, "x", null]})));
+        assert!(equals(&json!([1, {"a": -2}]), &json!([1.0, {"a": -2.0}])));
+        assert!(equals(&json!(1u64 << 53), &json!(9007199254740992.0)));
+        assert!(!equals(&json!(u64::MAX), &json!(-1)));
+        assert!(!equals(&json!(1), &json!(1.5)));
+        assert!(!equals(&json!(1), &json!("1")));
+    }
+
+    #[test]
+    fn test_numeric_equality() {
+        let schema = json!({
+            "uniqueItems": true,
+            "items": {"enum": [1, 2.0, 2.5]},
+            "contains": {"const": 2.0}
+        });
+        let cfg = crate::Config::from_schema(&schema, None).unwrap();
+        assert!(cfg.validate(&json!([1.0, 2])).is_ok());
+        assert!(cfg.validate(&json!([1, 2.5])).is_err());
+        assert_eq!(cfg.validate(&json!([2, 2.0])).unwrap_err().count(), 1);
+
+        let cfg = cfg.with_epsilon(0.01);
+        assert!(cfg.validate(&json!([0.999, 2.001])).is_ok());
+        assert!(equals_within(&json!([1.0]), &json!([1.005]), 0.01));
+        assert!(!equals(&json!([1.0]), &json!([1.005])));
+    }
+
+    #[test]
+    fn test_property_names() {
+        let schema =
+            json!({"propertyNames": {"maxLength": 2, "pattern": "^a", "not": {"enum": ["ab"]}}});
+        let cfg = crate::Config::from_schema(&schema, None)
+            .unwrap()
+            .with_error_values();
+        assert!(cfg.validate(&json!({"a": 1, "ac": 2})).is_ok());
+        let errors: Vec<_> = cfg
+            .validate(&json!({"abc": 1, "ab": 2, "b": 3}))
+            .unwrap_err()
+            .collect();
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].instance(), Some(&json!("ab")));
+    }
+}
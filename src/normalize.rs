@@ -0,0 +1,106 @@
+//! Rewriting a schema into a canonical form, to keep the style of a
+//! collection of schemas consistent.
+//!
+//! Unlike [`optimize`](fn.optimize.html), normalizing only changes how a
+//! schema is spelled, not its structure, so the result is still meant to be
+//! read and edited by humans.
+
+use serde_json::{Map, Value};
+
+use crate::schemas;
+use crate::util;
+
+/// Normalize a schema.
+///
+/// The following rules are applied to every subschema:
+///
+/// * In draft 6 and later, the empty schema `{}` is written as `true`, and
+///   `{"not": {}}` or `{"not": true}` as `false`.
+/// * A `type` array with a single member is written as a string, and
+///   duplicate members of `type` and `required` are removed.
+///
+/// Since JSON objects are kept sorted by key, serializing the result gives a
+/// stable key order.
+///
+/// ## Example:
+///
+/// ```rust
+/// # use serde_json::json;
+/// # use jsonschema_valid::schemas;
+/// let schema = json!({
+///     "type": ["object"],
+///     "required": ["id", "id"],
+///     "properties": {"id": {}, "legacy": {"not": {}}}
+/// });
+/// assert_eq!(
+///     jsonschema_valid::normalize(&schema, &schemas::Draft7),
+///     json!({
+///         "type": "object",
+///         "required": ["id"],
+///         "properties": {"id": true, "legacy": false}
+///     })
+/// );
+/// ```
+pub fn normalize(schema: &Value, draft: &dyn schemas::Draft) -> Value {
+    let mut result = schema.clone();
+    normalize_in_place(&mut result, draft.get_draft_number() >= 6);
+    result
+}
+
+fn normalize_in_place(schema: &mut Value, boolean_schemas: bool) {
+    util::for_each_subschema_mut(schema, &mut |_, subschema| {
+        normalize_in_place(subschema, boolean_schemas)
+    });
+
+    if let Value::Object(object) = schema {
+        normalize_type(object);
+        dedup(object, "required");
+        if boolean_schemas {
+            if object.is_empty() {
+                *schema = Value::Bool(true);
+            } else if object.len() == 1 && object.get("not") == Some(&Value::Bool(true)) {
+                *schema = Value::Bool(false);
+            }
+        }
+    }
+}
+
+fn normalize_type(object: &mut Map<String, Value>) {
+    dedup(object, "type");
+    if let Some(Value::Array(types)) = object.get_mut("type") {
+        if types.len() == 1 {
+            let single = types.remove(0);
+            object.insert("type".to_string(), single);
+        }
+    }
+}
+
+/// Remove duplicate members from the array in `keyword`, keeping the first
+/// occurrence of each.
+fn dedup(object: &mut Map<String, Value>, keyword: &str) {
+    if let Some(Value::Array(members)) = object.get_mut(keyword) {
+        let mut unique: Vec<Value> = Vec::with_capacity(members.len());
+        for member in members.drain(..) {
+            if !unique.contains(&member) {
+                unique.push(member);
+            }
+        }
+        *members = unique;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_boolean_schemas_only_from_draft6() {
+        let schema = json!({"items": {}, "not": {"not": {}}});
+        assert_eq!(normalize(&schema, &schemas::Draft4), schema);
+        assert_eq!(
+            normalize(&schema, &schemas::Draft6),
+            json!({"items": true, "not": false})
+        );
+    }
+}
@@ -0,0 +1,602 @@
+//! Validating OpenAPI request parameters.
+//!
+//! Besides the body, an OpenAPI operation describes its query, path, header
+//! and cookie parameters with a schema each. These parameters arrive as
+//! strings, serialized according to the parameter's `style` and `explode`
+//! settings. `Parameter` turns the raw string back into a JSON value of the
+//! shape the schema describes (a primitive, an array or an object) and
+//! validates it.
+//!
+//! Primitive values are converted to the type the schema asks for where
+//! possible (e.g. `"5"` becomes `5` for an `integer` schema); values that
+//! can't be converted are left as strings, so the schema reports them.
+//! References to components (`$ref` in the parameter or its schema) are not
+//! resolved and must be inlined beforehand.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! use jsonschema_valid::openapi::Parameter;
+//!
+//! let parameter = Parameter::from_openapi(&json!({
+//!     "name": "ids",
+//!     "in": "query",
+//!     "schema": {"type": "array", "items": {"type": "integer"}, "maxItems": 2}
+//! }))
+//! .unwrap();
+//!
+//! assert_eq!(
+//!     parameter.validate(Some("ids=3&ids=4&sort=asc")).unwrap(),
+//!     Some(json!([3, 4]))
+//! );
+//! assert_eq!(parameter.validate(Some("ids=3&ids=4&ids=5")).unwrap_err().len(), 1);
+//! ```
+
+use percent_encoding::percent_decode_str;
+use serde_json::{Map, Value};
+
+use crate::config::Config;
+use crate::error::ValidationError;
+
+/// Where a parameter is found in a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Location {
+    /// In the query string.
+    Query,
+    /// In a templated segment of the path.
+    Path,
+    /// In a header.
+    Header,
+    /// In the `Cookie` header.
+    Cookie,
+}
+
+/// How a parameter value is serialized, as given by its `style`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// `5`, `3,4,5`
+    Simple,
+    /// `.5`, `.3.4.5`
+    Label,
+    /// `;id=5`, `;id=3;id=4`
+    Matrix,
+    /// `id=5`, `id=3&id=4`
+    Form,
+    /// `id=3%204%205`
+    SpaceDelimited,
+    /// `id=3|4|5`
+    PipeDelimited,
+    /// `id[role]=admin&id[name]=Alex`
+    DeepObject,
+}
+
+/// An OpenAPI parameter, with the schema its values are validated against.
+pub struct Parameter {
+    name: String,
+    location: Location,
+    style: Style,
+    explode: bool,
+    required: bool,
+    cfg: Config<'static>,
+}
+
+/// The shape of the values a schema describes.
+#[derive(Clone, Copy, PartialEq)]
+enum Shape {
+    Primitive,
+    Array,
+    Object,
+}
+
+impl Parameter {
+    /// Create a parameter from an OpenAPI Parameter Object.
+    ///
+    /// `style` and `explode` default as in the OpenAPI specification: `form`
+    /// with `explode` for query and cookie parameters, `simple` without it for
+    /// path and header parameters. Path parameters are always required.
+    pub fn from_openapi(parameter: &Value) -> Result<Parameter, String> {
+        let name = parameter
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| "Parameter has no name".to_string())?;
+        let location = match parameter.get("in").and_then(Value::as_str) {
+            Some("query") => Location::Query,
+            Some("path") => Location::Path,
+            Some("header") => Location::Header,
+            Some("cookie") => Location::Cookie,
+            other => {
+                return Err(format!(
+                    "Parameter {} has an invalid location {:?}",
+                    name, other
+                ))
+            }
+        };
+        let style = match parameter.get("style").and_then(Value::as_str) {
+            None => match location {
+                Location::Query | Location::Cookie => Style::Form,
+                Location::Path | Location::Header => Style::Simple,
+            },
+            Some("simple") => Style::Simple,
+            Some("label") => Style::Label,
+            Some("matrix") => Style::Matrix,
+            Some("form") => Style::Form,
+            Some("spaceDelimited") => Style::SpaceDelimited,
+            Some("pipeDelimited") => Style::PipeDelimited,
+            Some("deepObject") => Style::DeepObject,
+            Some(other) => return Err(format!("Parameter {} has unknown style {}", name, other)),
+        };
+        let explode = parameter
+            .get("explode")
+            .and_then(Value::as_bool)
+            .unwrap_or(style == Style::Form);
+        let required = location == Location::Path
+            || parameter.get("required").and_then(Value::as_bool) == Some(true);
+        let schema = parameter
+            .get("schema")
+            .cloned()
+            .ok_or_else(|| format!("Parameter {} has no schema", name))?;
+        let cfg = Config::from_owned_schema(schema, None)
+            .map_err(|err| format!("Parameter {}: {}", name, err.message()))?;
+        Ok(Parameter {
+            name: name.to_string(),
+            location,
+            style,
+            explode,
+            required,
+            cfg,
+        })
+    }
+
+    /// The name of the parameter.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Where the parameter is found in a request.
+    pub fn location(&self) -> Location {
+        self.location
+    }
+
+    /// Turn the raw input into the value of the parameter. Returns `None` if
+    /// the parameter is not present.
+    ///
+    /// What `input` is depends on the location of the parameter: the whole
+    /// query string (without the leading `?`) for query parameters, the value
+    /// of the `Cookie` header for cookie parameters, the value of the path
+    /// segment for path parameters and the value of the header for header
+    /// parameters. `None` means there is no such input at all.
+    pub fn deserialize(&self, input: Option<&str>) -> Result<Option<Value>, String> {
+        let input = match input {
+            Some(input) => input,
+            None => return Ok(None),
+        };
+        match self.location {
+            Location::Query => self.parse_pairs(&split_pairs(input, '&', true)),
+            Location::Cookie => self.parse_pairs(&split_pairs(input, ';', false)),
+            Location::Path | Location::Header => self.parse_single(input).map(Some),
+        }
+    }
+
+    /// Deserialize the parameter from the raw input (see `deserialize`) and
+    /// validate it against its schema.
+    ///
+    /// The instance paths of the errors start with the name of the
+    /// parameter. Returns the value of the parameter if it is valid, or
+    /// `None` if it is absent and not required.
+    pub fn validate(&self, input: Option<&str>) -> Result<Option<Value>, Vec<ValidationError>> {
+        let value = match self.deserialize(input) {
+            Ok(Some(value)) => value,
+            Ok(None) if self.required => {
+                return Err(vec![ValidationError::new(&format!(
+                    "Missing required parameter {}",
+                    self.name
+                ))
+                .instance_ctx(self.name.clone())])
+            }
+            Ok(None) => return Ok(None),
+            Err(message) => {
+                return Err(vec![
+                    ValidationError::new(&message).instance_ctx(self.name.clone())
+                ])
+            }
+        };
+        let errors: Vec<ValidationError> = match self.cfg.validate(&value) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .map(|err| err.instance_ctx(self.name.clone()))
+                .collect(),
+        };
+        if errors.is_empty() {
+            Ok(Some(value))
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn shape(&self) -> Shape {
+        let schema = self.cfg.get_schema();
+        let types: Vec<&str> = match schema.get("type") {
+            Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+            Some(Value::String(single)) => vec![single.as_str()],
+            _ => Vec::new(),
+        };
+        if types.contains(&"array") || (types.is_empty() && schema.get("items").is_some()) {
+            Shape::Array
+        } else if types.contains(&"object")
+            || (types.is_empty() && schema.get("properties").is_some())
+        {
+            Shape::Object
+        } else {
+            Shape::Primitive
+        }
+    }
+
+    /// Deserialize from `name=value` pairs, for the `form`, `spaceDelimited`,
+    /// `pipeDelimited` and `deepObject` styles.
+    fn parse_pairs(&self, pairs: &[(String, String)]) -> Result<Option<Value>, String> {
+        let schema = self.cfg.get_schema();
+        let shape = self.shape();
+        let mut own = pairs
+            .iter()
+            .filter(|(key, _)| *key == self.name)
+            .map(|(_, value)| value.as_str());
+
+        match (self.style, shape) {
+            (Style::DeepObject, _) => {
+                let prefix = format!("{}[", self.name);
+                let mut object = Map::new();
+                for (key, value) in pairs {
+                    if key.starts_with(&prefix) && key.ends_with(']') {
+                        let property = &key[prefix.len()..key.len() - 1];
+                        let value = coerce(value, property_schema(schema, property));
+                        object.insert(property.to_string(), value);
+                    }
+                }
+                Ok(if object.is_empty() {
+                    None
+                } else {
+                    Some(Value::Object(object))
+                })
+            }
+            (_, Shape::Primitive) => Ok(own.next().map(|value| coerce(value, Some(schema)))),
+            (Style::Form, Shape::Array) if self.explode => {
+                let items: Vec<Value> = own
+                    .map(|value| coerce(value, schema.get("items")))
+                    .collect();
+                Ok(if items.is_empty() {
+                    None
+                } else {
+                    Some(Value::Array(items))
+                })
+            }
+            (Style::Form, Shape::Object) if self.explode => {
+                let properties = schema.get("properties").and_then(Value::as_object);
+                let mut object = Map::new();
+                for (key, value) in pairs {
+                    let known = match properties {
+                        Some(properties) => properties.contains_key(key),
+                        None => true,
+                    };
+                    if known {
+                        let value = coerce(value, property_schema(schema, key));
+                        object.insert(key.clone(), value);
+                    }
+                }
+                Ok(if object.is_empty() {
+                    None
+                } else {
+                    Some(Value::Object(object))
+                })
+            }
+            (style, shape) => {
+                let separator = match style {
+                    Style::SpaceDelimited => ' ',
+                    Style::PipeDelimited => '|',
+                    _ => ',',
+                };
+                match own.next() {
+                    Some(value) => self
+                        .collect(value.split(separator).map(str::to_string), shape)
+                        .map(Some),
+                    None => Ok(None),
+                }
+            }
+        }
+    }
+
+    /// Deserialize a single serialized value, for the `simple`, `label` and
+    /// `matrix` styles.
+    fn parse_single(&self, input: &str) -> Result<Value, String> {
+        let shape = self.shape();
+        let malformed = || format!("Malformed value for parameter {}", self.name);
+        let parts: Vec<String> = match self.style {
+            Style::Label => {
+                let rest = input.strip_prefix('.').ok_or_else(malformed)?;
+                let separator = if self.explode && shape != Shape::Primitive {
+                    '.'
+                } else {
+                    ','
+                };
+                rest.split(separator).map(decode).collect()
+            }
+            Style::Matrix if self.explode && shape != Shape::Primitive => {
+                let rest = input.strip_prefix(';').ok_or_else(malformed)?;
+                let prefix = format!("{}=", self.name);
+                match shape {
+                    Shape::Array => rest
+                        .split(';')
+                        .map(|part| part.strip_prefix(&prefix).map(decode).ok_or_else(malformed))
+                        .collect::<Result<_, _>>()?,
+                    _ => rest.split(';').map(decode).collect(),
+                }
+            }
+            Style::Matrix => {
+                let prefix = format!(";{}", self.name);
+                let rest = input.strip_prefix(&prefix).ok_or_else(malformed)?;
+                // An empty value is serialized without the `=`.
+                let rest = rest.strip_prefix('=').unwrap_or(rest);
+                rest.split(',').map(decode).collect()
+            }
+            _ => input.split(',').map(decode).collect(),
+        };
+        self.collect(parts.into_iter(), shape)
+    }
+
+    /// Build a value of the given shape from its serialized parts. Objects
+    /// are given either as alternating names and values, or (exploded) as
+    /// `name=value` parts.
+    fn collect(
+        &self,
+        mut parts: impl Iterator<Item = String>,
+        shape: Shape,
+    ) -> Result<Value, String> {
+        let schema = self.cfg.get_schema();
+        match shape {
+            Shape::Primitive => {
+                let parts: Vec<String> = parts.collect();
+                Ok(coerce(&parts.join(","), Some(schema)))
+            }
+            Shape::Array => Ok(Value::Array(
+                parts
+                    .filter(|part| !part.is_empty())
+                    .map(|part| coerce(&part, schema.get("items")))
+                    .collect(),
+            )),
+            Shape::Object => {
+                let mut object = Map::new();
+                let exploded = self.explode && self.style != Style::Form;
+                while let Some(part) = parts.next() {
+                    let (key, value) = if exploded {
+                        match part.find('=') {
+                            Some(index) => {
+                                (part[..index].to_string(), part[index + 1..].to_string())
+                            }
+                            None => (part, String::new()),
+                        }
+                    } else {
+                        match parts.next() {
+                            Some(value) => (part, value),
+                            None if part.is_empty() => break,
+                            None => {
+                                return Err(format!(
+                                    "Malformed value for parameter {}: missing the value of {}",
+                                    self.name, part
+                                ))
+                            }
+                        }
+                    };
+                    let value = coerce(&value, property_schema(schema, &key));
+                    object.insert(key, value);
+                }
+                Ok(Value::Object(object))
+            }
+        }
+    }
+}
+
+fn decode(s: &str) -> String {
+    percent_decode_str(s).decode_utf8_lossy().into_owned()
+}
+
+/// Split `name=value` pairs, decoding both. In query strings, `+` stands for
+/// a space.
+fn split_pairs(input: &str, separator: char, query: bool) -> Vec<(String, String)> {
+    input
+        .split(separator)
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let pair = if query {
+                pair.replace('+', " ")
+            } else {
+                pair.to_string()
+            };
+            match pair.find('=') {
+                Some(index) => (decode(&pair[..index]), decode(&pair[index + 1..])),
+                None => (decode(&pair), String::new()),
+            }
+        })
+        .collect()
+}
+
+fn property_schema<'a>(schema: &'a Value, property: &str) -> Option<&'a Value> {
+    schema
+        .get("properties")
+        .and_then(|x| x.get(property))
+        .or_else(|| schema.get("additionalProperties"))
+}
+
+/// Convert a string to the primitive type the schema asks for, if possible.
+fn coerce(value: &str, schema: Option<&Value>) -> Value {
+    let types: Vec<&str> = match schema.and_then(|x| x.get("type")) {
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        Some(Value::String(single)) => vec![single.as_str()],
+        _ => Vec::new(),
+    };
+    for typename in types {
+        let converted = match typename {
+            "integer" => value.parse::<i64>().ok().map(Value::from),
+            "number" => value
+                .parse::<i64>()
+                .map(Value::from)
+                .ok()
+                .or_else(|| value.parse::<f64>().ok().map(Value::from)),
+            "boolean" => value.parse::<bool>().ok().map(Value::Bool),
+            "null" if value.is_empty() => Some(Value::Null),
+            "string" => Some(Value::String(value.to_string())),
+            _ => None,
+        };
+        if let Some(converted) = converted {
+            return converted;
+        }
+    }
+    Value::String(value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parameter(location: &str, style: &str, explode: bool, schema: Value) -> Parameter {
+        Parameter::from_openapi(&json!({
+            "name": "id",
+            "in": location,
+            "style": style,
+            "explode": explode,
+            "schema": schema
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_styles() {
+        let array = json!({"type": "array", "items": {"type": "integer"}});
+        let object = json!({
+            "type": "object",
+            "properties": {"role": {"type": "string"}, "age": {"type": "integer"}}
+        });
+        let boolean = json!({"type": "boolean"});
+        let expected_object = json!({"role": "admin", "age": 30});
+        let cases = vec![
+            ("path", "simple", false, &array, "3,4,5", json!([3, 4, 5])),
+            ("path", "label", true, &array, ".3.4.5", json!([3, 4, 5])),
+            (
+                "path",
+                "matrix",
+                true,
+                &array,
+                ";id=3;id=4;id=5",
+                json!([3, 4, 5]),
+            ),
+            (
+                "path",
+                "matrix",
+                false,
+                &array,
+                ";id=3,4,5",
+                json!([3, 4, 5]),
+            ),
+            (
+                "header",
+                "simple",
+                false,
+                &object,
+                "role,admin,age,30",
+                expected_object.clone(),
+            ),
+            (
+                "header",
+                "simple",
+                true,
+                &object,
+                "role=admin,age=30",
+                expected_object.clone(),
+            ),
+            (
+                "path",
+                "label",
+                false,
+                &object,
+                ".role,admin,age,30",
+                expected_object.clone(),
+            ),
+            (
+                "path",
+                "matrix",
+                true,
+                &object,
+                ";role=admin;age=30",
+                expected_object.clone(),
+            ),
+            (
+                "query",
+                "form",
+                false,
+                &array,
+                "id=3,4,5&x=1",
+                json!([3, 4, 5]),
+            ),
+            (
+                "query",
+                "spaceDelimited",
+                false,
+                &array,
+                "id=3%204%205",
+                json!([3, 4, 5]),
+            ),
+            (
+                "query",
+                "pipeDelimited",
+                false,
+                &array,
+                "id=3|4|5",
+                json!([3, 4, 5]),
+            ),
+            (
+                "query",
+                "form",
+                true,
+                &object,
+                "role=admin&age=30&x=1",
+                expected_object.clone(),
+            ),
+            (
+                "query",
+                "deepObject",
+                true,
+                &object,
+                "id[role]=admin&id[age]=30",
+                expected_object,
+            ),
+            (
+                "cookie",
+                "form",
+                true,
+                &boolean,
+                "a=b; id=true",
+                json!(true),
+            ),
+        ];
+        for (location, style, explode, schema, input, expected) in cases {
+            let parameter = parameter(location, style, explode, schema.clone());
+            assert_eq!(
+                parameter.validate(Some(input)).unwrap(),
+                Some(expected),
+                "{} {} {}",
+                location,
+                style,
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let parameter = parameter("path", "simple", false, json!({"type": "integer"}));
+        let errors = parameter.validate(Some("abc")).unwrap_err();
+        assert_eq!(errors[0].instance_path(), vec!["id"]);
+        assert_eq!(parameter.validate(None).unwrap_err().len(), 1);
+    }
+}
@@ -0,0 +1,415 @@
+//! Simplification passes that make a schema cheaper to validate against.
+//!
+//! Every pass produces a schema that is equivalent to the input, so the
+//! optimized schema can be used in place of the original for validation. The
+//! optimized schema is not intended to be shown to humans: annotations are
+//! kept, but the structure may differ considerably from what was authored.
+
+use serde_json::{Map, Value};
+
+use crate::schemas;
+use crate::util;
+
+/// Keywords that only apply to instances of the given JSON type.
+pub(crate) const TYPE_KEYWORDS: &[(&str, &[&str])] = &[
+    ("string", &["maxLength", "minLength", "pattern"]),
+    (
+        "number",
+        &[
+            "exclusiveMaximum",
+            "exclusiveMinimum",
+            "maximum",
+            "minimum",
+            "multipleOf",
+        ],
+    ),
+    (
+        "array",
+        &[
+            "additionalItems",
+            "contains",
+            "items",
+            "maxItems",
+            "minItems",
+            "uniqueItems",
+        ],
+    ),
+    (
+        "object",
+        &[
+            "additionalProperties",
+            "dependencies",
+            "maxProperties",
+            "minProperties",
+            "patternProperties",
+            "properties",
+            "propertyNames",
+            "required",
+        ],
+    ),
+];
+
+/// Optimize a schema for validation.
+///
+/// The following passes are applied to every subschema:
+///
+/// * Keywords that can't apply to any of the types allowed by `type` are
+///   dropped (e.g. `minLength` next to `"type": "integer"`).
+/// * An `enum` with a single member is folded into `const` (draft 6 and later).
+/// * An `allOf` nested directly inside another `allOf` is collapsed into it.
+/// * Applicators whose result is known in advance are precomputed: `true`
+///   branches are removed from `allOf`, an `anyOf` containing `true` is
+///   removed, and an `allOf` containing `false` turns the whole subschema
+///   into `false` (draft 6 and later, where boolean schemas are allowed).
+///   With `unevaluatedProperties` or `unevaluatedItems` anywhere in the
+///   schema, an `anyOf` is kept, since its other branches may evaluate
+///   properties or items.
+///
+/// Subschemas that local `$ref`s point into are left as they are, so the
+/// references keep pointing to the same place.
+///
+/// Validation doesn't optimize schemas by itself, and neither does
+/// `cache::compile`: optimize a schema first to validate against, or
+/// compile, the optimized form.
+///
+/// ## Example:
+///
+/// ```rust
+/// # use serde_json::json;
+/// # use jsonschema_valid::schemas;
+/// let schema = json!({
+///     "type": "integer",
+///     "minLength": 3,
+///     "enum": [4],
+///     "allOf": [{"allOf": [{"minimum": 0}, true]}]
+/// });
+/// assert_eq!(
+///     jsonschema_valid::optimize(&schema, &schemas::Draft7),
+///     json!({"type": "integer", "const": 4, "allOf": [{"minimum": 0}]})
+/// );
+/// ```
+pub fn optimize(schema: &Value, draft: &dyn schemas::Draft) -> Value {
+    let mut targets = Vec::new();
+    util::local_ref_targets(schema, &mut targets);
+    let mut unevaluated = false;
+    scan(schema, &mut unevaluated);
+    let options = Options {
+        boolean_schemas: draft.get_draft_number() >= 6,
+        unevaluated,
+        targets,
+    };
+    let mut result = schema.clone();
+    optimize_in_place(&mut result, &mut String::new(), &options);
+    result
+}
+
+/// What the passes need to know about the whole schema.
+struct Options {
+    boolean_schemas: bool,
+    /// Whether the schema uses `unevaluatedProperties` or `unevaluatedItems`.
+    unevaluated: bool,
+    /// The JSON pointers that local `$ref`s point to.
+    targets: Vec<String>,
+}
+
+/// Find out whether `unevaluated*` is used.
+fn scan(schema: &Value, unevaluated: &mut bool) {
+    if let Value::Object(object) = schema {
+        *unevaluated |=
+            object.contains_key("unevaluatedProperties") || object.contains_key("unevaluatedItems");
+    }
+    util::for_each_subschema(schema, &mut |_, subschema| scan(subschema, unevaluated));
+}
+
+fn optimize_in_place(schema: &mut Value, pointer: &mut String, options: &Options) {
+    util::for_each_subschema_mut(schema, &mut |path, subschema| {
+        let len = pointer.len();
+        pointer.push('/');
+        pointer.push_str(&path);
+        optimize_in_place(subschema, pointer, options);
+        pointer.truncate(len);
+    });
+
+    let prefix = format!("{}/", pointer);
+    if options.targets.iter().any(|x| x.starts_with(&prefix)) {
+        return;
+    }
+    let unsatisfiable = match schema {
+        // Draft 7 and earlier ignore everything next to a `$ref`.
+        Value::Object(object) if !object.contains_key("$ref") => {
+            drop_inapplicable_keywords(object);
+            collapse_all_of(object);
+            if options.boolean_schemas {
+                fold_enum(object);
+                precompute_applicators(object, options.unevaluated) == Some(false)
+            } else {
+                false
+            }
+        }
+        _ => false,
+    };
+    if unsatisfiable {
+        *schema = Value::Bool(false);
+    }
+}
+
+/// The type names that `drop_inapplicable_keywords` knows about. Anything
+/// else (draft 3's `"any"` or schemas in a `type` array) may accept instances
+/// of any type.
+const TYPE_NAMES: &[&str] = &[
+    "array", "boolean", "integer", "null", "number", "object", "string",
+];
+
+fn drop_inapplicable_keywords(object: &mut Map<String, Value>) {
+    let mut types = Vec::new();
+    match object.get("type") {
+        Some(value) => {
+            for x in util::iter_or_once(value) {
+                match x.as_str() {
+                    Some(x) if TYPE_NAMES.contains(&x) => {
+                        types.push(if x == "integer" { "number" } else { x }.to_string())
+                    }
+                    _ => return,
+                }
+            }
+        }
+        None => return,
+    }
+    if types.is_empty() {
+        return;
+    }
+    for (typename, keywords) in TYPE_KEYWORDS {
+        if !types.iter().any(|x| x == typename) {
+            for keyword in keywords.iter() {
+                object.remove(*keyword);
+            }
+        }
+    }
+}
+
+fn fold_enum(object: &mut Map<String, Value>) {
+    if object.contains_key("const") {
+        return;
+    }
+    if let Some(Value::Array(members)) = object.get("enum") {
+        if members.len() == 1 {
+            let member = members[0].clone();
+            object.remove("enum");
+            object.insert("const".to_string(), member);
+        }
+    }
+}
+
+fn collapse_all_of(object: &mut Map<String, Value>) {
+    if let Some(Value::Array(branches)) = object.get_mut("allOf") {
+        let mut collapsed = Vec::with_capacity(branches.len());
+        for branch in branches.drain(..) {
+            match branch {
+                Value::Object(mut inner) if inner.len() == 1 && inner.contains_key("allOf") => {
+                    match inner.remove("allOf") {
+                        Some(Value::Array(inner_branches)) => collapsed.extend(inner_branches),
+                        Some(other) => {
+                            inner.insert("allOf".to_string(), other);
+                            collapsed.push(Value::Object(inner));
+                        }
+                        None => {}
+                    }
+                }
+                branch => collapsed.push(branch),
+            }
+        }
+        *branches = collapsed;
+    }
+}
+
+/// Remove applicators with a known result. Returns `Some(false)` if the
+/// schema can never be satisfied. With `unevaluated`, an `anyOf` is never
+/// removed, since the annotations of its branches may be needed.
+fn precompute_applicators(object: &mut Map<String, Value>, unevaluated: bool) -> Option<bool> {
+    if let Some(Value::Array(branches)) = object.get_mut("allOf") {
+        if branches.iter().any(|x| x == &Value::Bool(false)) {
+            return Some(false);
+        }
+        branches.retain(|x| !always_valid(x));
+        if branches.is_empty() {
+            object.remove("allOf");
+        }
+    }
+    if let Some(Value::Array(branches)) = object.get_mut("anyOf") {
+        if branches.iter().any(always_valid) {
+            if !unevaluated {
+                object.remove("anyOf");
+            }
+        } else {
+            branches.retain(|x| x != &Value::Bool(false));
+            if branches.is_empty() {
+                return Some(false);
+            }
+        }
+    }
+    match object.get("not") {
+        Some(not) if always_valid(not) => Some(false),
+        _ => None,
+    }
+}
+
+/// Is the subschema `true` or the equivalent empty schema `{}`?
+fn always_valid(schema: &Value) -> bool {
+    match schema {
+        Value::Bool(b) => *b,
+        Value::Object(object) => object.is_empty(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use serde_json::json;
+
+    /// Optimize `schema`, and check that the optimized schema accepts and
+    /// rejects the same instances as the original.
+    fn optimize_checked(schema: &Value, draft: &dyn schemas::Draft, instances: &[Value]) -> Value {
+        let optimized = optimize(schema, draft);
+        let original_cfg = Config::from_schema(schema, Some(draft)).unwrap();
+        let optimized_cfg = Config::from_owned_schema(optimized.clone(), Some(draft)).unwrap();
+        for instance in instances {
+            assert_eq!(
+                original_cfg.validate(instance).is_ok(),
+                optimized_cfg.validate(instance).is_ok(),
+                "{} against {}",
+                instance,
+                optimized
+            );
+        }
+        optimized
+    }
+
+    #[test]
+    fn test_inapplicable_keywords() {
+        let schema = json!({
+            "type": ["integer", "null"],
+            "minLength": 3,
+            "maximum": 5,
+            "required": ["a"]
+        });
+        let instances = [json!(3), json!(6), json!(null), json!("abc"), json!({})];
+        assert_eq!(
+            optimize_checked(&schema, &schemas::Draft7, &instances),
+            json!({"type": ["integer", "null"], "maximum": 5})
+        );
+    }
+
+    #[test]
+    fn test_draft3_types() {
+        let schema = json!({
+            "properties": {
+                "a": {"type": "any", "minLength": 3},
+                "b": {"type": ["integer", {"type": "string"}], "minLength": 3},
+                "c": {"type": "integer", "minLength": 3}
+            }
+        });
+        let instances = [
+            json!({"a": "ab"}),
+            json!({"a": "abc"}),
+            json!({"b": "ab"}),
+            json!({"b": 1}),
+            json!({"c": "ab"}),
+        ];
+        assert_eq!(
+            optimize_checked(&schema, &schemas::Draft3, &instances),
+            json!({
+                "properties": {
+                    "a": {"type": "any", "minLength": 3},
+                    "b": {"type": ["integer", {"type": "string"}], "minLength": 3},
+                    "c": {"type": "integer"}
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_enum() {
+        let schema = json!({"enum": [{"a": 1}]});
+        let instances = [json!({"a": 1}), json!({"a": 2}), json!(1)];
+        assert_eq!(
+            optimize_checked(&schema, &schemas::Draft7, &instances),
+            json!({"const": {"a": 1}})
+        );
+        assert_eq!(
+            optimize_checked(&schema, &schemas::Draft4, &instances),
+            schema
+        );
+    }
+
+    #[test]
+    fn test_nested_all_of() {
+        let schema = json!({
+            "allOf": [{"allOf": [{"minimum": 0}, {"maximum": 9}]}, {"multipleOf": 2}]
+        });
+        let instances = [json!(-2), json!(4), json!(5), json!(10)];
+        assert_eq!(
+            optimize_checked(&schema, &schemas::Draft4, &instances),
+            json!({"allOf": [{"minimum": 0}, {"maximum": 9}, {"multipleOf": 2}]})
+        );
+    }
+
+    #[test]
+    fn test_applicators() {
+        let schema = json!({
+            "properties": {
+                "a": {"allOf": [true, {}, {"type": "string"}], "anyOf": [false, {"minLength": 2}]},
+                "b": {"allOf": [{"type": "string"}, false]},
+                "c": {"anyOf": [{"type": "string"}, true]},
+                "d": {"not": {}}
+            }
+        });
+        let instances = [
+            json!({}),
+            json!({"a": "xy"}),
+            json!({"a": "x"}),
+            json!({"a": 1}),
+            json!({"b": "x"}),
+            json!({"c": 1}),
+            json!({"d": null}),
+        ];
+        assert_eq!(
+            optimize_checked(&schema, &schemas::Draft7, &instances),
+            json!({
+                "properties": {
+                    "a": {"allOf": [{"type": "string"}], "anyOf": [{"minLength": 2}]},
+                    "b": false,
+                    "c": {},
+                    "d": false
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_unevaluated_properties() {
+        let schema = json!({
+            "anyOf": [true, {"properties": {"a": true}}],
+            "unevaluatedProperties": false
+        });
+        let instances = [json!({"a": 1}), json!({"b": 1})];
+        assert_eq!(
+            optimize_checked(&schema, &schemas::Draft202012, &instances),
+            schema
+        );
+    }
+
+    #[test]
+    fn test_ref_targets() {
+        let schema = json!({
+            "allOf": [{"allOf": [{"minimum": 1}, {"maximum": 3}]}],
+            "properties": {"x": {"$ref": "#/allOf/0/allOf/1"}}
+        });
+        let instances = [json!({"x": 4}), json!({"x": 0}), json!(2), json!(0)];
+        assert_eq!(
+            optimize_checked(&schema, &schemas::Draft7, &instances),
+            schema
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! Overriding options of a compiled schema for a single validation.
+//!
+//! One compiled schema is often shared by endpoints with different needs:
+//! one rejects a request at the first error, another reports every error,
+//! and responses may contain properties that requests must not set.
+//! `ValidationOptions` holds the options that can be chosen for each call
+//! of `Config::validate_with_options`:
+//!
+//! - fail-fast: stop at the first error;
+//! - a maximum number of errors to report;
+//! - whether `format` is asserted;
+//! - the direction of the data, which makes `readOnly` properties invalid
+//!   in data written to the owner of the schema, e.g. in requests, and
+//!   `writeOnly` properties invalid in data read from it, e.g. in responses.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::{schemas, Config};
+//! use jsonschema_valid::options::{Direction, ValidationOptions};
+//!
+//! let schema = json!({
+//!     "properties": {
+//!         "id": {"type": "integer", "readOnly": true},
+//!         "email": {"type": "string", "format": "email"}
+//!     }
+//! });
+//! let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+//! let request = json!({"id": 1, "email": "nobody"});
+//!
+//! let strict = ValidationOptions::new().with_direction(Direction::Write);
+//! let errors: Vec<_> = cfg.validate_with_options(&request, &strict).unwrap_err().collect();
+//! assert_eq!(errors.len(), 2);
+//! assert_eq!(errors[0].keyword(), Some("format"));
+//! assert_eq!(errors[1].keyword(), Some("readOnly"));
+//!
+//! let fail_fast = strict.clone().with_fail_fast(true);
+//! assert_eq!(cfg.validate_with_options(&request, &fail_fast).unwrap_err().count(), 1);
+//!
+//! let lenient = ValidationOptions::new().with_format_assertion(false);
+//! assert!(cfg.validate_with_options(&request, &lenient).is_ok());
+//! ```
+
+use crate::formats::FormatMode;
+
+/// The direction in which validated data flows, relative to the owner of
+/// the schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// The data is read from the owner, e.g. a response of a service:
+    /// properties marked `writeOnly` must not be present.
+    Read,
+    /// The data is written to the owner, e.g. a request to a service:
+    /// properties marked `readOnly` must not be present.
+    Write,
+}
+
+impl Direction {
+    /// The keyword marking the values that must not be present in data
+    /// flowing in this direction.
+    pub(crate) fn forbidden_keyword(self) -> &'static str {
+        match self {
+            Direction::Read => "writeOnly",
+            Direction::Write => "readOnly",
+        }
+    }
+}
+
+/// Options for a single validation; see the module documentation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationOptions {
+    fail_fast: bool,
+    max_errors: Option<usize>,
+    format_mode: Option<FormatMode>,
+    direction: Option<Direction>,
+}
+
+/// The options of `Config::format_warnings`: formats are asserted.
+pub(crate) static ASSERT_FORMATS: ValidationOptions = ValidationOptions {
+    fail_fast: false,
+    max_errors: None,
+    format_mode: Some(FormatMode::Assertion),
+    direction: None,
+};
+
+impl ValidationOptions {
+    /// The options of a validation with `Config::validate`: every error is
+    /// reported, `format` is checked according to the `Config`, and
+    /// `readOnly` and `writeOnly` are ignored.
+    pub fn new() -> ValidationOptions {
+        ValidationOptions::default()
+    }
+
+    /// Stop at the first error.
+    pub fn with_fail_fast(mut self, fail_fast: bool) -> Self {
+        self.fail_fast = fail_fast;
+        self
+    }
+
+    /// Report at most `max_errors` errors, and at least one.
+    pub fn with_max_errors(mut self, max_errors: usize) -> Self {
+        self.max_errors = Some(max_errors);
+        self
+    }
+
+    /// Whether strings are checked against their `format`, regardless of
+    /// `Config::with_format_mode`.
+    pub fn with_format_assertion(mut self, assert: bool) -> Self {
+        self.format_mode = Some(if assert {
+            FormatMode::Assertion
+        } else {
+            FormatMode::Disabled
+        });
+        self
+    }
+
+    /// Reject `readOnly` or `writeOnly` values, depending on the direction
+    /// of the data.
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = Some(direction);
+        self
+    }
+
+    /// The number of errors to report at most.
+    pub(crate) fn error_limit(&self) -> usize {
+        match (self.fail_fast, self.max_errors) {
+            (true, _) => 1,
+            (false, Some(max_errors)) => max_errors.max(1),
+            (false, None) => usize::MAX,
+        }
+    }
+
+    pub(crate) fn format_mode(&self) -> Option<FormatMode> {
+        self.format_mode
+    }
+
+    pub(crate) fn direction(&self) -> Option<Direction> {
+        self.direction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::schemas;
+    use serde_json::json;
+
+    #[test]
+    fn test_read_direction_and_max_errors() {
+        let schema = json!({
+            "items": {"$ref": "#/definitions/user"},
+            "definitions": {
+                "user": {
+                    "properties": {"password": {"writeOnly": true}, "name": {"type": "string"}}
+                }
+            }
+        });
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let response = json!([{"password": "x"}, {"name": 1}, {"password": "y"}]);
+        assert!(cfg.validate(&json!([{"password": "x"}])).is_ok());
+
+        let read = ValidationOptions::new().with_direction(Direction::Read);
+        let errors: Vec<_> = cfg
+            .validate_with_options(&response, &read)
+            .unwrap_err()
+            .map(|err| {
+                (
+                    err.instance_path().join("/"),
+                    err.keyword().unwrap().to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                ("0/password".to_string(), "writeOnly".to_string()),
+                ("1/name".to_string(), "type".to_string()),
+                ("2/password".to_string(), "writeOnly".to_string()),
+            ]
+        );
+        let limited = read.with_max_errors(2);
+        assert_eq!(
+            cfg.validate_with_options(&response, &limited)
+                .unwrap_err()
+                .count(),
+            2
+        );
+    }
+}
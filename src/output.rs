@@ -0,0 +1,93 @@
+//! Settings for how validation errors are presented.
+//!
+//! These are set on a `Config` and applied to every error it reports: the
+//! wording of messages, what is kept of instance values, and how much of
+//! the surrounding instance is attached to errors.
+
+use std::fmt;
+
+use crate::error::ValidationError;
+
+/// How much of the enclosing object to attach to errors that occur inside
+/// objects. See `Config::with_error_context`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextMode {
+    /// Attach the keys of the enclosing object.
+    Keys,
+    /// Attach the enclosing object serialized as JSON, truncated to at most the
+    /// given number of characters.
+    Snippet(usize),
+}
+
+/// How instance values are redacted from errors. See
+/// `Config::with_redaction`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Redaction {
+    /// Leave instance values out of errors entirely.
+    Omit,
+    /// Replace instance values with a SHA-256 digest of their JSON
+    /// representation, so that errors about the same value can be correlated.
+    /// Note that the digest is unsalted, so values from a small set of
+    /// possibilities can still be recovered from it.
+    Hash,
+}
+
+/// Information about the object enclosing the part of the instance that
+/// failed validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParentContext {
+    /// The keys of the enclosing object.
+    Keys(Vec<String>),
+    /// The (possibly truncated) enclosing object serialized as JSON.
+    Snippet(String),
+}
+
+impl fmt::Display for ParentContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParentContext::Keys(keys) => write!(f, "in object with keys {}", keys.join(", ")),
+            ParentContext::Snippet(snippet) => write!(f, "in {}", snippet),
+        }
+    }
+}
+
+/// Produces the message for a validation error.
+///
+/// A `MessageFormatter` receives the structured error (the failing keyword,
+/// its value in the schema, the failing instance and the paths to them) and
+/// renders the message shown to users. Set one on a `Config` with
+/// `Config::with_formatter` to control the tone and wording of all messages.
+///
+/// ## Example:
+///
+/// ```rust
+/// # use serde_json::json;
+/// use jsonschema_valid::{Config, MessageFormatter, ValidationError};
+///
+/// struct Terse;
+///
+/// impl MessageFormatter for Terse {
+///     fn format_message(&self, error: &ValidationError) -> String {
+///         format!("fails {}", error.keyword().unwrap_or("schema"))
+///     }
+/// }
+///
+/// let schema = json!({"type": "integer"});
+/// let data = json!("string");
+/// let cfg = Config::from_schema(&schema, None).unwrap().with_formatter(Terse);
+/// let errors: Vec<ValidationError> = cfg.validate(&data).unwrap_err().collect();
+/// assert_eq!(errors[0].message(), "fails type");
+/// ```
+pub trait MessageFormatter: Send + Sync {
+    /// Render the message for the given error.
+    fn format_message(&self, error: &ValidationError) -> String;
+}
+
+/// The default `MessageFormatter`, producing English messages.
+pub struct EnglishFormatter;
+
+impl MessageFormatter for EnglishFormatter {
+    fn format_message(&self, error: &ValidationError) -> String {
+        error.message().to_string()
+    }
+}
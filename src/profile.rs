@@ -0,0 +1,137 @@
+//! Selecting which keywords take part in a validation.
+//!
+//! A `Profile` enables or disables keywords, individually or in groups, so
+//! that one compiled schema can serve several purposes: e.g. strict
+//! validation when data is ingested, and a lenient preview that ignores
+//! `format`. Disabled keywords are skipped as if they weren't in the schema.
+//!
+//! A profile applies to all validations with a `Config` when set with
+//! `Config::with_profile`, or to a single one with
+//! `Config::validate_with_profile`.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::{schemas, Config};
+//! use jsonschema_valid::profile::{self, Profile};
+//!
+//! let schema = json!({"properties": {"at": {"type": "string", "format": "date"}}});
+//! let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+//! let instance = json!({"at": "tomorrow"});
+//! assert!(cfg.validate(&instance).is_err());
+//!
+//! let lenient = Profile::all().without(profile::FORMAT_KEYWORDS);
+//! assert!(cfg.validate_with_profile(&instance, &lenient).is_ok());
+//! let structural = Profile::only(profile::STRUCTURAL_KEYWORDS);
+//! assert!(cfg.validate_with_profile(&json!({"at": 1}), &structural).is_err());
+//! ```
+
+use std::collections::HashSet;
+
+/// The keywords that check the formats of strings.
+pub const FORMAT_KEYWORDS: &[&str] = &["format"];
+
+/// The keywords that check the encoding and media type of string contents.
+pub const CONTENT_KEYWORDS: &[&str] = &["contentEncoding", "contentMediaType", "contentSchema"];
+
+/// The keywords that check the shape of the instance: the types of values,
+/// which properties and elements exist, and the applicators that reach the
+/// nested values.
+pub const STRUCTURAL_KEYWORDS: &[&str] = &[
+    "$ref",
+    "additionalItems",
+    "additionalProperties",
+    "allOf",
+    "anyOf",
+    "contains",
+    "dependencies",
+    "else",
+    "if",
+    "items",
+    "not",
+    "oneOf",
+    "patternProperties",
+    "properties",
+    "propertyNames",
+    "required",
+    "then",
+    "type",
+];
+
+/// A selection of keywords to validate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    /// The only enabled keywords, or `None` if all are enabled except those
+    /// in `disabled`.
+    enabled: Option<HashSet<String>>,
+    disabled: HashSet<String>,
+}
+
+impl Default for Profile {
+    fn default() -> Profile {
+        Profile::all()
+    }
+}
+
+impl Profile {
+    /// A profile with all keywords enabled.
+    pub fn all() -> Profile {
+        Profile {
+            enabled: None,
+            disabled: HashSet::new(),
+        }
+    }
+
+    /// A profile with only the given keywords enabled.
+    pub fn only(keywords: &[&str]) -> Profile {
+        Profile {
+            enabled: Some(keywords.iter().map(|x| x.to_string()).collect()),
+            disabled: HashSet::new(),
+        }
+    }
+
+    /// Enable the given keywords as well.
+    pub fn with(mut self, keywords: &[&str]) -> Self {
+        for keyword in keywords {
+            self.disabled.remove(*keyword);
+            if let Some(enabled) = &mut self.enabled {
+                enabled.insert(keyword.to_string());
+            }
+        }
+        self
+    }
+
+    /// Disable the given keywords.
+    pub fn without(mut self, keywords: &[&str]) -> Self {
+        self.disabled.extend(keywords.iter().map(|x| x.to_string()));
+        self
+    }
+
+    /// Whether `keyword` is validated.
+    pub fn enables(&self, keyword: &str) -> bool {
+        let enabled = match &self.enabled {
+            Some(enabled) => enabled.contains(keyword),
+            None => true,
+        };
+        enabled && !self.disabled.contains(keyword)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile() {
+        let profile = Profile::only(STRUCTURAL_KEYWORDS)
+            .with(&["format", "minimum"])
+            .without(&["minimum", "anyOf"]);
+        assert!(profile.enables("type"));
+        assert!(profile.enables("format"));
+        assert!(!profile.enables("minimum"));
+        assert!(!profile.enables("anyOf"));
+        assert!(!profile.enables("maxLength"));
+        assert!(Profile::all().with(&["x"]).enables("maxLength"));
+    }
+}
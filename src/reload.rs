@@ -0,0 +1,220 @@
+//! Replacing a compiled schema while it's in use.
+//!
+//! Long-running services that validate against schemas from a registry need
+//! to pick up changes to them without restarting. A `ReloadableSchema` holds
+//! the function that builds a `Config`, typically fetching the schema and the
+//! documents it references, e.g. with `Config::with_http_resolver`, and the
+//! last `Config` it built.
+//!
+//! `ReloadableSchema::reload` builds the `Config` again, checks it with
+//! `Config::warm_up`, and swaps it in atomically: validations that already
+//! got the previous `Config` from `ReloadableSchema::current` finish with it,
+//! and later ones get the new one. If building or checking the new `Config`
+//! fails, the previous one stays in use.
+//!
+//! With the `reload` feature, `ReloadableSchema::spawn_reloads` reloads in a
+//! background thread at a fixed interval.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::{schemas, Config};
+//! use std::sync::atomic::{AtomicU64, Ordering};
+//! use jsonschema_valid::reload::ReloadableSchema;
+//!
+//! // Stands in for a registry the schema is fetched from.
+//! let registry = AtomicU64::new(1);
+//! let schema = ReloadableSchema::new(move || {
+//!     let maximum = registry.fetch_add(1, Ordering::SeqCst);
+//!     Config::from_owned_schema(json!({"maximum": maximum}), Some(&schemas::Draft7))
+//! })
+//! .unwrap();
+//! assert!(schema.current().validate(&json!(2)).is_err());
+//!
+//! schema.reload().unwrap();
+//! assert!(schema.current().validate(&json!(2)).is_ok());
+//! ```
+
+use std::sync::{Arc, RwLock};
+#[cfg(feature = "reload")]
+use std::{sync::mpsc, thread, time::Duration};
+
+use crate::config::Config;
+use crate::error::ValidationError;
+
+/// The function that builds the `Config` of a `ReloadableSchema`.
+type Compile = dyn Fn() -> Result<Config<'static>, ValidationError> + Send + Sync;
+
+/// A compiled schema that can be rebuilt and replaced while it's in use.
+pub struct ReloadableSchema {
+    compile: Box<Compile>,
+    current: RwLock<Arc<Config<'static>>>,
+}
+
+impl ReloadableSchema {
+    /// Build the `Config` with `compile`, which is called again on every
+    /// reload.
+    ///
+    /// Fails if `compile` does, or if the `Config` it returns doesn't pass
+    /// `Config::warm_up`.
+    pub fn new<F>(compile: F) -> Result<ReloadableSchema, ValidationError>
+    where
+        F: Fn() -> Result<Config<'static>, ValidationError> + Send + Sync + 'static,
+    {
+        let cfg = build(&compile)?;
+        Ok(ReloadableSchema {
+            compile: Box::new(compile),
+            current: RwLock::new(Arc::new(cfg)),
+        })
+    }
+
+    /// The `Config` currently in use.
+    ///
+    /// It isn't affected by later reloads, so a validation should get it once
+    /// and use it throughout.
+    pub fn current(&self) -> Arc<Config<'static>> {
+        // The lock only guards replacing the `Arc`, so a poisoned lock can
+        // safely be reused.
+        self.current
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    /// Build the `Config` again and replace the current one with it.
+    ///
+    /// If building or checking it fails, the current `Config` is kept and the
+    /// error is returned. The errors found by `Config::warm_up` are the
+    /// causes of the returned error.
+    pub fn reload(&self) -> Result<(), ValidationError> {
+        let cfg = Arc::new(build(&*self.compile)?);
+        *self.current.write().unwrap_or_else(|err| err.into_inner()) = cfg;
+        Ok(())
+    }
+
+    /// Reload every `interval` in a background thread, until the returned
+    /// `Reloader` is stopped or dropped.
+    ///
+    /// Failed reloads are passed to `on_error`, e.g. to log them, and the
+    /// current `Config` is kept until a later reload succeeds.
+    ///
+    /// This is only available with the `reload` feature.
+    #[cfg(feature = "reload")]
+    pub fn spawn_reloads<F>(self: &Arc<Self>, interval: Duration, on_error: F) -> Reloader
+    where
+        F: Fn(ValidationError) + Send + 'static,
+    {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let schema = Arc::clone(self);
+        let thread = thread::spawn(move || {
+            while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                if let Err(err) = schema.reload() {
+                    on_error(err);
+                }
+            }
+        });
+        Reloader {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+/// Build a `Config` with `compile` and check it.
+fn build(compile: &Compile) -> Result<Config<'static>, ValidationError> {
+    let cfg = compile()?;
+    let errors: Vec<ValidationError> = match cfg.warm_up() {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.collect(),
+    };
+    if errors.is_empty() {
+        return Ok(cfg);
+    }
+    Err(ValidationError::new(&format!(
+        "The schema has {} error{}",
+        errors.len(),
+        if errors.len() == 1 { "" } else { "s" }
+    ))
+    .with_causes(errors))
+}
+
+/// The background thread started by `ReloadableSchema::spawn_reloads`.
+///
+/// Dropping it stops the thread, after the reload in progress if any.
+#[cfg(feature = "reload")]
+pub struct Reloader {
+    stop: Option<mpsc::Sender<()>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+#[cfg(feature = "reload")]
+impl Reloader {
+    /// Stop reloading, and wait for the reload in progress if any.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        // Dropping the sender wakes the thread up.
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(feature = "reload")]
+impl Drop for Reloader {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_failed_reload() {
+        let calls = AtomicUsize::new(0);
+        let schema = ReloadableSchema::new(move || {
+            let schema = match calls.fetch_add(1, Ordering::SeqCst) {
+                0 => json!({"type": "string"}),
+                _ => json!({"type": "strng"}),
+            };
+            Config::from_owned_schema(schema, Some(&schemas::Draft7))
+        })
+        .unwrap();
+        let before = schema.current();
+        let err = schema.reload().unwrap_err();
+        assert_eq!(err.message(), "The schema has 1 error");
+        assert_eq!(err.causes()[0].instance_path(), ["type"]);
+        assert!(Arc::ptr_eq(&before, &schema.current()));
+    }
+
+    #[cfg(feature = "reload")]
+    #[test]
+    fn test_spawn_reloads() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let schema = Arc::new(
+            ReloadableSchema::new(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Config::from_owned_schema(json!({}), Some(&schemas::Draft7))
+            })
+            .unwrap(),
+        );
+        let reloader = schema.spawn_reloads(Duration::from_millis(1), |err| panic!("{}", err));
+        while calls.load(Ordering::SeqCst) < 3 {
+            thread::yield_now();
+        }
+        reloader.stop();
+        let reloads = calls.load(Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(calls.load(Ordering::SeqCst), reloads);
+    }
+}
@@ -0,0 +1,483 @@
+//! Fetching the schema documents that `$ref`s point to over HTTP.
+//!
+//! An `HttpResolver` fetches the documents referenced by `http` and `https`
+//! URLs, and `Config::with_http_resolver` uses it to load all the documents
+//! a schema references, and the ones they reference in turn, before
+//! validating. Fetched documents are cached in memory, and optionally on
+//! disk, so that long-running services compiling schemas over and over
+//! again don't fetch them every time:
+//!
+//! * A response with `Cache-Control: max-age=<n>` is reused for `n` seconds,
+//!   one with `no-cache` is revalidated every time, and one with `no-store`
+//!   isn't cached at all.
+//! * Otherwise, the time to live set with `HttpResolver::with_ttl` applies.
+//!   Without one, documents are revalidated every time.
+//! * A stale document with an `ETag` is revalidated with `If-None-Match`, and
+//!   reused if the server replies that it hasn't changed.
+//!
+//! Requests go through the proxy set in the `http_proxy` and `https_proxy`
+//! environment variables, if any. Authentication headers can be added with
+//! `HttpResolver::with_header` and `HttpResolver::with_bearer_token`, and a
+//! client configured otherwise, e.g. with another proxy or additional root
+//! certificates, can be used with `HttpResolver::with_session`. To make sure
+//! a fetched document is the one that was reviewed, pin its hash with
+//! `Config::with_pinned_hash`.
+//!
+//! This module is only available with the `remote` feature.
+//!
+//! ## Example:
+//!
+//! ```no_run
+//! # fn main() -> Result<(), jsonschema_valid::ValidationError> {
+//! # use serde_json::json;
+//! # use jsonschema_valid::{schemas, Config};
+//! use std::time::Duration;
+//! use jsonschema_valid::remote::HttpResolver;
+//!
+//! // Shared by all compilations, so they share its cache.
+//! let resolver = HttpResolver::new()
+//!     .with_ttl(Duration::from_secs(600))
+//!     .with_bearer_token("secret");
+//!
+//! let schema = json!({"$ref": "https://example.com/schemas/user.json"});
+//! let cfg = Config::from_schema(&schema, Some(&schemas::Draft7))?
+//!     .with_http_resolver(&resolver)?;
+//! assert!(cfg.validate(&json!({"name": "x"})).is_ok());
+//! # Ok(()) }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use attohttpc::header::{HeaderMap, HeaderName};
+use attohttpc::{Session, StatusCode};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::error::ValidationError;
+use crate::resolver::SchemeResolver;
+
+/// A fetched document, and until when it can be used without asking the
+/// server again.
+#[derive(Clone)]
+struct Entry {
+    document: Value,
+    etag: Option<String>,
+    expires: SystemTime,
+}
+
+/// Fetches schema documents over HTTP, and caches them.
+pub struct HttpResolver {
+    session: Session,
+    headers: Vec<(String, String)>,
+    connect_timeout: Duration,
+    read_timeout: Duration,
+    retries: u32,
+    backoff: Duration,
+    max_size: Option<u64>,
+    ttl: Option<Duration>,
+    cache_dir: Option<PathBuf>,
+    cache: Mutex<HashMap<String, Entry>>,
+}
+
+impl Default for HttpResolver {
+    fn default() -> Self {
+        HttpResolver::new()
+    }
+}
+
+impl HttpResolver {
+    /// Create a resolver with an empty in-memory cache.
+    ///
+    /// Connecting times out after 10 seconds, and reading after 30 seconds
+    /// without receiving anything. Failed requests aren't retried, and the
+    /// size of responses isn't limited.
+    pub fn new() -> HttpResolver {
+        HttpResolver {
+            session: Session::new(),
+            headers: Vec::new(),
+            connect_timeout: Duration::from_secs(10),
+            read_timeout: Duration::from_secs(30),
+            retries: 0,
+            backoff: Duration::from_millis(500),
+            max_size: None,
+            ttl: None,
+            cache_dir: None,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Send requests with `session`, which carries the settings of the client
+    /// such as the proxy, the trusted certificates and the default headers.
+    pub fn with_session(mut self, session: Session) -> Self {
+        self.session = session;
+        self
+    }
+
+    /// Send the header `name` with `value` with every request.
+    ///
+    /// An invalid name or value makes fetching fail.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Authenticate requests with the bearer token `token`.
+    pub fn with_bearer_token(self, token: &str) -> Self {
+        self.with_header("Authorization", &format!("Bearer {}", token))
+    }
+
+    /// Give up connecting to the server after `connect`, and waiting for
+    /// data from it after `read`.
+    pub fn with_timeouts(mut self, connect: Duration, read: Duration) -> Self {
+        self.connect_timeout = connect;
+        self.read_timeout = read;
+        self
+    }
+
+    /// Retry requests that fail to connect, time out, or get a 429 or 5xx
+    /// status, up to `retries` times, waiting `backoff` before the first
+    /// retry and twice as long before each following one.
+    pub fn with_retries(mut self, retries: u32, backoff: Duration) -> Self {
+        self.retries = retries;
+        self.backoff = backoff;
+        self
+    }
+
+    /// Fail to fetch documents larger than `bytes`.
+    pub fn with_max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Reuse fetched documents for `ttl` when the server doesn't say how long
+    /// they stay fresh.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Also keep fetched documents in files in the directory `dir`, so that
+    /// other processes can reuse them.
+    pub fn with_cache_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Fetch the JSON document at `url`, or take it from the cache if it's
+    /// still fresh there.
+    pub fn fetch(&self, url: &url::Url) -> Result<Value, ValidationError> {
+        let key = url.as_str();
+        let cached = self.cache.lock().unwrap().get(key).cloned();
+        let cached = cached.or_else(|| self.load(key));
+        let now = SystemTime::now();
+        if let Some(entry) = &cached {
+            if entry.expires > now {
+                return Ok(entry.document.clone());
+            }
+        }
+
+        let etag = cached.as_ref().and_then(|entry| entry.etag.as_deref());
+        let mut attempt = 0;
+        let (status, headers, body) = loop {
+            match self.send(url, etag) {
+                Ok((status, _, _))
+                    if (status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS)
+                        && attempt < self.retries => {}
+                Err((_, true)) if attempt < self.retries => {}
+                Ok(response) => break response,
+                Err((err, _)) => return Err(err),
+            }
+            thread::sleep(self.backoff * 2u32.saturating_pow(attempt));
+            attempt += 1;
+        };
+        let (expires, store) = self.freshness(&headers, now);
+        let entry = match cached {
+            Some(entry) if status == StatusCode::NOT_MODIFIED => Entry { expires, ..entry },
+            _ if status.is_success() => Entry {
+                document: serde_json::from_slice(&body).map_err(|err| fetch_error(url, &err))?,
+                etag: header(&headers, "etag"),
+                expires,
+            },
+            _ => return Err(fetch_error(url, &status)),
+        };
+        if store {
+            self.store(key, &entry);
+        }
+        Ok(entry.document)
+    }
+
+    /// Send a single request for `url`, returning the status, headers and
+    /// body of the response, or the error and whether it's worth retrying.
+    fn send(
+        &self,
+        url: &url::Url,
+        etag: Option<&str>,
+    ) -> Result<(StatusCode, HeaderMap, Vec<u8>), (ValidationError, bool)> {
+        let fail = |err: &dyn fmt::Display, retry| (fetch_error(url, err), retry);
+        let mut request = self
+            .session
+            .get(url.as_str())
+            .header("Accept", "application/schema+json, application/json")
+            .connect_timeout(self.connect_timeout)
+            .read_timeout(self.read_timeout);
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|err| fail(&err, false))?;
+            request = request
+                .try_header(name, value.as_str())
+                .map_err(|err| fail(&err, false))?;
+        }
+        if let Some(etag) = etag {
+            request = request.header("If-None-Match", etag);
+        }
+        let (status, headers, reader) = request.send().map_err(|err| fail(&err, true))?.split();
+
+        let limit = self.max_size.unwrap_or(u64::MAX);
+        let too_large = format!("the document is larger than {} bytes", limit);
+        let length = header(&headers, "content-length").and_then(|x| x.parse::<u64>().ok());
+        if length.unwrap_or(0) > limit {
+            return Err(fail(&too_large, false));
+        }
+        let mut body = Vec::new();
+        reader
+            .take(limit.saturating_add(1))
+            .read_to_end(&mut body)
+            .map_err(|err| fail(&err, true))?;
+        if body.len() as u64 > limit {
+            return Err(fail(&too_large, false));
+        }
+        Ok((status, headers, body))
+    }
+
+    /// Until when a response with the given headers is fresh, and whether
+    /// it may be stored at all.
+    fn freshness(&self, headers: &HeaderMap, now: SystemTime) -> (SystemTime, bool) {
+        let cache_control = header(headers, "cache-control")
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let (mut max_age, mut no_cache, mut store) = (None, false, true);
+        for directive in cache_control.split(',').map(str::trim) {
+            match directive {
+                "no-store" => store = false,
+                "no-cache" => no_cache = true,
+                _ if directive.starts_with("max-age=") => {
+                    max_age = directive["max-age=".len()..].trim_matches('"').parse().ok()
+                }
+                _ => {}
+            }
+        }
+        let ttl = match (no_cache, max_age) {
+            (true, _) => Duration::from_secs(0),
+            (false, Some(seconds)) => Duration::from_secs(seconds),
+            (false, None) => self.ttl.unwrap_or_default(),
+        };
+        (now + ttl, store)
+    }
+
+    /// The file caching the document at `url`.
+    fn cache_file(&self, url: &str) -> Option<PathBuf> {
+        let digest = Sha256::digest(url.as_bytes());
+        let name: String = digest.iter().map(|x| format!("{:02x}", x)).collect();
+        Some(self.cache_dir.as_ref()?.join(name + ".json"))
+    }
+
+    fn load(&self, url: &str) -> Option<Entry> {
+        let contents = fs::read(self.cache_file(url)?).ok()?;
+        let cached: Value = serde_json::from_slice(&contents).ok()?;
+        if cached["url"] != url {
+            return None;
+        }
+        Some(Entry {
+            document: cached["document"].clone(),
+            etag: cached["etag"].as_str().map(String::from),
+            expires: UNIX_EPOCH + Duration::from_secs(cached["expires"].as_u64()?),
+        })
+    }
+
+    fn store(&self, url: &str, entry: &Entry) {
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(url.to_string(), entry.clone());
+        if let Some(path) = self.cache_file(url) {
+            let expires = entry
+                .expires
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |x| x.as_secs());
+            let cached = json!({
+                "url": url,
+                "etag": entry.etag,
+                "expires": expires,
+                "document": entry.document,
+            });
+            // The disk cache is only an optimization, so failing to write
+            // to it isn't an error.
+            if let Some(dir) = path.parent() {
+                let _ = fs::create_dir_all(dir);
+            }
+            let _ = fs::write(path, cached.to_string());
+        }
+    }
+}
+
+impl SchemeResolver for HttpResolver {
+    fn resolve(&self, url: &url::Url) -> Result<Value, ValidationError> {
+        self.fetch(url)
+    }
+}
+
+impl SchemeResolver for &HttpResolver {
+    fn resolve(&self, url: &url::Url) -> Result<Value, ValidationError> {
+        self.fetch(url)
+    }
+}
+
+fn fetch_error(url: &url::Url, err: &dyn fmt::Display) -> ValidationError {
+    ValidationError::new(&format!("Can't fetch {}: {}", url, err))
+}
+
+fn header(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::sync::Arc;
+
+    /// Serve `document` with the given extra headers, answering requests
+    /// with a matching `If-None-Match` with 304, and record the requests.
+    fn serve(headers: &'static str, document: Value) -> (String, Arc<Mutex<Vec<String>>>) {
+        serve_with(move |_, request| {
+            let body = document.to_string();
+            Some(if request.contains("if-none-match: \"v1\"") {
+                "HTTP/1.1 304 Not Modified\r\nContent-Length: 0\r\n\r\n".to_string()
+            } else {
+                format!(
+                    "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\n{}Content-Length: {}\r\n\r\n{}",
+                    headers,
+                    body.len(),
+                    body
+                )
+            })
+        })
+    }
+
+    /// Answer the `n`th request with `respond(n, request)`, or not at all if
+    /// it returns `None`, and record the requests.
+    fn serve_with<F>(respond: F) -> (String, Arc<Mutex<Vec<String>>>)
+    where
+        F: Fn(usize, &str) -> Option<String> + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/schema.json", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let n = stream.read(&mut buffer).unwrap();
+                    request.extend_from_slice(&buffer[..n]);
+                }
+                let request = String::from_utf8(request).unwrap().to_ascii_lowercase();
+                let n = log.lock().unwrap().len();
+                let response = respond(n, &request);
+                log.lock().unwrap().push(request);
+                match response {
+                    Some(response) => stream.write_all(response.as_bytes()).unwrap(),
+                    None => thread::sleep(Duration::from_secs(1)),
+                }
+            }
+        });
+        (url, requests)
+    }
+
+    #[test]
+    fn test_cache() {
+        let document = json!({"definitions": {"name": {"type": "string"}}});
+        let (url, requests) = serve("Cache-Control: max-age=60\r\n", document.clone());
+        let resolver = HttpResolver::new();
+        let url = url::Url::parse(&url).unwrap();
+        assert_eq!(resolver.fetch(&url).unwrap(), document);
+        assert_eq!(resolver.fetch(&url).unwrap(), document);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+
+        let schema =
+            json!({"properties": {"name": {"$ref": format!("{}#/definitions/name", url)}}});
+        let cfg = crate::Config::from_schema(&schema, None)
+            .unwrap()
+            .with_http_resolver(&resolver)
+            .unwrap();
+        assert!(cfg.validate(&json!({"name": "x"})).is_ok());
+        assert!(cfg.validate(&json!({"name": 1})).is_err());
+        assert_eq!(requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_headers() {
+        let (url, requests) = serve("", json!({}));
+        let resolver = HttpResolver::new()
+            .with_header("X-Tenant", "a")
+            .with_bearer_token("secret");
+        resolver.fetch(&url::Url::parse(&url).unwrap()).unwrap();
+        let request = &requests.lock().unwrap()[0];
+        assert!(request.contains("x-tenant: a\r\n"));
+        assert!(request.contains("authorization: bearer secret\r\n"));
+    }
+
+    #[test]
+    fn test_retries() {
+        let (url, requests) = serve_with(|n, _| {
+            Some(match n {
+                0 => "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n".to_string(),
+                _ => "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}".to_string(),
+            })
+        });
+        let url = url::Url::parse(&url).unwrap();
+        let resolver = HttpResolver::new().with_retries(1, Duration::from_millis(1));
+        assert_eq!(resolver.fetch(&url).unwrap(), json!({}));
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_limits() {
+        let (url, _) = serve("", json!({"description": "x".repeat(100)}));
+        let resolver = HttpResolver::new().with_max_size(100);
+        let err = resolver.fetch(&url::Url::parse(&url).unwrap()).unwrap_err();
+        assert!(err
+            .message()
+            .ends_with("the document is larger than 100 bytes"));
+
+        let (url, _) = serve_with(|_, _| None);
+        let resolver =
+            HttpResolver::new().with_timeouts(Duration::from_secs(1), Duration::from_millis(50));
+        assert!(resolver.fetch(&url::Url::parse(&url).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_revalidation() {
+        let document = json!({"type": "string"});
+        let (url, requests) = serve("", document.clone());
+        let resolver = HttpResolver::new();
+        let url = url::Url::parse(&url).unwrap();
+        assert_eq!(resolver.fetch(&url).unwrap(), document);
+        assert_eq!(resolver.fetch(&url).unwrap(), document);
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].contains("if-none-match: \"v1\""));
+    }
+}
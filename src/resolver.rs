@@ -1,50 +1,221 @@
-use std::collections::HashMap;
+//! Resolving `$ref`s within a schema document.
+//!
+//! References to other documents are resolved against the documents loaded
+//! when compiling the schema, with the `SchemeResolver` registered for the
+//! scheme of their URL with `Config::with_scheme_resolver`.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::{json, Value};
+//! # use jsonschema_valid::{schemas, Config, ValidationError};
+//! let fetch = |url: &url::Url| -> Result<Value, ValidationError> {
+//!     match url.as_str() {
+//!         "pkg://common/name.json" => Ok(json!({"type": "string"})),
+//!         _ => Err(ValidationError::new("No such package")),
+//!     }
+//! };
+//! let schema = json!({"properties": {"name": {"$ref": "pkg://common/name.json"}}});
+//! let cfg = Config::from_schema(&schema, Some(&schemas::Draft7))
+//!     .unwrap()
+//!     .with_scheme_resolver("pkg", fetch)
+//!     .unwrap();
+//! assert!(cfg.validate(&json!({"name": 1})).is_err());
+//! ```
+
+use std::collections::{HashMap, HashSet};
 
 use serde_json::Value;
 
 use crate::context::Context;
 use crate::error::ValidationError;
 use crate::schemas;
+use crate::util;
 // TODO: Make the choice of resolver dynamic
 
-fn id_of(schema: &Value) -> Option<&str> {
+/// Fetches the schema documents referenced by URLs of a given scheme.
+///
+/// Implemented by closures taking the URL and returning the document.
+pub trait SchemeResolver: Send + Sync {
+    /// Get the JSON document at `url`.
+    fn resolve(&self, url: &url::Url) -> Result<Value, ValidationError>;
+}
+
+impl<F> SchemeResolver for F
+where
+    F: Fn(&url::Url) -> Result<Value, ValidationError> + Send + Sync,
+{
+    fn resolve(&self, url: &url::Url) -> Result<Value, ValidationError> {
+        self(url)
+    }
+}
+
+/// The id of `schema`. Since draft 6, ids are given with `$id`; with
+/// `legacy_ids`, as in draft 4, `id` is used too.
+fn id_of(schema: &Value, legacy_ids: bool) -> Option<&str> {
     if let Value::Object(object) = schema {
         object
             .get("$id")
-            .or_else(|| object.get("id"))
+            .or_else(|| object.get("id").filter(|_| legacy_ids))
             .and_then(Value::as_str)
     } else {
         None
     }
 }
 
-pub struct Resolver<'a> {
+/// Whether `id` gives the ids of the subschemas of `schema`, according to
+/// the draft it declares, or `default` if it declares none.
+pub(crate) fn uses_legacy_ids(schema: &Value, default: bool) -> bool {
+    schemas::draft_from_schema(schema).map_or(default, |draft| draft.uses_legacy_id())
+}
+
+/// Where a subschema that can be looked up directly is.
+struct Location {
+    /// The URL of the document it is in, unless it is the schema itself.
+    document: Option<String>,
+    /// The JSON pointer to it within the document.
+    pointer: String,
+    /// The URL that references within it are resolved against.
+    base_url: url::Url,
+}
+
+/// Resolves references within a schema document.
+///
+/// Subschemas with an id are stored by their JSON pointer within the document,
+/// rather than by reference, so the resolver doesn't borrow the document and
+/// a `Config` can own its schema.
+///
+/// The subschemas in `$defs` and `definitions`, and those with an anchor, are
+/// registered by the URL that references them, so references to them are
+/// resolved with a single lookup.
+///
+/// References to other documents are resolved against the documents added
+/// with `add_document`.
+pub struct Resolver {
     base_url: String,
-    id_mapping: HashMap<String, &'a Value>,
+    id_mapping: HashMap<String, Location>,
+    subschemas: HashMap<String, Location>,
+    documents: HashMap<String, Value>,
+    references: Vec<url::Url>,
+    schemes: HashSet<String>,
+    legacy_ids: bool,
 }
 
-fn find_ids<'a>(
-    schema: &'a Value,
-    id_mapping: &mut HashMap<String, &'a Value>,
+/// The ids, definitions, anchors and references found in a schema document.
+#[derive(Default)]
+struct Registry {
+    document: Option<String>,
+    legacy_ids: bool,
+    id_mapping: HashMap<String, Location>,
+    subschemas: HashMap<String, Location>,
+    references: Vec<url::Url>,
+}
+
+impl Registry {
+    fn location(&self, pointer: &str, base_url: url::Url) -> Location {
+        Location {
+            document: self.document.clone(),
+            pointer: pointer.to_string(),
+            base_url,
+        }
+    }
+
+    fn register(&mut self, resource: &url::Url, fragment: &str, pointer: &str, base_url: url::Url) {
+        let mut url = resource.clone();
+        url.set_fragment(Some(fragment));
+        let location = self.location(pointer, base_url);
+        self.subschemas.insert(url.to_string(), location);
+    }
+}
+
+/// The URL of the resource that `schema`, found in the resource at
+/// `base_url`, starts, if it has a non-fragment id.
+fn resource_url(
+    schema: &Value,
     base_url: &url::Url,
+    legacy_ids: bool,
+) -> Result<Option<url::Url>, ValidationError> {
+    match id_of(schema, legacy_ids) {
+        Some(id) if !id.starts_with('#') => {
+            let mut url = base_url.join(id)?;
+            url.set_fragment(None);
+            Ok(Some(url))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Record the ids, definitions and anchors in `schema`, which is at `pointer`
+/// in the document, within the resource at `base_url` that starts at the
+/// byte `resource_start` of `pointer`.
+fn find_ids(
+    schema: &Value,
+    registry: &mut Registry,
+    base_url: &url::Url,
+    resource_start: usize,
+    pointer: &mut String,
 ) -> Result<(), ValidationError> {
     match schema {
         Value::Object(object) => {
-            if let Some(url) = id_of(schema) {
-                id_mapping.insert(url.to_string(), schema);
-                let new_url = base_url.join(url)?;
-                for (_k, v) in object {
-                    find_ids(v, id_mapping, &new_url)?;
+            let (new_url, start) = match resource_url(schema, base_url, registry.legacy_ids)? {
+                Some(new_url) => {
+                    let location = registry.location(pointer, new_url.clone());
+                    registry.id_mapping.insert(new_url.to_string(), location);
+                    (new_url, pointer.len())
                 }
-            } else {
-                for (_k, v) in object {
-                    find_ids(v, id_mapping, base_url)?;
+                None => (base_url.clone(), resource_start),
+            };
+            let anchor = match id_of(schema, registry.legacy_ids) {
+                Some(id) if id.starts_with('#') => Some(&id[1..]),
+                _ => object.get("$anchor").and_then(Value::as_str),
+            };
+            // A `$dynamicAnchor` of draft 2020-12 is also a plain anchor.
+            let dynamic_anchor = object.get("$dynamicAnchor").and_then(Value::as_str);
+            for anchor in anchor.into_iter().chain(dynamic_anchor) {
+                registry.register(&new_url, anchor, pointer, new_url.clone());
+            }
+            if let Some(Value::String(reference)) = object.get("$ref") {
+                if let Ok(mut url) = new_url.join(reference) {
+                    url.set_fragment(None);
+                    registry.references.push(url);
                 }
             }
+            for keyword in &["$defs", "definitions"] {
+                if let Some(Value::Object(definitions)) = object.get(*keyword) {
+                    for (name, definition) in definitions {
+                        let definition_pointer = format!(
+                            "{}/{}/{}",
+                            pointer,
+                            keyword,
+                            util::escape_pointer_token(name)
+                        );
+                        let definition_url =
+                            resource_url(definition, &new_url, registry.legacy_ids)?
+                                .unwrap_or_else(|| new_url.clone());
+                        registry.register(
+                            &new_url,
+                            &definition_pointer[start..],
+                            &definition_pointer,
+                            definition_url,
+                        );
+                    }
+                }
+            }
+            for (k, v) in object {
+                let len = pointer.len();
+                pointer.push('/');
+                pointer.push_str(&util::escape_pointer_token(k));
+                find_ids(v, registry, &new_url, start, pointer)?;
+                pointer.truncate(len);
+            }
         }
         Value::Array(array) => {
-            for v in array {
-                find_ids(v, id_mapping, base_url)?;
+            for (i, v) in array.iter().enumerate() {
+                let len = pointer.len();
+                pointer.push('/');
+                pointer.push_str(&i.to_string());
+                find_ids(v, registry, base_url, resource_start, pointer)?;
+                pointer.truncate(len);
             }
         }
         _ => {}
@@ -52,29 +223,185 @@ fn find_ids<'a>(
     Ok(())
 }
 
-impl<'a> Resolver<'a> {
-    pub fn from_schema(schema: &'a Value) -> Result<Resolver<'a>, ValidationError> {
-        let base_url = match id_of(schema) {
+impl Resolver {
+    /// Create a resolver for the given schema document, recording the
+    /// location of every subschema with an id, definition and anchor.
+    ///
+    /// Whether ids are given with `id`, as in draft 4, or `$id` depends on
+    /// the draft declared by the document, draft 7 by default.
+    pub fn from_schema(schema: &Value) -> Result<Resolver, ValidationError> {
+        Resolver::from_schema_for_draft(schema, uses_legacy_ids(schema, false))
+    }
+
+    /// Create a resolver for the given schema document, as with
+    /// `from_schema`, where `legacy_ids` is whether `id` gives ids.
+    pub(crate) fn from_schema_for_draft(
+        schema: &Value,
+        legacy_ids: bool,
+    ) -> Result<Resolver, ValidationError> {
+        let base_url = match id_of(schema, legacy_ids) {
             Some(url) => url.to_string(),
             None => "document:///".to_string(),
         };
 
-        let mut id_mapping: HashMap<String, &'a Value> = HashMap::new();
-
-        find_ids(schema, &mut id_mapping, &url::Url::parse(&base_url)?)?;
+        let mut registry = Registry {
+            legacy_ids,
+            ..Registry::default()
+        };
+        find_ids(
+            schema,
+            &mut registry,
+            &url::Url::parse(&base_url)?,
+            0,
+            &mut String::new(),
+        )?;
 
         Ok(Resolver {
             base_url,
-            id_mapping,
+            id_mapping: registry.id_mapping,
+            subschemas: registry.subschemas,
+            documents: HashMap::new(),
+            references: registry.references,
+            schemes: HashSet::new(),
+            legacy_ids,
+        })
+    }
+
+    /// Create a resolver for the given schema document, found at
+    /// `base_url`: relative references and ids in it are resolved against
+    /// that URL rather than a placeholder, and references to the URL itself
+    /// lead to the schema. An id at the root of the schema is resolved
+    /// against `base_url` too.
+    pub fn from_schema_with_base(
+        schema: &Value,
+        base_url: &url::Url,
+    ) -> Result<Resolver, ValidationError> {
+        Resolver::from_schema_with_base_for_draft(schema, base_url, uses_legacy_ids(schema, false))
+    }
+
+    fn from_schema_with_base_for_draft(
+        schema: &Value,
+        base_url: &url::Url,
+        legacy_ids: bool,
+    ) -> Result<Resolver, ValidationError> {
+        let mut base_url = base_url.clone();
+        base_url.set_fragment(None);
+        let mut registry = Registry {
+            legacy_ids,
+            ..Registry::default()
+        };
+        find_ids(schema, &mut registry, &base_url, 0, &mut String::new())?;
+        // References are joined with the ids in their context, including
+        // the one at the root, so the base URL is kept as given.
+        let url = resource_url(schema, &base_url, legacy_ids)?.unwrap_or_else(|| base_url.clone());
+        let location = registry.location("", url.clone());
+        registry
+            .id_mapping
+            .entry(url.to_string())
+            .or_insert(location);
+
+        Ok(Resolver {
+            base_url: base_url.to_string(),
+            id_mapping: registry.id_mapping,
+            subschemas: registry.subschemas,
+            documents: HashMap::new(),
+            references: registry.references,
+            schemes: HashSet::new(),
+            legacy_ids,
         })
     }
 
+    /// A resolver like this one, but for `schema` found at `base_url`; see
+    /// `from_schema_with_base`. The documents and schemes added to this one
+    /// are kept.
+    pub(crate) fn rebase(
+        self,
+        schema: &Value,
+        base_url: &url::Url,
+    ) -> Result<Resolver, ValidationError> {
+        let mut resolver =
+            Resolver::from_schema_with_base_for_draft(schema, base_url, self.legacy_ids)?;
+        for (url, document) in self.documents {
+            resolver.add_document(&url::Url::parse(&url)?, document)?;
+        }
+        resolver.schemes = self.schemes;
+        Ok(resolver)
+    }
+
+    /// Add the schema document `document`, found at `url`, so that
+    /// references to it and to the subschemas in it can be resolved.
+    pub fn add_document(&mut self, url: &url::Url, document: Value) -> Result<(), ValidationError> {
+        let mut url = url.clone();
+        url.set_fragment(None);
+        let mut registry = Registry {
+            document: Some(url.to_string()),
+            legacy_ids: uses_legacy_ids(&document, self.legacy_ids),
+            ..Registry::default()
+        };
+        find_ids(&document, &mut registry, &url, 0, &mut String::new())?;
+        self.id_mapping.extend(registry.id_mapping);
+        self.subschemas.extend(registry.subschemas);
+        self.references.extend(registry.references);
+        self.documents.insert(url.to_string(), document);
+        Ok(())
+    }
+
+    /// Record that documents with URLs of the given scheme are loaded by a
+    /// `SchemeResolver`.
+    pub(crate) fn add_scheme(&mut self, scheme: &str) {
+        self.schemes.insert(scheme.to_string());
+    }
+
+    /// Check whether references to the document at `url` can be resolved.
+    pub fn has_document(&self, url: &url::Url) -> bool {
+        let url = url.as_str();
+        url == "document:///"
+            || schemas::draft_from_url(url).is_some()
+            || self.id_mapping.contains_key(url)
+            || self.documents.contains_key(url)
+    }
+
+    /// The URLs of the documents that are referenced by the schema or the
+    /// documents added with `add_document`, but not known themselves.
+    pub fn missing_documents(&self) -> Vec<url::Url> {
+        let mut seen = HashSet::new();
+        self.references
+            .iter()
+            .filter(|url| !self.has_document(url) && seen.insert(url.as_str()))
+            .cloned()
+            .collect()
+    }
+
+    /// The URL that references in the schema are resolved against, unless
+    /// an id in the schema changes it.
+    pub(crate) fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// The documents added with `add_document`, by their URL.
+    pub(crate) fn documents(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.documents
+            .iter()
+            .map(|(url, document)| (url.as_str(), document))
+    }
+
+    /// Get the value at `location`, in `instance` or one of the documents.
+    fn locate<'a>(&'a self, location: &Location, instance: &'a Value) -> Option<&'a Value> {
+        let document = match &location.document {
+            Some(url) => self.documents.get(url)?,
+            None => instance,
+        };
+        document.pointer(&location.pointer)
+    }
+
+    /// Resolve the reference `url_ref` against the ids of the schema
+    /// documents in `ctx`.
     pub fn join_url(&self, url_ref: &str, ctx: &Context) -> Result<url::Url, ValidationError> {
         let mut urls: Vec<&str> = Vec::new();
         urls.push(url_ref);
         let mut frame = ctx;
         loop {
-            if let Some(id) = id_of(frame.x) {
+            if let Some(id) = id_of(frame.x, self.legacy_ids) {
                 urls.push(id);
             }
             match frame.parent {
@@ -87,8 +414,11 @@ impl<'a> Resolver<'a> {
         Ok(url?)
     }
 
-    pub fn resolve_url(
-        &self,
+    /// Get the schema document at `url`, which is either `instance`, a
+    /// document added with `add_document`, a subschema of either with an
+    /// id, or a draft's metaschema.
+    pub fn resolve_url<'a>(
+        &'a self,
         url: &url::Url,
         instance: &'a Value,
     ) -> Result<&'a Value, ValidationError> {
@@ -97,32 +427,332 @@ impl<'a> Resolver<'a> {
             "document:///" => Ok(instance),
             _ => match schemas::draft_from_url(url_str) {
                 Some(value) => Ok(value.get_schema()),
-                _ => match self.id_mapping.get(url_str) {
+                _ => match self
+                    .id_mapping
+                    .get(url_str)
+                    .and_then(|location| self.locate(location, instance))
+                    .or_else(|| self.documents.get(url_str))
+                {
                     Some(value) => Ok(value),
-                    None => Err(ValidationError::new("Can't fetch document")),
+                    None if url.scheme() == "document" || self.schemes.contains(url.scheme()) => {
+                        Err(ValidationError::new("Can't fetch document"))
+                    }
+                    None => Err(ValidationError::new(&format!(
+                        "Can't fetch document: no resolver for the URI scheme '{}'",
+                        url.scheme()
+                    ))),
                 },
             },
         }
     }
 
-    pub fn resolve_fragment(
-        &self,
+    /// Get the schema that the reference `url` points to, together with
+    /// the URL of the document containing it.
+    pub fn resolve_fragment<'a>(
+        &'a self,
         url: &str,
         ctx: &Context,
         instance: &'a Value,
     ) -> Result<(url::Url, &'a Value), ValidationError> {
         let url = self.join_url(url, ctx)?;
+        if let Some(location) = self.subschemas.get(url.as_str()) {
+            if let Some(x) = self.locate(location, instance) {
+                return Ok((location.base_url.clone(), x));
+            }
+        }
         let mut resource = url.clone();
         resource.set_fragment(None);
         let document = self.resolve_url(&resource, instance)?;
-        let fragment =
-            percent_encoding::percent_decode(url.fragment().unwrap_or_else(|| "").as_bytes())
-                .decode_utf8()
-                .unwrap();
+        let fragment = percent_encoding::percent_decode(url.fragment().unwrap_or("").as_bytes())
+            .decode_utf8()
+            .map_err(|_| ValidationError::new(&format!("Invalid fragment in {}", url)))?;
+        if !(fragment.is_empty() || fragment.starts_with('/')) {
+            // Anchors in documents that aren't registered, i.e. metaschemas.
+            if let Some(value) = find_anchor(document, &fragment) {
+                return Ok((resource, value));
+            }
+        }
         // TODO Prevent infinite reference recursion
-        match document.pointer(&fragment) {
-            Some(x) => Ok((resource, x)),
-            None => Err(ValidationError::new("Couldn't resolve JSON pointer")),
+        follow_pointer(document, resource, &fragment, self.legacy_ids)
+    }
+}
+
+/// Follow the JSON pointer `pointer` from `document`, whose URL is `url`.
+///
+/// Returns the value it points to, and the URL that references within it are
+/// resolved against, taking into account the ids of the subschemas on the way.
+fn follow_pointer<'a>(
+    document: &'a Value,
+    url: url::Url,
+    pointer: &str,
+    legacy_ids: bool,
+) -> Result<(url::Url, &'a Value), ValidationError> {
+    if !(pointer.is_empty() || pointer.starts_with('/')) {
+        return Err(ValidationError::new("Couldn't resolve JSON pointer"));
+    }
+    let mut base_url = url;
+    let mut value = document;
+    for token in pointer.split('/').skip(1) {
+        let token = token.replace("~1", "/").replace("~0", "~");
+        let next = match value {
+            Value::Object(object) => object.get(&token),
+            Value::Array(array) => token.parse::<usize>().ok().and_then(|i| array.get(i)),
+            _ => None,
+        };
+        value = next.ok_or_else(|| ValidationError::new("Couldn't resolve JSON pointer"))?;
+        if let Some(id) = id_of(value, legacy_ids) {
+            base_url = base_url.join(id)?;
+            base_url.set_fragment(None);
+        }
+    }
+    Ok((base_url, value))
+}
+
+/// Find the subschema of `schema` with the `$anchor` or `$dynamicAnchor`
+/// `anchor`.
+fn find_anchor<'a>(schema: &'a Value, anchor: &str) -> Option<&'a Value> {
+    let names = ["$anchor", "$dynamicAnchor"];
+    if names
+        .iter()
+        .any(|name| schema.get(name).and_then(Value::as_str) == Some(anchor))
+    {
+        return Some(schema);
+    }
+    let mut found = None;
+    util::for_each_subschema(schema, &mut |_, subschema| {
+        if found.is_none() {
+            found = find_anchor(subschema, anchor);
+        }
+    });
+    found
+}
+
+/// Collect the `$ref`s in `schema`, which is at `pointer` in a document and
+/// has the base URL `base_url`, with the JSON pointer of each and the URL it
+/// refers to. `legacy_ids` is whether `id` gives ids, as in draft 4.
+pub(crate) fn find_refs<'a>(
+    schema: &'a Value,
+    base_url: &url::Url,
+    legacy_ids: bool,
+    pointer: &mut String,
+    refs: &mut Vec<(String, &'a str, Result<url::Url, url::ParseError>)>,
+) {
+    let base_url = match id_of(schema, legacy_ids).and_then(|id| base_url.join(id).ok()) {
+        Some(url) => url,
+        None => base_url.clone(),
+    };
+    if let Some(Value::String(ref_)) = schema.get("$ref") {
+        refs.push((format!("{}/$ref", pointer), ref_, base_url.join(ref_)));
+    }
+    util::for_each_subschema(schema, &mut |path, subschema| {
+        let len = pointer.len();
+        pointer.push('/');
+        pointer.push_str(&path);
+        find_refs(subschema, &base_url, legacy_ids, pointer, refs);
+        pointer.truncate(len);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_nested_fragment() {
+        let schema = json!({
+            "$id": "http://example.com/root.json",
+            "properties": {"user": {"$ref": "other.json#/components/schemas/User"}},
+            "definitions": {
+                "other": {
+                    "$id": "other.json",
+                    "components": {"schemas": {"User": {
+                        "$id": "nested/user.json",
+                        "properties": {"name": {"$ref": "name.json"}}
+                    }}}
+                },
+                "name": {"$id": "nested/name.json", "type": "string"}
+            }
+        });
+        let resolver = Resolver::from_schema(&schema).unwrap();
+        let (url, user) = resolver
+            .resolve_fragment(
+                "other.json#/components/schemas/User",
+                &Context::new_from(&schema),
+                &schema,
+            )
+            .unwrap();
+        assert_eq!(url.as_str(), "http://example.com/nested/user.json");
+        assert_eq!(
+            user,
+            &schema["definitions"]["other"]["components"]["schemas"]["User"]
+        );
+
+        let cfg = crate::Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        assert!(cfg.validate(&json!({"user": {"name": "x"}})).is_ok());
+        assert!(cfg.validate(&json!({"user": {"name": 1}})).is_err());
+    }
+
+    #[test]
+    fn test_base_url() {
+        let schema = json!({
+            "$id": "v1/order.json",
+            "properties": {
+                "parent": {"$ref": "order.json"},
+                "id": {"$ref": "#/definitions/id"},
+                "customer": {"$ref": "customer.json"}
+            },
+            "definitions": {"id": {"type": "integer"}}
+        });
+        let base = url::Url::parse("https://example.com/schemas/").unwrap();
+        let resolver = Resolver::from_schema_with_base(&schema, &base).unwrap();
+        let ctx = Context::new_from(&schema);
+        let (url, parent) = resolver
+            .resolve_fragment("order.json", &ctx, &schema)
+            .unwrap();
+        assert_eq!(url.as_str(), "https://example.com/schemas/v1/order.json");
+        assert_eq!(parent, &schema);
+        let (_, id) = resolver
+            .resolve_fragment("#/definitions/id", &ctx, &schema)
+            .unwrap();
+        assert_eq!(id, &json!({"type": "integer"}));
+        assert_eq!(
+            resolver.missing_documents(),
+            [url::Url::parse("https://example.com/schemas/v1/customer.json").unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_definitions() {
+        let schema = json!({
+            "$id": "http://example.com/root.json",
+            "$defs": {
+                "a": {
+                    "$id": "a.json",
+                    "$defs": {"b": {"$anchor": "bee", "type": "integer"}}
+                }
+            },
+            "definitions": {"c": {"$id": "#cee", "type": "string"}}
+        });
+        let resolver = Resolver::from_schema(&schema).unwrap();
+        let b = &schema["$defs"]["a"]["$defs"]["b"];
+        let ctx = Context::new_from(&schema);
+        for (reference, url, target) in &[
+            (
+                "#/$defs/a",
+                "http://example.com/a.json",
+                &schema["$defs"]["a"],
+            ),
+            ("a.json#/$defs/b", "http://example.com/a.json", b),
+            ("a.json#bee", "http://example.com/a.json", b),
+            (
+                "#/definitions/c",
+                "http://example.com/root.json",
+                &schema["definitions"]["c"],
+            ),
+            (
+                "#cee",
+                "http://example.com/root.json",
+                &schema["definitions"]["c"],
+            ),
+        ] {
+            assert!(resolver
+                .subschemas
+                .contains_key(resolver.join_url(reference, &ctx).unwrap().as_str()));
+            let (resolved_url, resolved) =
+                resolver.resolve_fragment(reference, &ctx, &schema).unwrap();
+            assert_eq!(resolved_url.as_str(), *url, "{}", reference);
+            assert_eq!(resolved, *target, "{}", reference);
         }
     }
+
+    #[test]
+    fn test_unknown_scheme() {
+        let schema = json!({"$ref": "s3://bucket/schema.json"});
+        let cfg = crate::Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let errors: Vec<ValidationError> = cfg.validate_refs().unwrap_err().collect();
+        assert_eq!(
+            errors[0].message(),
+            "Can't resolve reference s3://bucket/schema.json: \
+             Can't fetch document: no resolver for the URI scheme 's3'"
+        );
+    }
+
+    #[test]
+    fn test_pinned_hash() {
+        let schema = json!({"$ref": "registry://a.json#/definitions/x"});
+        let url = url::Url::parse("registry://a.json").unwrap();
+        let fetch = |_: &url::Url| -> Result<Value, ValidationError> {
+            Ok(json!({"definitions": {"x": {"type": "integer"}}}))
+        };
+        let err = crate::Config::from_schema(&schema, Some(&schemas::Draft7))
+            .unwrap()
+            .with_pinned_hash(&url, "00")
+            .with_scheme_resolver("registry", fetch)
+            .err()
+            .unwrap();
+        assert!(err.message().starts_with(
+            "Document registry://a.json doesn't match its pinned hash: expected 00, got "
+        ));
+    }
+
+    #[test]
+    fn test_warm_up() {
+        let schema = json!({"properties": {"a": {"$ref": "a.json"}}});
+        let a = json!({
+            "properties": {
+                "b": {"$ref": "#/definitions/missing"},
+                "c": {"type": "string", "pattern": "("},
+                "d": {"minLength": "1"}
+            }
+        });
+        let cfg = crate::Config::from_schema(&schema, Some(&schemas::Draft7))
+            .unwrap()
+            .with_document(&url::Url::parse("document:///a.json").unwrap(), a)
+            .unwrap();
+        let mut errors: Vec<ValidationError> = cfg.warm_up().unwrap_err().collect();
+        errors.sort_by_key(|err| err.instance_path().join("/"));
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].instance_path(), ["properties", "b", "$ref"]);
+        assert!(errors[0]
+            .message()
+            .starts_with("In document:///a.json: Can't resolve reference #/definitions/missing"));
+        assert_eq!(errors[1].instance_path(), ["properties", "c", "pattern"]);
+        assert!(errors[1]
+            .message()
+            .starts_with("In document:///a.json: ( invalid for regex"));
+        assert_eq!(errors[2].instance_path(), ["properties", "d", "minLength"]);
+    }
+
+    #[test]
+    fn test_ids_by_draft() {
+        // In draft 4, `id` gives ids; since draft 6, only `$id` does.
+        let schema = json!({
+            "id": "http://example.com/root.json",
+            "properties": {"a": {"$ref": "item.json"}},
+            "definitions": {"item": {"id": "item.json", "type": "integer"}}
+        });
+        for (draft, valid) in &[
+            (&schemas::Draft4 as &dyn schemas::Draft, true),
+            (&schemas::Draft6, false),
+            (&schemas::Draft7, false),
+        ] {
+            let cfg = crate::Config::from_schema(&schema, Some(*draft)).unwrap();
+            assert_eq!(cfg.validate(&json!({"a": 1})).is_ok(), *valid);
+        }
+
+        let mut draft6 = schema.clone();
+        draft6["$schema"] = json!("http://json-schema.org/draft-06/schema#");
+        let resolver = Resolver::from_schema(&draft6).unwrap();
+        assert!(resolver
+            .resolve_fragment("http://example.com/item.json", &Context::new(), &draft6)
+            .is_err());
+        let mut draft4 = schema;
+        draft4["$schema"] = json!("http://json-schema.org/draft-04/schema#");
+        let resolver = Resolver::from_schema(&draft4).unwrap();
+        let (_, item) = resolver
+            .resolve_fragment("http://example.com/item.json", &Context::new(), &draft4)
+            .unwrap();
+        assert_eq!(item, &draft4["definitions"]["item"]);
+    }
 }
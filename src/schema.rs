@@ -0,0 +1,13 @@
+//! Compiled schemas and the drafts of JSON Schema they are written in.
+//!
+//! A `Config` is a schema compiled for validation, together with the
+//! settings that apply to every validation against it. The simplest way to
+//! get one is `compile`, which detects the draft from the schema.
+
+pub use crate::config::Config;
+#[cfg(feature = "schemars")]
+pub use crate::config::SchemarsSchema;
+pub use crate::schemas::{
+    draft_from_number, draft_from_schema, draft_from_url, supported_keywords, supports, Draft,
+    Draft201909, Draft202012, Draft3, Draft4, Draft6, Draft7,
+};
@@ -4,15 +4,21 @@
 use lazy_static::lazy_static;
 use serde_json::Value;
 
-use crate::format;
-use crate::format::FormatChecker;
-use crate::validators;
-use crate::validators::Validator;
+use crate::formats;
+use crate::formats::FormatChecker;
+use crate::keywords;
+use crate::keywords::Keyword;
 
 /// The validator can validate JSON data against different versions of JSON Schema.
-pub trait Draft {
-    /// Get a validator function for the given item.
-    fn get_validator(&self, key: &str) -> Option<Validator>;
+///
+/// Drafts must be `Sync`, so that a `Config` can be shared between threads.
+pub trait Draft: Sync {
+    /// Get the implementation of the given keyword, if the draft defines it.
+    fn get_keyword(&self, key: &str) -> Option<Box<dyn Keyword>>;
+
+    /// Get the keywords that take part in validation in this draft. See
+    /// `supported_keywords`.
+    fn get_keywords(&self) -> &'static [&'static str];
 
     /// Get the JSON representation of the schema document.
     fn get_schema(&self) -> &'static Value;
@@ -22,50 +28,408 @@ pub trait Draft {
 
     /// Return the draft's number.
     fn get_draft_number(&self) -> u8;
+
+    /// Return the URI that identifies the dialect of the draft: the id of
+    /// its metaschema, which `draft_from_url` maps back to the draft.
+    /// Unlike the draft number, it tells apart dialects based on the same
+    /// draft.
+    fn get_dialect(&self) -> &'static str {
+        let metaschema = self.get_schema();
+        metaschema
+            .get("$id")
+            .or_else(|| metaschema.get("id"))
+            .and_then(Value::as_str)
+            .unwrap_or("")
+    }
+
+    /// Whether the ids of schemas are given with `id` rather than `$id`, as
+    /// in draft 4 and earlier.
+    fn uses_legacy_id(&self) -> bool {
+        false
+    }
+
+    /// Whether `exclusiveMaximum` and `exclusiveMinimum` are booleans that
+    /// make `maximum` and `minimum` exclusive, rather than bounds of their
+    /// own.
+    fn boolean_exclusive_bounds(&self) -> bool {
+        false
+    }
+
+    /// Whether this is the schema dialect of OpenAPI 3.0, in which
+    /// `nullable: true` also allows `null`.
+    fn is_openapi(&self) -> bool {
+        false
+    }
+}
+
+pub(crate) const DRAFT202012_KEYWORDS: &[&str] = &[
+    "$dynamicRef",
+    "$ref",
+    "additionalProperties",
+    "allOf",
+    "anyOf",
+    "const",
+    "contains",
+    "dependentRequired",
+    "dependentSchemas",
+    "else",
+    "enum",
+    "exclusiveMaximum",
+    "exclusiveMinimum",
+    "format",
+    "if",
+    "items",
+    "maxContains",
+    "maxItems",
+    "maxLength",
+    "maxProperties",
+    "maximum",
+    "minContains",
+    "minItems",
+    "minLength",
+    "minProperties",
+    "minimum",
+    "multipleOf",
+    "not",
+    "oneOf",
+    "pattern",
+    "patternProperties",
+    "prefixItems",
+    "properties",
+    "propertyNames",
+    "required",
+    "then",
+    "type",
+    "uniqueItems",
+    "unevaluatedItems",
+    "unevaluatedProperties",
+];
+
+/// The keywords of the applicator vocabulary of draft 2020-12, and the
+/// proposed `propertyDependencies` with the `draft-next` feature.
+#[cfg(not(feature = "draft-next"))]
+const DRAFT202012_APPLICATOR: &[&str] = &[
+    "additionalProperties",
+    "allOf",
+    "anyOf",
+    "contains",
+    "dependentSchemas",
+    "else",
+    "if",
+    "items",
+    "not",
+    "oneOf",
+    "patternProperties",
+    "prefixItems",
+    "properties",
+    "propertyNames",
+    "then",
+];
+#[cfg(feature = "draft-next")]
+const DRAFT202012_APPLICATOR: &[&str] = &[
+    "additionalProperties",
+    "allOf",
+    "anyOf",
+    "contains",
+    "dependentSchemas",
+    "else",
+    "if",
+    "items",
+    "not",
+    "oneOf",
+    "patternProperties",
+    "prefixItems",
+    "properties",
+    "propertyDependencies",
+    "propertyNames",
+    "then",
+];
+
+/// The vocabularies of draft 2020-12, with the keywords of `DRAFT202012_KEYWORDS`
+/// that each of them defines. The meta-data and content vocabularies only
+/// define annotations.
+pub(crate) const DRAFT202012_VOCABULARIES: &[(&str, &[&str])] = &[
+    (
+        "https://json-schema.org/draft/2020-12/vocab/core",
+        &["$dynamicRef", "$ref"],
+    ),
+    (
+        "https://json-schema.org/draft/2020-12/vocab/applicator",
+        DRAFT202012_APPLICATOR,
+    ),
+    (
+        "https://json-schema.org/draft/2020-12/vocab/unevaluated",
+        &["unevaluatedItems", "unevaluatedProperties"],
+    ),
+    (
+        "https://json-schema.org/draft/2020-12/vocab/validation",
+        &[
+            "const",
+            "dependentRequired",
+            "enum",
+            "exclusiveMaximum",
+            "exclusiveMinimum",
+            "maxContains",
+            "maxItems",
+            "maxLength",
+            "maxProperties",
+            "maximum",
+            "minContains",
+            "minItems",
+            "minLength",
+            "minProperties",
+            "minimum",
+            "multipleOf",
+            "pattern",
+            "required",
+            "type",
+            "uniqueItems",
+        ],
+    ),
+    (
+        "https://json-schema.org/draft/2020-12/vocab/format-annotation",
+        &["format"],
+    ),
+    (
+        "https://json-schema.org/draft/2020-12/vocab/format-assertion",
+        &["format"],
+    ),
+    ("https://json-schema.org/draft/2020-12/vocab/meta-data", &[]),
+    ("https://json-schema.org/draft/2020-12/vocab/content", &[]),
+];
+
+/// JSONSchema [Draft 2020-12](https://json-schema.org/specification-links.html#2020-12)
+pub struct Draft202012;
+
+impl Draft for Draft202012 {
+    fn get_keyword(&self, key: &str) -> Option<Box<dyn Keyword>> {
+        match key {
+            "$dynamicRef" => Some(Box::new(keywords::dynamicRef)),
+            "prefixItems" => Some(Box::new(keywords::prefixItems)),
+            "$recursiveRef" | "additionalItems" => None,
+            _ => Draft201909.get_keyword(key),
+        }
+    }
+
+    fn get_keywords(&self) -> &'static [&'static str] {
+        DRAFT202012_KEYWORDS
+    }
+
+    fn get_schema(&self) -> &'static Value {
+        lazy_static! {
+            static ref DRAFT202012: Value =
+                serde_json::from_str(include_str!("draft2020-12.json")).unwrap();
+        }
+        &DRAFT202012
+    }
+
+    fn get_format_checker(&self, key: &str) -> Option<FormatChecker> {
+        Draft7.get_format_checker(key)
+    }
+
+    fn get_draft_number(&self) -> u8 {
+        9
+    }
+}
+
+pub(crate) const DRAFT201909_KEYWORDS: &[&str] = &[
+    "$recursiveRef",
+    "$ref",
+    "additionalItems",
+    "additionalProperties",
+    "allOf",
+    "anyOf",
+    "const",
+    "contains",
+    "dependentRequired",
+    "dependentSchemas",
+    "else",
+    "enum",
+    "exclusiveMaximum",
+    "exclusiveMinimum",
+    "format",
+    "if",
+    "items",
+    "maxContains",
+    "maxItems",
+    "maxLength",
+    "maxProperties",
+    "maximum",
+    "minContains",
+    "minItems",
+    "minLength",
+    "minProperties",
+    "minimum",
+    "multipleOf",
+    "not",
+    "oneOf",
+    "pattern",
+    "patternProperties",
+    "properties",
+    "propertyNames",
+    "required",
+    "then",
+    "type",
+    "uniqueItems",
+    "unevaluatedItems",
+    "unevaluatedProperties",
+];
+
+/// JSONSchema [Draft 2019-09](https://json-schema.org/specification-links.html#draft-2019-09-formerly-known-as-draft-8)
+pub struct Draft201909;
+
+impl Draft for Draft201909 {
+    fn get_keyword(&self, key: &str) -> Option<Box<dyn Keyword>> {
+        match key {
+            "$recursiveRef" => Some(Box::new(keywords::recursiveRef)),
+            "$ref" => Some(Box::new(keywords::ref_)),
+            "additionalItems" => Some(Box::new(keywords::additionalItems)),
+            "additionalProperties" => Some(Box::new(keywords::additionalProperties)),
+            "allOf" => Some(Box::new(keywords::allOf)),
+            "anyOf" => Some(Box::new(keywords::anyOf)),
+            "const" => Some(Box::new(keywords::const_)),
+            "contains" => Some(Box::new(keywords::contains)),
+            "dependentRequired" => Some(Box::new(keywords::dependentRequired)),
+            "dependentSchemas" => Some(Box::new(keywords::dependentSchemas)),
+            "else" => Some(Box::new(keywords::else_)),
+            "enum" => Some(Box::new(keywords::enum_)),
+            "exclusiveMaximum" => Some(Box::new(keywords::exclusiveMaximum)),
+            "exclusiveMinimum" => Some(Box::new(keywords::exclusiveMinimum)),
+            "format" => Some(Box::new(keywords::format)),
+            "items" => Some(Box::new(keywords::items)),
+            "maxContains" => Some(Box::new(keywords::maxContains)),
+            "maxItems" => Some(Box::new(keywords::maxItems)),
+            "maxLength" => Some(Box::new(keywords::maxLength)),
+            "maxProperties" => Some(Box::new(keywords::maxProperties)),
+            "maximum" => Some(Box::new(keywords::maximum)),
+            "minContains" => Some(Box::new(keywords::minContains)),
+            "minItems" => Some(Box::new(keywords::minItems)),
+            "minLength" => Some(Box::new(keywords::minLength)),
+            "minProperties" => Some(Box::new(keywords::minProperties)),
+            "minimum" => Some(Box::new(keywords::minimum)),
+            "multipleOf" => Some(Box::new(keywords::multipleOf)),
+            "not" => Some(Box::new(keywords::not)),
+            "oneOf" => Some(Box::new(keywords::oneOf)),
+            "pattern" => Some(Box::new(keywords::pattern)),
+            "patternProperties" => Some(Box::new(keywords::patternProperties)),
+            "properties" => Some(Box::new(keywords::properties)),
+            "propertyNames" => Some(Box::new(keywords::propertyNames)),
+            "required" => Some(Box::new(keywords::required)),
+            "then" => Some(Box::new(keywords::then_)),
+            "type" => Some(Box::new(keywords::type_)),
+            "uniqueItems" => Some(Box::new(keywords::uniqueItems)),
+            "unevaluatedItems" => Some(Box::new(keywords::unevaluatedItems)),
+            "unevaluatedProperties" => Some(Box::new(keywords::unevaluatedProperties)),
+            _ => None,
+        }
+    }
+
+    fn get_keywords(&self) -> &'static [&'static str] {
+        DRAFT201909_KEYWORDS
+    }
+
+    fn get_schema(&self) -> &'static Value {
+        lazy_static! {
+            static ref DRAFT201909: Value =
+                serde_json::from_str(include_str!("draft2019-09.json")).unwrap();
+        }
+        &DRAFT201909
+    }
+
+    fn get_format_checker(&self, key: &str) -> Option<FormatChecker> {
+        Draft7.get_format_checker(key)
+    }
+
+    /// 2019-09 was formerly known as draft 8.
+    fn get_draft_number(&self) -> u8 {
+        8
+    }
 }
 
+pub(crate) const DRAFT7_KEYWORDS: &[&str] = &[
+    "$ref",
+    "additionalItems",
+    "additionalProperties",
+    "allOf",
+    "anyOf",
+    "const",
+    "contains",
+    "dependencies",
+    "else",
+    "enum",
+    "exclusiveMaximum",
+    "exclusiveMinimum",
+    "format",
+    "if",
+    "items",
+    "maxItems",
+    "maxLength",
+    "maxProperties",
+    "maximum",
+    "minItems",
+    "minLength",
+    "minProperties",
+    "minimum",
+    "multipleOf",
+    "not",
+    "oneOf",
+    "pattern",
+    "patternProperties",
+    "properties",
+    "propertyNames",
+    "required",
+    "then",
+    "type",
+    "uniqueItems",
+];
+
 /// JSONSchema [Draft 7](https://json-schema.org/specification-links.html#draft-7)
 pub struct Draft7;
 
 impl Draft for Draft7 {
-    fn get_validator(&self, key: &str) -> Option<Validator> {
+    fn get_keyword(&self, key: &str) -> Option<Box<dyn Keyword>> {
         match key {
-            "$ref" => Some(validators::ref_ as Validator),
-            "additionalItems" => Some(validators::additionalItems as Validator),
-            "additionalProperties" => Some(validators::additionalProperties as Validator),
-            "allOf" => Some(validators::allOf as Validator),
-            "anyOf" => Some(validators::anyOf as Validator),
-            "const" => Some(validators::const_ as Validator),
-            "contains" => Some(validators::contains as Validator),
-            "dependencies" => Some(validators::dependencies as Validator),
-            "enum" => Some(validators::enum_ as Validator),
-            "exclusiveMaximum" => Some(validators::exclusiveMaximum as Validator),
-            "exclusiveMinimum" => Some(validators::exclusiveMinimum as Validator),
-            "format" => Some(validators::format as Validator),
-            "if" => Some(validators::if_ as Validator),
-            "items" => Some(validators::items as Validator),
-            "maxItems" => Some(validators::maxItems as Validator),
-            "maxLength" => Some(validators::maxLength as Validator),
-            "maxProperties" => Some(validators::maxProperties as Validator),
-            "maximum" => Some(validators::maximum as Validator),
-            "minItems" => Some(validators::minItems as Validator),
-            "minLength" => Some(validators::minLength as Validator),
-            "minProperties" => Some(validators::minProperties as Validator),
-            "minimum" => Some(validators::minimum as Validator),
-            "multipleOf" => Some(validators::multipleOf as Validator),
-            "not" => Some(validators::not as Validator),
-            "oneOf" => Some(validators::oneOf as Validator),
-            "pattern" => Some(validators::pattern as Validator),
-            "patternProperties" => Some(validators::patternProperties as Validator),
-            "properties" => Some(validators::properties as Validator),
-            "propertyNames" => Some(validators::propertyNames as Validator),
-            "required" => Some(validators::required as Validator),
-            "type" => Some(validators::type_ as Validator),
-            "uniqueItems" => Some(validators::uniqueItems as Validator),
+            "$ref" => Some(Box::new(keywords::ref_)),
+            "additionalItems" => Some(Box::new(keywords::additionalItems)),
+            "additionalProperties" => Some(Box::new(keywords::additionalProperties)),
+            "allOf" => Some(Box::new(keywords::allOf)),
+            "anyOf" => Some(Box::new(keywords::anyOf)),
+            "const" => Some(Box::new(keywords::const_)),
+            "contains" => Some(Box::new(keywords::contains)),
+            "dependencies" => Some(Box::new(keywords::dependencies)),
+            "else" => Some(Box::new(keywords::else_)),
+            "enum" => Some(Box::new(keywords::enum_)),
+            "exclusiveMaximum" => Some(Box::new(keywords::exclusiveMaximum)),
+            "exclusiveMinimum" => Some(Box::new(keywords::exclusiveMinimum)),
+            "format" => Some(Box::new(keywords::format)),
+            "items" => Some(Box::new(keywords::items)),
+            "maxItems" => Some(Box::new(keywords::maxItems)),
+            "maxLength" => Some(Box::new(keywords::maxLength)),
+            "maxProperties" => Some(Box::new(keywords::maxProperties)),
+            "maximum" => Some(Box::new(keywords::maximum)),
+            "minItems" => Some(Box::new(keywords::minItems)),
+            "minLength" => Some(Box::new(keywords::minLength)),
+            "minProperties" => Some(Box::new(keywords::minProperties)),
+            "minimum" => Some(Box::new(keywords::minimum)),
+            "multipleOf" => Some(Box::new(keywords::multipleOf)),
+            "not" => Some(Box::new(keywords::not)),
+            "oneOf" => Some(Box::new(keywords::oneOf)),
+            "pattern" => Some(Box::new(keywords::pattern)),
+            "patternProperties" => Some(Box::new(keywords::patternProperties)),
+            "properties" => Some(Box::new(keywords::properties)),
+            "propertyNames" => Some(Box::new(keywords::propertyNames)),
+            "required" => Some(Box::new(keywords::required)),
+            "then" => Some(Box::new(keywords::then_)),
+            "type" => Some(Box::new(keywords::type_)),
+            "uniqueItems" => Some(Box::new(keywords::uniqueItems)),
             _ => None,
         }
     }
 
+    fn get_keywords(&self) -> &'static [&'static str] {
+        DRAFT7_KEYWORDS
+    }
+
     fn get_schema(&self) -> &'static Value {
         lazy_static! {
             static ref DRAFT7: Value = serde_json::from_str(include_str!("draft7.json")).unwrap();
@@ -75,21 +439,26 @@ impl Draft for Draft7 {
 
     fn get_format_checker(&self, key: &str) -> Option<FormatChecker> {
         match key {
-            "date" => Some(format::date as FormatChecker),
-            "date-time" => Some(format::datetime as FormatChecker),
-            "email" => Some(format::email as FormatChecker),
-            "hostname" => Some(format::hostname as FormatChecker),
-            "idn-email" => Some(format::email as FormatChecker),
-            "ipv4" => Some(format::ipv4 as FormatChecker),
-            "ipv6" => Some(format::ipv6 as FormatChecker),
-            "iri" => Some(format::iri as FormatChecker),
-            "iri-reference" => Some(format::iri_reference as FormatChecker),
-            "json-pointer" => Some(format::json_pointer as FormatChecker),
-            "regex" => Some(format::regex as FormatChecker),
-            "time" => Some(format::time as FormatChecker),
-            "uri" => Some(format::uri as FormatChecker),
-            "uri-reference" => Some(format::uri_reference as FormatChecker),
-            "uri-template" => Some(format::uri_template as FormatChecker),
+            "date" => Some(formats::date as FormatChecker),
+            "date-time" => Some(formats::datetime as FormatChecker),
+            "email" => Some(formats::email as FormatChecker),
+            "hostname" => Some(formats::hostname as FormatChecker),
+            #[cfg(feature = "idn")]
+            "idn-email" => Some(formats::idn_email as FormatChecker),
+            #[cfg(not(feature = "idn"))]
+            "idn-email" => Some(formats::email as FormatChecker),
+            #[cfg(feature = "idn")]
+            "idn-hostname" => Some(formats::idn_hostname as FormatChecker),
+            "ipv4" => Some(formats::ipv4 as FormatChecker),
+            "ipv6" => Some(formats::ipv6 as FormatChecker),
+            "iri" => Some(formats::iri as FormatChecker),
+            "iri-reference" => Some(formats::iri_reference as FormatChecker),
+            "json-pointer" => Some(formats::json_pointer as FormatChecker),
+            "regex" => Some(formats::regex as FormatChecker),
+            "time" => Some(formats::time as FormatChecker),
+            "uri" => Some(formats::uri as FormatChecker),
+            "uri-reference" => Some(formats::uri_reference as FormatChecker),
+            "uri-template" => Some(formats::uri_template as FormatChecker),
             _ => None,
         }
     }
@@ -99,47 +468,85 @@ impl Draft for Draft7 {
     }
 }
 
+pub(crate) const DRAFT6_KEYWORDS: &[&str] = &[
+    "$ref",
+    "additionalItems",
+    "additionalProperties",
+    "allOf",
+    "anyOf",
+    "const",
+    "contains",
+    "dependencies",
+    "enum",
+    "exclusiveMaximum",
+    "exclusiveMinimum",
+    "format",
+    "items",
+    "maxItems",
+    "maxLength",
+    "maxProperties",
+    "maximum",
+    "minItems",
+    "minLength",
+    "minProperties",
+    "minimum",
+    "multipleOf",
+    "not",
+    "oneOf",
+    "pattern",
+    "patternProperties",
+    "properties",
+    "propertyNames",
+    "required",
+    "type",
+    "uniqueItems",
+];
+
 /// JSONSchema [Draft 6](https://json-schema.org/specification-links.html#draft-6)
 pub struct Draft6;
 
 impl Draft for Draft6 {
-    fn get_validator(&self, key: &str) -> Option<Validator> {
+    fn get_keyword(&self, key: &str) -> Option<Box<dyn Keyword>> {
         match key {
-            "$ref" => Some(validators::ref_ as Validator),
-            "additionalItems" => Some(validators::additionalItems as Validator),
-            "additionalProperties" => Some(validators::additionalProperties as Validator),
-            "allOf" => Some(validators::allOf as Validator),
-            "anyOf" => Some(validators::anyOf as Validator),
-            "const" => Some(validators::const_ as Validator),
-            "contains" => Some(validators::contains as Validator),
-            "dependencies" => Some(validators::dependencies as Validator),
-            "enum" => Some(validators::enum_ as Validator),
-            "exclusiveMaximum" => Some(validators::exclusiveMaximum as Validator),
-            "exclusiveMinimum" => Some(validators::exclusiveMinimum as Validator),
-            "format" => Some(validators::format as Validator),
-            "items" => Some(validators::items as Validator),
-            "maxItems" => Some(validators::maxItems as Validator),
-            "maxLength" => Some(validators::maxLength as Validator),
-            "maxProperties" => Some(validators::maxProperties as Validator),
-            "maximum" => Some(validators::maximum as Validator),
-            "minItems" => Some(validators::minItems as Validator),
-            "minLength" => Some(validators::minLength as Validator),
-            "minProperties" => Some(validators::minProperties as Validator),
-            "minimum" => Some(validators::minimum as Validator),
-            "multipleOf" => Some(validators::multipleOf as Validator),
-            "not" => Some(validators::not as Validator),
-            "oneOf" => Some(validators::oneOf as Validator),
-            "pattern" => Some(validators::pattern as Validator),
-            "patternProperties" => Some(validators::patternProperties as Validator),
-            "properties" => Some(validators::properties as Validator),
-            "propertyNames" => Some(validators::propertyNames as Validator),
-            "required" => Some(validators::required as Validator),
-            "type" => Some(validators::type_ as Validator),
-            "uniqueItems" => Some(validators::uniqueItems as Validator),
+            "$ref" => Some(Box::new(keywords::ref_)),
+            "additionalItems" => Some(Box::new(keywords::additionalItems)),
+            "additionalProperties" => Some(Box::new(keywords::additionalProperties)),
+            "allOf" => Some(Box::new(keywords::allOf)),
+            "anyOf" => Some(Box::new(keywords::anyOf)),
+            "const" => Some(Box::new(keywords::const_)),
+            "contains" => Some(Box::new(keywords::contains)),
+            "dependencies" => Some(Box::new(keywords::dependencies)),
+            "enum" => Some(Box::new(keywords::enum_)),
+            "exclusiveMaximum" => Some(Box::new(keywords::exclusiveMaximum)),
+            "exclusiveMinimum" => Some(Box::new(keywords::exclusiveMinimum)),
+            "format" => Some(Box::new(keywords::format)),
+            "items" => Some(Box::new(keywords::items)),
+            "maxItems" => Some(Box::new(keywords::maxItems)),
+            "maxLength" => Some(Box::new(keywords::maxLength)),
+            "maxProperties" => Some(Box::new(keywords::maxProperties)),
+            "maximum" => Some(Box::new(keywords::maximum)),
+            "minItems" => Some(Box::new(keywords::minItems)),
+            "minLength" => Some(Box::new(keywords::minLength)),
+            "minProperties" => Some(Box::new(keywords::minProperties)),
+            "minimum" => Some(Box::new(keywords::minimum)),
+            "multipleOf" => Some(Box::new(keywords::multipleOf)),
+            "not" => Some(Box::new(keywords::not)),
+            "oneOf" => Some(Box::new(keywords::oneOf)),
+            "pattern" => Some(Box::new(keywords::pattern)),
+            "patternProperties" => Some(Box::new(keywords::patternProperties)),
+            "properties" => Some(Box::new(keywords::properties)),
+            "propertyNames" => Some(Box::new(keywords::propertyNames)),
+            "required" => Some(Box::new(keywords::required)),
+            "type" => Some(Box::new(keywords::type_)),
+            "uniqueItems" => Some(Box::new(keywords::uniqueItems)),
             _ => None,
         }
     }
 
+    fn get_keywords(&self) -> &'static [&'static str] {
+        DRAFT6_KEYWORDS
+    }
+
     fn get_schema(&self) -> &'static Value {
         lazy_static! {
             static ref DRAFT6: Value = serde_json::from_str(include_str!("draft6.json")).unwrap();
@@ -149,18 +556,18 @@ impl Draft for Draft6 {
 
     fn get_format_checker(&self, key: &str) -> Option<FormatChecker> {
         match key {
-            "date" => Some(format::date as FormatChecker),
-            "date-time" => Some(format::datetime as FormatChecker),
-            "email" => Some(format::email as FormatChecker),
-            "hostname" => Some(format::hostname as FormatChecker),
-            "ipv4" => Some(format::ipv4 as FormatChecker),
-            "ipv6" => Some(format::ipv6 as FormatChecker),
-            "json-pointer" => Some(format::json_pointer as FormatChecker),
-            "regex" => Some(format::regex as FormatChecker),
-            "time" => Some(format::time as FormatChecker),
-            "uri" => Some(format::uri as FormatChecker),
-            "uri-reference" => Some(format::uri_reference as FormatChecker),
-            "uri-template" => Some(format::uri_template as FormatChecker),
+            "date" => Some(formats::date as FormatChecker),
+            "date-time" => Some(formats::datetime as FormatChecker),
+            "email" => Some(formats::email as FormatChecker),
+            "hostname" => Some(formats::hostname as FormatChecker),
+            "ipv4" => Some(formats::ipv4 as FormatChecker),
+            "ipv6" => Some(formats::ipv6 as FormatChecker),
+            "json-pointer" => Some(formats::json_pointer as FormatChecker),
+            "regex" => Some(formats::regex as FormatChecker),
+            "time" => Some(formats::time as FormatChecker),
+            "uri" => Some(formats::uri as FormatChecker),
+            "uri-reference" => Some(formats::uri_reference as FormatChecker),
+            "uri-template" => Some(formats::uri_template as FormatChecker),
             _ => None,
         }
     }
@@ -170,42 +577,77 @@ impl Draft for Draft6 {
     }
 }
 
+pub(crate) const DRAFT4_KEYWORDS: &[&str] = &[
+    "$ref",
+    "additionalItems",
+    "additionalProperties",
+    "allOf",
+    "anyOf",
+    "dependencies",
+    "enum",
+    "exclusiveMaximum",
+    "exclusiveMinimum",
+    "format",
+    "items",
+    "maxItems",
+    "maxLength",
+    "maxProperties",
+    "maximum",
+    "minItems",
+    "minLength",
+    "minProperties",
+    "minimum",
+    "multipleOf",
+    "not",
+    "oneOf",
+    "pattern",
+    "patternProperties",
+    "properties",
+    "required",
+    "type",
+    "uniqueItems",
+];
+
 /// JSONSchema [Draft 4](https://json-schema.org/specification-links.html#draft-4)
 pub struct Draft4;
 
 impl Draft for Draft4 {
-    fn get_validator(&self, key: &str) -> Option<Validator> {
+    fn get_keyword(&self, key: &str) -> Option<Box<dyn Keyword>> {
         match key {
-            "$ref" => Some(validators::ref_ as Validator),
-            "additionalItems" => Some(validators::additionalItems as Validator),
-            "additionalProperties" => Some(validators::additionalProperties as Validator),
-            "allOf" => Some(validators::allOf as Validator),
-            "anyOf" => Some(validators::anyOf as Validator),
-            "dependencies" => Some(validators::dependencies as Validator),
-            "enum" => Some(validators::enum_ as Validator),
-            "format" => Some(validators::format as Validator),
-            "items" => Some(validators::items as Validator),
-            "maxItems" => Some(validators::maxItems as Validator),
-            "maxLength" => Some(validators::maxLength as Validator),
-            "maxProperties" => Some(validators::maxProperties as Validator),
-            "maximum" => Some(validators::maximum_draft4 as Validator),
-            "minItems" => Some(validators::minItems as Validator),
-            "minLength" => Some(validators::minLength as Validator),
-            "minProperties" => Some(validators::minProperties as Validator),
-            "minimum" => Some(validators::minimum_draft4 as Validator),
-            "multipleOf" => Some(validators::multipleOf as Validator),
-            "not" => Some(validators::not as Validator),
-            "oneOf" => Some(validators::oneOf as Validator),
-            "pattern" => Some(validators::pattern as Validator),
-            "patternProperties" => Some(validators::patternProperties as Validator),
-            "properties" => Some(validators::properties as Validator),
-            "required" => Some(validators::required as Validator),
-            "type" => Some(validators::type_ as Validator),
-            "uniqueItems" => Some(validators::uniqueItems as Validator),
+            "$ref" => Some(Box::new(keywords::ref_)),
+            "additionalItems" => Some(Box::new(keywords::additionalItems)),
+            "additionalProperties" => Some(Box::new(keywords::additionalProperties)),
+            "allOf" => Some(Box::new(keywords::allOf)),
+            "anyOf" => Some(Box::new(keywords::anyOf)),
+            "dependencies" => Some(Box::new(keywords::dependencies)),
+            "enum" => Some(Box::new(keywords::enum_)),
+            "format" => Some(Box::new(keywords::format)),
+            "items" => Some(Box::new(keywords::items)),
+            "maxItems" => Some(Box::new(keywords::maxItems)),
+            "maxLength" => Some(Box::new(keywords::maxLength)),
+            "maxProperties" => Some(Box::new(keywords::maxProperties)),
+            "maximum" => Some(Box::new(keywords::maximum_draft4)),
+            "minItems" => Some(Box::new(keywords::minItems)),
+            "minLength" => Some(Box::new(keywords::minLength)),
+            "minProperties" => Some(Box::new(keywords::minProperties)),
+            "minimum" => Some(Box::new(keywords::minimum_draft4)),
+            "multipleOf" => Some(Box::new(keywords::multipleOf)),
+            "not" => Some(Box::new(keywords::not)),
+            "oneOf" => Some(Box::new(keywords::oneOf)),
+            "pattern" => Some(Box::new(keywords::pattern)),
+            "patternProperties" => Some(Box::new(keywords::patternProperties)),
+            "properties" => Some(Box::new(keywords::properties)),
+            "required" => Some(Box::new(keywords::required)),
+            "type" => Some(Box::new(keywords::type_)),
+            "uniqueItems" => Some(Box::new(keywords::uniqueItems)),
             _ => None,
         }
     }
 
+    fn get_keywords(&self) -> &'static [&'static str] {
+        DRAFT4_KEYWORDS
+    }
+
     fn get_schema(&self) -> &'static Value {
         lazy_static! {
             static ref DRAFT4: Value = serde_json::from_str(include_str!("draft4.json")).unwrap();
@@ -215,28 +657,266 @@ impl Draft for Draft4 {
 
     fn get_format_checker(&self, key: &str) -> Option<FormatChecker> {
         match key {
-            "date-time" => Some(format::datetime as FormatChecker),
-            "email" => Some(format::email as FormatChecker),
-            "hostname" => Some(format::hostname as FormatChecker),
-            "ipv4" => Some(format::ipv4 as FormatChecker),
-            "ipv6" => Some(format::ipv6 as FormatChecker),
-            "regex" => Some(format::regex as FormatChecker),
-            "uri" => Some(format::uri as FormatChecker),
+            "date-time" => Some(formats::datetime as FormatChecker),
+            "email" => Some(formats::email as FormatChecker),
+            "hostname" => Some(formats::hostname as FormatChecker),
+            "ipv4" => Some(formats::ipv4 as FormatChecker),
+            "ipv6" => Some(formats::ipv6 as FormatChecker),
+            "regex" => Some(formats::regex as FormatChecker),
+            "uri" => Some(formats::uri as FormatChecker),
+            _ => None,
+        }
+    }
+
+    fn get_draft_number(&self) -> u8 {
+        4
+    }
+
+    fn uses_legacy_id(&self) -> bool {
+        true
+    }
+
+    fn boolean_exclusive_bounds(&self) -> bool {
+        true
+    }
+}
+
+pub(crate) const DRAFT3_KEYWORDS: &[&str] = &[
+    "$ref",
+    "additionalItems",
+    "additionalProperties",
+    "dependencies",
+    "disallow",
+    "divisibleBy",
+    "enum",
+    "exclusiveMaximum",
+    "exclusiveMinimum",
+    "extends",
+    "format",
+    "items",
+    "maxItems",
+    "maxLength",
+    "maximum",
+    "minItems",
+    "minLength",
+    "minimum",
+    "pattern",
+    "patternProperties",
+    "properties",
+    "required",
+    "type",
+    "uniqueItems",
+];
+
+/// JSONSchema [Draft 3](https://json-schema.org/specification-links.html#draft-3)
+pub struct Draft3;
+
+impl Draft for Draft3 {
+    fn get_keyword(&self, key: &str) -> Option<Box<dyn Keyword>> {
+        match key {
+            "disallow" => Some(Box::new(keywords::disallow)),
+            "divisibleBy" => Some(Box::new(keywords::divisibleBy)),
+            "extends" => Some(Box::new(keywords::extends)),
+            "properties" => Some(Box::new(keywords::properties_draft3)),
+            "type" => Some(Box::new(keywords::type_draft3)),
+            // `required` is a flag of the subschemas in `properties`.
+            "required" => None,
+            _ if DRAFT3_KEYWORDS.contains(&key) => Draft4.get_keyword(key),
+            _ => None,
+        }
+    }
+
+    fn get_keywords(&self) -> &'static [&'static str] {
+        DRAFT3_KEYWORDS
+    }
+
+    fn get_schema(&self) -> &'static Value {
+        lazy_static! {
+            static ref DRAFT3: Value = serde_json::from_str(include_str!("draft3.json")).unwrap();
+        }
+        &DRAFT3
+    }
+
+    fn get_format_checker(&self, key: &str) -> Option<FormatChecker> {
+        match key {
+            "date" => Some(formats::date as FormatChecker),
+            "date-time" => Some(formats::datetime as FormatChecker),
+            "email" => Some(formats::email as FormatChecker),
+            "host-name" => Some(formats::hostname as FormatChecker),
+            "ip-address" => Some(formats::ipv4 as FormatChecker),
+            "ipv6" => Some(formats::ipv6 as FormatChecker),
+            "regex" => Some(formats::regex as FormatChecker),
+            "uri" => Some(formats::uri as FormatChecker),
+            _ => None,
+        }
+    }
+
+    fn get_draft_number(&self) -> u8 {
+        3
+    }
+
+    fn uses_legacy_id(&self) -> bool {
+        true
+    }
+
+    fn boolean_exclusive_bounds(&self) -> bool {
+        true
+    }
+}
+
+pub(crate) const OPENAPI30_KEYWORDS: &[&str] = &[
+    "$ref",
+    "additionalProperties",
+    "allOf",
+    "anyOf",
+    "enum",
+    "exclusiveMaximum",
+    "exclusiveMinimum",
+    "format",
+    "items",
+    "maxItems",
+    "maxLength",
+    "maxProperties",
+    "maximum",
+    "minItems",
+    "minLength",
+    "minProperties",
+    "minimum",
+    "multipleOf",
+    "not",
+    "oneOf",
+    "pattern",
+    "properties",
+    "required",
+    "type",
+    "uniqueItems",
+];
+
+/// The Schema Object of [OpenAPI 3.0](https://spec.openapis.org/oas/v3.0.3#schema-object),
+/// which describes request and response bodies.
+///
+/// It is the subset of draft 4 (strictly, of the draft that followed it)
+/// without `patternProperties`, `dependencies`, `additionalItems` and the
+/// keywords of later drafts; its metaschema rejects schemas that use them.
+/// `nullable: true` also allows `null` for the `type` next to it, but not
+/// for an `enum`. `example`, `xml` and `externalDocs` are annotations, and
+/// `discriminator` picks the subschema of the `oneOf` next to it, and `id`
+/// is an ordinary property rather than the id of the schema. Its draft number
+/// is 4, that of the draft it is based on; `get_dialect` tells it apart from
+/// `Draft4`.
+pub struct OpenApi30;
+
+impl Draft for OpenApi30 {
+    fn get_keyword(&self, key: &str) -> Option<Box<dyn Keyword>> {
+        match key {
+            "oneOf" => Some(Box::new(keywords::oneOf_openapi)),
+            "type" => Some(Box::new(keywords::type_openapi)),
+            _ if OPENAPI30_KEYWORDS.contains(&key) => Draft4.get_keyword(key),
             _ => None,
         }
     }
 
+    fn get_keywords(&self) -> &'static [&'static str] {
+        OPENAPI30_KEYWORDS
+    }
+
+    fn get_schema(&self) -> &'static Value {
+        lazy_static! {
+            static ref OPENAPI30: Value =
+                serde_json::from_str(include_str!("openapi3.0.json")).unwrap();
+        }
+        &OPENAPI30
+    }
+
+    fn get_format_checker(&self, key: &str) -> Option<FormatChecker> {
+        match key {
+            "byte" => Some(formats::byte as FormatChecker),
+            "date" => Some(formats::date as FormatChecker),
+            _ => Draft4.get_format_checker(key),
+        }
+    }
+
     fn get_draft_number(&self) -> u8 {
         4
     }
+
+    fn boolean_exclusive_bounds(&self) -> bool {
+        true
+    }
+
+    fn is_openapi(&self) -> bool {
+        true
+    }
+}
+
+/// Keywords of newer drafts of JSON schema, which take part in
+/// validation in every draft with the `draft-next` feature.
+#[cfg(feature = "draft-next")]
+pub(crate) const NEXT_KEYWORDS: &[&str] = &[
+    "dependentRequired",
+    "dependentSchemas",
+    "maxContains",
+    "minContains",
+    "prefixItems",
+    "propertyDependencies",
+    "unevaluatedItems",
+    "unevaluatedProperties",
+];
+#[cfg(not(feature = "draft-next"))]
+pub(crate) const NEXT_KEYWORDS: &[&str] = &[];
+
+/// Get the implementation of a keyword in `NEXT_KEYWORDS`.
+pub(crate) fn get_next_keyword(key: &str) -> Option<Box<dyn Keyword>> {
+    match key {
+        #[cfg(feature = "draft-next")]
+        "dependentRequired" => Some(Box::new(keywords::dependentRequired)),
+        #[cfg(feature = "draft-next")]
+        "dependentSchemas" => Some(Box::new(keywords::dependentSchemas)),
+        #[cfg(feature = "draft-next")]
+        "maxContains" => Some(Box::new(keywords::maxContains)),
+        #[cfg(feature = "draft-next")]
+        "minContains" => Some(Box::new(keywords::minContains)),
+        #[cfg(feature = "draft-next")]
+        "prefixItems" => Some(Box::new(keywords::prefixItems)),
+        #[cfg(feature = "draft-next")]
+        "propertyDependencies" => Some(Box::new(keywords::propertyDependencies)),
+        #[cfg(feature = "draft-next")]
+        "unevaluatedItems" => Some(Box::new(keywords::unevaluatedItems)),
+        #[cfg(feature = "draft-next")]
+        "unevaluatedProperties" => Some(Box::new(keywords::unevaluatedProperties)),
+        _ => None,
+    }
 }
 
-/// Get the `Draft` from a JSON Schema URL.
+/// Get the `Draft` from a JSON Schema URL, with or without the empty
+/// fragment of the canonical form, e.g.
+/// `http://json-schema.org/draft-04/schema#`.
 pub fn draft_from_url(url: &str) -> Option<&'static dyn Draft> {
-    match url {
+    match url.strip_suffix('#').unwrap_or(url) {
+        "https://json-schema.org/draft/2020-12/schema" => Some(&Draft202012),
+        "https://json-schema.org/draft/2019-09/schema" => Some(&Draft201909),
         "http://json-schema.org/draft-07/schema" => Some(&Draft7),
         "http://json-schema.org/draft-06/schema" => Some(&Draft6),
         "http://json-schema.org/draft-04/schema" => Some(&Draft4),
+        "http://json-schema.org/draft-03/schema" => Some(&Draft3),
+        // OpenAPI 3.0 has no URL for its dialect; this is the id of the
+        // metaschema of `OpenApi30`.
+        "urn:jsonschema-valid:openapi-3.0" => Some(&OpenApi30),
+        _ => None,
+    }
+}
+
+/// Get the `Draft` from its number, as returned by `Draft::get_draft_number`.
+/// Dialects based on a draft, such as `OpenApi30`, share its number and are
+/// only found by `draft_from_url`, given their `Draft::get_dialect`.
+pub fn draft_from_number(number: u8) -> Option<&'static dyn Draft> {
+    match number {
+        9 => Some(&Draft202012),
+        8 => Some(&Draft201909),
+        7 => Some(&Draft7),
+        6 => Some(&Draft6),
+        4 => Some(&Draft4),
+        3 => Some(&Draft3),
         _ => None,
     }
 }
@@ -249,3 +929,544 @@ pub fn draft_from_schema(schema: &Value) -> Option<&'static dyn Draft> {
         .and_then(Value::as_str)
         .and_then(|x| draft_from_url(x))
 }
+
+/// Get the keywords that take part in validation in the given draft.
+///
+/// This includes keywords that are only evaluated together with another
+/// one, such as `then` with `if`, but not annotations like `title` or
+/// `default`, nor keywords that only hold subschemas for `$ref`, like
+/// `definitions`. Keywords in a schema that aren't in this list are
+/// ignored during validation.
+pub fn supported_keywords(draft: &dyn Draft) -> &'static [&'static str] {
+    draft.get_keywords()
+}
+
+/// Check whether the given keyword takes part in validation in the given
+/// draft. See `supported_keywords`.
+pub fn supports(keyword: &str, draft: &dyn Draft) -> bool {
+    draft.get_keywords().contains(&keyword)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supported_keywords() {
+        for draft in &[
+            &Draft3 as &dyn Draft,
+            &Draft4,
+            &Draft6,
+            &Draft7,
+            &Draft201909,
+            &Draft202012,
+        ] {
+            for keyword in supported_keywords(*draft) {
+                let sibling = ["exclusiveMaximum", "exclusiveMinimum", "if", "required"];
+                assert!(draft.get_keyword(keyword).is_some() || sibling.contains(keyword));
+            }
+        }
+        assert!(supports("if", &Draft7));
+        assert!(!supports("if", &Draft6));
+        assert!(supports("exclusiveMaximum", &Draft4));
+        assert!(!supports("title", &Draft7));
+    }
+
+    #[cfg(feature = "draft-next")]
+    #[test]
+    fn test_property_dependencies() {
+        use crate::config::Config;
+        use serde_json::json;
+
+        let schema = json!({
+            "propertyDependencies": {
+                "kind": {
+                    "click": {"required": ["x", "y"]},
+                    "key": {"properties": {"code": {"type": "integer"}}}
+                }
+            }
+        });
+        let cfg = Config::from_schema(&schema, Some(&Draft6)).unwrap();
+        assert!(cfg.supports("propertyDependencies"));
+        assert!(cfg
+            .validate(&json!({"kind": "click", "x": 1, "y": 2}))
+            .is_ok());
+        assert!(cfg.validate(&json!({"kind": "scroll"})).is_ok());
+        assert!(cfg.validate(&json!({"kind": 1})).is_ok());
+        let errors: Vec<_> = cfg
+            .validate(&json!({"kind": "key", "code": "a"}))
+            .unwrap_err()
+            .collect();
+        assert_eq!(
+            errors[0].schema_path(),
+            [
+                "propertyDependencies",
+                "kind",
+                "key",
+                "properties",
+                "code",
+                "type"
+            ]
+        );
+
+        // Only with the applicator vocabulary in draft 2020-12.
+        let url = url::Url::parse("https://example.com/meta").unwrap();
+        let mut custom = schema.clone();
+        custom["$schema"] = json!(url.as_str());
+        let metaschema = |vocabulary: &str| {
+            json!({"$vocabulary": {
+                "https://json-schema.org/draft/2020-12/vocab/core": true,
+                format!("https://json-schema.org/draft/2020-12/vocab/{}", vocabulary): true
+            }})
+        };
+        let cfg = Config::from_schema(&custom, Some(&Draft202012))
+            .unwrap()
+            .with_document(&url, metaschema("applicator"))
+            .unwrap();
+        assert!(cfg.supports("propertyDependencies"));
+        let cfg = Config::from_schema(&custom, Some(&Draft202012))
+            .unwrap()
+            .with_document(&url, metaschema("validation"))
+            .unwrap();
+        assert!(!cfg.supports("propertyDependencies"));
+    }
+
+    #[cfg(feature = "draft-next")]
+    #[test]
+    fn test_unevaluated_properties() {
+        use crate::config::Config;
+        use serde_json::json;
+
+        let schema = json!({
+            "definitions": {"named": {"properties": {"name": {"type": "string"}}}},
+            "allOf": [{"$ref": "#/definitions/named"}],
+            "anyOf": [{"properties": {"a": true}, "required": ["a"]}, {"properties": {"b": true}}],
+            "if": {"properties": {"kind": {"const": "x"}}, "required": ["kind"]},
+            "then": {"patternProperties": {"^x-": true}},
+            "unevaluatedProperties": false
+        });
+        let cfg = Config::from_schema(&schema, Some(&Draft7)).unwrap();
+        assert!(cfg.validate(&json!({"name": "n", "a": 1, "b": 2})).is_ok());
+        assert!(cfg.validate(&json!({"kind": "x", "x-y": 1})).is_ok());
+
+        let errors: Vec<_> = cfg
+            .validate(&json!({"kind": "y", "x-y": 1, "name": "n"}))
+            .unwrap_err()
+            .collect();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].message(),
+            "Unevaluated properties are not allowed. Found kind, x-y"
+        );
+    }
+
+    #[cfg(feature = "draft-next")]
+    #[test]
+    fn test_unevaluated_items() {
+        use crate::config::Config;
+        use serde_json::json;
+
+        let schema = json!({
+            "definitions": {"pair": {"items": [{"type": "string"}, {"type": "integer"}]}},
+            "anyOf": [{"$ref": "#/definitions/pair"}, {"items": [true]}],
+            "if": {"contains": {"const": "end"}},
+            "then": {"contains": {"const": "end"}},
+            "unevaluatedItems": {"type": "boolean"}
+        });
+        let cfg = Config::from_schema(&schema, Some(&Draft7)).unwrap();
+        assert!(cfg.validate(&json!(["a", 1, true, false])).is_ok());
+        assert!(cfg.validate(&json!(["a", 1, "end", "end"])).is_ok());
+
+        let errors: Vec<_> = cfg
+            .validate(&json!(["a", "b", "c", true]))
+            .unwrap_err()
+            .collect();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].instance_path(), ["1"]);
+        assert_eq!(errors[1].instance_path(), ["2"]);
+        assert_eq!(errors[1].schema_path(), ["unevaluatedItems", "type"]);
+
+        let schema = json!({"allOf": [{"items": [true]}], "unevaluatedItems": false});
+        let cfg = Config::from_schema(&schema, Some(&Draft7)).unwrap();
+        let errors: Vec<_> = cfg.validate(&json!([1, 2, 3])).unwrap_err().collect();
+        assert_eq!(
+            errors[0].message(),
+            "Unevaluated items are not allowed. Found items 1, 2"
+        );
+    }
+
+    #[cfg(feature = "draft-next")]
+    #[test]
+    fn test_prefix_items() {
+        use crate::config::Config;
+        use serde_json::json;
+
+        let schema = json!({
+            "prefixItems": [{"type": "string"}, {"type": "integer"}],
+            "items": {"type": "boolean"}
+        });
+        let cfg = Config::from_schema(&schema, Some(&Draft7)).unwrap();
+        assert!(cfg.validate(&json!(["a", 1, true, false])).is_ok());
+        assert!(cfg.validate(&json!(["a"])).is_ok());
+
+        let errors: Vec<_> = cfg
+            .validate(&json!([1, 1, "b"]))
+            .unwrap_err()
+            .map(|err| (err.instance_path().join("/"), err.schema_path().join("/")))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                ("2".to_string(), "items/type".to_string()),
+                ("0".to_string(), "prefixItems/0/type".to_string()),
+            ]
+        );
+    }
+
+    #[cfg(feature = "draft-next")]
+    #[test]
+    fn test_min_and_max_contains() {
+        use crate::config::Config;
+        use serde_json::json;
+
+        let schema = json!({"contains": {"type": "integer"}, "minContains": 2, "maxContains": 3});
+        let cfg = Config::from_schema(&schema, Some(&Draft7)).unwrap();
+        assert!(cfg.validate(&json!([1, "a", 2])).is_ok());
+        assert!(cfg.validate(&json!([1, 2, 3, "a"])).is_ok());
+        let messages = |instance| -> Vec<String> {
+            cfg.validate(&instance)
+                .unwrap_err()
+                .map(|err| err.message().to_string())
+                .collect()
+        };
+        assert_eq!(
+            messages(json!([1, "a"])),
+            ["1 items valid under contains < minContains 2"]
+        );
+        assert_eq!(
+            messages(json!([1, 2, 3, 4])),
+            ["4 items valid under contains > maxContains 3"]
+        );
+
+        let schema = json!({"contains": {"type": "integer"}, "minContains": 0});
+        let cfg = Config::from_schema(&schema, Some(&Draft7)).unwrap();
+        assert!(cfg.validate(&json!(["a"])).is_ok());
+        let schema = json!({"contains": {"type": "integer"}, "maxContains": 1});
+        let cfg = Config::from_schema(&schema, Some(&Draft7)).unwrap();
+        assert!(cfg.validate(&json!(["a"])).is_err());
+    }
+
+    #[cfg(feature = "draft-next")]
+    #[test]
+    fn test_dependent_required_and_schemas() {
+        use crate::config::Config;
+        use serde_json::json;
+
+        let schema = json!({
+            "dependentRequired": {"card": ["billing"]},
+            "dependentSchemas": {"billing": {"properties": {"card": {"type": "string"}}}}
+        });
+        let cfg = Config::from_schema(&schema, Some(&Draft7)).unwrap();
+        assert!(cfg
+            .validate(&json!({"card": "1234", "billing": "x"}))
+            .is_ok());
+        assert!(cfg.validate(&json!({"billing": "x"})).is_ok());
+
+        let errors: Vec<_> = cfg
+            .validate(&json!({"card": 1234}))
+            .unwrap_err()
+            .map(|err| err.schema_path().join("/"))
+            .collect();
+        assert_eq!(errors, ["dependentRequired"]);
+        let errors: Vec<_> = cfg
+            .validate(&json!({"card": 1234, "billing": "x"}))
+            .unwrap_err()
+            .map(|err| err.schema_path().join("/"))
+            .collect();
+        assert_eq!(errors, ["dependentSchemas/billing/properties/card/type"]);
+    }
+
+    #[test]
+    fn test_draft4_semantics() {
+        use crate::config::Config;
+        use serde_json::json;
+
+        let schema = json!({
+            "$schema": "http://json-schema.org/draft-04/schema#",
+            "id": "http://example.com/price.json",
+            "properties": {
+                "amount": {"minimum": 0, "exclusiveMinimum": true},
+                "tags": {"items": {"$ref": "#/definitions/tag"}, "contains": {"const": "x"}},
+                "meta": {"propertyNames": {"maxLength": 1}}
+            },
+            "definitions": {"tag": {"type": "string"}}
+        });
+        let cfg = Config::from_schema(&schema, None).unwrap();
+        assert_eq!(cfg.get_draft_number(), 4);
+        assert!(cfg.validate_schema().is_ok());
+        assert!(cfg
+            .validate(&json!({"amount": 1, "tags": ["a"], "meta": {"long": 1}}))
+            .is_ok());
+        assert!(cfg.validate(&json!({"amount": 0})).is_err());
+        assert!(cfg.validate(&json!({"tags": [1]})).is_err());
+
+        assert!(!cfg.supports("const"));
+        assert!(!cfg.supports("contains"));
+        assert!(!cfg.supports("propertyNames"));
+        assert_eq!(
+            draft_from_url("http://json-schema.org/draft-07/schema#").map(Draft::get_draft_number),
+            Some(7)
+        );
+    }
+
+    #[test]
+    fn test_draft201909() {
+        use crate::config::Config;
+        use crate::formats::FormatMode;
+        use serde_json::json;
+
+        let schema = json!({
+            "$schema": "https://json-schema.org/draft/2019-09/schema",
+            "$id": "https://example.com/tree",
+            "$recursiveAnchor": true,
+            "$ref": "#/$defs/node",
+            "properties": {"children": {"items": {"$recursiveRef": "#"}}},
+            "dependentRequired": {"width": ["height"]},
+            "unevaluatedProperties": false,
+            "$defs": {"node": {"properties": {"name": {"format": "email"}}}}
+        });
+        let cfg = Config::from_schema(&schema, None).unwrap();
+        assert_eq!(cfg.get_draft_number(), 8);
+        assert_eq!(cfg.get_format_mode(), FormatMode::Annotation);
+        assert!(cfg.validate_schema().is_ok());
+        assert!(cfg
+            .validate(&json!({"name": "root", "children": [{"name": "leaf"}]}))
+            .is_ok());
+        let errors: Vec<_> = cfg
+            .validate(&json!({"children": [{"width": 1}]}))
+            .unwrap_err()
+            .map(|err| err.keyword().unwrap().to_string())
+            .collect();
+        assert_eq!(errors, ["dependentRequired", "unevaluatedProperties"]);
+
+        // An extension of the tree, which `$recursiveRef` in the tree refers
+        // to for its children.
+        let strict = json!({
+            "$schema": "https://json-schema.org/draft/2019-09/schema",
+            "$id": "https://example.com/strict-tree",
+            "$recursiveAnchor": true,
+            "$ref": "tree",
+            "required": ["name"]
+        });
+        let strict = Config::from_schema(&strict, None)
+            .unwrap()
+            .with_document(
+                &url::Url::parse("https://example.com/tree").unwrap(),
+                schema.clone(),
+            )
+            .unwrap();
+        assert!(strict
+            .validate(&json!({"name": "root", "children": []}))
+            .is_ok());
+        assert!(strict
+            .validate(&json!({"name": "root", "children": [{}]}))
+            .is_err());
+        assert!(draft_from_url("https://json-schema.org/draft/2019-09/schema#").is_some());
+
+        let invalid = json!({"properties": {"a": {"items": {"minContains": -1}}}});
+        let cfg = Config::from_schema(&invalid, Some(&Draft201909)).unwrap();
+        assert!(cfg.validate_schema().is_err());
+    }
+
+    #[test]
+    fn test_draft202012() {
+        use crate::config::Config;
+        use serde_json::json;
+
+        let schema = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "$id": "https://example.com/tree",
+            "$dynamicAnchor": "node",
+            "properties": {
+                "children": {"items": {"$dynamicRef": "#node"}},
+                "position": {
+                    "prefixItems": [{"type": "number"}, {"type": "number"}],
+                    "items": false,
+                    "additionalItems": {"type": "string"}
+                }
+            }
+        });
+        let cfg = Config::from_schema(&schema, None).unwrap();
+        assert_eq!(cfg.get_draft_number(), 9);
+        assert!(cfg.validate_schema().is_ok());
+        assert!(cfg
+            .validate(&json!({"position": [1, 2], "children": [{"children": []}]}))
+            .is_ok());
+        assert!(cfg.validate(&json!({"position": [1, 2, "3"]})).is_err());
+        assert!(!cfg.supports("additionalItems"));
+        // A proposal for the next draft.
+        assert_eq!(
+            cfg.supports("propertyDependencies"),
+            cfg!(feature = "draft-next")
+        );
+
+        // An extension of the tree, which `$dynamicRef` in the tree refers
+        // to for its children.
+        let strict = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "$id": "https://example.com/strict-tree",
+            "$dynamicAnchor": "node",
+            "$ref": "tree",
+            "required": ["name"]
+        });
+        let strict = Config::from_schema(&strict, None)
+            .unwrap()
+            .with_document(
+                &url::Url::parse("https://example.com/tree").unwrap(),
+                schema.clone(),
+            )
+            .unwrap();
+        assert!(strict
+            .validate(&json!({"name": "root", "children": []}))
+            .is_ok());
+        assert!(strict
+            .validate(&json!({"name": "root", "children": [{}]}))
+            .is_err());
+
+        let invalid = json!({"prefixItems": {"type": "string"}});
+        let cfg = Config::from_schema(&invalid, Some(&Draft202012)).unwrap();
+        assert!(cfg.validate_schema().is_err());
+
+        let custom = json!({"$schema": "https://example.com/custom"});
+        let metaschema = json!({
+            "$vocabulary": {
+                "https://json-schema.org/draft/2020-12/vocab/core": true,
+                "https://example.com/vocab/units": true
+            }
+        });
+        let cfg = Config::from_schema(&custom, Some(&Draft202012)).unwrap();
+        let url = url::Url::parse("https://example.com/custom").unwrap();
+        assert!(cfg.with_document(&url, metaschema).is_err());
+    }
+
+    #[test]
+    fn test_draft3() {
+        use crate::config::Config;
+        use serde_json::json;
+
+        let schema = json!({
+            "$schema": "http://json-schema.org/draft-03/schema#",
+            "extends": {"$ref": "#/definitions/named"},
+            "properties": {
+                "size": {"type": ["integer", {"enum": ["small", "large"]}], "divisibleBy": 2},
+                "color": {"disallow": "null"}
+            },
+            "definitions": {"named": {"properties": {"name": {"required": true}}}}
+        });
+        let cfg = Config::from_schema(&schema, None).unwrap();
+        assert_eq!(cfg.get_draft_number(), 3);
+        assert!(cfg.validate_schema().is_ok());
+        assert!(cfg
+            .validate(&json!({"name": "a", "size": 4, "color": "red"}))
+            .is_ok());
+        assert!(cfg.validate(&json!({"name": "a", "size": "large"})).is_ok());
+        let errors: Vec<_> = cfg
+            .validate(&json!({"size": 3, "color": null}))
+            .unwrap_err()
+            .map(|err| {
+                (
+                    err.keyword().unwrap().to_string(),
+                    err.schema_path().join("/"),
+                )
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                (
+                    "required".to_string(),
+                    "extends/properties/name/required".to_string()
+                ),
+                (
+                    "disallow".to_string(),
+                    "properties/color/disallow".to_string()
+                ),
+                (
+                    "divisibleBy".to_string(),
+                    "properties/size/divisibleBy".to_string()
+                ),
+            ]
+        );
+
+        let invalid = json!({"properties": {"a": {"required": ["b"]}}});
+        let cfg = Config::from_schema(&invalid, Some(&Draft3)).unwrap();
+        assert!(cfg.validate_schema().is_err());
+    }
+
+    #[test]
+    fn test_openapi30() {
+        use crate::config::Config;
+        use serde_json::json;
+
+        let schema = json!({
+            "type": "object",
+            "required": ["id"],
+            "properties": {
+                "id": {"type": "integer", "minimum": 0, "exclusiveMinimum": true},
+                "note": {"type": "string", "nullable": true, "example": "Leave at the door"},
+                "size": {"type": "string", "enum": ["s", "m"], "nullable": true},
+                "tags": {"type": "array", "items": {"type": "string"}},
+                "extra": {"patternProperties": {"^x": {"type": "string"}}}
+            }
+        });
+        let cfg = Config::from_schema(&schema, Some(&OpenApi30)).unwrap();
+        assert_eq!(cfg.get_draft_number(), 4);
+        assert_eq!(
+            cfg.get_draft().get_dialect(),
+            "urn:jsonschema-valid:openapi-3.0"
+        );
+        assert!(draft_from_url(cfg.get_draft().get_dialect())
+            .unwrap()
+            .is_openapi());
+        assert!(!cfg.supports("patternProperties"));
+        assert!(cfg
+            .validate(&json!({"id": 1, "note": null, "extra": {"xy": 1}}))
+            .is_ok());
+        let errors: Vec<_> = cfg
+            .validate(&json!({"id": 0, "tags": null, "size": null}))
+            .unwrap_err()
+            .map(|err| err.schema_path().join("/"))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "properties/id/minimum",
+                "properties/size/enum",
+                "properties/tags/type"
+            ]
+        );
+
+        let errors: Vec<_> = cfg
+            .validate_schema()
+            .unwrap_err()
+            .map(|err| err.instance_path().join("/"))
+            .collect();
+        assert_eq!(errors, ["properties/extra/patternProperties"]);
+        let invalid = json!({"type": ["string", "null"]});
+        let cfg = Config::from_schema(&invalid, Some(&OpenApi30)).unwrap();
+        assert!(cfg.validate_schema().is_err());
+
+        // `id` doesn't give the schema a URI that references can use.
+        let schema = json!({
+            "id": "http://example.com/root.json",
+            "properties": {
+                "a": {"$ref": "http://example.com/root.json#/properties/b"},
+                "b": {"type": "integer"}
+            }
+        });
+        let cfg = Config::from_schema(&schema, Some(&OpenApi30)).unwrap();
+        assert!(cfg.validate_refs().is_err());
+        let cfg = Config::from_schema(&schema, Some(&Draft4)).unwrap();
+        assert!(cfg.validate_refs().is_ok());
+    }
+}
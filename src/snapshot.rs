@@ -0,0 +1,108 @@
+//! Rendering validation results for snapshot tests.
+//!
+//! `render` turns the result of a validation into text that only changes
+//! when the validation behavior does, to be compared against a golden file
+//! or recorded with a snapshot testing library such as `insta`. The errors
+//! are sorted by the location in the instance, then the location in the
+//! schema, then the message, so the order errors are found in doesn't
+//! matter, and locations are written as escaped JSON pointers in URI
+//! fragment form.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::{schemas, Config};
+//! use jsonschema_valid::snapshot;
+//!
+//! let schema = json!({"properties": {"a/b": {"maxLength": 1}}, "required": ["c"]});
+//! let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+//!
+//! assert_eq!(snapshot::render(cfg.validate(&json!({"c": 1}))), "valid\n");
+//! let rendered = snapshot::render(cfg.validate(&json!({"a/b": "xy"})));
+//! assert_eq!(
+//!     rendered.lines().collect::<Vec<_>>(),
+//!     [
+//!         "invalid: 2 errors",
+//!         "# (#/required): required properties c are missing",
+//!         "#/a~1b (#/properties/a~1b/maxLength): 2 < maxLength 1",
+//!     ]
+//! );
+//! ```
+
+use std::fmt::Write;
+
+use crate::error::{ErrorIterator, ValidationError};
+use crate::util;
+
+fn fragment(path: &[&str]) -> String {
+    let mut result = "#".to_string();
+    for token in path {
+        result.push('/');
+        result.push_str(&util::escape_pointer_token(token));
+    }
+    result
+}
+
+/// Render the result of a validation, as returned by `Config::validate`.
+pub fn render(result: Result<(), ErrorIterator>) -> String {
+    match result {
+        Ok(()) => render_errors(&[]),
+        Err(errors) => render_errors(&errors.collect::<Vec<_>>()),
+    }
+}
+
+/// Render a list of errors, which is valid if there are none.
+///
+/// The first line is `valid`, or `invalid` and the number of errors. Each
+/// error follows on a line of its own, with the location of the failing
+/// part of the instance, the location of the failing keyword in the schema
+/// in parentheses, and the message.
+pub fn render_errors(errors: &[ValidationError]) -> String {
+    if errors.is_empty() {
+        return "valid\n".to_string();
+    }
+    let mut lines: Vec<(String, String, &str)> = errors
+        .iter()
+        .map(|err| {
+            (
+                fragment(&err.instance_path()),
+                fragment(&err.schema_path()),
+                err.message(),
+            )
+        })
+        .collect();
+    lines.sort();
+    let mut result = format!(
+        "invalid: {} error{}\n",
+        errors.len(),
+        if errors.len() == 1 { "" } else { "s" }
+    );
+    for (instance, schema, message) in lines {
+        // Keep each error on one line even if the message isn't.
+        let message = message.replace('\n', "\\n");
+        writeln!(result, "{} ({}): {}", instance, schema, message).unwrap();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::schemas;
+    use serde_json::json;
+
+    #[test]
+    fn test_order() {
+        let schema = json!({"items": {"type": "string"}, "maxItems": 1});
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let mut errors: Vec<ValidationError> = cfg.validate(&json!([2, 1])).unwrap_err().collect();
+        let rendered = render_errors(&errors);
+        errors.reverse();
+        assert_eq!(render_errors(&errors), rendered);
+        assert_eq!(rendered.lines().count(), 4);
+        assert!(rendered.starts_with("invalid: 3 errors\n# (#/maxItems): "));
+        assert!(rendered.ends_with("#/1 (#/items/type): 1 is not of type \"string\"\n"));
+    }
+}
@@ -0,0 +1,165 @@
+//! Rendering validation errors as annotated snippets of the source text.
+//!
+//! Messages with JSON pointers are hard to act on in large documents. Given
+//! the text of a JSON document and errors from validating it, `render`
+//! prints each error in the style of compiler diagnostics: the message, the
+//! line and column of the offending value, and the line itself with the
+//! value underlined and, if recorded with `Config::with_error_values`, the
+//! failing constraint next to it.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::{schemas, Config};
+//! use jsonschema_valid::snippet::render;
+//!
+//! let schema = json!({"properties": {"port": {"type": "integer"}}});
+//! let cfg = Config::from_schema(&schema, Some(&schemas::Draft7))
+//!     .unwrap()
+//!     .with_error_values();
+//! let text = "{\n  \"port\": \"80\"\n}\n";
+//!
+//! let instance = serde_json::from_str(text).unwrap();
+//! let errors: Vec<_> = cfg.validate(&instance).unwrap_err().collect();
+//! assert_eq!(
+//!     render("config.json", text, &errors[0]),
+//!     concat!(
+//!         "error[type]: \"80\" is not of type \"integer\"\n",
+//!         " --> config.json:2:11\n",
+//!         "  |\n",
+//!         "2 |   \"port\": \"80\"\n",
+//!         "  |           ^^^^ type: \"integer\" at /properties/port/type\n",
+//!     )
+//! );
+//! ```
+
+use std::fmt::Write;
+
+use crate::error::ValidationError;
+use crate::lsp;
+use crate::util;
+
+/// The longest rendering of the value of a failing keyword; longer values
+/// are cut off.
+const MAX_CONSTRAINT_LENGTH: usize = 60;
+
+/// Render `error`, from validating the JSON document `text` named `name`
+/// (e.g. its file name), as an annotated snippet of `text`.
+///
+/// Scalars are underlined entirely; for objects and arrays, only the
+/// opening bracket is. If the offending value can't be found in `text`, only
+/// the message and the name are rendered.
+pub fn render(name: &str, text: &str, error: &ValidationError) -> String {
+    let mut result = match error.keyword() {
+        Some(keyword) => format!("error[{}]: {}\n", keyword, error.message()),
+        None => format!("error: {}\n", error.message()),
+    };
+    let (start, end) = match lsp::locate(text, &error.instance_path()) {
+        Some(range) => range,
+        None => {
+            writeln!(result, " --> {}", name).unwrap();
+            return result;
+        }
+    };
+
+    let line_start = text[..start].rfind('\n').map_or(0, |index| index + 1);
+    let line_end = text[start..]
+        .find('\n')
+        .map_or(text.len(), |index| start + index);
+    let line = text[line_start..line_end].trim_end_matches('\r');
+    let number = (text[..start].matches('\n').count() + 1).to_string();
+    let column = text[line_start..start].chars().count();
+    let width = text[start..end.min(line_end)].chars().count().max(1);
+    let gutter = " ".repeat(number.len());
+
+    let schema_path: String = error
+        .schema_path()
+        .iter()
+        .map(|token| format!("/{}", util::escape_pointer_token(token)))
+        .collect();
+    let label = match (error.keyword(), error.keyword_value()) {
+        (Some(keyword), Some(value)) => {
+            let mut value = value.to_string();
+            if value.chars().count() > MAX_CONSTRAINT_LENGTH {
+                value = value
+                    .chars()
+                    .take(MAX_CONSTRAINT_LENGTH)
+                    .collect::<String>()
+                    + "...";
+            }
+            format!("{}: {} at {}", keyword, value, schema_path)
+        }
+        _ => format!("at {}", schema_path),
+    };
+
+    writeln!(result, "{}--> {}:{}:{}", gutter, name, number, column + 1).unwrap();
+    writeln!(result, "{} |", gutter).unwrap();
+    writeln!(result, "{} | {}", number, line).unwrap();
+    writeln!(
+        result,
+        "{} | {}{} {}",
+        gutter,
+        " ".repeat(column),
+        "^".repeat(width),
+        label
+    )
+    .unwrap();
+    if let Some(url) = error.docs_url() {
+        writeln!(result, "{} |", gutter).unwrap();
+        writeln!(result, "{} = help: see {}", gutter, url).unwrap();
+    }
+    result
+}
+
+/// Render each of `errors`, from validating the JSON document `text` named
+/// `name`, as with `render`, separated by blank lines.
+pub fn render_all(name: &str, text: &str, errors: &[ValidationError]) -> String {
+    errors
+        .iter()
+        .map(|error| render(name, text, error))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::schemas;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_all() {
+        let schema = json!({"items": {"required": ["id"]}, "maxItems": 1});
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7))
+            .unwrap()
+            .with_docs_url("https://example.com/{keyword}")
+            .with_error_values();
+        let text = "[\n  {\"id\": 1},\n  {}\n]";
+        let instance = serde_json::from_str(text).unwrap();
+        let errors: Vec<_> = cfg.validate(&instance).unwrap_err().collect();
+        assert_eq!(
+            render_all("list.json", text, &errors),
+            [
+                "error[required]: required properties id are missing",
+                " --> list.json:3:3",
+                "  |",
+                "3 |   {}",
+                "  |   ^ required: [\"id\"] at /items/required",
+                "  |",
+                "  = help: see https://example.com/required",
+                "",
+                "error[maxItems]: 2 > maxItems 1",
+                " --> list.json:1:1",
+                "  |",
+                "1 | [",
+                "  | ^ maxItems: 1 at /maxItems",
+                "  |",
+                "  = help: see https://example.com/maxItems",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
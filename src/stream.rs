@@ -0,0 +1,1588 @@
+//! Validating JSON documents as a stream of parser events.
+//!
+//! `StreamValidator` consumes the events of a JSON parser (the start and end
+//! of objects and arrays, object keys, and values) and only keeps track of
+//! the subschemas that apply to the objects and arrays currently open, so
+//! documents far larger than memory can be validated. `Parser` produces
+//! these events from any reader, and `validate_reader` combines the two.
+//! `validate_file` reads a file through a memory mapping instead, so even
+//! the buffering of the reader is left to the operating system.
+//! `validate_seq` and `validate_seq_file` validate each document of a JSON
+//! text sequence (`application/json-seq`) separately.
+//!
+//! All of these transparently decompress gzip input with the `gzip` feature,
+//! and zstd input with the `zstd` feature; see `Decompress`.
+//!
+//! All keywords work on strings, numbers, booleans and `null`. On objects and
+//! arrays, the keywords about their structure work fully, but `enum`,
+//! `const` and `uniqueItems` would need the whole value at once; they, and
+//! custom keywords, are reported as errors instead of being checked.
+//!
+//! Errors about objects and arrays don't carry the failing instance, nor the
+//! enclosing object set up with `Config::with_error_context`, and errors may
+//! be reported in a different order than by `Config::validate`.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::json;
+//! # use jsonschema_valid::{schemas, Config};
+//! use jsonschema_valid::stream::validate_reader;
+//!
+//! let schema = json!({"items": {"required": ["id"]}});
+//! let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+//! let input = r#"[{"id": 1}, {"name": "x"}]"#;
+//!
+//! let errors = validate_reader(&cfg, input.as_bytes()).unwrap();
+//! assert_eq!(errors.len(), 1);
+//! assert_eq!(errors[0].instance_path(), vec!["1"]);
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, Read};
+use std::path::Path;
+use std::rc::Rc;
+
+use memmap2::Mmap;
+use regex::Regex;
+use serde_json::{json, Map, Value};
+
+use crate::config::Config;
+use crate::context::Context;
+use crate::error::ValidationError;
+use crate::keywords;
+use crate::node::NodeKind;
+use crate::util;
+
+/// An event of a JSON parser.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// The start of an object.
+    StartObject,
+    /// The key of the next member of the current object.
+    Key(String),
+    /// The end of an object.
+    EndObject,
+    /// The start of an array.
+    StartArray,
+    /// The end of an array.
+    EndArray,
+    /// A complete value, usually a string, number, boolean or `null`.
+    Value(Value),
+}
+
+/// An error from streaming validation.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading the input failed.
+    Io(io::Error),
+    /// The input is not valid JSON.
+    Syntax {
+        /// The offset in bytes at which the error was found.
+        offset: usize,
+        /// A description of the error.
+        message: String,
+    },
+    /// The events don't form a single JSON document.
+    Event(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Syntax { offset, message } => write!(f, "{} at byte {}", message, offset),
+            Error::Event(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+/// Keywords that only apply to strings, numbers, booleans and `null`.
+const SCALAR_KEYWORDS: &[&str] = &[
+    "contentEncoding",
+    "contentMediaType",
+    "contentSchema",
+    "divisibleBy",
+    "exclusiveMaximum",
+    "exclusiveMinimum",
+    "format",
+    "maxLength",
+    "maximum",
+    "minLength",
+    "minimum",
+    "multipleOf",
+    "pattern",
+];
+
+/// Keywords that apply to objects or arrays and can be checked without
+/// keeping them in memory.
+const STRUCTURAL_KEYWORDS: &[&str] = &[
+    "$ref",
+    "additionalItems",
+    "additionalProperties",
+    "allOf",
+    "anyOf",
+    "contains",
+    "dependencies",
+    "if",
+    "items",
+    "maxItems",
+    "maxProperties",
+    "minItems",
+    "minProperties",
+    "not",
+    "oneOf",
+    "patternProperties",
+    "properties",
+    "propertyNames",
+    "required",
+    "type",
+    "uniqueItems",
+];
+
+/// What becomes of the result of an evaluation.
+enum Delivery {
+    /// Its errors are errors of the parent evaluation.
+    Errors,
+    /// It decides whether an array item matches `contains`.
+    Contains,
+    /// It is one of several results the parent evaluation combines, such
+    /// as a branch of `anyOf`.
+    Branch(String),
+}
+
+/// The connection of an evaluation to the one it was started by.
+struct Link<'c> {
+    parent: usize,
+    keyword: &'c str,
+    value: &'c Value,
+    delivery: Delivery,
+    instance_token: Option<String>,
+    schema_token: Option<String>,
+}
+
+impl<'c> Link<'c> {
+    fn new(parent: usize, keyword: &'c str, value: &'c Value, delivery: Delivery) -> Link<'c> {
+        Link {
+            parent,
+            keyword,
+            value,
+            delivery,
+            instance_token: None,
+            schema_token: None,
+        }
+    }
+
+    fn at<T: ToString>(mut self, token: T) -> Self {
+        self.instance_token = Some(token.to_string());
+        self
+    }
+
+    fn under<T: ToString>(mut self, token: T) -> Self {
+        self.schema_token = Some(token.to_string());
+        self
+    }
+
+    fn wrap(&self, cfg: &Config, mut err: ValidationError) -> ValidationError {
+        if let Some(token) = &self.schema_token {
+            err = err.schema_ctx(token.clone());
+        }
+        if let Some(token) = &self.instance_token {
+            err = err.instance_ctx(token.clone());
+        }
+        wrap(cfg, err, self.keyword, self.value)
+    }
+}
+
+/// Attach the keyword an error comes from, as `keywords::descend` does.
+fn wrap(cfg: &Config, err: ValidationError, keyword: &str, value: &Value) -> ValidationError {
+    let err = err.keyword_ctx(cfg, keyword, value, None);
+    if keyword == "$ref" {
+        err
+    } else {
+        err.schema_ctx(keyword.to_string())
+    }
+}
+
+/// Whether the draft 3 `properties` or `type` keyword with the value `value`
+/// has `required` flags or schemas among the types, respectively.
+fn needs_whole_value_in_draft3(keyword: &str, value: &Value) -> bool {
+    match (keyword, value) {
+        ("properties", Value::Object(properties)) => properties
+            .values()
+            .any(|subschema| subschema.get("required") == Some(&Value::Bool(true))),
+        ("type", _) => util::iter_or_once(value).any(Value::is_object),
+        _ => false,
+    }
+}
+
+/// The evaluation of a schema against an object or array that is open.
+struct Eval<'c> {
+    schema: &'c Value,
+    kind: NodeKind,
+    scopes: Rc<Vec<Value>>,
+    link: Option<Link<'c>>,
+    errors: Vec<ValidationError>,
+    keys: Option<HashSet<String>>,
+    count: usize,
+    patterns: Vec<(&'c Value, Regex)>,
+    patterns_valid: bool,
+    extras: Vec<String>,
+    contains_found: bool,
+    branches: Vec<(&'c str, String, Vec<ValidationError>)>,
+}
+
+impl<'c> Eval<'c> {
+    /// Take the result of the branch `label` of `keyword`.
+    fn take_branch(&mut self, keyword: &str, label: &str) -> Option<Vec<ValidationError>> {
+        let index = self
+            .branches
+            .iter()
+            .position(|(k, l, _)| *k == keyword && l == label)?;
+        Some(self.branches.swap_remove(index).2)
+    }
+}
+
+/// An object or array that is open.
+struct Frame {
+    kind: NodeKind,
+    evals: Vec<usize>,
+    key: Option<String>,
+    index: usize,
+}
+
+/// A schema to evaluate a value against.
+type Child<'c> = (&'c Value, Rc<Vec<Value>>, Option<Link<'c>>);
+
+/// Validates a JSON document given as a sequence of parser events.
+pub struct StreamValidator<'c> {
+    cfg: &'c Config<'c>,
+    evals: Vec<Option<Eval<'c>>>,
+    free: Vec<usize>,
+    stack: Vec<Frame>,
+    regexes: HashMap<&'c str, Result<Regex, String>>,
+    done: bool,
+    errors: Vec<ValidationError>,
+}
+
+impl<'c> StreamValidator<'c> {
+    /// Create a validator for a document to be validated against the schema
+    /// in `cfg`.
+    pub fn new(cfg: &'c Config<'c>) -> StreamValidator<'c> {
+        StreamValidator {
+            cfg,
+            evals: Vec::new(),
+            free: Vec::new(),
+            stack: Vec::new(),
+            regexes: HashMap::new(),
+            done: false,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Process the next event of the document.
+    ///
+    /// Fails if the event can't occur at this point of a JSON document.
+    pub fn push(&mut self, event: Event) -> Result<(), Error> {
+        if self.done {
+            return Err(Error::Event(
+                "Event after the end of the document".to_string(),
+            ));
+        }
+        match event {
+            Event::Key(key) => {
+                let evals = match self.stack.last_mut() {
+                    Some(frame) if frame.kind == NodeKind::Object && frame.key.is_none() => {
+                        frame.key = Some(key.clone());
+                        frame.evals.clone()
+                    }
+                    _ => return Err(Error::Event("Unexpected key".to_string())),
+                };
+                for id in evals {
+                    self.key(id, &key);
+                }
+            }
+            Event::StartObject => self.start(NodeKind::Object)?,
+            Event::StartArray => self.start(NodeKind::Array)?,
+            Event::EndObject => self.end(NodeKind::Object)?,
+            Event::EndArray => self.end(NodeKind::Array)?,
+            Event::Value(value) => {
+                for (schema, scopes, link) in self.enter()? {
+                    let errors = self.descend(&value, schema, &scopes);
+                    self.deliver(link, errors);
+                }
+                self.leave();
+            }
+        }
+        Ok(())
+    }
+
+    /// Finish validation, returning the validation errors, if any.
+    ///
+    /// Fails if the events didn't form a complete document.
+    pub fn finish(self) -> Result<Vec<ValidationError>, Error> {
+        if self.done {
+            Ok(self.errors)
+        } else {
+            Err(Error::Event("Incomplete document".to_string()))
+        }
+    }
+
+    fn eval(&mut self, id: usize) -> &mut Eval<'c> {
+        self.evals[id].as_mut().expect("evaluation is active")
+    }
+
+    /// Validate a complete value with the regular validator.
+    fn descend(
+        &self,
+        instance: &Value,
+        schema: &'c Value,
+        scopes: &[Value],
+    ) -> Vec<ValidationError> {
+        let cfg = self.cfg;
+        in_context(Context::new_from(cfg.get_schema()), scopes, &mut |ctx| {
+            keywords::descend(cfg, instance, schema, None, ctx).collect()
+        })
+    }
+
+    /// The schemas that apply to the next value in the current object or
+    /// array, or to the document.
+    fn enter(&mut self) -> Result<Vec<Child<'c>>, Error> {
+        let (evals, key, index) = match self.stack.last() {
+            None => {
+                let schema = self.cfg.get_schema();
+                return Ok(vec![(schema, Rc::new(Vec::new()), None)]);
+            }
+            Some(frame) if frame.kind == NodeKind::Object && frame.key.is_none() => {
+                return Err(Error::Event("Value without a key".to_string()))
+            }
+            Some(frame) => (frame.evals.clone(), frame.key.clone(), frame.index),
+        };
+        let mut children = Vec::new();
+        for id in evals {
+            self.children(id, key.as_deref(), index, &mut children);
+        }
+        Ok(children)
+    }
+
+    /// Step past a complete value.
+    fn leave(&mut self) {
+        match self.stack.last_mut() {
+            None => self.done = true,
+            Some(frame) => {
+                frame.key = None;
+                frame.index += 1;
+            }
+        }
+    }
+
+    fn start(&mut self, kind: NodeKind) -> Result<(), Error> {
+        let mut evals = Vec::new();
+        for (schema, scopes, link) in self.enter()? {
+            self.spawn(schema, scopes, kind, link, &mut evals);
+        }
+        self.stack.push(Frame {
+            kind,
+            evals,
+            key: None,
+            index: 0,
+        });
+        Ok(())
+    }
+
+    fn end(&mut self, kind: NodeKind) -> Result<(), Error> {
+        match self.stack.last() {
+            Some(frame) if frame.kind == kind && frame.key.is_none() => {}
+            _ => {
+                return Err(Error::Event(
+                    "Unexpected end of object or array".to_string(),
+                ))
+            }
+        }
+        let frame = self.stack.pop().unwrap();
+        // Evaluations combining the results of others were started before
+        // them, so finish in reverse.
+        for id in frame.evals.into_iter().rev() {
+            self.finish_eval(id);
+        }
+        self.leave();
+        Ok(())
+    }
+
+    /// The keywords of `schema` to evaluate, unless it is a boolean or a
+    /// `$ref`, which replaces all other keywords before draft 2019-09.
+    fn active(&self, schema: &'c Value) -> Option<&'c Map<String, Value>> {
+        match schema {
+            Value::Object(object)
+                if !(object.contains_key("$ref")
+                    && self.cfg.get_keyword("$ref").is_some()
+                    && self.cfg.get_draft_number() < 8) =>
+            {
+                Some(object)
+            }
+            _ => None,
+        }
+    }
+
+    /// The value of `keyword` in `object`, if the keyword is supported.
+    fn get(&self, object: &'c Map<String, Value>, keyword: &str) -> Option<&'c Value> {
+        match self.cfg.get_keyword(keyword) {
+            Some(_) => object.get(keyword),
+            None => None,
+        }
+    }
+
+    /// Record an error of `keyword` in an evaluation.
+    fn fail(&mut self, id: usize, keyword: &str, value: &Value, err: ValidationError) {
+        let err = wrap(self.cfg, err, keyword, value);
+        self.eval(id).errors.push(err);
+    }
+
+    /// Start evaluating `schema` against an object or array, and all the
+    /// schemas that apply to it in place, such as those in `allOf`.
+    fn spawn(
+        &mut self,
+        schema: &'c Value,
+        scopes: Rc<Vec<Value>>,
+        kind: NodeKind,
+        link: Option<Link<'c>>,
+        out: &mut Vec<usize>,
+    ) {
+        let cfg = self.cfg;
+        let eval = Eval {
+            schema,
+            kind,
+            scopes: scopes.clone(),
+            link,
+            errors: Vec::new(),
+            keys: None,
+            count: 0,
+            patterns: Vec::new(),
+            patterns_valid: true,
+            extras: Vec::new(),
+            contains_found: false,
+            branches: Vec::new(),
+        };
+        let id = match self.free.pop() {
+            Some(id) => {
+                self.evals[id] = Some(eval);
+                id
+            }
+            None => {
+                self.evals.push(Some(eval));
+                self.evals.len() - 1
+            }
+        };
+        out.push(id);
+
+        let object = match schema {
+            Value::Object(object) => object,
+            _ => return,
+        };
+        if let Some(ref_) = self.get(object, "$ref") {
+            if let Value::String(sref) = ref_ {
+                let resolved =
+                    in_context(Context::new_from(cfg.get_schema()), &scopes, &mut |ctx| {
+                        cfg.get_resolver()
+                            .resolve_fragment(sref, &ctx, cfg.get_schema())
+                    });
+                match resolved {
+                    Ok((scope, resolved)) => {
+                        let mut inner = (*scopes).clone();
+                        inner.push(json!({"$id": scope.to_string()}));
+                        let link = Link::new(id, "$ref", ref_, Delivery::Errors);
+                        self.spawn(resolved, Rc::new(inner), kind, Some(link), out);
+                    }
+                    Err(_) => {
+                        let msg = format!("Couldn't resolve reference {}", sref);
+                        self.fail(id, "$ref", ref_, ValidationError::new(&msg));
+                    }
+                }
+            }
+            if self.active(schema).is_none() {
+                return;
+            }
+        }
+
+        let subschema = |schema: &'c Value| {
+            if cfg.get_draft_number() >= 6 {
+                util::bool_to_object_schema(schema)
+            } else {
+                schema
+            }
+        };
+        let mut invalid_pattern = None;
+        if kind == NodeKind::Object {
+            if object.contains_key("required") || object.contains_key("dependencies") {
+                self.eval(id).keys = Some(HashSet::new());
+            }
+            if let Some(Value::Object(patterns)) = object.get("patternProperties") {
+                for (pattern, subschema) in patterns {
+                    let re = self
+                        .regexes
+                        .entry(pattern)
+                        .or_insert_with(|| Regex::new(pattern).map_err(|err| err.to_string()));
+                    match re.clone() {
+                        Ok(re) => self.eval(id).patterns.push((subschema, re)),
+                        Err(err) => {
+                            self.eval(id).patterns_valid = false;
+                            if invalid_pattern.is_none() {
+                                invalid_pattern = Some((pattern, err));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        for (k, v) in object {
+            if cfg.get_keyword(k).is_none() {
+                continue;
+            }
+            match (k.as_str(), kind) {
+                // The `required` flags and the schemas among the types of
+                // draft 3 aren't supported while streaming.
+                ("properties", NodeKind::Object) | ("type", _)
+                    if cfg.get_draft_number() == 3 && needs_whole_value_in_draft3(k, v) =>
+                {
+                    let msg = format!("{} can't be checked while streaming {}", k, describe(kind));
+                    self.fail(id, k, v, ValidationError::new(&msg));
+                }
+                ("allOf", _) => {
+                    if let Value::Array(subschemas) = v {
+                        for (index, sub) in subschemas.iter().enumerate() {
+                            let link = Link::new(id, k, v, Delivery::Errors).under(index);
+                            self.spawn(subschema(sub), scopes.clone(), kind, Some(link), out);
+                        }
+                    }
+                }
+                ("anyOf", _) | ("oneOf", _) => {
+                    if let Value::Array(subschemas) = v {
+                        for (index, sub) in subschemas.iter().enumerate() {
+                            let link = Link::new(id, k, v, Delivery::Branch(index.to_string()));
+                            self.spawn(subschema(sub), scopes.clone(), kind, Some(link), out);
+                        }
+                    }
+                }
+                ("not", _) => {
+                    let link = Link::new(id, k, v, Delivery::Branch(String::new()));
+                    self.spawn(v, scopes.clone(), kind, Some(link), out);
+                }
+                ("then", _) | ("else", _) => {
+                    if let Some(if_) = object.get("if") {
+                        let link = Link::new(id, k, v, Delivery::Branch("if".to_string()));
+                        self.spawn(if_, scopes.clone(), kind, Some(link), out);
+                        let link = Link::new(id, k, v, Delivery::Branch(k.to_string()));
+                        self.spawn(v, scopes.clone(), kind, Some(link), out);
+                    }
+                }
+                ("dependencies", NodeKind::Object) => {
+                    if let Value::Object(dependencies) = v {
+                        for (property, dependency) in dependencies {
+                            let dependency = util::bool_to_object_schema(dependency);
+                            if dependency.is_object() {
+                                let link =
+                                    Link::new(id, k, v, Delivery::Branch(property.to_string()));
+                                self.spawn(dependency, scopes.clone(), kind, Some(link), out);
+                            }
+                        }
+                    }
+                }
+                ("type", _) => {
+                    let nullable = kind == NodeKind::Null && keywords::nullable(cfg, Some(schema));
+                    if !type_matches(kind, v) && !nullable {
+                        let msg = format!("{} is not of type {}", describe(kind), v);
+                        self.fail(id, k, v, ValidationError::new(&msg));
+                    }
+                }
+                ("patternProperties", NodeKind::Object) => {
+                    if let Value::Object(patterns) = v {
+                        for pattern in patterns.keys() {
+                            if let Some(Err(_)) = self.regexes.get(pattern.as_str()) {
+                                let err =
+                                    ValidationError::new(&format!("Invalid pattern '{}'", pattern))
+                                        .schema_ctx(pattern.clone());
+                                self.fail(id, k, v, err);
+                            }
+                        }
+                    }
+                }
+                ("additionalProperties", NodeKind::Object) => {
+                    if let Some((pattern, err)) = &invalid_pattern {
+                        let msg = format!(
+                            "Can't check for additional properties: invalid pattern '{}' in patternProperties: {}",
+                            pattern, err
+                        );
+                        self.fail(id, k, v, ValidationError::new(&msg));
+                    }
+                }
+                ("enum", _) | ("const", _) => {
+                    let msg = format!("{} can't be checked while streaming {}", k, describe(kind));
+                    self.fail(id, k, v, ValidationError::new(&msg));
+                }
+                ("uniqueItems", NodeKind::Array) => {
+                    if v == &Value::Bool(true) {
+                        let msg =
+                            format!("{} can't be checked while streaming {}", k, describe(kind));
+                        self.fail(id, k, v, ValidationError::new(&msg));
+                    }
+                }
+                (k, _) if SCALAR_KEYWORDS.contains(&k) || STRUCTURAL_KEYWORDS.contains(&k) => {}
+                (k, _) => {
+                    let msg = format!("{} can't be checked while streaming {}", k, describe(kind));
+                    self.fail(id, k, v, ValidationError::new(&msg));
+                }
+            }
+        }
+    }
+
+    /// Process the key of the next member of an object.
+    fn key(&mut self, id: usize, key: &str) {
+        let eval = self.eval(id);
+        eval.count += 1;
+        if let Some(keys) = &mut eval.keys {
+            keys.insert(key.to_string());
+        }
+        let (schema, scopes) = (eval.schema, eval.scopes.clone());
+        if let Some(names) = self
+            .active(schema)
+            .and_then(|x| self.get(x, "propertyNames"))
+        {
+            let errors = self.descend(&Value::String(key.to_string()), names, &scopes);
+            for err in errors {
+                self.fail(id, "propertyNames", names, err);
+            }
+        }
+    }
+
+    /// Find the schemas that apply to the next value in the object or array
+    /// of an evaluation.
+    fn children(&mut self, id: usize, key: Option<&str>, index: usize, out: &mut Vec<Child<'c>>) {
+        let schema = self.eval(id).schema;
+        let object = match self.active(schema) {
+            Some(object) => object,
+            None => return,
+        };
+        let properties = self.get(object, "properties");
+        let pattern_properties = self.get(object, "patternProperties");
+        let additional_properties = self.get(object, "additionalProperties");
+        let items = self.get(object, "items").map(|items| {
+            if self.cfg.get_draft_number() >= 6 {
+                util::bool_to_object_schema(items)
+            } else {
+                items
+            }
+        });
+        let additional_items = self.get(object, "additionalItems");
+        let contains = self.get(object, "contains");
+
+        let eval = self.eval(id);
+        let scopes = eval.scopes.clone();
+        let mut add =
+            |schema: &'c Value, link: Link<'c>| out.push((schema, scopes.clone(), Some(link)));
+        match key {
+            Some(key) => {
+                let mut matched = false;
+                if let Some(v) = properties {
+                    if let Some(sub) = v.get(key) {
+                        matched = true;
+                        add(
+                            sub,
+                            Link::new(id, "properties", v, Delivery::Errors)
+                                .at(key)
+                                .under(key),
+                        );
+                    }
+                }
+                if let Some(v) = pattern_properties {
+                    let patterns = v.as_object().map(|x| x.keys()).into_iter().flatten();
+                    for (pattern, (sub, re)) in patterns.zip(&eval.patterns) {
+                        if re.is_match(key) {
+                            matched = true;
+                            let link = Link::new(id, "patternProperties", v, Delivery::Errors);
+                            add(sub, link.at(key).under(pattern));
+                        }
+                    }
+                }
+                if let (Some(v), false, true) =
+                    (additional_properties, matched, eval.patterns_valid)
+                {
+                    match v {
+                        Value::Object(_) => {
+                            let link = Link::new(id, "additionalProperties", v, Delivery::Errors);
+                            add(v, link.at(key));
+                        }
+                        Value::Bool(false) => eval.extras.push(key.to_string()),
+                        _ => {}
+                    }
+                }
+            }
+            None => {
+                eval.count += 1;
+                match items {
+                    Some(v @ Value::Object(_)) => {
+                        add(v, Link::new(id, "items", v, Delivery::Errors).at(index));
+                    }
+                    Some(v @ Value::Array(list)) => {
+                        if let Some(sub) = list.get(index) {
+                            let link = Link::new(id, "items", v, Delivery::Errors);
+                            add(sub, link.at(index).under(index));
+                        }
+                    }
+                    _ => {}
+                }
+                if let (Some(v @ Value::Object(_)), Some(Value::Array(list))) =
+                    (additional_items, object.get("items"))
+                {
+                    if index >= list.len() {
+                        add(
+                            v,
+                            Link::new(id, "additionalItems", v, Delivery::Errors).at(index),
+                        );
+                    }
+                }
+                if let Some(v) = contains {
+                    add(v, Link::new(id, "contains", v, Delivery::Contains));
+                }
+            }
+        }
+    }
+
+    /// Pass on the result of an evaluation.
+    fn deliver(&mut self, link: Option<Link<'c>>, errors: Vec<ValidationError>) {
+        let cfg = self.cfg;
+        let link = match link {
+            Some(link) => link,
+            None => {
+                let cfg = self.cfg;
+                self.errors
+                    .extend(errors.into_iter().map(|err| cfg.finish_error(err)));
+                return;
+            }
+        };
+        let parent = self.eval(link.parent);
+        match &link.delivery {
+            Delivery::Errors => parent
+                .errors
+                .extend(errors.into_iter().map(|err| link.wrap(cfg, err))),
+            Delivery::Contains => {
+                if errors.is_empty() {
+                    parent.contains_found = true;
+                }
+            }
+            Delivery::Branch(label) => parent.branches.push((link.keyword, label.clone(), errors)),
+        }
+    }
+
+    /// Finish an evaluation at the end of its object or array.
+    fn finish_eval(&mut self, id: usize) {
+        let cfg = self.cfg;
+        let mut eval = self.evals[id].take().expect("evaluation is active");
+        self.free.push(id);
+        let mut errors = std::mem::take(&mut eval.errors);
+        let object = match eval.schema {
+            Value::Bool(false) => {
+                errors.push(ValidationError::new("false schema always fails"));
+                None
+            }
+            schema => self.active(schema),
+        };
+        let own = |k: &str, v: &Value, msg: String| wrap(cfg, ValidationError::new(&msg), k, v);
+
+        for (k, v) in object.into_iter().flatten() {
+            if self.cfg.get_keyword(k).is_none() {
+                continue;
+            }
+            match (k.as_str(), eval.kind) {
+                ("required", NodeKind::Object) => {
+                    if let (Some(keys), Value::Array(required)) = (&eval.keys, v) {
+                        let missing: Vec<&str> = required
+                            .iter()
+                            .filter_map(Value::as_str)
+                            .filter(|x| !keys.contains(*x))
+                            .collect();
+                        if !missing.is_empty() {
+                            let msg =
+                                format!("required properties {} are missing", missing.join(", "));
+                            errors.push(own(k, v, msg));
+                        }
+                    }
+                }
+                ("dependencies", NodeKind::Object) => {
+                    let keys = eval.keys.take().unwrap_or_default();
+                    if let Value::Object(dependencies) = v {
+                        for (property, dependency) in dependencies {
+                            if !keys.contains(property) {
+                                continue;
+                            }
+                            let dependency = util::bool_to_object_schema(dependency);
+                            if dependency.is_object() {
+                                let branch = eval.take_branch(k, property).unwrap_or_default();
+                                errors.extend(
+                                    branch.into_iter().map(|err| {
+                                        wrap(cfg, err.schema_ctx(property.clone()), k, v)
+                                    }),
+                                );
+                            } else if util::iter_or_once(dependency)
+                                .filter_map(Value::as_str)
+                                .any(|key| !keys.contains(key))
+                            {
+                                errors.push(own(k, v, "dependency".to_string()));
+                            }
+                        }
+                    }
+                    eval.keys = Some(keys);
+                }
+                ("minProperties", NodeKind::Object) | ("minItems", NodeKind::Array) => {
+                    if let Some(limit) = v.as_u64() {
+                        if (eval.count as u64) < limit {
+                            errors.push(own(k, v, format!("{} < {} {}", eval.count, k, v)));
+                        }
+                    }
+                }
+                ("maxProperties", NodeKind::Object) | ("maxItems", NodeKind::Array) => {
+                    if let Some(limit) = v.as_u64() {
+                        if (eval.count as u64) > limit {
+                            errors.push(own(k, v, format!("{} > {} {}", eval.count, k, v)));
+                        }
+                    }
+                }
+                ("contains", NodeKind::Array) if !eval.contains_found => {
+                    let msg = "No items in array valid under the given schema".to_string();
+                    errors.push(own(k, v, msg));
+                }
+                ("additionalItems", NodeKind::Array) => {
+                    if let (Value::Bool(false), Some(Value::Array(items))) =
+                        (v, object.and_then(|x| x.get("items")))
+                    {
+                        if eval.count > items.len() {
+                            errors.push(own(k, v, "Additional items are not allowed".to_string()));
+                        }
+                    }
+                }
+                ("additionalProperties", NodeKind::Object) if !eval.extras.is_empty() => {
+                    let msg = format!(
+                        "Additional properties are not allowed. Found {}",
+                        eval.extras.join(", ")
+                    );
+                    errors.push(own(k, v, msg));
+                }
+                ("anyOf", _) | ("oneOf", _) if v.is_array() => {
+                    let mut matched: Vec<usize> = eval
+                        .branches
+                        .iter()
+                        .filter(|(keyword, _, errors)| keyword == k && errors.is_empty())
+                        .filter_map(|(_, index, _)| index.parse().ok())
+                        .collect();
+                    matched.sort_unstable();
+                    let msg = match (k.as_str(), matched.len()) {
+                        ("anyOf", 0) => Some("anyOf failed".to_string()),
+                        ("oneOf", 0) => Some("nothing matched in oneOf".to_string()),
+                        ("oneOf", 1) | ("anyOf", _) => None,
+                        _ => Some(keywords::more_than_one_matched(&matched)),
+                    };
+                    if let Some(msg) = msg {
+                        errors.push(own(k, v, msg));
+                    }
+                }
+                ("not", _) => {
+                    if let Some(branch) = eval.take_branch(k, "") {
+                        if branch.is_empty() {
+                            errors.push(own(k, v, "not".to_string()));
+                        }
+                    }
+                }
+                ("then", _) | ("else", _) => {
+                    let valid = match eval.take_branch(k, "if") {
+                        Some(branch) => branch.is_empty(),
+                        None => continue,
+                    };
+                    let branch = eval.take_branch(k, k);
+                    if valid == (k == "then") {
+                        errors.extend(branch.into_iter().flatten().map(|err| wrap(cfg, err, k, v)));
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.deliver(eval.link, errors);
+    }
+}
+
+/// Call `f` with the context made of the schema in `ctx` and the resolution
+/// scopes in `scopes`.
+fn in_context<R>(ctx: Context, scopes: &[Value], f: &mut dyn FnMut(Context) -> R) -> R {
+    match scopes.split_first() {
+        Some((scope, rest)) => in_context(ctx.push(scope), rest, f),
+        None => f(ctx),
+    }
+}
+
+fn type_matches(kind: NodeKind, schema: &Value) -> bool {
+    util::iter_or_once(schema).any(|typename| match typename.as_str() {
+        Some("object") => kind == NodeKind::Object,
+        Some("array") => kind == NodeKind::Array,
+        Some("null") | Some("boolean") | Some("number") | Some("integer") | Some("string") => false,
+        _ => true,
+    })
+}
+
+fn describe(kind: NodeKind) -> &'static str {
+    match kind {
+        NodeKind::Object => "an object",
+        _ => "an array",
+    }
+}
+
+/// A pull parser producing the events of a single JSON document.
+///
+/// The parser reads the document incrementally, so it never holds more than
+/// a single string or number of it in memory at once.
+pub struct Parser<R> {
+    reader: R,
+    offset: usize,
+    stack: Vec<NodeKind>,
+    state: State,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Value,
+    FirstValue,
+    Key,
+    FirstKey,
+    Comma,
+    Done,
+}
+
+impl<R: BufRead> Parser<R> {
+    /// Create a parser reading a JSON document from `reader`.
+    pub fn new(reader: R) -> Parser<R> {
+        Parser {
+            reader,
+            offset: 0,
+            stack: Vec::new(),
+            state: State::Value,
+        }
+    }
+
+    /// Get the next event of the document, or `None` at the end of the input
+    /// after a complete document.
+    pub fn next_event(&mut self) -> Result<Option<Event>, Error> {
+        let byte = match self.skip_whitespace()? {
+            Some(byte) => byte,
+            None if self.state == State::Done => return Ok(None),
+            None => return self.syntax("Unexpected end of input"),
+        };
+        match self.state {
+            State::Done => self.syntax("Trailing characters after the document"),
+            State::FirstKey if byte == b'}' => {
+                self.bump();
+                Ok(Some(self.close()))
+            }
+            State::FirstKey | State::Key => {
+                if byte != b'"' {
+                    return self.syntax("Expected a key");
+                }
+                let key = self.string()?;
+                if self.skip_whitespace()? != Some(b':') {
+                    return self.syntax("Expected ':'");
+                }
+                self.bump();
+                self.state = State::Value;
+                Ok(Some(Event::Key(key)))
+            }
+            State::Comma => match (byte, self.stack.last()) {
+                (b',', Some(NodeKind::Object)) => {
+                    self.bump();
+                    self.state = State::Key;
+                    self.next_event()
+                }
+                (b',', _) => {
+                    self.bump();
+                    self.state = State::Value;
+                    self.next_event()
+                }
+                (b'}', Some(NodeKind::Object)) | (b']', Some(NodeKind::Array)) => {
+                    self.bump();
+                    Ok(Some(self.close()))
+                }
+                _ => self.syntax("Expected ',' or the end of the object or array"),
+            },
+            State::FirstValue if byte == b']' => {
+                self.bump();
+                Ok(Some(self.close()))
+            }
+            State::Value | State::FirstValue => self.value(byte).map(Some),
+        }
+    }
+
+    fn syntax<T>(&self, message: &str) -> Result<T, Error> {
+        Err(Error::Syntax {
+            offset: self.offset,
+            message: message.to_string(),
+        })
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>, Error> {
+        loop {
+            match self.reader.fill_buf() {
+                Ok(buffer) => return Ok(buffer.first().copied()),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(Error::Io(err)),
+            }
+        }
+    }
+
+    fn bump(&mut self) {
+        self.reader.consume(1);
+        self.offset += 1;
+    }
+
+    fn skip_whitespace(&mut self) -> Result<Option<u8>, Error> {
+        loop {
+            match self.peek()? {
+                Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') => self.bump(),
+                other => return Ok(other),
+            }
+        }
+    }
+
+    fn after_value(&mut self) {
+        self.state = if self.stack.is_empty() {
+            State::Done
+        } else {
+            State::Comma
+        };
+    }
+
+    fn close(&mut self) -> Event {
+        let kind = self.stack.pop();
+        self.after_value();
+        match kind {
+            Some(NodeKind::Object) => Event::EndObject,
+            _ => Event::EndArray,
+        }
+    }
+
+    fn value(&mut self, byte: u8) -> Result<Event, Error> {
+        let event = match byte {
+            b'{' => {
+                self.bump();
+                self.stack.push(NodeKind::Object);
+                self.state = State::FirstKey;
+                return Ok(Event::StartObject);
+            }
+            b'[' => {
+                self.bump();
+                self.stack.push(NodeKind::Array);
+                self.state = State::FirstValue;
+                return Ok(Event::StartArray);
+            }
+            b'"' => Event::Value(Value::String(self.string()?)),
+            _ => {
+                let start = self.offset;
+                let mut token = Vec::new();
+                while let Some(byte) = self.peek()? {
+                    if !(byte.is_ascii_alphanumeric() || b"+-.".contains(&byte)) {
+                        break;
+                    }
+                    token.push(byte);
+                    self.bump();
+                }
+                let value = match &token[..] {
+                    b"true" => Value::Bool(true),
+                    b"false" => Value::Bool(false),
+                    b"null" => Value::Null,
+                    _ => match serde_json::from_slice::<Value>(&token) {
+                        Ok(value @ Value::Number(_)) => value,
+                        _ => {
+                            return Err(Error::Syntax {
+                                offset: start,
+                                message: "Expected a value".to_string(),
+                            })
+                        }
+                    },
+                };
+                Event::Value(value)
+            }
+        };
+        self.after_value();
+        Ok(event)
+    }
+
+    /// Read a string, starting at its opening quote.
+    fn string(&mut self) -> Result<String, Error> {
+        let start = self.offset;
+        let mut raw = vec![b'"'];
+        self.bump();
+        loop {
+            let byte = match self.peek()? {
+                Some(byte) => byte,
+                None => return self.syntax("Unterminated string"),
+            };
+            raw.push(byte);
+            self.bump();
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    if let Some(byte) = self.peek()? {
+                        raw.push(byte);
+                        self.bump();
+                    }
+                }
+                _ => {}
+            }
+        }
+        serde_json::from_slice(&raw).map_err(|err| Error::Syntax {
+            offset: start,
+            message: format!("Invalid string: {}", err),
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for Parser<R> {
+    type Item = Result<Event, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_event() {
+            Ok(Some(event)) => Some(Ok(event)),
+            Ok(None) => None,
+            Err(err) => {
+                self.state = State::Done;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+/// The magic bytes starting gzip data.
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+/// The magic bytes starting zstd data.
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// A reader that decompresses gzip or zstd data, recognized by their magic
+/// bytes, and passes anything else through unchanged. As JSON can't start
+/// with these bytes, this is transparent for JSON input.
+///
+/// Decompressing gzip requires the `gzip` feature, and zstd the `zstd`
+/// feature; without them, reading compressed data fails with an error
+/// naming the feature.
+pub struct Decompress<'r> {
+    inner: Box<dyn BufRead + 'r>,
+    detected: bool,
+}
+
+impl<'r> Decompress<'r> {
+    /// Wrap `reader`. Nothing is read from it until the first read.
+    pub fn new<R: BufRead + 'r>(reader: R) -> Decompress<'r> {
+        Decompress {
+            inner: Box::new(reader),
+            detected: false,
+        }
+    }
+
+    /// Look at the first bytes and put a decoder in front of the reader if
+    /// they are the magic bytes of a compression format.
+    fn detect(&mut self) -> io::Result<()> {
+        if self.detected {
+            return Ok(());
+        }
+        self.detected = true;
+        let magic = self.inner.fill_buf()?;
+        let (gzip, zstd) = (magic.starts_with(GZIP_MAGIC), magic.starts_with(ZSTD_MAGIC));
+        if gzip {
+            #[cfg(feature = "gzip")]
+            {
+                let reader = std::mem::replace(&mut self.inner, Box::new(io::empty()));
+                let decoder = flate2::bufread::MultiGzDecoder::new(reader);
+                self.inner = Box::new(io::BufReader::new(decoder));
+            }
+            #[cfg(not(feature = "gzip"))]
+            return Err(unsupported("gzip"));
+        } else if zstd {
+            #[cfg(feature = "zstd")]
+            {
+                let reader = std::mem::replace(&mut self.inner, Box::new(io::empty()));
+                let decoder = ruzstd::StreamingDecoder::new(reader)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+                self.inner = Box::new(io::BufReader::new(decoder));
+            }
+            #[cfg(not(feature = "zstd"))]
+            return Err(unsupported("zstd"));
+        }
+        Ok(())
+    }
+}
+
+/// The error for input compressed with a format whose feature is disabled.
+#[cfg(not(all(feature = "gzip", feature = "zstd")))]
+fn unsupported(format: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "The input is {} compressed, which requires the {} feature",
+            format, format
+        ),
+    )
+}
+
+impl Read for Decompress<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.detect()?;
+        self.inner.read(buf)
+    }
+}
+
+impl BufRead for Decompress<'_> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.detect()?;
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.inner.consume(amount)
+    }
+}
+
+/// Validate the JSON document read from `reader` against the schema in
+/// `cfg`, without holding the whole document in memory.
+///
+/// Returns the validation errors, which are empty if the document is valid.
+/// Fails if the document can't be read or isn't valid JSON.
+pub fn validate_reader<R: Read>(cfg: &Config, reader: R) -> Result<Vec<ValidationError>, Error> {
+    validate_events(
+        cfg,
+        Parser::new(Decompress::new(io::BufReader::new(reader))),
+    )
+}
+
+/// Validate the JSON document in the file at `path` against the schema in
+/// `cfg`, reading it through a memory mapping.
+///
+/// The file must not be modified while it is being validated.
+pub fn validate_file<P: AsRef<Path>>(cfg: &Config, path: P) -> Result<Vec<ValidationError>, Error> {
+    let file = File::open(path)?;
+    // Safety: the file is only read through the mapping, which is dropped
+    // before returning. Changes made to it by other processes meanwhile are
+    // the caller's responsibility, as documented above.
+    let map = unsafe { Mmap::map(&file)? };
+    validate_events(cfg, Parser::new(Decompress::new(&map[..])))
+}
+
+fn validate_events<R: BufRead>(
+    cfg: &Config,
+    parser: Parser<R>,
+) -> Result<Vec<ValidationError>, Error> {
+    let mut validator = StreamValidator::new(cfg);
+    for event in parser {
+        validator.push(event?)?;
+    }
+    validator.finish()
+}
+
+/// The record separator starting each document of a JSON text sequence.
+const RS: u8 = 0x1e;
+
+/// The part of a JSON text sequence up to the next record separator.
+struct Record<'r, R> {
+    inner: &'r mut R,
+}
+
+impl<R: BufRead> Read for Record<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Record<'_, R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let buffer = self.inner.fill_buf()?;
+        let end = buffer.iter().position(|&x| x == RS).unwrap_or(buffer.len());
+        Ok(&buffer[..end])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.inner.consume(amount)
+    }
+}
+
+/// Validates each document of a JSON text sequence (RFC 7464,
+/// `application/json-seq`) in turn.
+///
+/// Iterating yields the index of each document in the sequence, counting
+/// from 0, with its validation errors, or the reason it couldn't be
+/// validated. A malformed document doesn't stop the iteration; the next one
+/// starts at the next record separator. Offsets in syntax errors are
+/// relative to the start of the document.
+pub struct Sequence<'c, R> {
+    cfg: &'c Config<'c>,
+    reader: R,
+    index: usize,
+    failed: bool,
+}
+
+impl<'c, R: BufRead> Sequence<'c, R> {
+    /// Create an iterator validating the documents of the JSON text sequence
+    /// read from `reader` against the schema in `cfg`.
+    pub fn new(cfg: &'c Config<'c>, reader: R) -> Sequence<'c, R> {
+        Sequence {
+            cfg,
+            reader,
+            index: 0,
+            failed: false,
+        }
+    }
+
+    /// Skip separators and whitespace up to the next document; `false` at
+    /// the end of the input.
+    fn skip_separators(&mut self) -> io::Result<bool> {
+        loop {
+            let buffer = self.reader.fill_buf()?;
+            let skip = buffer
+                .iter()
+                .position(|&x| !(x == RS || x.is_ascii_whitespace()));
+            match (skip, buffer.len()) {
+                (_, 0) => return Ok(false),
+                (Some(n), _) => {
+                    self.reader.consume(n);
+                    return Ok(true);
+                }
+                (None, n) => self.reader.consume(n),
+            }
+        }
+    }
+
+    /// Skip the rest of the current document.
+    fn skip_record(&mut self) -> io::Result<()> {
+        loop {
+            let buffer = self.reader.fill_buf()?;
+            match buffer.iter().position(|&x| x == RS) {
+                _ if buffer.is_empty() => return Ok(()),
+                Some(n) => {
+                    self.reader.consume(n);
+                    return Ok(());
+                }
+                None => {
+                    let n = buffer.len();
+                    self.reader.consume(n);
+                }
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Sequence<'_, R> {
+    type Item = (usize, Result<Vec<ValidationError>, Error>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let index = self.index;
+        match self.skip_separators() {
+            Ok(true) => {}
+            Ok(false) => return None,
+            Err(err) => {
+                self.failed = true;
+                return Some((index, Err(Error::Io(err))));
+            }
+        }
+        self.index += 1;
+        let parser = Parser::new(Record {
+            inner: &mut self.reader,
+        });
+        let result = validate_events(self.cfg, parser);
+        if let Err(Error::Io(_)) = result {
+            self.failed = true;
+        } else if let Err(err) = self.skip_record() {
+            self.failed = true;
+            return Some((index, Err(Error::Io(err))));
+        }
+        Some((index, result))
+    }
+}
+
+/// Validate each document of the JSON text sequence read from `reader`
+/// against the schema in `cfg`. See `Sequence` for the results.
+pub fn validate_seq<'c, R: Read + 'c>(
+    cfg: &'c Config<'c>,
+    reader: R,
+) -> Sequence<'c, Decompress<'c>> {
+    Sequence::new(cfg, Decompress::new(io::BufReader::new(reader)))
+}
+
+/// Validate each document of the JSON text sequence in the file at `path`
+/// against the schema in `cfg`, reading it through a memory mapping. See
+/// `Sequence` for the results.
+///
+/// The file must not be modified while it is being validated.
+pub fn validate_seq_file<'c, P: AsRef<Path>>(
+    cfg: &'c Config<'c>,
+    path: P,
+) -> Result<Sequence<'c, Decompress<'c>>, Error> {
+    let file = File::open(path)?;
+    // Safety: as in `validate_file`.
+    let map = unsafe { Mmap::map(&file)? };
+    Ok(Sequence::new(cfg, Decompress::new(io::Cursor::new(map))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas;
+
+    fn errors(mut errors: Vec<ValidationError>) -> Vec<String> {
+        errors.sort_by_key(|err| (err.instance_path().join("/"), err.schema_path().join("/")));
+        errors
+            .iter()
+            .map(|err| {
+                format!(
+                    "{} {} {}",
+                    err.instance_path().join("/"),
+                    err.schema_path().join("/"),
+                    err.message()
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_same_as_validate() {
+        let schema = json!({
+            "definitions": {"id": {"type": "integer", "minimum": 1}},
+            "properties": {
+                "id": {"$ref": "#/definitions/id"},
+                "tags": {"items": {"type": "string"}, "contains": {"const": "x"}, "maxItems": 2},
+                "point": {"anyOf": [{"required": ["x"]}, {"required": ["y"]}]},
+                "kind": {"oneOf": [{"type": "object"}, {"type": "array"}]}
+            },
+            "patternProperties": {"^x-": {"type": "number"}},
+            "additionalProperties": false,
+            "dependencies": {"id": ["tags"], "point": {"required": ["kind"]}},
+            "if": {"required": ["id"]},
+            "then": {"properties": {"id": {"not": {"maximum": 3}}}},
+            "else": {"required": ["point"]},
+            "propertyNames": {"maxLength": 5}
+        });
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        for instance in &[
+            json!({"id": 7, "tags": ["x"], "point": {"y": 1}, "kind": [], "x-a": 1}),
+            json!({"id": 0, "tags": [1, "y", "z"], "point": {}, "kind": "", "x-a": "", "other": 1}),
+            json!({"id": 2, "point": {"x": 1}}),
+            json!({"tags": ["x"]}),
+            json!([{"id": 1}]),
+        ] {
+            let text = instance.to_string();
+            let streamed = validate_reader(&cfg, text.as_bytes()).unwrap();
+            let expected = match cfg.validate(instance) {
+                Ok(()) => Vec::new(),
+                Err(errors) => errors.collect(),
+            };
+            assert_eq!(errors(streamed), errors(expected), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_buffering_keywords() {
+        let schema = json!({"items": {"uniqueItems": true}});
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let errors = validate_reader(&cfg, &b"[[1, 2], 3]"[..]).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].message(),
+            "uniqueItems can't be checked while streaming an array"
+        );
+        assert_eq!(errors[0].instance_path(), vec!["0"]);
+    }
+
+    #[test]
+    fn test_validate_file() {
+        let path = std::env::temp_dir().join("jsonschema-valid-stream-test.json");
+        std::fs::write(&path, r#"{"a": [1, 2, "x"]}"#).unwrap();
+        let schema = json!({"properties": {"a": {"items": {"type": "integer"}}}});
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let errors = validate_file(&cfg, &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].instance_path(), vec!["a", "2"]);
+    }
+
+    #[test]
+    fn test_sequence() {
+        let schema = json!({"required": ["id"]});
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let input = b"\x1e{\"id\": 1}\n\x1e{}\n\x1e{\"id\": \n\x1e\x1e[]\n";
+        let results: Vec<_> = validate_seq(&cfg, &input[..])
+            .map(|(index, result)| (index, result.map(|errors| errors.len())))
+            .collect();
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].0, 0);
+        assert_eq!(results[0].1.as_ref().unwrap(), &0);
+        assert_eq!(results[1].1.as_ref().unwrap(), &1);
+        assert!(matches!(results[2].1, Err(Error::Syntax { .. })));
+        assert_eq!(results[3].0, 3);
+        assert_eq!(results[3].1.as_ref().unwrap(), &0);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip_sequence() {
+        use std::io::Write;
+
+        let schema = json!({"required": ["id"]});
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft7)).unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"\x1e{\"id\": 1}\n\x1e{}\n").unwrap();
+        let input = encoder.finish().unwrap();
+        let results: Vec<usize> = validate_seq(&cfg, &input[..])
+            .map(|(_, result)| result.unwrap().len())
+            .collect();
+        assert_eq!(results, [0, 1]);
+    }
+
+    #[test]
+    fn test_parser() {
+        let events: Vec<Event> = Parser::new(r#" {"a": [1, "\u00e9", {}], "b": null} "#.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                Event::StartObject,
+                Event::Key("a".to_string()),
+                Event::StartArray,
+                Event::Value(json!(1)),
+                Event::Value(json!("é")),
+                Event::StartObject,
+                Event::EndObject,
+                Event::EndArray,
+                Event::Key("b".to_string()),
+                Event::Value(Value::Null),
+                Event::EndObject,
+            ]
+        );
+        for (input, offset) in &[("[1,]", 3), ("{\"a\" 1}", 5), ("[1] 2", 4), ("[tru]", 1)] {
+            match Parser::new(input.as_bytes()).collect::<Result<Vec<_>, _>>() {
+                Err(Error::Syntax { offset: x, .. }) => assert_eq!(x, *offset, "{}", input),
+                other => panic!("{}: {:?}", input, other),
+            }
+        }
+    }
+}
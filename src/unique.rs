@@ -3,63 +3,72 @@ use std::collections::HashSet;
 use std::hash::Hash;
 use std::hash::Hasher;
 
-use serde_json::Value;
+use crate::node::{self, JsonNode, NodeKind};
 
 struct ValueWrapper<'a> {
-    x: &'a Value,
+    x: &'a dyn JsonNode,
 }
 
 impl<'a> Hash for ValueWrapper<'a> {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        match self.x {
-            Value::Array(array) => {
+        match self.x.kind() {
+            NodeKind::Array => {
                 0.hash(state);
-                for element in array {
+                for element in self.x.elements() {
                     ValueWrapper { x: element }.hash(state);
                 }
             }
-            Value::Object(object) => {
+            NodeKind::Object => {
+                // Hash the members in key order, since equal objects may list
+                // them in different orders.
                 1.hash(state);
-                for (key, val) in object {
+                let mut members: Vec<(&str, &dyn JsonNode)> = self.x.members().collect();
+                members.sort_by_key(|(key, _)| *key);
+                for (key, val) in members {
                     key.hash(state);
                     ValueWrapper { x: val }.hash(state);
                 }
             }
-            Value::String(string) => {
+            NodeKind::String => {
                 2.hash(state);
-                string.hash(state)
+                self.x.as_str().hash(state)
             }
-            Value::Number(number) => {
-                if number.is_f64() {
-                    3.hash(state);
-                    number.as_f64().unwrap().to_bits().hash(state);
-                } else if number.is_u64() {
-                    4.hash(state);
-                    number.as_u64().unwrap().hash(state);
-                } else {
-                    5.hash(state);
-                    number.as_i64().unwrap().hash(state);
+            NodeKind::Number => {
+                // Hash integers the same whether they are represented as
+                // integers or floats, since they compare equal.
+                match self.x.as_number() {
+                    Some(number) => match node::integer_value(&number) {
+                        Some(integer) => {
+                            3.hash(state);
+                            integer.hash(state);
+                        }
+                        None => {
+                            4.hash(state);
+                            number.as_f64().map(f64::to_bits).hash(state);
+                        }
+                    },
+                    None => 4.hash(state),
                 }
             }
-            Value::Bool(bool) => {
+            NodeKind::Bool => {
                 6.hash(state);
-                bool.hash(state)
+                self.x.as_bool().hash(state)
             }
-            Value::Null => 0.hash(state),
+            NodeKind::Null => 0.hash(state),
         }
     }
 }
 
 impl<'a> PartialEq for ValueWrapper<'a> {
     fn eq(&self, other: &ValueWrapper<'a>) -> bool {
-        self.x == other.x
+        node::equals(self.x, other.x)
     }
 }
 
 impl<'a> Eq for ValueWrapper<'a> {}
 
-pub fn has_unique_elements(iter: &mut dyn Iterator<Item = &Value>) -> bool {
+pub fn has_unique_elements(iter: &mut dyn Iterator<Item = &dyn JsonNode>) -> bool {
     let mut uniq = HashSet::new();
-    iter.map(|x| ValueWrapper { x: &x })
+    iter.map(|x| ValueWrapper { x })
         .all(move |x| uniq.insert(x))
 }
@@ -27,3 +27,162 @@ pub fn iter_or_once<'a>(instance: &'a Value) -> Box<dyn Iterator<Item = &'a Valu
         _ => Box::new(iter::once(instance)),
     }
 }
+
+/// Escape a single reference token for use in a JSON pointer (RFC 6901).
+pub fn escape_pointer_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// Split a JSON pointer into its unescaped reference tokens.
+pub fn pointer_tokens(pointer: &str) -> impl Iterator<Item = String> + '_ {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+}
+
+/// Keywords whose value is a single subschema.
+pub const SCHEMA_KEYWORDS: &[&str] = &[
+    "additionalItems",
+    "additionalProperties",
+    "contains",
+    "contentSchema",
+    "else",
+    "if",
+    "items",
+    "not",
+    "propertyNames",
+    "then",
+    "unevaluatedItems",
+    "unevaluatedProperties",
+];
+
+/// Keywords whose value is an array of subschemas.
+pub const SCHEMA_ARRAY_KEYWORDS: &[&str] = &["allOf", "anyOf", "items", "oneOf", "prefixItems"];
+
+/// Keywords whose value is an object mapping names to subschemas.
+pub const SCHEMA_MAP_KEYWORDS: &[&str] = &[
+    "$defs",
+    "definitions",
+    "dependencies",
+    "dependentSchemas",
+    "patternProperties",
+    "properties",
+];
+
+/// Call `f` with every immediate subschema of `schema`, along with the
+/// (already escaped) JSON pointer of the subschema relative to `schema`.
+pub fn for_each_subschema<'a>(schema: &'a Value, f: &mut dyn FnMut(String, &'a Value)) {
+    if let Value::Object(object) = schema {
+        for (key, value) in object {
+            let keyword = key.as_str();
+            match value {
+                Value::Object(_) | Value::Bool(_) if SCHEMA_KEYWORDS.contains(&keyword) => {
+                    f(escape_pointer_token(key), value)
+                }
+                Value::Array(array) if SCHEMA_ARRAY_KEYWORDS.contains(&keyword) => {
+                    for (index, item) in array.iter().enumerate() {
+                        f(format!("{}/{}", escape_pointer_token(key), index), item);
+                    }
+                }
+                #[cfg(feature = "draft-next")]
+                Value::Object(map) if keyword == "propertyDependencies" => {
+                    for (name, values) in map {
+                        for (value, item) in values.as_object().into_iter().flatten() {
+                            f(
+                                format!(
+                                    "{}/{}/{}",
+                                    escape_pointer_token(key),
+                                    escape_pointer_token(name),
+                                    escape_pointer_token(value)
+                                ),
+                                item,
+                            );
+                        }
+                    }
+                }
+                Value::Object(map) if SCHEMA_MAP_KEYWORDS.contains(&keyword) => {
+                    for (name, item) in map {
+                        if item.is_object() || item.is_boolean() {
+                            f(
+                                format!(
+                                    "{}/{}",
+                                    escape_pointer_token(key),
+                                    escape_pointer_token(name)
+                                ),
+                                item,
+                            );
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Like `for_each_subschema`, but with mutable access to the subschemas.
+pub fn for_each_subschema_mut(schema: &mut Value, f: &mut dyn FnMut(String, &mut Value)) {
+    if let Value::Object(object) = schema {
+        for (key, value) in object.iter_mut() {
+            let keyword = key.as_str();
+            match value {
+                Value::Object(_) | Value::Bool(_) if SCHEMA_KEYWORDS.contains(&keyword) => {
+                    f(escape_pointer_token(key), value)
+                }
+                Value::Array(array) if SCHEMA_ARRAY_KEYWORDS.contains(&keyword) => {
+                    for (index, item) in array.iter_mut().enumerate() {
+                        f(format!("{}/{}", escape_pointer_token(key), index), item);
+                    }
+                }
+                #[cfg(feature = "draft-next")]
+                Value::Object(map) if keyword == "propertyDependencies" => {
+                    for (name, values) in map.iter_mut() {
+                        for (value, item) in values.as_object_mut().into_iter().flatten() {
+                            f(
+                                format!(
+                                    "{}/{}/{}",
+                                    escape_pointer_token(key),
+                                    escape_pointer_token(name),
+                                    escape_pointer_token(value)
+                                ),
+                                item,
+                            );
+                        }
+                    }
+                }
+                Value::Object(map) if SCHEMA_MAP_KEYWORDS.contains(&keyword) => {
+                    for (name, item) in map.iter_mut() {
+                        if item.is_object() || item.is_boolean() {
+                            f(
+                                format!(
+                                    "{}/{}",
+                                    escape_pointer_token(key),
+                                    escape_pointer_token(name)
+                                ),
+                                item,
+                            );
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Collect the JSON pointers that the local (`#/...`) `$ref`s in `schema`
+/// point to.
+pub fn local_ref_targets(schema: &Value, targets: &mut Vec<String>) {
+    if let Some(fragment) = schema
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|reference| reference.strip_prefix('#'))
+    {
+        let fragment = percent_encoding::percent_decode(fragment.as_bytes()).decode_utf8_lossy();
+        targets.push(fragment.into_owned());
+    }
+    for_each_subschema(schema, &mut |_, subschema| {
+        local_ref_targets(subschema, targets)
+    });
+}
@@ -0,0 +1,154 @@
+//! Vocabularies of keywords for dialects of draft 2020-12.
+//!
+//! From draft 2020-12 on, a metaschema lists the vocabularies that schemas
+//! written against it use in `$vocabulary`, by URI, each marked as required
+//! (`true`) or optional (`false`). When `Config::with_document` adds the
+//! metaschema that the `$schema` of the schema names, only the keywords of
+//! the required vocabularies take part in validation:
+//!
+//! - an unknown required vocabulary is an error, as the schema can't be
+//!   validated as its authors intended;
+//! - optional vocabularies aren't used, and are reported by
+//!   `Config::get_vocabulary_warnings`.
+//!
+//! The vocabularies of draft 2020-12 are known. Custom dialects can bring
+//! their own: a `Vocabulary` is a URI with the implementations of its
+//! keywords, registered with `Config::with_vocabulary` before the
+//! metaschema is added.
+//!
+//! ## Example:
+//!
+//! ```rust
+//! # use serde_json::{json, Value};
+//! # use jsonschema_valid::{schemas, Config, Context};
+//! use jsonschema_valid::error::{make_error, no_error, ErrorIterator};
+//! use jsonschema_valid::node::JsonNode;
+//! use jsonschema_valid::vocabulary::Vocabulary;
+//!
+//! fn even<'a>(
+//!     _cfg: &'a Config<'a>,
+//!     instance: &'a dyn JsonNode,
+//!     schema: &'a Value,
+//!     _parent_schema: Option<&'a Value>,
+//!     _ref_context: Context<'a>,
+//! ) -> ErrorIterator<'a> {
+//!     match instance.as_number().and_then(|x| x.as_i64()) {
+//!         Some(x) if schema == &json!(true) && x % 2 != 0 => make_error(format!("{} is odd", x)),
+//!         _ => no_error(),
+//!     }
+//! }
+//!
+//! let schema = json!({"$schema": "https://example.com/parity", "even": true, "minimum": 10});
+//! let metaschema = json!({
+//!     "$schema": "https://json-schema.org/draft/2020-12/schema",
+//!     "$vocabulary": {
+//!         "https://json-schema.org/draft/2020-12/vocab/core": true,
+//!         "https://json-schema.org/draft/2020-12/vocab/validation": false,
+//!         "https://example.com/vocab/parity": true
+//!     }
+//! });
+//! let url = url::Url::parse("https://example.com/parity").unwrap();
+//! let cfg = Config::from_schema(&schema, Some(&schemas::Draft202012))
+//!     .unwrap()
+//!     .with_vocabulary(Vocabulary::new("https://example.com/vocab/parity").with_keyword("even", even))
+//!     .with_document(&url, metaschema)
+//!     .unwrap();
+//!
+//! assert!(cfg.validate(&json!(3)).is_err());
+//! // `minimum` is in the validation vocabulary, which is optional.
+//! assert!(cfg.validate(&json!(2)).is_ok());
+//! assert_eq!(cfg.get_vocabulary_warnings().len(), 1);
+//! ```
+
+use crate::keywords::Keyword;
+
+/// A named bundle of keywords; see the module documentation.
+pub struct Vocabulary<'a> {
+    uri: String,
+    keywords: Vec<(String, Box<dyn Keyword + 'a>)>,
+}
+
+impl<'a> Vocabulary<'a> {
+    /// Create a vocabulary without keywords, identified by `uri` in
+    /// `$vocabulary`.
+    pub fn new(uri: &str) -> Vocabulary<'a> {
+        Vocabulary {
+            uri: uri.to_string(),
+            keywords: Vec::new(),
+        }
+    }
+
+    /// Add the keyword `name` with the given implementation.
+    pub fn with_keyword<K: Keyword + 'a>(mut self, name: &str, keyword: K) -> Self {
+        self.keywords.push((name.to_string(), Box::new(keyword)));
+        self
+    }
+
+    /// Get the URI identifying the vocabulary.
+    pub fn get_uri(&self) -> &str {
+        &self.uri
+    }
+
+    /// Get the names of the keywords of the vocabulary.
+    pub fn get_keywords(&self) -> impl Iterator<Item = &str> {
+        self.keywords.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub(crate) fn into_keywords(self) -> Vec<(String, Box<dyn Keyword + 'a>)> {
+        self.keywords
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::formats::FormatMode;
+    use crate::schemas;
+    use serde_json::json;
+
+    #[test]
+    fn test_standard_vocabularies() {
+        let schema = json!({
+            "$schema": "https://example.com/strict",
+            "type": "string",
+            "format": "email",
+            "unevaluatedProperties": false
+        });
+        let url = url::Url::parse("https://example.com/strict#").unwrap();
+        let metaschema = |vocabularies: &[(&str, bool)]| {
+            let vocabularies: serde_json::Map<_, _> = vocabularies
+                .iter()
+                .map(|(name, required)| {
+                    (
+                        format!("https://json-schema.org/draft/2020-12/vocab/{}", name),
+                        json!(required),
+                    )
+                })
+                .collect();
+            json!({ "$vocabulary": vocabularies })
+        };
+
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft202012))
+            .unwrap()
+            .with_document(
+                &url,
+                metaschema(&[
+                    ("core", true),
+                    ("validation", true),
+                    ("format-assertion", true),
+                ]),
+            )
+            .unwrap();
+        assert_eq!(cfg.get_format_mode(), FormatMode::Assertion);
+        assert!(cfg.get_keyword("unevaluatedProperties").is_none());
+        assert!(cfg.get_vocabulary_warnings().is_empty());
+        assert!(cfg.validate(&json!("a@example.com")).is_ok());
+        assert!(cfg.validate(&json!("nobody")).is_err());
+        assert!(cfg.validate(&json!(1)).is_err());
+
+        let cfg = Config::from_schema(&schema, Some(&schemas::Draft202012))
+            .unwrap()
+            .with_document(&url, metaschema(&[("core", true), ("units", true)]));
+        assert!(cfg.is_err());
+    }
+}
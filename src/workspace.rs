@@ -0,0 +1,195 @@
+//! Loading a directory of schemas that reference each other.
+//!
+//! `Workspace::load` reads every `.json` file in a directory and its
+//! subdirectories, and makes each available to the `$ref`s of the others by
+//! its `file://` URL and by its `$id`, so that both relative references
+//! between the files and references by id resolve.
+//! `Workspace::check_references` then reports the references that don't
+//! resolve, in all the files at once, and `Workspace::compile` compiles one
+//! of the files for validation.
+//!
+//! ## Example:
+//!
+//! ```rust,no_run
+//! # fn main() -> Result<(), jsonschema_valid::ValidationError> {
+//! # use serde_json::json;
+//! use jsonschema_valid::workspace::Workspace;
+//!
+//! let workspace = Workspace::load("schemas")?;
+//! for (path, err) in workspace.check_references() {
+//!     eprintln!("{}: {}", path.display(), err);
+//! }
+//!
+//! let cfg = workspace.compile("schemas/user.json")?;
+//! assert!(cfg.validate(&json!({"name": "x"})).is_ok());
+//! # Ok(()) }
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::config::Config;
+use crate::context::Context;
+use crate::error::ValidationError;
+use crate::resolver::{find_refs, uses_legacy_ids, Resolver};
+use crate::schemas;
+use crate::util;
+
+/// A set of schema files loaded from a directory.
+pub struct Workspace {
+    documents: Vec<(PathBuf, url::Url, Value)>,
+    resolver: Resolver,
+}
+
+impl Workspace {
+    /// Load all the files with the `.json` extension in the directory `dir`
+    /// and its subdirectories.
+    ///
+    /// Fails if a file can't be read, isn't valid JSON, or has an invalid id.
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<Workspace, ValidationError> {
+        let mut paths = Vec::new();
+        find_files(dir.as_ref(), &mut paths)?;
+        paths.sort();
+        let mut resolver = Resolver::from_schema(&Value::Null)?;
+        let mut documents = Vec::new();
+        for path in paths {
+            let fail = |err: &dyn fmt::Display| {
+                ValidationError::new(&format!("{}: {}", path.display(), err))
+            };
+            let contents = fs::read(&path).map_err(|err| fail(&err))?;
+            let document: Value = serde_json::from_slice(&contents).map_err(|err| fail(&err))?;
+            let path = path.canonicalize().map_err(|err| fail(&err))?;
+            let url = url::Url::from_file_path(&path).map_err(|_| fail(&"Invalid path"))?;
+            resolver
+                .add_document(&url, document.clone())
+                .map_err(|err| fail(&err.message()))?;
+            documents.push((path, url, document));
+        }
+        Ok(Workspace {
+            documents,
+            resolver,
+        })
+    }
+
+    /// The paths of the loaded files, with the URL of each.
+    pub fn files(&self) -> impl Iterator<Item = (&Path, &url::Url)> {
+        self.documents
+            .iter()
+            .map(|(path, url, _)| (path.as_path(), url))
+    }
+
+    /// Check that every `$ref` in the loaded files resolves.
+    ///
+    /// Returns the path of the file and the error for each that doesn't. The
+    /// instance path of the error is the location of the `$ref` in the file.
+    pub fn check_references(&self) -> Vec<(PathBuf, ValidationError)> {
+        let mut errors = Vec::new();
+        for (path, url, document) in &self.documents {
+            let mut refs = Vec::new();
+            let legacy_ids = uses_legacy_ids(document, false);
+            find_refs(document, url, legacy_ids, &mut String::new(), &mut refs);
+            for (pointer, ref_, target) in refs {
+                let err = match target {
+                    Ok(target) => {
+                        match self.resolver.resolve_fragment(
+                            target.as_str(),
+                            &Context::new(),
+                            &Value::Null,
+                        ) {
+                            Ok(_) => continue,
+                            Err(err) => err.message().to_string(),
+                        }
+                    }
+                    Err(err) => err.to_string(),
+                };
+                let err =
+                    ValidationError::new(&format!("Can't resolve reference {}: {}", ref_, err));
+                let err = util::pointer_tokens(&pointer)
+                    .collect::<Vec<_>>()
+                    .into_iter()
+                    .rev()
+                    .fold(err, |err, token| err.instance_ctx(token));
+                errors.push((path.clone(), err));
+            }
+        }
+        errors
+    }
+
+    /// Compile the loaded file at `path` for validation, with its references
+    /// resolved against all the loaded files.
+    pub fn compile<P: AsRef<Path>>(&self, path: P) -> Result<Config<'static>, ValidationError> {
+        let path = path.as_ref();
+        let fail =
+            |err: &dyn fmt::Display| ValidationError::new(&format!("{}: {}", path.display(), err));
+        let path = path.canonicalize().map_err(|err| fail(&err))?;
+        let (_, url, document) = self
+            .documents
+            .iter()
+            .find(|(x, _, _)| *x == path)
+            .ok_or_else(|| fail(&"Not in the workspace"))?;
+        // Referring to the file, rather than compiling it directly, resolves
+        // its relative references against its URL.
+        let schema = json!({ "$ref": url.as_str() });
+        let mut cfg = Config::from_owned_schema(schema, schemas::draft_from_schema(document))?;
+        for (_, url, document) in &self.documents {
+            cfg = cfg.with_document(url, document.clone())?;
+        }
+        Ok(cfg)
+    }
+}
+
+fn find_files(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<(), ValidationError> {
+    let fail =
+        |err: &dyn fmt::Display| ValidationError::new(&format!("{}: {}", dir.display(), err));
+    for entry in fs::read_dir(dir).map_err(|err| fail(&err))? {
+        let path = entry.map_err(|err| fail(&err))?.path();
+        if path.is_dir() {
+            find_files(&path, paths)?;
+        } else if path.extension().is_some_and(|x| x == "json") {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspace() {
+        let dir = std::env::temp_dir().join("jsonschema-valid-workspace-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        let a = json!({
+            "properties": {
+                "b": {"$ref": "sub/b.json#/definitions/x"},
+                "c": {"$ref": "c.json"}
+            }
+        });
+        let b = json!({
+            "$id": "http://example.com/b.json",
+            "definitions": {
+                "x": {"$ref": "http://example.com/b.json#/definitions/y"},
+                "y": {"type": "integer"}
+            }
+        });
+        fs::write(dir.join("a.json"), a.to_string()).unwrap();
+        fs::write(dir.join("sub/b.json"), b.to_string()).unwrap();
+
+        let workspace = Workspace::load(&dir).unwrap();
+        assert_eq!(workspace.files().count(), 2);
+        let errors = workspace.check_references();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].0.ends_with("a.json"));
+        assert_eq!(errors[0].1.instance_path(), vec!["properties", "c", "$ref"]);
+
+        let cfg = workspace.compile(dir.join("a.json")).unwrap();
+        assert!(cfg.validate(&json!({"b": 1})).is_ok());
+        assert!(cfg.validate(&json!({"b": "x"})).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}